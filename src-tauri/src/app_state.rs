@@ -1,39 +1,95 @@
-use std::env;
+use std::{
+    path::Path,
+    sync::{Arc, RwLock},
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{config::AppConfig, filesystem};
+use crate::{
+    auth::Permission,
+    config::{AppConfig, Settings},
+    encryption::{BackupKey, Key},
+    error::{Error, Result},
+    filesystem,
+    repository::users::User,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct AppState {
     pub config: AppConfig,
+    /// Who is signed in at this desk
+    #[serde(skip)]
+    pub session: Arc<RwLock<Option<User>>>,
+    /// Set once an encrypted database is unlocked
+    #[serde(skip)]
+    pub encryption_key: Arc<RwLock<Option<Key>>>,
+    /// Set once the backup passphrase is entered, the database key keeps it
+    /// on disk otherwise
+    #[serde(skip)]
+    pub backup_key: Arc<RwLock<Option<BackupKey>>>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
-        let data_dir_path = filesystem::DATA_DIR.clone();
-        let data_dir = data_dir_path.display().to_string();
-        let db_path = data_dir_path.join("database.db").display().to_string();
-        let db_url = format!("sqlite:{}", db_path);
-
-        let sync_interval_minutes = env::var("SYNC_INTERVAL_MINUTES")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(60);
-
-        let ip_address = local_ip_address::local_ip()
-            .map(|ip| ip.to_string())
-            .unwrap_or_else(|_| "127.0.0.1".to_string());
-
-        let config = AppConfig {
-            db_path: db_path,
-            db_url: db_url,
-            data_dir: data_dir,
-            sync_interval_minutes: sync_interval_minutes,
-            ip_address: ip_address,
-            port: 14200,
-        };
-
-        Self { config: config }
+        let (settings, mut errors) = Settings::load(&filesystem::settings_path());
+        let settings = settings.with_env_overrides(&mut errors);
+        let config = AppConfig::from_settings(&settings, &mut errors);
+
+        for error in &errors {
+            log::warn!("{}", error);
+        }
+
+        // Validation only checks that the folder can be made
+        std::fs::create_dir_all(&config.data_dir).expect("Data directory is not writable");
+
+        Self {
+            config,
+            session: Arc::default(),
+            encryption_key: Arc::default(),
+            backup_key: Arc::default(),
+        }
+    }
+}
+
+impl AppState {
+    pub fn current_user(&self) -> Option<User> {
+        self.session.read().unwrap().clone()
+    }
+
+    pub fn encryption_key(&self) -> Option<Key> {
+        self.encryption_key.read().unwrap().clone()
+    }
+
+    /// The key new backups are sealed with, see [`BackupKey::load`].
+    pub async fn backup_key(&self) -> Result<Option<BackupKey>> {
+        let unlocked = self.backup_key.read().unwrap().clone();
+        BackupKey::load(
+            Path::new(&self.config.data_dir),
+            self.encryption_key().as_ref(),
+            unlocked.as_ref(),
+        )
+        .await
+    }
+
+    /// The signed in user, if their role allows `permission`.
+    pub fn require(&self, permission: Permission) -> Result<User> {
+        let user = self.current_user().ok_or(Error::SignedOut)?;
+        if !user.role.allows(permission) {
+            log::warn!("{} was denied {:?}", user.username, permission);
+            return Err(Error::Forbidden);
+        }
+
+        Ok(user)
+    }
+
+    /// Picks up edits to the signed in user's own account.
+    pub fn refresh_session(&self, user: &User) {
+        let mut session = self.session.write().unwrap();
+        if session
+            .as_ref()
+            .is_some_and(|current| current.id == user.id)
+        {
+            *session = Some(user.clone());
+        }
     }
 }
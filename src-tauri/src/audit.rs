@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::Result,
+    repository::{search_term, PagedList, PagingParams},
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryParams {
+    #[serde(flatten)]
+    pub paging: PagingParams,
+    /// Changes to the patient, their statements and everything on them
+    pub patient_id: Option<String>,
+    /// Changes to the statement and its payments, sessions and attachments
+    pub statement_id: Option<String>,
+    /// Only changes to this table, e.g. `payments`
+    pub table_name: Option<String>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct AuditRow {
+    id: i64,
+    table_name: String,
+    row_id: String,
+    operation: String,
+    patient_id: Option<String>,
+    statement_id: Option<String>,
+    old_values: Option<String>,
+    new_values: Option<String>,
+    changed_at: String,
+    user_id: Option<String>,
+    user_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub table_name: String,
+    pub row_id: String,
+    /// `insert`, `update` or `delete`
+    pub operation: String,
+    pub patient_id: Option<String>,
+    pub statement_id: Option<String>,
+    /// The row before the change, missing for inserts
+    pub old_values: Option<Value>,
+    /// The row after the change, missing for deletes
+    pub new_values: Option<Value>,
+    /// Columns whose value differs between `old_values` and `new_values`
+    pub changed_fields: Vec<String>,
+    pub changed_at: String,
+    /// Who was signed in, missing for changes made before accounts existed
+    pub user_id: Option<String>,
+    pub user_name: Option<String>,
+}
+
+fn parse_values(values: Option<String>) -> Option<Value> {
+    values.and_then(|values| serde_json::from_str(&values).ok())
+}
+
+fn changed_fields(old: Option<&Value>, new: Option<&Value>) -> Vec<String> {
+    let empty = Map::new();
+    let old = old.and_then(Value::as_object).unwrap_or(&empty);
+    let new = new.and_then(Value::as_object).unwrap_or(&empty);
+
+    // `updated_at` changes on every edit, which says nothing about what was edited
+    new.iter()
+        .filter(|(key, value)| key.as_str() != "updated_at" && old.get(*key) != Some(value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+impl From<AuditRow> for AuditEntry {
+    fn from(row: AuditRow) -> Self {
+        let old_values = parse_values(row.old_values);
+        let new_values = parse_values(row.new_values);
+        let changed_fields = match row.operation.as_str() {
+            "update" => changed_fields(old_values.as_ref(), new_values.as_ref()),
+            _ => Vec::new(),
+        };
+
+        Self {
+            id: row.id,
+            table_name: row.table_name,
+            row_id: row.row_id,
+            operation: row.operation,
+            patient_id: row.patient_id,
+            statement_id: row.statement_id,
+            old_values,
+            new_values,
+            changed_fields,
+            changed_at: row.changed_at,
+            user_id: row.user_id,
+            user_name: row.user_name,
+        }
+    }
+}
+
+// Payments removed along with their statement lose the patient lookup in
+// the trigger, so a patient's history also follows their statements
+const HISTORY_FILTERS: &str = r#"
+    WHERE
+        (?1 IS NULL OR patient_id = ?1 OR statement_id IN (
+            SELECT row_id FROM audit_log WHERE table_name = 'statements' AND patient_id = ?1
+        )) AND
+        (?2 IS NULL OR statement_id = ?2) AND
+        (?3 IS NULL OR table_name = ?3)
+"#;
+
+/// Changes recorded by the audit triggers, newest first.
+pub async fn history(pool: &SqlitePool, params: &HistoryParams) -> Result<PagedList<AuditEntry>> {
+    let patient_id = search_term(params.patient_id.as_deref());
+    let statement_id = search_term(params.statement_id.as_deref());
+    let table_name = search_term(params.table_name.as_deref());
+
+    let sql = format!(
+        r#"
+        SELECT
+            id, table_name, row_id, operation, patient_id, statement_id, old_values, new_values, changed_at, user_id,
+            (SELECT display_name FROM users WHERE users.id = audit_log.user_id) AS user_name
+        FROM audit_log
+        {HISTORY_FILTERS}
+        ORDER BY id DESC
+        LIMIT ?4 OFFSET ?5
+        "#
+    );
+
+    let rows = sqlx::query_as::<_, AuditRow>(&sql)
+        .bind(patient_id)
+        .bind(statement_id)
+        .bind(table_name)
+        .bind(params.paging.limit())
+        .bind(params.paging.offset())
+        .fetch_all(pool)
+        .await?;
+
+    let total: i64 =
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM audit_log {HISTORY_FILTERS}"))
+            .bind(patient_id)
+            .bind(statement_id)
+            .bind(table_name)
+            .fetch_one(pool)
+            .await?;
+
+    let entries = rows.into_iter().map(AuditEntry::from).collect();
+
+    Ok(PagedList::new(entries, &params.paging, total))
+}
+
+#[tauri::command]
+pub async fn get_history(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    params: Option<HistoryParams>,
+) -> Result<PagedList<AuditEntry>> {
+    state.require(Permission::ViewHistory)?;
+    history(&pool, &params.unwrap_or_default()).await
+}
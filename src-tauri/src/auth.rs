@@ -0,0 +1,235 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    error::{Error, Result},
+    repository::users::{self, AddUser, SaveUser, User},
+};
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Doctor,
+    Receptionist,
+    Accountant,
+}
+
+/// What a command needs the signed in user's role to allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Look up patients, statements, appointments and attachments
+    ViewRecords,
+    EditPatients,
+    DeletePatients,
+    /// Open and edit statements and their attachments
+    EditStatements,
+    DeleteStatements,
+    /// Record treatment sessions on a statement
+    EditSessions,
+    TakePayments,
+    EditPayments,
+    DeletePayments,
+    ManageAppointments,
+    /// Balances, aging and overpayment reports
+    ViewFinancials,
+    ViewHistory,
+    ManageRecycleBin,
+    /// Doctors and clinics
+    ManageDirectory,
+    ManageUsers,
+    ManageSettings,
+    ManageBackups,
+}
+
+impl Role {
+    pub fn allows(self, permission: Permission) -> bool {
+        use Permission::*;
+
+        match self {
+            Role::Admin => true,
+            Role::Doctor => matches!(
+                permission,
+                ViewRecords
+                    | EditPatients
+                    | EditStatements
+                    | EditSessions
+                    | ManageAppointments
+                    | ViewFinancials
+            ),
+            Role::Receptionist => matches!(
+                permission,
+                ViewRecords | EditPatients | EditStatements | TakePayments | ManageAppointments
+            ),
+            Role::Accountant => matches!(
+                permission,
+                ViewRecords
+                    | EditStatements
+                    | TakePayments
+                    | EditPayments
+                    | ViewFinancials
+                    | ViewHistory
+            ),
+        }
+    }
+}
+
+fn validate_password(password: &str) -> Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(Error::Validation(format!(
+            "Password must be at least {MIN_PASSWORD_LENGTH} characters"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Hashes a new password with Argon2id and a random salt.
+pub async fn hash_password(password: &str) -> Result<String> {
+    validate_password(password)?;
+
+    // Hashing is deliberately slow, keep it off the async workers
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|err| Error::Validation(format!("Password could not be hashed: {err}")))
+    })
+    .await
+    .map_err(|err| Error::Validation(format!("Password could not be hashed: {err}")))?
+}
+
+async fn verify_password(hash: String, password: &str) -> bool {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Checks a username and password against the active users.
+pub async fn authenticate(pool: &SqlitePool, username: &str, password: &str) -> Result<User> {
+    let Some((user, hash)) = users::find_credentials(pool, username).await? else {
+        return Err(Error::InvalidCredentials);
+    };
+
+    if !verify_password(hash, password).await {
+        return Err(Error::InvalidCredentials);
+    }
+
+    Ok(user)
+}
+
+/// Signs `user` in at this desk, so their changes are audited under their name.
+pub async fn start_session(pool: &SqlitePool, state: &AppState, user: User) -> Result<()> {
+    sqlx::query("INSERT OR REPLACE INTO app_session (id, user_id) VALUES (1, ?)")
+        .bind(&user.id)
+        .execute(pool)
+        .await?;
+
+    log::info!("Signed in as {} ({:?})", user.username, user.role);
+    *state.session.write().unwrap() = Some(user);
+
+    Ok(())
+}
+
+/// Signs out, also clearing a session left behind by a crash.
+pub async fn end_session(pool: &SqlitePool, state: &AppState) -> Result<()> {
+    sqlx::query("DELETE FROM app_session").execute(pool).await?;
+    *state.session.write().unwrap() = None;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionInfo {
+    pub user: Option<User>,
+    /// No accounts exist yet, the first one becomes the admin
+    pub needs_setup: bool,
+}
+
+#[tauri::command]
+pub async fn get_session(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<SessionInfo> {
+    Ok(SessionInfo {
+        user: state.current_user(),
+        needs_setup: users::count(&pool).await? == 0,
+    })
+}
+
+#[tauri::command]
+pub async fn login(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    username: String,
+    password: String,
+) -> Result<User> {
+    let user = authenticate(&pool, &username, &password).await?;
+    start_session(&pool, &state, user.clone()).await?;
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn logout(pool: State<'_, SqlitePool>, state: State<'_, AppState>) -> Result<()> {
+    end_session(&pool, &state).await
+}
+
+/// Creates the first account on a fresh install and signs it in.
+#[tauri::command]
+pub async fn create_first_admin(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    username: String,
+    display_name: String,
+    password: String,
+) -> Result<User> {
+    let user = users::create_first(
+        &pool,
+        &AddUser {
+            user: SaveUser {
+                username,
+                display_name,
+                role: Role::Admin,
+                active: true,
+            },
+            password,
+        },
+    )
+    .await?;
+    start_session(&pool, &state, user.clone()).await?;
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn change_password(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    current_password: String,
+    new_password: String,
+) -> Result<()> {
+    let user = state.current_user().ok_or(Error::SignedOut)?;
+    authenticate(&pool, &user.username, &current_password).await?;
+
+    users::set_password(&pool, &user.id, &new_password).await
+}
@@ -0,0 +1,903 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Datelike, Local, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, State};
+use tokio::fs;
+
+use super::{
+    local::checked_name, sealed_name, BackupEntry, BackupProgress, BackupProperties, BackupStage,
+    BackupStorage, BackupVerification, RemoteFile, RestoreKeys,
+};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    database,
+    encryption::{self, BackupKey, Key},
+    error::{Error, Result},
+    repository::attachments::attachments_dir,
+    sync::SyncScheduler,
+};
+
+const ARCHIVE_PREFIX: &str = "sgmc_archive_";
+const ARCHIVE_EXTENSION: &str = "tar";
+const MANIFEST_ENTRY: &str = "manifest.json";
+const ATTACHMENTS_ENTRY: &str = "attachments";
+const FORMAT_VERSION: u32 = 1;
+
+/// Describes an archive, stored as its last entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub format: u32,
+    pub created_time: String,
+    pub app_version: String,
+    pub schema_version: i64,
+    pub patient_count: i64,
+    /// Whether the entries are sealed with a backup passphrase
+    pub encrypted: bool,
+    /// The entry holding the gzipped database
+    pub database: String,
+    pub files: Vec<ArchiveFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveFile {
+    pub path: String,
+    pub size: u64,
+    /// Hex encoded SHA-256 of the entry as stored
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveSummary {
+    pub name: String,
+    /// Archive size in bytes
+    pub size: u64,
+    pub manifest: ArchiveManifest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveCheck {
+    pub name: String,
+    pub ok: bool,
+    pub problems: Vec<String>,
+    /// What the snapshot inside holds, once the checksums match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<BackupVerification>,
+}
+
+/// How many archives rotation keeps in a folder.
+///
+/// The newest archive of each of the last `keep_daily` days and of each of
+/// the last `keep_weekly` weeks survives. Both at 0 turns rotation off.
+#[derive(Debug, Clone, Copy)]
+pub struct Rotation {
+    pub keep_daily: u32,
+    pub keep_weekly: u32,
+}
+
+fn is_archive_name(name: &str) -> bool {
+    name.starts_with(ARCHIVE_PREFIX)
+        && Path::new(name).extension().and_then(|e| e.to_str()) == Some(ARCHIVE_EXTENSION)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn append(builder: &mut tar::Builder<File>, path: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    builder.append_data(&mut header, path, data)
+}
+
+/// Writes a `VACUUM INTO` snapshot and every attachment into one tar file
+/// in `dir`, followed by a manifest with their checksums.
+///
+/// Entries are stored the way [`super::create`] uploads them: attachments as
+/// they are on disk, or decrypted and sealed with `backup_key` when one is
+/// set. The archive is written under a temporary name and renamed when
+/// complete, so a pulled USB drive never leaves a half archive behind.
+pub async fn create<P>(
+    dir: &Path,
+    pool: &SqlitePool,
+    data_dir: &Path,
+    app_version: &str,
+    local: Option<&Key>,
+    backup_key: Option<&BackupKey>,
+    progress: P,
+) -> Result<ArchiveSummary>
+where
+    P: Fn(BackupProgress) + Send + 'static,
+{
+    fs::create_dir_all(dir).await?;
+
+    progress(BackupProgress::stage(BackupStage::Snapshot));
+    let snapshot = super::snapshot_database(pool, local, backup_key).await?;
+
+    let mut conn = pool.acquire().await?;
+    let schema_version = database::schema_version(&mut conn).await?;
+    drop(conn);
+
+    let patient_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM patients")
+        .fetch_one(pool)
+        .await?;
+
+    let now = Utc::now();
+    let name = format!(
+        "{ARCHIVE_PREFIX}{}.{ARCHIVE_EXTENSION}",
+        now.format("%Y-%m-%dT%H-%M-%S-%3fZ")
+    );
+    let database = if backup_key.is_some() {
+        sealed_name("database.db.gz")
+    } else {
+        "database.db.gz".to_string()
+    };
+
+    let manifest = ArchiveManifest {
+        format: FORMAT_VERSION,
+        created_time: now.to_rfc3339_opts(SecondsFormat::Millis, true),
+        app_version: app_version.to_string(),
+        schema_version,
+        patient_count,
+        encrypted: backup_key.is_some(),
+        database,
+        files: Vec::new(),
+    };
+
+    let path = dir.join(&name);
+    let attachments = attachments_dir(data_dir);
+    let local = local.cloned();
+    let backup_key = backup_key.cloned();
+
+    let manifest = tokio::task::spawn_blocking(move || {
+        write_archive(
+            &path,
+            snapshot,
+            &attachments,
+            manifest,
+            local.as_ref(),
+            backup_key.as_ref(),
+            &progress,
+        )
+    })
+    .await
+    .map_err(|err| Error::Backup(err.to_string()))??;
+
+    let size = fs::metadata(dir.join(&name)).await?.len();
+    Ok(ArchiveSummary {
+        name,
+        size,
+        manifest,
+    })
+}
+
+fn write_archive(
+    path: &Path,
+    snapshot: Vec<u8>,
+    attachments: &Path,
+    mut manifest: ArchiveManifest,
+    local: Option<&Key>,
+    backup_key: Option<&BackupKey>,
+    progress: &dyn Fn(BackupProgress),
+) -> Result<ArchiveManifest> {
+    let partial = path.with_extension("partial");
+    let written = (|| -> Result<()> {
+        let mut builder = tar::Builder::new(File::create(&partial)?);
+
+        let database = manifest.database.clone();
+        let mut add = |entry: String, data: &[u8]| -> Result<()> {
+            append(&mut builder, &entry, data)?;
+            manifest.files.push(ArchiveFile {
+                path: entry,
+                size: data.len() as u64,
+                sha256: sha256_hex(data),
+            });
+            Ok(())
+        };
+
+        add(database, &snapshot)?;
+        drop(snapshot);
+
+        let mut files = Vec::new();
+        if attachments.is_dir() {
+            for entry in std::fs::read_dir(attachments)? {
+                let entry = entry?;
+                if entry.file_type()?.is_file() {
+                    files.push(entry.path());
+                }
+            }
+        }
+        files.sort();
+
+        let total = files.len();
+        for (index, file) in files.into_iter().enumerate() {
+            progress(BackupProgress {
+                stage: BackupStage::Attachments,
+                current: index,
+                total,
+            });
+
+            let mut name = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let data = match backup_key {
+                Some(backup_key) => {
+                    name = sealed_name(&name);
+                    backup_key.seal(&encryption::open_file(local, std::fs::read(&file)?)?)?
+                }
+                None => std::fs::read(&file)?,
+            };
+            add(format!("{ATTACHMENTS_ENTRY}/{name}"), &data)?;
+        }
+
+        append(
+            &mut builder,
+            MANIFEST_ENTRY,
+            &serde_json::to_vec_pretty(&manifest)?,
+        )?;
+        builder.into_inner()?.sync_all()?;
+        Ok(())
+    })();
+
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(err);
+    }
+
+    std::fs::rename(&partial, path)?;
+    progress(BackupProgress::stage(BackupStage::Done));
+    Ok(manifest)
+}
+
+/// Where each entry's data starts in the archive and how long it is.
+type ArchiveIndex = BTreeMap<String, (u64, u64)>;
+
+fn read_index(path: &Path) -> Result<ArchiveIndex> {
+    let mut archive = tar::Archive::new(File::open(path)?);
+    let mut index = BTreeMap::new();
+    for entry in archive.entries_with_seek()? {
+        let entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        index.insert(name, (entry.raw_file_position(), entry.size()));
+    }
+
+    Ok(index)
+}
+
+fn read_entry(file: &mut File, (offset, size): (u64, u64)) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity(size as usize);
+    file.by_ref().take(size).read_to_end(&mut data)?;
+    if data.len() as u64 != size {
+        return Err(Error::Backup("Archive is truncated".to_string()));
+    }
+
+    Ok(data)
+}
+
+fn read_manifest(path: &Path, index: &ArchiveIndex) -> Result<ArchiveManifest> {
+    let position = *index.get(MANIFEST_ENTRY).ok_or_else(|| {
+        Error::Backup("Archive has no manifest, it may be incomplete".to_string())
+    })?;
+    let manifest: ArchiveManifest =
+        serde_json::from_slice(&read_entry(&mut File::open(path)?, position)?)?;
+
+    if manifest.format > FORMAT_VERSION {
+        return Err(Error::Backup(format!(
+            "Archive was made by a newer version of the app (format {})",
+            manifest.format
+        )));
+    }
+
+    Ok(manifest)
+}
+
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|err| Error::Backup(err.to_string()))?
+}
+
+/// Archives in `dir`, newest first. Unreadable ones are logged and skipped.
+pub async fn list(dir: &Path) -> Result<Vec<ArchiveSummary>> {
+    if !fs::try_exists(dir).await? {
+        return Ok(Vec::new());
+    }
+
+    let mut archives = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_archive_name(&name) || !entry.file_type().await?.is_file() {
+            continue;
+        }
+
+        let path = entry.path();
+        let size = entry.metadata().await?.len();
+        let manifest = blocking(move || read_manifest(&path, &read_index(&path)?)).await;
+        match manifest {
+            Ok(manifest) => archives.push(ArchiveSummary {
+                name,
+                size,
+                manifest,
+            }),
+            Err(err) => log::warn!("Skipping unreadable archive {}: {}", name, err),
+        }
+    }
+
+    archives.sort_by(|a, b| b.manifest.created_time.cmp(&a.manifest.created_time));
+    Ok(archives)
+}
+
+/// Re-reads every entry and compares it with the manifest checksums.
+pub async fn verify(dir: &Path, name: &str) -> Result<ArchiveCheck> {
+    let path = dir.join(checked_name(name)?);
+    if !fs::try_exists(&path).await? {
+        return Err(Error::NotFound("Archive"));
+    }
+
+    let problems = blocking(move || {
+        let index = read_index(&path)?;
+        let manifest = read_manifest(&path, &index)?;
+
+        let mut problems = Vec::new();
+        let mut file = File::open(&path)?;
+        let mut listed = HashSet::new();
+        for expected in &manifest.files {
+            listed.insert(expected.path.as_str());
+
+            let Some(&position) = index.get(&expected.path) else {
+                problems.push(format!("{} is missing", expected.path));
+                continue;
+            };
+            match read_entry(&mut file, position) {
+                Ok(data) if sha256_hex(&data) != expected.sha256 => {
+                    problems.push(format!("{} does not match its checksum", expected.path))
+                }
+                Ok(_) => {}
+                Err(err) => problems.push(format!("{}: {}", expected.path, err)),
+            }
+        }
+
+        if !listed.contains(manifest.database.as_str()) {
+            problems.push("The database is not listed in the manifest".to_string());
+        }
+        for extra in index.keys() {
+            if extra != MANIFEST_ENTRY && !listed.contains(extra.as_str()) {
+                problems.push(format!("{extra} is not listed in the manifest"));
+            }
+        }
+
+        Ok(problems)
+    })
+    .await?;
+
+    Ok(ArchiveCheck {
+        name: name.to_string(),
+        ok: problems.is_empty(),
+        problems,
+        backup: None,
+    })
+}
+
+/// Deletes archives in `dir` that fall outside `rotation`, returning their names.
+pub async fn rotate(dir: &Path, rotation: Rotation) -> Result<Vec<String>> {
+    if rotation.keep_daily == 0 && rotation.keep_weekly == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Newest first, so the first archive seen for a day or week is the one kept
+    let archives = list(dir).await?;
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    let mut removed = Vec::new();
+
+    for (position, archive) in archives.into_iter().enumerate() {
+        let Ok(created) = DateTime::parse_from_rfc3339(&archive.manifest.created_time) else {
+            continue;
+        };
+        let created = created.with_timezone(&Local);
+        let day = created.date_naive();
+        let week = created.iso_week();
+
+        let mut keep = position == 0;
+        if !days.contains(&day) && days.len() < rotation.keep_daily as usize {
+            days.insert(day);
+            keep = true;
+        }
+        if !weeks.contains(&week) && weeks.len() < rotation.keep_weekly as usize {
+            weeks.insert(week);
+            keep = true;
+        }
+
+        if !keep {
+            fs::remove_file(dir.join(&archive.name)).await?;
+            removed.push(archive.name);
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Reads one archive through [`BackupStorage`] so [`super::restore`] can
+/// restore from it like from any other backend.
+pub struct ArchiveReader {
+    path: PathBuf,
+    manifest: ArchiveManifest,
+    index: ArchiveIndex,
+}
+
+impl ArchiveReader {
+    pub async fn open(dir: &Path, name: &str) -> Result<Self> {
+        let path = dir.join(checked_name(name)?);
+        if !fs::try_exists(&path).await? {
+            return Err(Error::NotFound("Archive"));
+        }
+
+        let read_path = path.clone();
+        let (index, manifest) = blocking(move || {
+            let index = read_index(&read_path)?;
+            let manifest = read_manifest(&read_path, &index)?;
+            Ok((index, manifest))
+        })
+        .await?;
+
+        Ok(Self {
+            path,
+            manifest,
+            index,
+        })
+    }
+
+    async fn read(&self, entry: &str) -> Result<Vec<u8>> {
+        let position = *self
+            .index
+            .get(entry)
+            .ok_or_else(|| Error::Backup(format!("{entry} is missing from the archive")))?;
+        let path = self.path.clone();
+        blocking(move || read_entry(&mut File::open(path)?, position)).await
+    }
+}
+
+fn read_only() -> Error {
+    Error::Backup("Archives cannot be changed once written".to_string())
+}
+
+impl BackupStorage for ArchiveReader {
+    async fn upload_snapshot(
+        &self,
+        _name: &str,
+        _data: Vec<u8>,
+        _properties: &BackupProperties,
+    ) -> Result<BackupEntry> {
+        Err(read_only())
+    }
+
+    async fn list_snapshots(&self) -> Result<Vec<BackupEntry>> {
+        Ok(vec![BackupEntry {
+            id: self.manifest.database.clone(),
+            name: self.manifest.database.clone(),
+            created_time: self.manifest.created_time.clone(),
+            properties: BackupProperties {
+                app_version: Some(self.manifest.app_version.clone()),
+                patient_count: Some(self.manifest.patient_count.to_string()),
+                encrypted: self.manifest.encrypted.then(|| true.to_string()),
+            },
+        }])
+    }
+
+    async fn download_snapshot(&self, id: &str) -> Result<Vec<u8>> {
+        if id != self.manifest.database {
+            return Err(Error::NotFound("Backup"));
+        }
+
+        self.read(id).await
+    }
+
+    async fn list_attachments(&self) -> Result<Vec<RemoteFile>> {
+        let prefix = format!("{ATTACHMENTS_ENTRY}/");
+        // Entry names come from the archive, a crafted one must not reach outside the folder
+        self.index
+            .keys()
+            .filter_map(|entry| Some((entry, entry.strip_prefix(&prefix)?)))
+            .map(|(entry, name)| {
+                Ok(RemoteFile {
+                    id: entry.clone(),
+                    name: checked_name(name)?.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    async fn upload_attachment(&self, _name: &str, _data: Vec<u8>) -> Result<()> {
+        Err(read_only())
+    }
+
+    async fn download_attachment(&self, file: &RemoteFile) -> Result<Vec<u8>> {
+        self.read(&file.id).await
+    }
+}
+
+fn rotation(state: &AppState) -> Rotation {
+    Rotation {
+        keep_daily: state.config.archive_keep_daily,
+        keep_weekly: state.config.archive_keep_weekly,
+    }
+}
+
+/// Archives the database and attachments into `folder`, then rotates it.
+#[tauri::command]
+pub async fn create_archive(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    folder: String,
+) -> Result<ArchiveSummary> {
+    state.require(Permission::ManageBackups)?;
+    if folder.trim().is_empty() {
+        return Err(Error::Validation("Backup folder is required".to_string()));
+    }
+    let _guard = scheduler
+        .try_begin()
+        .ok_or_else(|| Error::Backup("A backup is already running".to_string()))?;
+    let dir = PathBuf::from(&folder);
+
+    let data_dir = PathBuf::from(&state.config.data_dir);
+    let version = app.package_info().version.to_string();
+    let local = state.encryption_key();
+    let backup_key = state.backup_key().await?;
+
+    let emitter = app.clone();
+    let summary = create(
+        &dir,
+        &pool,
+        &data_dir,
+        &version,
+        local.as_ref(),
+        backup_key.as_ref(),
+        move |progress| {
+            let _ = emitter.emit("backup-progress", progress);
+        },
+    )
+    .await?;
+
+    // The new archive is safe either way, a failed rotation only leaves extra files
+    match rotate(&dir, rotation(&state)).await {
+        Ok(removed) if !removed.is_empty() => {
+            log::info!(
+                "Rotated out {} archives: {}",
+                removed.len(),
+                removed.join(", ")
+            )
+        }
+        Ok(_) => {}
+        Err(err) => log::warn!("Archive rotation failed: {}", err),
+    }
+
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn list_archives(
+    state: State<'_, AppState>,
+    folder: String,
+) -> Result<Vec<ArchiveSummary>> {
+    state.require(Permission::ManageBackups)?;
+    list(Path::new(&folder)).await
+}
+
+/// Checks the archive against its manifest, then the snapshot inside like
+/// [`super::verify_backup`].
+#[tauri::command]
+pub async fn verify_archive(
+    state: State<'_, AppState>,
+    folder: String,
+    name: String,
+    passphrase: Option<String>,
+) -> Result<ArchiveCheck> {
+    state.require(Permission::ManageBackups)?;
+    let dir = Path::new(&folder);
+
+    let mut check = verify(dir, &name).await?;
+    if !check.ok {
+        return Ok(check);
+    }
+
+    let reader = ArchiveReader::open(dir, &name).await?;
+    let backup = super::verify(
+        &reader,
+        &reader.manifest.database,
+        Path::new(&state.config.data_dir),
+        RestoreKeys {
+            local: state.encryption_key().as_ref(),
+            passphrase: passphrase.as_deref(),
+        },
+    )
+    .await?;
+
+    check.ok = backup.restorable;
+    check.problems.extend(backup.problems.iter().cloned());
+    check.backup = Some(backup);
+    Ok(check)
+}
+
+/// Restores an archive after checking it against its manifest.
+#[tauri::command]
+pub async fn restore_archive(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    folder: String,
+    name: String,
+    passphrase: Option<String>,
+) -> Result<()> {
+    state.require(Permission::ManageBackups)?;
+    // No backup may read the database while it is being replaced
+    let _guard = scheduler
+        .try_begin()
+        .ok_or_else(|| Error::Backup("A backup is already running".to_string()))?;
+    let dir = Path::new(&folder);
+
+    let check = verify(dir, &name).await?;
+    if !check.ok {
+        return Err(Error::Backup(format!(
+            "Archive is damaged: {}",
+            check.problems.join("; ")
+        )));
+    }
+
+    let reader = ArchiveReader::open(dir, &name).await?;
+    let database = reader.manifest.database.clone();
+    let config = &state.config;
+
+    super::restore(
+        &reader,
+        &database,
+        &pool,
+        Path::new(&config.db_path),
+        Path::new(&config.data_dir),
+        RestoreKeys {
+            local: state.encryption_key().as_ref(),
+            passphrase: passphrase.as_deref(),
+        },
+        super::emit_progress(&app),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::repository::new_id;
+
+    struct Folder(PathBuf);
+
+    impl Folder {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("sgmc-archive-{}", new_id()));
+            std::fs::create_dir_all(root.join("archives")).unwrap();
+            std::fs::create_dir_all(root.join("attachments")).unwrap();
+            Self(root)
+        }
+
+        fn archives(&self) -> PathBuf {
+            self.0.join("archives")
+        }
+
+        /// Writes an archive holding a fake snapshot and the attachments folder.
+        fn archive_at(&self, created: DateTime<Local>) -> String {
+            let created = created.with_timezone(&Utc);
+            let name = format!(
+                "{ARCHIVE_PREFIX}{}.{ARCHIVE_EXTENSION}",
+                created.format("%Y-%m-%dT%H-%M-%S-%3fZ")
+            );
+            let manifest = ArchiveManifest {
+                format: FORMAT_VERSION,
+                created_time: created.to_rfc3339_opts(SecondsFormat::Millis, true),
+                app_version: "test".to_string(),
+                schema_version: 1,
+                patient_count: 0,
+                encrypted: false,
+                database: "database.db.gz".to_string(),
+                files: Vec::new(),
+            };
+
+            write_archive(
+                &self.archives().join(&name),
+                b"snapshot".to_vec(),
+                &self.0.join("attachments"),
+                manifest,
+                None,
+                None,
+                &|_| {},
+            )
+            .unwrap();
+            name
+        }
+
+        /// A hand made tar with the given entries, for archives this app would never write.
+        fn raw_archive(&self, entries: &[(&str, &[u8])]) -> String {
+            let name = format!("{ARCHIVE_PREFIX}raw-{}.{ARCHIVE_EXTENSION}", new_id());
+            let file = File::create(self.archives().join(&name)).unwrap();
+            let mut builder = tar::Builder::new(file);
+            for (path, data) in entries {
+                append(&mut builder, path, data).unwrap();
+            }
+            builder.into_inner().unwrap();
+            name
+        }
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn at(month: u32, day: u32, hour: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, month, day, hour, 0, 0)
+            .single()
+            .unwrap()
+    }
+
+    async fn names(dir: &Path) -> Vec<String> {
+        list(dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|archive| archive.name)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn writes_every_entry_into_the_manifest() {
+        let folder = Folder::new();
+        std::fs::write(folder.0.join("attachments").join("scan.jpg"), "scan").unwrap();
+
+        let name = folder.archive_at(at(3, 18, 12));
+
+        let archives = list(&folder.archives()).await.unwrap();
+        assert_eq!(archives.len(), 1);
+        let files: Vec<_> = archives[0]
+            .manifest
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), file.sha256.clone()))
+            .collect();
+        assert_eq!(
+            files,
+            [
+                ("database.db.gz", sha256_hex(b"snapshot")),
+                ("attachments/scan.jpg", sha256_hex(b"scan")),
+            ]
+        );
+        assert!(!folder.archives().join(&name).with_extension("partial").exists());
+
+        let check = verify(&folder.archives(), &name).await.unwrap();
+        assert!(check.ok, "{:?}", check.problems);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_newest_archive_of_each_day_and_week() {
+        let folder = Folder::new();
+        // 2026-03-18 is a Wednesday, the 16th opens its week
+        let newest = folder.archive_at(at(3, 18, 12));
+        let same_day = folder.archive_at(at(3, 18, 9));
+        let day_before = folder.archive_at(at(3, 17, 12));
+        let third_day = folder.archive_at(at(3, 16, 12));
+        let week_before = folder.archive_at(at(3, 13, 12));
+        let same_week = folder.archive_at(at(3, 12, 12));
+        let third_week = folder.archive_at(at(3, 5, 12));
+
+        let rotation = Rotation {
+            keep_daily: 2,
+            keep_weekly: 2,
+        };
+        let mut removed = rotate(&folder.archives(), rotation).await.unwrap();
+        removed.sort();
+        let mut expected = vec![same_day, third_day, same_week, third_week];
+        expected.sort();
+        assert_eq!(removed, expected);
+
+        assert_eq!(
+            names(&folder.archives()).await,
+            [newest, day_before, week_before]
+        );
+    }
+
+    #[tokio::test]
+    async fn never_rotates_out_the_newest_archive() {
+        let folder = Folder::new();
+        let newest = folder.archive_at(at(1, 2, 12));
+        folder.archive_at(at(1, 2, 8));
+        folder.archive_at(at(1, 1, 8));
+
+        let rotation = Rotation {
+            keep_daily: 0,
+            keep_weekly: 1,
+        };
+        rotate(&folder.archives(), rotation).await.unwrap();
+        assert_eq!(names(&folder.archives()).await, std::slice::from_ref(&newest));
+
+        // Nothing else is left to remove
+        assert!(rotate(&folder.archives(), rotation).await.unwrap().is_empty());
+        assert_eq!(names(&folder.archives()).await, [newest]);
+    }
+
+    #[tokio::test]
+    async fn leaves_archives_alone_with_rotation_off() {
+        let folder = Folder::new();
+        folder.archive_at(at(1, 2, 12));
+        folder.archive_at(at(1, 1, 12));
+
+        let rotation = Rotation {
+            keep_daily: 0,
+            keep_weekly: 0,
+        };
+        assert!(rotate(&folder.archives(), rotation).await.unwrap().is_empty());
+        assert_eq!(names(&folder.archives()).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_an_entry_that_no_longer_matches_its_checksum() {
+        let folder = Folder::new();
+        let name = folder.archive_at(at(3, 18, 12));
+        let path = folder.archives().join(&name);
+
+        let (offset, _) = read_index(&path).unwrap()["database.db.gz"];
+        let mut archive = std::fs::read(&path).unwrap();
+        archive[offset as usize] ^= 0xff;
+        std::fs::write(&path, archive).unwrap();
+
+        let check = verify(&folder.archives(), &name).await.unwrap();
+        assert!(!check.ok);
+        assert_eq!(check.problems, ["database.db.gz does not match its checksum"]);
+    }
+
+    #[tokio::test]
+    async fn refuses_archives_without_a_readable_manifest() {
+        let folder = Folder::new();
+        let missing = folder.raw_archive(&[("database.db.gz", b"snapshot")]);
+        let garbled = folder.raw_archive(&[
+            ("database.db.gz", b"snapshot"),
+            (MANIFEST_ENTRY, b"{\"format\": 1, \"files\": ["),
+        ]);
+
+        assert!(matches!(
+            verify(&folder.archives(), &missing).await,
+            Err(Error::Backup(_))
+        ));
+        assert!(matches!(
+            verify(&folder.archives(), &garbled).await,
+            Err(Error::Json(_))
+        ));
+
+        // Listing and rotation skip them rather than fail
+        assert!(list(&folder.archives()).await.unwrap().is_empty());
+        let rotation = Rotation {
+            keep_daily: 1,
+            keep_weekly: 0,
+        };
+        assert!(rotate(&folder.archives(), rotation).await.unwrap().is_empty());
+    }
+}
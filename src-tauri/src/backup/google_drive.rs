@@ -0,0 +1,321 @@
+use std::env;
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use super::{BackupEntry, BackupProperties, BackupStorage, RemoteFile};
+use crate::{
+    error::{Error, Result},
+    repository::attachments::now_millis,
+};
+
+const TOKEN_ENDPOINT: &str = "https://oauth2.googleapis.com/token";
+const DRIVE_API_URL: &str = "https://www.googleapis.com/drive/v3/files";
+const UPLOAD_API_URL: &str = "https://www.googleapis.com/upload/drive/v3/files";
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+/// Written by the sign-in flow in the webview; shared through the store plugin.
+const STORE_PATH: &str = "auth_store.bin";
+const ROOT_FOLDER: &str = "SGMC Backups";
+const ATTACHMENTS_FOLDER: &str = "attachments";
+const MULTIPART_BOUNDARY: &str = "sgmc_backup_boundary";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileList<T> {
+    #[serde(default = "Vec::new")]
+    files: Vec<T>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: i64,
+}
+
+pub struct GoogleDriveStorage {
+    client: reqwest::Client,
+    token: String,
+    root_folder_id: String,
+    attachments_folder_id: String,
+}
+
+// Injected at build time for releases, falling back to the environment in development
+fn client_credentials() -> Result<(String, String)> {
+    let client_id = option_env!("VITE_GOOGLE_CLIENT_ID")
+        .map(str::to_string)
+        .or_else(|| env::var("VITE_GOOGLE_CLIENT_ID").ok());
+    let client_secret = option_env!("VITE_GOOGLE_CLIENT_SECRET")
+        .map(str::to_string)
+        .or_else(|| env::var("VITE_GOOGLE_CLIENT_SECRET").ok());
+
+    match (client_id, client_secret) {
+        (Some(id), Some(secret)) => Ok((id, secret)),
+        _ => Err(Error::Backup(
+            "Google client credentials are not configured".to_string(),
+        )),
+    }
+}
+
+/// Returns a valid access token, refreshing it the same way the webview does.
+pub async fn access_token(app: &AppHandle, client: &reqwest::Client) -> Result<String> {
+    let store = app
+        .store(STORE_PATH)
+        .map_err(|err| Error::Backup(err.to_string()))?;
+
+    let access_token = store
+        .get("access_token")
+        .and_then(|v| v.as_str().map(str::to_string));
+    let expiry = store.get("token_expiry").and_then(|v| v.as_i64());
+
+    if let (Some(token), Some(expiry)) = (access_token, expiry) {
+        if now_millis() < expiry - 60_000 {
+            return Ok(token);
+        }
+    }
+
+    let refresh_token = store
+        .get("refresh_token")
+        .and_then(|v| v.as_str().map(str::to_string))
+        .ok_or(Error::Unauthenticated)?;
+
+    let (client_id, client_secret) = client_credentials()?;
+    let response = client
+        .post(TOKEN_ENDPOINT)
+        .form(&[
+            ("client_id", client_id.as_str()),
+            ("client_secret", client_secret.as_str()),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token.as_str()),
+        ])
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        store.delete("access_token");
+        store.delete("refresh_token");
+        let _ = store.save();
+        return Err(Error::Unauthenticated);
+    }
+
+    let token: TokenResponse = response.json().await?;
+    store.set("access_token", json!(token.access_token));
+    store.set(
+        "token_expiry",
+        json!(now_millis() + token.expires_in * 1000),
+    );
+    store.save().map_err(|err| Error::Backup(err.to_string()))?;
+
+    Ok(token.access_token)
+}
+
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::Backup(format!(
+        "Google Drive request failed ({status}): {body}"
+    )))
+}
+
+impl GoogleDriveStorage {
+    pub async fn connect(app: &AppHandle) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let token = access_token(app, &client).await?;
+
+        let mut storage = Self {
+            client,
+            token,
+            root_folder_id: String::new(),
+            attachments_folder_id: String::new(),
+        };
+
+        storage.root_folder_id = storage.get_or_create_folder(ROOT_FOLDER, None).await?;
+        storage.attachments_folder_id = storage
+            .get_or_create_folder(ATTACHMENTS_FOLDER, Some(&storage.root_folder_id))
+            .await?;
+
+        Ok(storage)
+    }
+
+    async fn list_files<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        fields: &str,
+        order_by: Option<&str>,
+    ) -> Result<Vec<T>> {
+        let mut files = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut params = vec![
+                ("q", query.to_string()),
+                ("fields", format!("nextPageToken, files({fields})")),
+                ("pageSize", "1000".to_string()),
+            ];
+            if let Some(order_by) = order_by {
+                params.push(("orderBy", order_by.to_string()));
+            }
+            if let Some(page_token) = page_token.take() {
+                params.push(("pageToken", page_token));
+            }
+
+            let response = self
+                .client
+                .get(DRIVE_API_URL)
+                .bearer_auth(&self.token)
+                .query(&params)
+                .send()
+                .await?;
+            let page: FileList<T> = check(response).await?.json().await?;
+
+            files.extend(page.files);
+            match page.next_page_token {
+                Some(next) => page_token = Some(next),
+                None => break,
+            }
+        }
+
+        Ok(files)
+    }
+
+    async fn get_or_create_folder(&self, name: &str, parent_id: Option<&str>) -> Result<String> {
+        let mut query =
+            format!("name = '{name}' and mimeType = '{FOLDER_MIME_TYPE}' and trashed = false");
+        if let Some(parent_id) = parent_id {
+            query.push_str(&format!(" and '{parent_id}' in parents"));
+        }
+
+        let existing: Vec<RemoteFile> = self.list_files(&query, "id, name", None).await?;
+        if let Some(folder) = existing.into_iter().next() {
+            return Ok(folder.id);
+        }
+
+        let mut metadata = json!({ "name": name, "mimeType": FOLDER_MIME_TYPE });
+        if let Some(parent_id) = parent_id {
+            metadata["parents"] = json!([parent_id]);
+        }
+
+        let response = self
+            .client
+            .post(DRIVE_API_URL)
+            .bearer_auth(&self.token)
+            .json(&metadata)
+            .send()
+            .await?;
+        let folder: RemoteFile = check(response).await?.json().await?;
+
+        Ok(folder.id)
+    }
+
+    /// Uploads metadata and content in one `multipart/related` request.
+    async fn upload<T: DeserializeOwned>(
+        &self,
+        metadata: serde_json::Value,
+        data: Vec<u8>,
+        fields: &str,
+    ) -> Result<T> {
+        let mut body = Vec::with_capacity(data.len() + 512);
+        body.extend_from_slice(
+            format!(
+                "--{MULTIPART_BOUNDARY}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{metadata}\r\n--{MULTIPART_BOUNDARY}\r\nContent-Type: application/octet-stream\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&data);
+        body.extend_from_slice(format!("\r\n--{MULTIPART_BOUNDARY}--").as_bytes());
+
+        let response = self
+            .client
+            .post(UPLOAD_API_URL)
+            .bearer_auth(&self.token)
+            .query(&[("uploadType", "multipart"), ("fields", fields)])
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                format!("multipart/related; boundary={MULTIPART_BOUNDARY}"),
+            )
+            .body(body)
+            .send()
+            .await?;
+
+        Ok(check(response).await?.json().await?)
+    }
+
+    async fn download(&self, file_id: &str) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(format!("{DRIVE_API_URL}/{file_id}"))
+            .bearer_auth(&self.token)
+            .query(&[("alt", "media")])
+            .send()
+            .await?;
+
+        Ok(check(response).await?.bytes().await?.to_vec())
+    }
+}
+
+impl BackupStorage for GoogleDriveStorage {
+    async fn upload_snapshot(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+        properties: &BackupProperties,
+    ) -> Result<BackupEntry> {
+        let metadata = json!({
+            "name": name,
+            "parents": [self.root_folder_id],
+            "properties": properties,
+        });
+
+        self.upload(metadata, data, "id, name, createdTime, properties")
+            .await
+    }
+
+    async fn list_snapshots(&self) -> Result<Vec<BackupEntry>> {
+        // The attachments folder lives beside the snapshots, so skip folders
+        let query = format!(
+            "'{}' in parents and trashed = false and mimeType != '{FOLDER_MIME_TYPE}'",
+            self.root_folder_id
+        );
+
+        self.list_files(
+            &query,
+            "id, name, createdTime, properties",
+            Some("createdTime desc"),
+        )
+        .await
+    }
+
+    async fn download_snapshot(&self, id: &str) -> Result<Vec<u8>> {
+        self.download(id).await
+    }
+
+    async fn list_attachments(&self) -> Result<Vec<RemoteFile>> {
+        let query = format!(
+            "'{}' in parents and trashed = false",
+            self.attachments_folder_id
+        );
+
+        self.list_files(&query, "id, name", None).await
+    }
+
+    async fn upload_attachment(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        let metadata = json!({
+            "name": name,
+            "parents": [self.attachments_folder_id],
+        });
+
+        let _: RemoteFile = self.upload(metadata, data, "id, name").await?;
+        Ok(())
+    }
+
+    async fn download_attachment(&self, file: &RemoteFile) -> Result<Vec<u8>> {
+        self.download(&file.id).await
+    }
+}
@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{SecondsFormat, Utc};
+use tokio::fs;
+
+use super::{BackupEntry, BackupProperties, BackupStorage, RemoteFile};
+use crate::error::{Error, Result};
+
+const METADATA_EXTENSION: &str = "json";
+
+/// Keeps backups in a plain folder, e.g. a USB drive or a network share.
+///
+/// Layout: `snapshots/<name>` with a `<name>.json` entry beside it, and
+/// `attachments/<file>` mirroring the attachments folder.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        if root.as_os_str().is_empty() {
+            return Err(Error::Validation("Backup folder is required".to_string()));
+        }
+
+        Ok(Self { root })
+    }
+
+    fn snapshots_dir(&self) -> PathBuf {
+        self.root.join("snapshots")
+    }
+
+    fn attachments_dir(&self) -> PathBuf {
+        self.root.join("attachments")
+    }
+}
+
+// Ids come from the webview, so never let them walk out of the backup folder
+pub(super) fn checked_name(name: &str) -> Result<&str> {
+    if Path::new(name).file_name().and_then(|n| n.to_str()) != Some(name) {
+        return Err(Error::Validation(format!(
+            "Invalid backup file name: {name}"
+        )));
+    }
+
+    Ok(name)
+}
+
+fn metadata_path(snapshot: &Path) -> PathBuf {
+    let mut path = snapshot.as_os_str().to_owned();
+    path.push(".");
+    path.push(METADATA_EXTENSION);
+    PathBuf::from(path)
+}
+
+impl BackupStorage for LocalStorage {
+    async fn upload_snapshot(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+        properties: &BackupProperties,
+    ) -> Result<BackupEntry> {
+        let name = checked_name(name)?;
+        let dir = self.snapshots_dir();
+        fs::create_dir_all(&dir).await?;
+
+        let entry = BackupEntry {
+            id: name.to_string(),
+            name: name.to_string(),
+            created_time: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            properties: properties.clone(),
+        };
+
+        let path = dir.join(name);
+        fs::write(&path, data).await?;
+        fs::write(metadata_path(&path), serde_json::to_vec_pretty(&entry)?).await?;
+
+        Ok(entry)
+    }
+
+    async fn list_snapshots(&self) -> Result<Vec<BackupEntry>> {
+        let dir = self.snapshots_dir();
+        if !fs::try_exists(&dir).await? {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(METADATA_EXTENSION) {
+                continue;
+            }
+
+            match serde_json::from_slice::<BackupEntry>(&fs::read(&path).await?) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(err) => log::warn!(
+                    "Skipping unreadable backup entry {}: {}",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+
+        snapshots.sort_by(|a, b| b.created_time.cmp(&a.created_time));
+        Ok(snapshots)
+    }
+
+    async fn download_snapshot(&self, id: &str) -> Result<Vec<u8>> {
+        let path = self.snapshots_dir().join(checked_name(id)?);
+        if !fs::try_exists(&path).await? {
+            return Err(Error::NotFound("Backup"));
+        }
+
+        Ok(fs::read(path).await?)
+    }
+
+    async fn list_attachments(&self) -> Result<Vec<RemoteFile>> {
+        let dir = self.attachments_dir();
+        if !fs::try_exists(&dir).await? {
+            return Ok(Vec::new());
+        }
+
+        let mut files = Vec::new();
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                files.push(RemoteFile {
+                    id: name.clone(),
+                    name,
+                });
+            }
+        }
+
+        Ok(files)
+    }
+
+    async fn upload_attachment(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        let dir = self.attachments_dir();
+        fs::create_dir_all(&dir).await?;
+        fs::write(dir.join(checked_name(name)?), data).await?;
+
+        Ok(())
+    }
+
+    async fn download_attachment(&self, file: &RemoteFile) -> Result<Vec<u8>> {
+        Ok(fs::read(self.attachments_dir().join(checked_name(&file.id)?)).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_name_keeps_names_inside_the_folder() {
+        assert_eq!(
+            checked_name("sgmc_backup_2026-01-01.db.gz").unwrap(),
+            "sgmc_backup_2026-01-01.db.gz"
+        );
+
+        for name in ["../escape.db", "..", "nested/file.db", "/etc/passwd", ""] {
+            assert!(
+                matches!(checked_name(name), Err(Error::Validation(_))),
+                "{name:?} was accepted"
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn checked_name_rejects_windows_paths() {
+        for name in [r"..\escape.db", r"C:\Windows\win.ini", r"\\server\share"] {
+            assert!(checked_name(name).is_err(), "{name:?} was accepted");
+        }
+    }
+}
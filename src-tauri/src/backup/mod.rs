@@ -0,0 +1,1212 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    future::Future,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, SqlitePool};
+use tauri::{AppHandle, Emitter, State};
+use tokio::fs;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    encryption::{self, BackupKey, BackupOpener, Key},
+    error::{Error, Result},
+    repository::attachments::attachments_dir,
+    sync::SyncScheduler,
+};
+
+pub mod archive;
+pub mod google_drive;
+pub mod local;
+pub mod wal;
+
+pub use google_drive::GoogleDriveStorage;
+pub use local::LocalStorage;
+
+use local::checked_name;
+
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+// Added to the name of everything sealed with the backup key, so plain
+// copies made before backup encryption was enabled are uploaded again
+const SEALED_SUFFIX: &str = "enc";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupProperties {
+    pub app_version: Option<String>,
+    pub patient_count: Option<String>,
+    /// `"true"` when the snapshot is sealed with a backup passphrase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted: Option<String>,
+}
+
+/// A database snapshot stored in a backend, shaped like a Drive file resource.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub id: String,
+    pub name: String,
+    pub created_time: String,
+    #[serde(default)]
+    pub properties: BackupProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemoteFile {
+    pub id: String,
+    pub name: String,
+}
+
+/// Where snapshots and attachment copies are kept.
+///
+/// Snapshots are whole gzipped databases; attachments are mirrored file by
+/// file under their on-disk name so later backups only upload new files.
+pub trait BackupStorage {
+    fn upload_snapshot(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+        properties: &BackupProperties,
+    ) -> impl Future<Output = Result<BackupEntry>> + Send;
+
+    /// Newest first.
+    fn list_snapshots(&self) -> impl Future<Output = Result<Vec<BackupEntry>>> + Send;
+
+    fn download_snapshot(&self, id: &str) -> impl Future<Output = Result<Vec<u8>>> + Send;
+
+    fn list_attachments(&self) -> impl Future<Output = Result<Vec<RemoteFile>>> + Send;
+
+    fn upload_attachment(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    fn download_attachment(
+        &self,
+        file: &RemoteFile,
+    ) -> impl Future<Output = Result<Vec<u8>>> + Send;
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupStage {
+    Attachments,
+    Snapshot,
+    Uploading,
+    Downloading,
+    Restoring,
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    pub stage: BackupStage,
+    pub current: usize,
+    pub total: usize,
+}
+
+impl BackupProgress {
+    fn stage(stage: BackupStage) -> Self {
+        Self {
+            stage,
+            current: 0,
+            total: 0,
+        }
+    }
+}
+
+/// Uploads new attachments, then a compressed snapshot of the database.
+///
+/// With a `backup_key` both are decrypted with the database key `local` and
+/// sealed with it instead, so they can be restored with the backup
+/// passphrase alone.
+pub async fn create<S, P>(
+    storage: &S,
+    pool: &SqlitePool,
+    data_dir: &Path,
+    app_version: &str,
+    local: Option<&Key>,
+    backup_key: Option<&BackupKey>,
+    progress: P,
+) -> Result<BackupEntry>
+where
+    S: BackupStorage,
+    P: Fn(BackupProgress),
+{
+    // Attachments are best effort, a failed upload is retried by the next backup
+    if let Err(err) = sync_attachments(storage, data_dir, local, backup_key, &progress).await {
+        log::warn!("Attachment sync failed: {}", err);
+    }
+
+    progress(BackupProgress::stage(BackupStage::Snapshot));
+    let snapshot = snapshot_database(pool, local, backup_key).await?;
+
+    let patient_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM patients")
+        .fetch_one(pool)
+        .await?;
+
+    let properties = BackupProperties {
+        app_version: Some(app_version.to_string()),
+        patient_count: Some(patient_count.to_string()),
+        encrypted: backup_key.map(|_| true.to_string()),
+    };
+
+    let mut name = format!(
+        "sgmc_backup_{}.db.gz",
+        chrono::Utc::now().format("%Y-%m-%dT%H-%M-%S-%3fZ")
+    );
+    if backup_key.is_some() {
+        name = sealed_name(&name);
+    }
+
+    progress(BackupProgress::stage(BackupStage::Uploading));
+    let entry = storage
+        .upload_snapshot(&name, snapshot, &properties)
+        .await?;
+
+    progress(BackupProgress::stage(BackupStage::Done));
+    Ok(entry)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestoreKeys<'a> {
+    /// The database key, restored files are encrypted with it
+    pub local: Option<&'a Key>,
+    /// Opens backups sealed with a backup passphrase
+    pub passphrase: Option<&'a str>,
+}
+
+/// What a snapshot holds and whether it can replace the live database.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupVerification {
+    pub backup_id: String,
+    /// `PRAGMA integrity_check` and the foreign key check both passed
+    pub integrity_ok: bool,
+    pub schema_version: i64,
+    /// The newest schema this build knows, older snapshots are migrated on the next launch
+    pub latest_schema_version: i64,
+    pub patient_count: i64,
+    pub attachment_count: usize,
+    /// Files the snapshot refers to that are neither in the backup nor on this machine
+    pub missing_attachments: Vec<String>,
+    pub problems: Vec<String>,
+    /// False when the snapshot is damaged or too new for this build
+    pub restorable: bool,
+}
+
+/// Downloads the snapshot and checks it without touching live data.
+///
+/// The copy is opened read-only from a temporary file and removed afterwards.
+pub async fn verify<S>(
+    storage: &S,
+    backup_id: &str,
+    data_dir: &Path,
+    keys: RestoreKeys<'_>,
+) -> Result<BackupVerification>
+where
+    S: BackupStorage,
+{
+    let mut opener = keys.passphrase.map(BackupOpener::new);
+    let temp_path =
+        std::env::temp_dir().join(format!("temp_verify_{}.db", uuid::Uuid::new_v4().simple()));
+
+    let verification = async {
+        let source_key =
+            download_database(storage, backup_id, keys.local, opener.as_mut(), &temp_path).await?;
+        inspect(storage, backup_id, &temp_path, source_key, data_dir).await
+    }
+    .await;
+    let _ = fs::remove_file(&temp_path).await;
+
+    verification
+}
+
+/// Replaces the database file with the snapshot, then downloads missing attachments.
+///
+/// The snapshot is checked with [`verify`]'s checks first and refused if it
+/// would not open. With encryption on, a plain snapshot is encrypted with
+/// the database key on the way in and an encrypted one has to open with it.
+/// The pool is closed before the file is swapped, so the app has to be
+/// relaunched afterwards.
+pub async fn restore<S, P>(
+    storage: &S,
+    backup_id: &str,
+    pool: &SqlitePool,
+    db_path: &Path,
+    data_dir: &Path,
+    keys: RestoreKeys<'_>,
+    progress: P,
+) -> Result<()>
+where
+    S: BackupStorage,
+    P: Fn(BackupProgress),
+{
+    let key = keys.local;
+    let mut opener = keys.passphrase.map(BackupOpener::new);
+
+    progress(BackupProgress::stage(BackupStage::Downloading));
+    let download_path = db_path.with_extension("db.download");
+    let staged = async {
+        let source_key =
+            download_database(storage, backup_id, key, opener.as_mut(), &download_path).await?;
+        let verification =
+            inspect(storage, backup_id, &download_path, source_key, data_dir).await?;
+        Ok::<_, Error>((source_key, verification))
+    }
+    .await;
+    let (source_key, verification) = match staged {
+        Ok(staged) => staged,
+        Err(err) => {
+            let _ = fs::remove_file(&download_path).await;
+            return Err(err);
+        }
+    };
+
+    if !verification.restorable {
+        let _ = fs::remove_file(&download_path).await;
+        return Err(Error::Backup(format!(
+            "Backup failed verification: {}",
+            verification.problems.join("; ")
+        )));
+    }
+    if !verification.missing_attachments.is_empty() {
+        log::warn!(
+            "Restoring without {} missing attachments: {}",
+            verification.missing_attachments.len(),
+            verification.missing_attachments.join(", ")
+        );
+    }
+
+    if let Err(err) = restore_attachments(storage, data_dir, key, opener.as_mut(), &progress).await
+    {
+        log::warn!(
+            "Attachment restore failed, proceeding with database only: {}",
+            err
+        );
+    }
+
+    progress(BackupProgress::stage(BackupStage::Restoring));
+    let temp_path = db_path.with_extension("db.restore");
+    let exported = match key {
+        Some(key) => {
+            crate::database::export(&download_path, source_key, &temp_path, Some(key)).await
+        }
+        None => fs::rename(&download_path, &temp_path)
+            .await
+            .map_err(Error::from),
+    };
+    let _ = fs::remove_file(&download_path).await;
+    exported?;
+
+    replace_database(pool, &temp_path, db_path).await?;
+
+    progress(BackupProgress::stage(BackupStage::Done));
+    Ok(())
+}
+
+/// Closes the pool and moves the database at `path` over the live one.
+pub(crate) async fn replace_database(pool: &SqlitePool, path: &Path, db_path: &Path) -> Result<()> {
+    pool.close().await;
+
+    // Stale journal files from the old database would be replayed onto the new one
+    for suffix in ["-wal", "-shm"] {
+        let mut journal = db_path.as_os_str().to_owned();
+        journal.push(suffix);
+        let _ = fs::remove_file(PathBuf::from(journal)).await;
+    }
+
+    fs::rename(path, db_path).await?;
+    Ok(())
+}
+
+/// Downloads, opens and inflates a snapshot into `path`, returning the key
+/// the database inside is encrypted with.
+async fn download_database<'a, S>(
+    storage: &S,
+    backup_id: &str,
+    key: Option<&'a Key>,
+    opener: Option<&mut BackupOpener>,
+    path: &Path,
+) -> Result<Option<&'a Key>>
+where
+    S: BackupStorage,
+{
+    let mut data = storage.download_snapshot(backup_id).await?;
+
+    // Opened before anything is written, so a wrong passphrase changes nothing
+    if encryption::is_backup_sealed(&data) {
+        let opener = opener.ok_or_else(|| {
+            Error::Backup("This backup is encrypted, enter its passphrase".to_string())
+        })?;
+        data = opener.open(&data).await?;
+    }
+    let database = decompress_snapshot(data).await?;
+
+    // Snapshots of an encrypted database are encrypted with its key
+    let plain = database.starts_with(SQLITE_HEADER);
+    if !plain && key.is_none() {
+        return Err(Error::Backup(
+            "Backup is encrypted or not a SQLite database, enable encryption to restore it"
+                .to_string(),
+        ));
+    }
+
+    fs::write(path, &database).await?;
+    Ok(if plain { None } else { key })
+}
+
+// Problems are collected rather than returned, so the summary shows all of them
+async fn inspect<S>(
+    storage: &S,
+    backup_id: &str,
+    path: &Path,
+    key: Option<&Key>,
+    data_dir: &Path,
+) -> Result<BackupVerification>
+where
+    S: BackupStorage,
+{
+    let latest_schema_version = crate::database::latest_version();
+    let mut verification = BackupVerification {
+        backup_id: backup_id.to_string(),
+        integrity_ok: false,
+        schema_version: 0,
+        latest_schema_version,
+        patient_count: 0,
+        attachment_count: 0,
+        missing_attachments: Vec::new(),
+        problems: Vec::new(),
+        restorable: false,
+    };
+
+    let mut conn = match crate::database::open_read_only(path, key).await {
+        Ok(conn) => conn,
+        Err(err) => {
+            verification
+                .problems
+                .push(format!("The snapshot does not open: {err}"));
+            return Ok(verification);
+        }
+    };
+
+    match crate::database::check_integrity(&mut conn).await {
+        Ok(()) => verification.integrity_ok = true,
+        Err(err) => verification.problems.push(err.to_string()),
+    }
+
+    let referenced = async {
+        verification.schema_version = crate::database::schema_version(&mut conn).await?;
+        verification.patient_count = sqlx::query_scalar("SELECT COUNT(*) FROM patients")
+            .fetch_one(&mut conn)
+            .await?;
+        let paths: Vec<String> = sqlx::query_scalar("SELECT file_path FROM attachments")
+            .fetch_all(&mut conn)
+            .await?;
+        Ok::<_, Error>(paths)
+    }
+    .await;
+    let _ = conn.close().await;
+
+    let referenced = match referenced {
+        Ok(referenced) => referenced,
+        Err(err) => {
+            verification
+                .problems
+                .push(format!("The snapshot could not be read: {err}"));
+            return Ok(verification);
+        }
+    };
+
+    if verification.schema_version > latest_schema_version {
+        verification.problems.push(format!(
+            "The backup was made by a newer version of the app (schema {}, this build supports {})",
+            verification.schema_version, latest_schema_version
+        ));
+    }
+    verification.attachment_count = referenced.len();
+
+    let sealed_suffix = format!(".{SEALED_SUFFIX}");
+    match storage.list_attachments().await {
+        Ok(files) => {
+            let archived: HashSet<String> = files
+                .into_iter()
+                .map(|file| match file.name.strip_suffix(&sealed_suffix) {
+                    Some(name) => name.to_string(),
+                    None => file.name,
+                })
+                .collect();
+
+            let local = attachments_dir(data_dir);
+            for file_path in referenced {
+                // Paths are absolute on the machine that made the backup, only the name carries over
+                let name = Path::new(&file_path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or(file_path);
+                if !archived.contains(&name) && !fs::try_exists(local.join(&name)).await? {
+                    verification.missing_attachments.push(name);
+                }
+            }
+        }
+        Err(err) => verification
+            .problems
+            .push(format!("Attachments could not be listed: {err}")),
+    }
+
+    verification.restorable =
+        verification.integrity_ok && verification.schema_version <= latest_schema_version;
+    Ok(verification)
+}
+
+fn sealed_name(name: &str) -> String {
+    format!("{name}.{SEALED_SUFFIX}")
+}
+
+async fn sync_attachments<S, P>(
+    storage: &S,
+    data_dir: &Path,
+    local: Option<&Key>,
+    backup_key: Option<&BackupKey>,
+    progress: &P,
+) -> Result<()>
+where
+    S: BackupStorage,
+    P: Fn(BackupProgress),
+{
+    let dir = attachments_dir(data_dir);
+    if !fs::try_exists(&dir).await? {
+        return Ok(());
+    }
+
+    let remote: HashSet<String> = storage
+        .list_attachments()
+        .await?
+        .into_iter()
+        .map(|file| file.name)
+        .collect();
+
+    let mut pending = Vec::new();
+    let mut entries = fs::read_dir(&dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let mut name = entry.file_name().to_string_lossy().to_string();
+        if backup_key.is_some() {
+            name = sealed_name(&name);
+        }
+        if entry.file_type().await?.is_file() && !remote.contains(&name) {
+            pending.push((name, entry.path()));
+        }
+    }
+
+    let total = pending.len();
+    for (index, (name, path)) in pending.into_iter().enumerate() {
+        progress(BackupProgress {
+            stage: BackupStage::Attachments,
+            current: index,
+            total,
+        });
+
+        let data = match backup_key {
+            Some(backup_key) => backup_key.seal(&encryption::read_file(&path, local).await?)?,
+            None => fs::read(&path).await?,
+        };
+        if let Err(err) = storage.upload_attachment(&name, data).await {
+            log::warn!("Failed to upload {}: {}", name, err);
+        }
+    }
+
+    Ok(())
+}
+
+async fn restore_attachments<S, P>(
+    storage: &S,
+    data_dir: &Path,
+    local: Option<&Key>,
+    mut opener: Option<&mut BackupOpener>,
+    progress: &P,
+) -> Result<()>
+where
+    S: BackupStorage,
+    P: Fn(BackupProgress),
+{
+    let dir = attachments_dir(data_dir);
+    fs::create_dir_all(&dir).await?;
+
+    let sealed_suffix = format!(".{SEALED_SUFFIX}");
+    let mut missing = BTreeMap::new();
+    for file in storage.list_attachments().await? {
+        // Names come from the backup storage, never let them leave the attachments folder
+        let name = match file.name.strip_suffix(&sealed_suffix) {
+            Some(name) => checked_name(name)?.to_string(),
+            None => checked_name(&file.name)?.to_string(),
+        };
+        if fs::try_exists(dir.join(&name)).await? {
+            continue;
+        }
+
+        // Files uploaded before backup encryption have a plain copy too,
+        // which restores without the passphrase
+        if name == file.name {
+            missing.insert(name, file);
+        } else {
+            missing.entry(name).or_insert(file);
+        }
+    }
+
+    let total = missing.len();
+    for (index, (name, file)) in missing.into_iter().enumerate() {
+        progress(BackupProgress {
+            stage: BackupStage::Attachments,
+            current: index,
+            total,
+        });
+
+        let data = match storage.download_attachment(&file).await {
+            Ok(data) => data,
+            Err(err) => {
+                log::warn!("Failed to download {}: {}", file.name, err);
+                continue;
+            }
+        };
+
+        let data = if encryption::is_backup_sealed(&data) {
+            let Some(opener) = opener.as_deref_mut() else {
+                log::warn!("Skipping {}, it is encrypted", file.name);
+                continue;
+            };
+            match opener.open(&data).await {
+                Ok(data) => encryption::seal_file(local, data)?,
+                Err(err) => {
+                    log::warn!("Failed to decrypt {}: {}", file.name, err);
+                    continue;
+                }
+            }
+        } else if encryption::is_sealed(&data) {
+            // Sealed with this machine's database key
+            data
+        } else {
+            encryption::seal_file(local, data)?
+        };
+
+        fs::write(dir.join(&name), data).await?;
+    }
+
+    Ok(())
+}
+
+/// Takes a consistent copy of the live database with `VACUUM INTO` and gzips it.
+///
+/// The copy is encrypted with the database key `local` like the database
+/// itself, unless a `backup_key` is given. Then it is decrypted and the
+/// compressed copy is sealed with the backup key instead.
+pub async fn snapshot_database(
+    pool: &SqlitePool,
+    local: Option<&Key>,
+    backup_key: Option<&BackupKey>,
+) -> Result<Vec<u8>> {
+    let temp_path = std::env::temp_dir().join(format!(
+        "temp_snapshot_{}.db",
+        uuid::Uuid::new_v4().simple()
+    ));
+
+    sqlx::query("VACUUM INTO ?")
+        .bind(temp_path.display().to_string())
+        .execute(pool)
+        .await?;
+
+    let data = match (local, backup_key) {
+        (Some(local), Some(_)) => {
+            let plain_path = temp_path.with_extension("plain.db");
+            let data = async {
+                crate::database::export(&temp_path, Some(local), &plain_path, None).await?;
+                Ok(fs::read(&plain_path).await?)
+            }
+            .await;
+            let _ = fs::remove_file(&plain_path).await;
+            data
+        }
+        _ => fs::read(&temp_path).await.map_err(Error::from),
+    };
+    let _ = fs::remove_file(&temp_path).await;
+
+    let compressed = compress(data?).await?;
+    match backup_key {
+        Some(backup_key) => backup_key.seal(&compressed),
+        None => Ok(compressed),
+    }
+}
+
+async fn compress(data: Vec<u8>) -> Result<Vec<u8>> {
+    tokio::task::spawn_blocking(move || {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data)?;
+        Ok(encoder.finish()?)
+    })
+    .await
+    .map_err(|err| Error::Backup(err.to_string()))?
+}
+
+// Older backups were uploaded uncompressed, so only inflate gzip data
+async fn decompress_snapshot(data: Vec<u8>) -> Result<Vec<u8>> {
+    if !data.starts_with(GZIP_MAGIC) {
+        return Ok(data);
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let mut decoded = Vec::new();
+        GzDecoder::new(data.as_slice()).read_to_end(&mut decoded)?;
+        Ok(decoded)
+    })
+    .await
+    .map_err(|err| Error::Backup(err.to_string()))?
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum BackupTarget {
+    GoogleDrive,
+    Local { path: String },
+}
+
+/// The backend selected by a command, resolved once per call.
+pub enum Storage {
+    GoogleDrive(GoogleDriveStorage),
+    Local(LocalStorage),
+}
+
+impl Storage {
+    pub async fn open(app: &AppHandle, target: &BackupTarget) -> Result<Self> {
+        match target {
+            BackupTarget::GoogleDrive => Ok(Storage::GoogleDrive(
+                GoogleDriveStorage::connect(app).await?,
+            )),
+            BackupTarget::Local { path } => Ok(Storage::Local(LocalStorage::new(path)?)),
+        }
+    }
+}
+
+impl BackupStorage for Storage {
+    async fn upload_snapshot(
+        &self,
+        name: &str,
+        data: Vec<u8>,
+        properties: &BackupProperties,
+    ) -> Result<BackupEntry> {
+        match self {
+            Storage::GoogleDrive(storage) => storage.upload_snapshot(name, data, properties).await,
+            Storage::Local(storage) => storage.upload_snapshot(name, data, properties).await,
+        }
+    }
+
+    async fn list_snapshots(&self) -> Result<Vec<BackupEntry>> {
+        match self {
+            Storage::GoogleDrive(storage) => storage.list_snapshots().await,
+            Storage::Local(storage) => storage.list_snapshots().await,
+        }
+    }
+
+    async fn download_snapshot(&self, id: &str) -> Result<Vec<u8>> {
+        match self {
+            Storage::GoogleDrive(storage) => storage.download_snapshot(id).await,
+            Storage::Local(storage) => storage.download_snapshot(id).await,
+        }
+    }
+
+    async fn list_attachments(&self) -> Result<Vec<RemoteFile>> {
+        match self {
+            Storage::GoogleDrive(storage) => storage.list_attachments().await,
+            Storage::Local(storage) => storage.list_attachments().await,
+        }
+    }
+
+    async fn upload_attachment(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        match self {
+            Storage::GoogleDrive(storage) => storage.upload_attachment(name, data).await,
+            Storage::Local(storage) => storage.upload_attachment(name, data).await,
+        }
+    }
+
+    async fn download_attachment(&self, file: &RemoteFile) -> Result<Vec<u8>> {
+        match self {
+            Storage::GoogleDrive(storage) => storage.download_attachment(file).await,
+            Storage::Local(storage) => storage.download_attachment(file).await,
+        }
+    }
+}
+
+fn emit_progress(app: &AppHandle) -> impl Fn(BackupProgress) + '_ {
+    move |progress| {
+        let _ = app.emit("backup-progress", progress);
+    }
+}
+
+#[tauri::command]
+pub async fn create_backup(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    target: BackupTarget,
+) -> Result<BackupEntry> {
+    state.require(Permission::ManageBackups)?;
+    let _guard = scheduler
+        .try_begin()
+        .ok_or_else(|| Error::Backup("A backup is already running".to_string()))?;
+    let storage = Storage::open(&app, &target).await?;
+    let data_dir = PathBuf::from(&state.config.data_dir);
+    let version = app.package_info().version.to_string();
+    let local = state.encryption_key();
+    let backup_key = state.backup_key().await?;
+
+    create(
+        &storage,
+        &pool,
+        &data_dir,
+        &version,
+        local.as_ref(),
+        backup_key.as_ref(),
+        emit_progress(&app),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn list_backups(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    target: BackupTarget,
+) -> Result<Vec<BackupEntry>> {
+    state.require(Permission::ManageBackups)?;
+    Storage::open(&app, &target).await?.list_snapshots().await
+}
+
+#[tauri::command]
+pub async fn verify_backup(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    target: BackupTarget,
+    backup_id: String,
+    passphrase: Option<String>,
+) -> Result<BackupVerification> {
+    state.require(Permission::ManageBackups)?;
+    let storage = Storage::open(&app, &target).await?;
+
+    verify(
+        &storage,
+        &backup_id,
+        Path::new(&state.config.data_dir),
+        RestoreKeys {
+            local: state.encryption_key().as_ref(),
+            passphrase: passphrase.as_deref(),
+        },
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn restore_backup(
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    target: BackupTarget,
+    backup_id: String,
+    passphrase: Option<String>,
+) -> Result<()> {
+    state.require(Permission::ManageBackups)?;
+    // No backup may read the database while it is being replaced
+    let _guard = scheduler
+        .try_begin()
+        .ok_or_else(|| Error::Backup("A backup is already running".to_string()))?;
+    let storage = Storage::open(&app, &target).await?;
+    let config = &state.config;
+
+    restore(
+        &storage,
+        &backup_id,
+        &pool,
+        Path::new(&config.db_path),
+        Path::new(&config.data_dir),
+        RestoreKeys {
+            local: state.encryption_key().as_ref(),
+            passphrase: passphrase.as_deref(),
+        },
+        emit_progress(&app),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::*;
+    use crate::{
+        database::{self, open_read_only},
+        repository::{attachments, new_id, test_statement},
+    };
+
+    const SCAN: &[u8] = b"scanned page";
+
+    // `VACUUM INTO` from an in-memory database never reaches the disk, so
+    // every desk here keeps its database in a file
+    async fn desk_pool(data_dir: &Path) -> SqlitePool {
+        fs::create_dir_all(data_dir).await.unwrap();
+        let db_url = format!("sqlite:{}", data_dir.join("app.db").display());
+        database::prepare(&db_url, data_dir, None).await.unwrap();
+        database::connect(&db_url, None, true).unwrap()
+    }
+
+    /// A desk with one statement and its scan, backing up to a folder.
+    struct Fixture {
+        root: PathBuf,
+        data_dir: PathBuf,
+        pool: SqlitePool,
+        storage: LocalStorage,
+        scan_name: String,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("sgmc-backup-{}", new_id()));
+            let data_dir = root.join("desk");
+            let pool = desk_pool(&data_dir).await;
+
+            let statement_id = test_statement(&pool, 500).await;
+            let scan = attachments::create(
+                &pool,
+                &data_dir,
+                None,
+                &attachments::AddAttachment {
+                    statement_id,
+                    file_name: "scan.jpg".to_string(),
+                    file_type: "image/jpeg".to_string(),
+                    file_data: base64::engine::general_purpose::STANDARD.encode(SCAN),
+                },
+            )
+            .await
+            .unwrap();
+            let scan_name = Path::new(&scan.file_path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+
+            Self {
+                storage: LocalStorage::new(root.join("backups")).unwrap(),
+                root,
+                data_dir,
+                pool,
+                scan_name,
+            }
+        }
+
+        async fn back_up(&self, backup_key: Option<&BackupKey>) -> BackupEntry {
+            create(
+                &self.storage,
+                &self.pool,
+                &self.data_dir,
+                "test",
+                None,
+                backup_key,
+                |_| {},
+            )
+            .await
+            .unwrap()
+        }
+
+        /// Restores onto a fresh desk, returning its data folder.
+        async fn restore(&self, backup_id: &str, keys: RestoreKeys<'_>) -> Result<PathBuf> {
+            let data_dir = self.root.join(format!("restored-{}", new_id()));
+            let pool = desk_pool(&data_dir).await;
+
+            restore(
+                &self.storage,
+                backup_id,
+                &pool,
+                &data_dir.join("app.db"),
+                &data_dir,
+                keys,
+                |_| {},
+            )
+            .await?;
+            Ok(data_dir)
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    async fn patient_count(data_dir: &Path) -> i64 {
+        let mut conn = open_read_only(&data_dir.join("app.db"), None).await.unwrap();
+        let count = sqlx::query_scalar("SELECT COUNT(*) FROM patients")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        count
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_a_backup_folder() {
+        let fixture = Fixture::new().await;
+
+        let entry = fixture.back_up(None).await;
+        assert_eq!(entry.properties.patient_count.as_deref(), Some("1"));
+        assert_eq!(entry.properties.encrypted, None);
+
+        let listed = fixture.storage.list_snapshots().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, entry.id);
+
+        let restored = fixture
+            .restore(&entry.id, RestoreKeys::default())
+            .await
+            .unwrap();
+        assert_eq!(patient_count(&restored).await, 1);
+        assert_eq!(
+            fs::read(attachments_dir(&restored).join(&fixture.scan_name))
+                .await
+                .unwrap(),
+            SCAN
+        );
+        assert!(!fs::try_exists(restored.join("app.db.download")).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn restores_a_sealed_backup_with_its_passphrase() {
+        let fixture = Fixture::new().await;
+        let backup_key = BackupKey::create("correct horse battery").await.unwrap();
+
+        let entry = fixture.back_up(Some(&backup_key)).await;
+        assert_eq!(entry.properties.encrypted.as_deref(), Some("true"));
+
+        // Neither the snapshot nor the scan is readable in the backup folder
+        let uploaded = fixture.storage.list_attachments().await.unwrap();
+        assert_eq!(uploaded.len(), 1);
+        assert_eq!(uploaded[0].name, sealed_name(&fixture.scan_name));
+        let sealed = fixture.storage.download_attachment(&uploaded[0]).await.unwrap();
+        assert!(!sealed.windows(SCAN.len()).any(|window| window == SCAN));
+
+        let keys = RestoreKeys {
+            local: None,
+            passphrase: Some("correct horse battery"),
+        };
+        let restored = fixture.restore(&entry.id, keys).await.unwrap();
+        assert_eq!(patient_count(&restored).await, 1);
+        assert_eq!(
+            fs::read(attachments_dir(&restored).join(&fixture.scan_name))
+                .await
+                .unwrap(),
+            SCAN
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_a_sealed_backup_without_its_passphrase() {
+        let fixture = Fixture::new().await;
+        let backup_key = BackupKey::create("correct horse battery").await.unwrap();
+        let entry = fixture.back_up(Some(&backup_key)).await;
+
+        let keys = RestoreKeys {
+            local: None,
+            passphrase: Some("wrong horse battery"),
+        };
+        let err = fixture.restore(&entry.id, keys).await.unwrap_err();
+        assert!(matches!(err, Error::WrongPassphrase));
+
+        let err = fixture
+            .restore(&entry.id, RestoreKeys::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Backup(_)));
+    }
+
+    /// Unpacks a snapshot so a test can tamper with it, see [`repack`].
+    async fn unpack(fixture: &Fixture, entry: &BackupEntry) -> PathBuf {
+        let data = fixture.storage.download_snapshot(&entry.id).await.unwrap();
+        let path = fixture.root.join(format!("unpacked-{}.db", new_id()));
+        fs::write(&path, decompress_snapshot(data).await.unwrap())
+            .await
+            .unwrap();
+        path
+    }
+
+    async fn repack(fixture: &Fixture, entry: &BackupEntry, path: &Path) {
+        let data = compress(fs::read(path).await.unwrap()).await.unwrap();
+        fixture
+            .storage
+            .upload_snapshot(&entry.id, data, &entry.properties)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn verifies_a_healthy_backup() {
+        let fixture = Fixture::new().await;
+        let entry = fixture.back_up(None).await;
+
+        let verification = verify(
+            &fixture.storage,
+            &entry.id,
+            &fixture.data_dir,
+            RestoreKeys::default(),
+        )
+        .await
+        .unwrap();
+        assert!(verification.restorable, "{:?}", verification.problems);
+        assert!(verification.integrity_ok);
+        assert_eq!(verification.schema_version, database::latest_version());
+        assert_eq!(verification.patient_count, 1);
+        assert_eq!(verification.attachment_count, 1);
+        assert!(verification.missing_attachments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn refuses_a_corrupt_snapshot() {
+        let fixture = Fixture::new().await;
+        let entry = fixture.back_up(None).await;
+
+        let path = unpack(&fixture, &entry).await;
+        let mut database = fs::read(&path).await.unwrap();
+        let half = database.len() / 2;
+        database[half..].fill(0xa5);
+        fs::write(&path, database).await.unwrap();
+        repack(&fixture, &entry, &path).await;
+
+        let verification = verify(
+            &fixture.storage,
+            &entry.id,
+            &fixture.data_dir,
+            RestoreKeys::default(),
+        )
+        .await
+        .unwrap();
+        assert!(!verification.restorable);
+        assert!(!verification.problems.is_empty());
+
+        let err = fixture
+            .restore(&entry.id, RestoreKeys::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Backup(_)));
+    }
+
+    #[tokio::test]
+    async fn refuses_a_truncated_snapshot() {
+        let fixture = Fixture::new().await;
+        let entry = fixture.back_up(None).await;
+
+        let mut data = fixture.storage.download_snapshot(&entry.id).await.unwrap();
+        data.truncate(data.len() / 2);
+        fixture
+            .storage
+            .upload_snapshot(&entry.id, data, &entry.properties)
+            .await
+            .unwrap();
+
+        assert!(verify(
+            &fixture.storage,
+            &entry.id,
+            &fixture.data_dir,
+            RestoreKeys::default(),
+        )
+        .await
+        .is_err());
+        assert!(fixture
+            .restore(&entry.id, RestoreKeys::default())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_a_snapshot_from_a_newer_schema() {
+        let fixture = Fixture::new().await;
+        let entry = fixture.back_up(None).await;
+        let newer = database::latest_version() + 1;
+
+        let path = unpack(&fixture, &entry).await;
+        let mut conn = sqlx::SqliteConnection::connect(&format!("sqlite:{}", path.display()))
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+             VALUES (?, 'from the future', 1, x'00', 0)",
+        )
+        .bind(newer)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        conn.close().await.unwrap();
+        repack(&fixture, &entry, &path).await;
+
+        let verification = verify(
+            &fixture.storage,
+            &entry.id,
+            &fixture.data_dir,
+            RestoreKeys::default(),
+        )
+        .await
+        .unwrap();
+        assert!(verification.integrity_ok);
+        assert_eq!(verification.schema_version, newer);
+        assert!(!verification.restorable);
+        assert_eq!(verification.problems.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn lists_attachments_missing_from_the_backup() {
+        let fixture = Fixture::new().await;
+        let entry = fixture.back_up(None).await;
+
+        let uploaded = fixture.storage.list_attachments().await.unwrap();
+        let copy = fixture.root.join("backups").join("attachments");
+        fs::remove_file(copy.join(&uploaded[0].name)).await.unwrap();
+
+        // Still on this machine, so nothing is lost yet
+        let verification = verify(
+            &fixture.storage,
+            &entry.id,
+            &fixture.data_dir,
+            RestoreKeys::default(),
+        )
+        .await
+        .unwrap();
+        assert!(verification.missing_attachments.is_empty());
+
+        let elsewhere = fixture.root.join("elsewhere");
+        let verification = verify(
+            &fixture.storage,
+            &entry.id,
+            &elsewhere,
+            RestoreKeys::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(verification.missing_attachments, std::slice::from_ref(&fixture.scan_name));
+        // The records survive, only the files are gone
+        assert!(verification.restorable);
+    }
+
+    #[tokio::test]
+    async fn refuses_ids_outside_the_backup_folder() {
+        let fixture = Fixture::new().await;
+
+        let err = fixture
+            .restore("../desk/attachments", RestoreKeys::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Validation(_)));
+    }
+}
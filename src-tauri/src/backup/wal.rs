@@ -0,0 +1,708 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use sqlx::{pool::PoolConnection, sqlite::SqliteConnection, Connection, Sqlite, SqlitePool};
+use tauri::{AppHandle, Manager, State};
+use tokio::{
+    fs,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    database,
+    encryption::Key,
+    error::{Error, Result},
+    filesystem,
+    sync::SyncScheduler,
+};
+
+const WAL_DIR: &str = "wal";
+const BASE_FILE: &str = "base.db";
+const SEGMENT_EXTENSION: &str = "wal";
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+const WAL_HEADER_LEN: usize = 32;
+const FRAME_HEADER_LEN: usize = 24;
+// A new chain every day keeps the number of segments replayed on restore small
+const CHAIN_LENGTH: chrono::Duration = chrono::Duration::days(1);
+
+/// `DATA_DIR/backups/wal`, one folder per chain.
+///
+/// A chain is a raw copy of the database file taken right after a full
+/// checkpoint (`base.db`), followed by numbered copies of the WAL. Replaying
+/// the segments in order onto the base gives the database as it was when
+/// the last one was copied.
+pub fn wal_dir(data_dir: &Path) -> PathBuf {
+    filesystem::backups_dir(data_dir).join(WAL_DIR)
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text, TIME_FORMAT)
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push("-wal");
+    PathBuf::from(path)
+}
+
+/// The length of `wal` up to the last commit of its current generation,
+/// and the salt that identifies that generation.
+///
+/// Frames left over from before the WAL was restarted carry an older salt,
+/// so the scan stops at the first one. SQLCipher only encrypts the pages,
+/// the headers read the same either way.
+fn committed_len(wal: &[u8]) -> Option<(usize, [u8; 8])> {
+    let header = wal.get(..WAL_HEADER_LEN)?;
+    let page_size = u32::from_be_bytes(header[8..12].try_into().ok()?) as usize;
+    let salt: [u8; 8] = header[16..24].try_into().ok()?;
+
+    let frame_len = FRAME_HEADER_LEN + page_size;
+    let mut offset = WAL_HEADER_LEN;
+    let mut committed = None;
+    while let Some(frame) = wal.get(offset..offset + frame_len) {
+        if frame[8..16] != salt {
+            break;
+        }
+        offset += frame_len;
+
+        // A non-zero database size marks the last frame of a transaction
+        if frame[4..8] != [0; 4] {
+            committed = Some(offset);
+        }
+    }
+
+    committed.map(|len| (len, salt))
+}
+
+async fn checkpoint(conn: &mut SqliteConnection, mode: &str) -> Result<(i64, i64, i64)> {
+    Ok(sqlx::query_as(&format!("PRAGMA wal_checkpoint({mode})"))
+        .fetch_one(conn)
+        .await?)
+}
+
+struct Chain {
+    dir: PathBuf,
+    started: DateTime<Utc>,
+    next_segment: u32,
+    /// What the previous segment held, so an idle WAL is not copied again
+    last_copied: Option<(usize, [u8; 8])>,
+}
+
+/// Copies the live WAL into the current chain before the pool checkpoints it.
+///
+/// The pool runs with automatic checkpoints off. Each run takes the write
+/// lock on one pool connection, copies the committed part of the WAL,
+/// checkpoints on another and only then lets writers in again, so every
+/// frame is copied before a checkpoint allows SQLite to overwrite it.
+pub struct WalArchiver {
+    db_path: PathBuf,
+    root: PathBuf,
+    retention: chrono::Duration,
+    chain: Option<Chain>,
+}
+
+/// Connections holding the write lock and running the checkpoint.
+///
+/// Both are taken before the lock, a busy pool would otherwise wait on us.
+struct Locked {
+    writer: PoolConnection<Sqlite>,
+    checkpointer: PoolConnection<Sqlite>,
+}
+
+impl Locked {
+    async fn acquire(pool: &SqlitePool) -> Result<Self> {
+        let mut locked = Self {
+            writer: pool.acquire().await?,
+            checkpointer: pool.acquire().await?,
+        };
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *locked.writer)
+            .await?;
+        Ok(locked)
+    }
+
+    async fn release(mut self) -> Result<()> {
+        if let Err(err) = sqlx::query("ROLLBACK").execute(&mut *self.writer).await {
+            // Never hand a connection stuck in a transaction back to the pool
+            let _ = self.writer.close().await;
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+impl WalArchiver {
+    /// Frames written before the first run are already in the first chain's base snapshot.
+    pub fn new(db_path: &Path, data_dir: &Path, retention_days: u32) -> Self {
+        Self {
+            db_path: db_path.to_path_buf(),
+            root: wal_dir(data_dir),
+            retention: chrono::Duration::days(i64::from(retention_days)),
+            chain: None,
+        }
+    }
+
+    /// Copies new commits into the chain, starting a new chain when there is
+    /// none yet or the current one is a day old.
+    pub async fn archive(&mut self, pool: &SqlitePool) -> Result<()> {
+        let now = Utc::now();
+        let expired = self
+            .chain
+            .as_ref()
+            .is_none_or(|chain| now - chain.started >= CHAIN_LENGTH);
+
+        if expired {
+            // Commits since the last copy go into the old chain first
+            if self.chain.is_some() {
+                self.copy_segment(pool).await?;
+            }
+            self.start_chain(pool).await?;
+            self.prune().await;
+            return Ok(());
+        }
+
+        self.copy_segment(pool).await
+    }
+
+    async fn start_chain(&mut self, pool: &SqlitePool) -> Result<()> {
+        let started = Utc::now();
+        let dir = self.root.join(format_time(started));
+        fs::create_dir_all(&dir).await?;
+
+        let base = dir.join(BASE_FILE);
+        let db_path = self.db_path.clone();
+
+        let mut locked = Locked::acquire(pool).await?;
+        let copied = async {
+            let (busy, log, checkpointed) = checkpoint(&mut locked.checkpointer, "PASSIVE").await?;
+            if busy != 0 || checkpointed < log {
+                return Err(Error::Backup(
+                    "The database is busy, the WAL chain starts on the next run".to_string(),
+                ));
+            }
+
+            // Everything committed is in the database file now and stays
+            // there while the lock is held
+            fs::copy(&db_path, &base).await?;
+            Ok(())
+        }
+        .await;
+        locked.release().await?;
+
+        if let Err(err) = copied {
+            let _ = fs::remove_dir_all(&dir).await;
+            return Err(err);
+        }
+
+        log::info!("Started WAL chain {}", dir.display());
+        self.chain = Some(Chain {
+            dir,
+            started,
+            next_segment: 1,
+            last_copied: None,
+        });
+        Ok(())
+    }
+
+    async fn copy_segment(&mut self, pool: &SqlitePool) -> Result<()> {
+        let Some(chain) = self.chain.as_ref() else {
+            return Ok(());
+        };
+        let dir = chain.dir.clone();
+        let number = chain.next_segment;
+        let last_copied = chain.last_copied;
+
+        let db_path = self.db_path.clone();
+
+        let mut locked = Locked::acquire(pool).await?;
+        let copied = async {
+            let wal = match fs::read(wal_path(&db_path)).await {
+                Ok(wal) => wal,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(Error::from(err)),
+            };
+            let Some(committed) = committed_len(&wal) else {
+                return Ok(None);
+            };
+            if Some(committed) == last_copied {
+                return Ok(None);
+            }
+
+            let name = format!(
+                "{number:06}_{}.{SEGMENT_EXTENSION}",
+                format_time(Utc::now())
+            );
+            let partial = dir.join(format!("{name}.partial"));
+            fs::write(&partial, &wal[..committed.0]).await?;
+            fs::rename(&partial, dir.join(&name)).await?;
+
+            // Only frames that were just copied can be checkpointed here
+            checkpoint(&mut locked.checkpointer, "PASSIVE").await?;
+            Ok(Some(committed))
+        }
+        .await;
+        locked.release().await?;
+        let copied = copied?;
+
+        if let (Some(committed), Some(chain)) = (copied, self.chain.as_mut()) {
+            chain.next_segment += 1;
+            chain.last_copied = Some(committed);
+        }
+        Ok(())
+    }
+
+    // Old chains are only a convenience, failing to remove one is logged
+    async fn prune(&self) {
+        let cutoff = Utc::now() - self.retention;
+        let current = self.chain.as_ref().map(|chain| chain.dir.as_path());
+
+        let chains = match list_chains(&self.root).await {
+            Ok(chains) => chains,
+            Err(err) => {
+                log::warn!("Failed to list WAL chains: {}", err);
+                return;
+            }
+        };
+
+        for chain in chains {
+            if Some(chain.dir.as_path()) == current || chain.last_point() >= cutoff {
+                continue;
+            }
+            match fs::remove_dir_all(&chain.dir).await {
+                Ok(()) => log::info!("Removed expired WAL chain {}", chain.dir.display()),
+                Err(err) => log::warn!("Failed to remove {}: {}", chain.dir.display(), err),
+            }
+        }
+    }
+}
+
+struct StoredChain {
+    dir: PathBuf,
+    started: DateTime<Utc>,
+    /// In replay order, with the time each was copied
+    segments: Vec<(PathBuf, DateTime<Utc>)>,
+}
+
+impl StoredChain {
+    fn last_point(&self) -> DateTime<Utc> {
+        self.segments
+            .last()
+            .map_or(self.started, |(_, copied)| *copied)
+    }
+}
+
+/// Chains with a base snapshot, oldest first.
+async fn list_chains(root: &Path) -> Result<Vec<StoredChain>> {
+    if !fs::try_exists(root).await? {
+        return Ok(Vec::new());
+    }
+
+    let mut chains = Vec::new();
+    let mut entries = fs::read_dir(root).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(started) = parse_time(&name) else {
+            continue;
+        };
+        let dir = entry.path();
+        if !fs::try_exists(dir.join(BASE_FILE)).await? {
+            continue;
+        }
+
+        let mut segments = Vec::new();
+        let mut files = fs::read_dir(&dir).await?;
+        while let Some(file) = files.next_entry().await? {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXTENSION) {
+                continue;
+            }
+            let copied = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.split_once('_'))
+                .and_then(|(_, time)| parse_time(time));
+            if let Some(copied) = copied {
+                segments.push((path, copied));
+            }
+        }
+        // Names start with the zero padded sequence number
+        segments.sort_by(|a, b| a.0.cmp(&b.0));
+
+        chains.push(StoredChain {
+            dir,
+            started,
+            segments,
+        });
+    }
+
+    chains.sort_by_key(|chain| chain.started);
+    Ok(chains)
+}
+
+/// Replays the chain onto a copy of its base at `path`, stopping after the
+/// last segment copied at or before `target`.
+async fn replay(
+    chain: &StoredChain,
+    target: DateTime<Utc>,
+    path: &Path,
+    key: Option<&Key>,
+) -> Result<DateTime<Utc>> {
+    let wal = wal_path(path);
+    let _ = fs::remove_file(&wal).await;
+    fs::copy(chain.dir.join(BASE_FILE), path).await?;
+
+    let mut reached = chain.started;
+    for (segment, copied) in chain
+        .segments
+        .iter()
+        .take_while(|(_, copied)| *copied <= target)
+    {
+        fs::copy(segment, &wal).await?;
+
+        // Closing the only connection removes the WAL once it is checkpointed
+        let mut conn = database::open_wal(path, key).await?;
+        let (busy, log, checkpointed) = checkpoint(&mut conn, "FULL").await?;
+        conn.close().await?;
+
+        // SQLite ignores a WAL whose header does not check out
+        if busy != 0 || log <= 0 || checkpointed < log {
+            return Err(Error::Backup(format!(
+                "WAL segment {} could not be replayed",
+                segment.display()
+            )));
+        }
+        reached = *copied;
+    }
+
+    let mut conn = database::open_wal(path, key).await?;
+    let checked = database::check_integrity(&mut conn).await;
+    conn.close().await?;
+    checked?;
+
+    Ok(reached)
+}
+
+/// Rebuilds the database as of `target` and swaps it in for the live one.
+///
+/// Returns the time actually restored to, which is the last WAL copy at or
+/// before `target`. The pool is closed, so the app has to be relaunched.
+pub async fn restore_to(
+    pool: &SqlitePool,
+    db_path: &Path,
+    data_dir: &Path,
+    key: Option<&Key>,
+    target: DateTime<Utc>,
+) -> Result<DateTime<Utc>> {
+    let chains = list_chains(&wal_dir(data_dir)).await?;
+    let chain = chains
+        .iter()
+        .rev()
+        .find(|chain| chain.started <= target)
+        .ok_or_else(|| Error::Backup("No recovery point that early is kept".to_string()))?;
+
+    let temp_path = db_path.with_extension("db.recover");
+    let reached = replay(chain, target, &temp_path, key).await;
+    let _ = fs::remove_file(wal_path(&temp_path)).await;
+    let reached = match reached {
+        Ok(reached) => reached,
+        Err(err) => {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(err);
+        }
+    };
+
+    super::replace_database(pool, &temp_path, db_path).await?;
+    log::info!("Restored the database to {}", reached);
+    Ok(reached)
+}
+
+/// Spawns the archiver loop when `wal_archive_interval_minutes` is set.
+pub fn spawn(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let minutes = state.config.wal_archive_interval_minutes;
+    if minutes == 0 {
+        return;
+    }
+    let period = Duration::from_secs(u64::from(minutes) * 60);
+
+    tauri::async_runtime::spawn(async move {
+        let config = &app.state::<AppState>().config;
+        let mut archiver = WalArchiver::new(
+            Path::new(&config.db_path),
+            Path::new(&config.data_dir),
+            config.wal_retention_days,
+        );
+
+        // The first tick fires at once and takes the chain's base snapshot
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            let pool = app.state::<SqlitePool>();
+            if pool.is_closed() {
+                log::info!("Stopping WAL archiving, the database was closed");
+                return;
+            }
+            if let Err(err) = archiver.archive(&pool).await {
+                log::error!("WAL archiving failed: {}", err);
+            }
+        }
+    });
+
+    log::info!("WAL archived every {} minutes", minutes);
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryRange {
+    /// Unix time in milliseconds of the base snapshot
+    pub from: i64,
+    /// Unix time in milliseconds of the last WAL copy
+    pub to: i64,
+    pub segments: usize,
+}
+
+/// The spans the database can be restored to, oldest first.
+#[tauri::command]
+pub async fn get_recovery_points(state: State<'_, AppState>) -> Result<Vec<RecoveryRange>> {
+    state.require(Permission::ManageBackups)?;
+    let chains = list_chains(&wal_dir(Path::new(&state.config.data_dir))).await?;
+
+    Ok(chains
+        .iter()
+        .map(|chain| RecoveryRange {
+            from: chain.started.timestamp_millis(),
+            to: chain.last_point().timestamp_millis(),
+            segments: chain.segments.len(),
+        })
+        .collect())
+}
+
+/// Restores the database to `at`, unix time in milliseconds, and returns
+/// the time of the recovery point actually used.
+#[tauri::command]
+pub async fn restore_to_point(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    at: i64,
+) -> Result<i64> {
+    state.require(Permission::ManageBackups)?;
+    // No backup may read the database while it is being replaced
+    let _guard = scheduler
+        .try_begin()
+        .ok_or_else(|| Error::Backup("A backup is already running".to_string()))?;
+    let target = DateTime::from_timestamp_millis(at)
+        .ok_or_else(|| Error::Validation(format!("Invalid recovery time: {at}")))?;
+    let config = &state.config;
+
+    let reached = restore_to(
+        &pool,
+        Path::new(&config.db_path),
+        Path::new(&config.data_dir),
+        state.encryption_key().as_ref(),
+        target,
+    )
+    .await?;
+
+    Ok(reached.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::new_id;
+
+    const PAGE_SIZE: usize = 512;
+    const SALT: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn wal_header(salt: [u8; 8]) -> Vec<u8> {
+        let mut header = vec![0; WAL_HEADER_LEN];
+        header[8..12].copy_from_slice(&(PAGE_SIZE as u32).to_be_bytes());
+        header[16..24].copy_from_slice(&salt);
+        header
+    }
+
+    // A frame ends a transaction when it carries the database size
+    fn frame(salt: [u8; 8], commit: bool) -> Vec<u8> {
+        let mut frame = vec![0; FRAME_HEADER_LEN + PAGE_SIZE];
+        frame[0..4].copy_from_slice(&1u32.to_be_bytes());
+        if commit {
+            frame[4..8].copy_from_slice(&2u32.to_be_bytes());
+        }
+        frame[8..16].copy_from_slice(&salt);
+        frame
+    }
+
+    #[test]
+    fn committed_len_stops_at_the_last_commit() {
+        let frame_len = FRAME_HEADER_LEN + PAGE_SIZE;
+        let mut wal = wal_header(SALT);
+        wal.extend(frame(SALT, false));
+        wal.extend(frame(SALT, true));
+        // Uncommitted, then a frame from before the WAL restarted
+        wal.extend(frame(SALT, false));
+        wal.extend(frame([9; 8], true));
+
+        assert_eq!(
+            committed_len(&wal),
+            Some((WAL_HEADER_LEN + 2 * frame_len, SALT))
+        );
+
+        // A frame cut short by a crash is not read
+        let mut torn = wal_header(SALT);
+        torn.extend(frame(SALT, true));
+        torn.extend(&frame(SALT, true)[..100]);
+        assert_eq!(committed_len(&torn), Some((WAL_HEADER_LEN + frame_len, SALT)));
+
+        let mut open = wal_header(SALT);
+        open.extend(frame(SALT, false));
+        assert_eq!(committed_len(&open), None);
+        assert_eq!(committed_len(&wal[..10]), None);
+    }
+
+    struct Fixture {
+        data_dir: PathBuf,
+        db_path: PathBuf,
+        pool: SqlitePool,
+    }
+
+    impl Fixture {
+        async fn new() -> Self {
+            let data_dir = std::env::temp_dir().join(format!("sgmc-wal-{}", new_id()));
+            fs::create_dir_all(&data_dir).await.unwrap();
+            let db_path = data_dir.join("app.db");
+            let db_url = format!("sqlite:{}", db_path.display());
+
+            database::prepare(&db_url, &data_dir, None).await.unwrap();
+            let pool = database::connect(&db_url, None, true).unwrap();
+            Self {
+                data_dir,
+                db_path,
+                pool,
+            }
+        }
+
+        async fn add_clinic(&self, name: &str) {
+            sqlx::query(
+                "INSERT INTO clinics (id, name, created_at, updated_at) VALUES (?, ?, 0, 0)",
+            )
+            .bind(new_id())
+            .bind(name)
+            .execute(&self.pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.data_dir);
+        }
+    }
+
+    async fn clinics(path: &Path) -> Vec<String> {
+        let mut conn = database::open_read_only(path, None).await.unwrap();
+        let names = sqlx::query_scalar("SELECT name FROM clinics ORDER BY name")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        conn.close().await.unwrap();
+        names
+    }
+
+    // Segment names carry the copy time in milliseconds
+    async fn tick() -> DateTime<Utc> {
+        time::sleep(Duration::from_millis(5)).await;
+        let now = Utc::now();
+        time::sleep(Duration::from_millis(5)).await;
+        now
+    }
+
+    #[tokio::test]
+    async fn replays_segments_up_to_the_target() {
+        let fixture = Fixture::new().await;
+        let mut archiver = WalArchiver::new(&fixture.db_path, &fixture.data_dir, 7);
+
+        fixture.add_clinic("Before the chain").await;
+        archiver.archive(&fixture.pool).await.unwrap();
+        fixture.add_clinic("First").await;
+        archiver.archive(&fixture.pool).await.unwrap();
+        let between = tick().await;
+        fixture.add_clinic("Second").await;
+        archiver.archive(&fixture.pool).await.unwrap();
+        // Nothing new, no segment
+        archiver.archive(&fixture.pool).await.unwrap();
+
+        let chains = list_chains(&wal_dir(&fixture.data_dir)).await.unwrap();
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].segments.len(), 2);
+
+        let replayed = fixture.data_dir.join("replayed.db");
+        let reached = replay(&chains[0], between, &replayed, None).await.unwrap();
+        assert_eq!(reached, chains[0].segments[0].1);
+        assert_eq!(clinics(&replayed).await, ["Before the chain", "First"]);
+
+        let reached = replay(&chains[0], Utc::now(), &replayed, None).await.unwrap();
+        assert_eq!(reached, chains[0].last_point());
+        assert_eq!(
+            clinics(&replayed).await,
+            ["Before the chain", "First", "Second"]
+        );
+    }
+
+    #[tokio::test]
+    async fn restores_the_live_database_to_a_point_in_time() {
+        let fixture = Fixture::new().await;
+        let mut archiver = WalArchiver::new(&fixture.db_path, &fixture.data_dir, 7);
+
+        let before_chain = tick().await;
+        archiver.archive(&fixture.pool).await.unwrap();
+        fixture.add_clinic("Kept").await;
+        archiver.archive(&fixture.pool).await.unwrap();
+        let target = tick().await;
+        fixture.add_clinic("Undone").await;
+        archiver.archive(&fixture.pool).await.unwrap();
+
+        let err = restore_to(
+            &fixture.pool,
+            &fixture.db_path,
+            &fixture.data_dir,
+            None,
+            before_chain,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::Backup(_)));
+
+        let reached = restore_to(
+            &fixture.pool,
+            &fixture.db_path,
+            &fixture.data_dir,
+            None,
+            target,
+        )
+        .await
+        .unwrap();
+        assert!(reached <= target);
+        assert!(fixture.pool.is_closed());
+        assert_eq!(clinics(&fixture.db_path).await, ["Kept"]);
+        assert!(!fs::try_exists(fixture.db_path.with_extension("db.recover"))
+            .await
+            .unwrap());
+    }
+}
@@ -1,7 +1,42 @@
+use std::{
+    env, fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
-use crate::AppState;
+use crate::{
+    auth::Permission,
+    discovery,
+    error::{Error, Result},
+    filesystem,
+    server::ServerInfo,
+    AppState,
+};
+
+const DEFAULT_PORT: u16 = 14200;
+const DEFAULT_PORT_RANGE: u16 = 10;
+const MAX_PORT_RANGE: u16 = 100;
+const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0";
+const DEFAULT_SYNC_INTERVAL_MINUTES: u32 = 60;
+const DEFAULT_PAIRING_TTL_MINUTES: u32 = 10;
+const MAX_INTERVAL_MINUTES: u32 = 24 * 60;
+const DEFAULT_REMINDER_LEAD_MINUTES: [u32; 2] = [24 * 60, 60];
+const MAX_REMINDER_LEAD_MINUTES: u32 = 7 * 24 * 60;
+const DEFAULT_OVERDUE_REMINDER_DAYS: u32 = 30;
+const MAX_OVERDUE_REMINDER_DAYS: u32 = 365;
+const DEFAULT_RECYCLE_BIN_RETENTION_DAYS: u32 = 30;
+const MAX_RECYCLE_BIN_RETENTION_DAYS: u32 = 10 * 365;
+const DEFAULT_ARCHIVE_KEEP_DAILY: u32 = 7;
+const DEFAULT_ARCHIVE_KEEP_WEEKLY: u32 = 4;
+const MAX_ARCHIVE_KEEP: u32 = 365;
+const DEFAULT_WAL_ARCHIVE_INTERVAL_MINUTES: u32 = 5;
+const DEFAULT_WAL_RETENTION_DAYS: u32 = 7;
+const MAX_WAL_RETENTION_DAYS: u32 = 365;
+const MIN_PEER_SYNC_SECRET_LEN: usize = 16;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -9,18 +44,591 @@ pub struct AppConfig {
     pub db_url: String,
     pub data_dir: String,
     pub sync_interval_minutes: u32,
+    pub pairing_ttl_minutes: u32,
     pub ip_address: String,
+    pub bind_address: String,
     pub port: u16,
+    /// How many ports after `port` to try when it is taken
+    pub port_range: u16,
+    /// How long before an appointment to notify, longest first
+    pub reminder_lead_minutes: Vec<u32>,
+    /// Age at which an unpaid statement is reported, 0 turns the reminder off
+    pub overdue_reminder_days: u32,
+    /// How long deleted items stay in the recycle bin, 0 keeps them forever
+    pub recycle_bin_retention_days: u32,
+    /// Days with an archive kept in a backup folder
+    pub archive_keep_daily: u32,
+    /// Weeks with an archive kept in a backup folder, both at 0 keeps every archive
+    pub archive_keep_weekly: u32,
+    /// How often the WAL is copied for point-in-time recovery, 0 turns it off
+    pub wal_archive_interval_minutes: u32,
+    /// How long archived WAL segments and their base snapshots are kept
+    pub wal_retention_days: u32,
+    /// Shared by the workstations that sync with each other, unset turns it off
+    #[serde(skip_serializing)]
+    pub peer_sync_secret: Option<String>,
+    /// Announced over mDNS as `<name>.local` for phones and other workstations
+    pub mdns_hostname: String,
+}
+
+/// The user editable part of the config, stored in `settings.json`.
+///
+/// Anything left unset falls back to the built-in default. Environment
+/// variables win over the file so a broken setting can be bypassed at launch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub data_dir: Option<String>,
+    pub port: Option<u16>,
+    pub port_range: Option<u16>,
+    pub bind_address: Option<String>,
+    pub sync_interval_minutes: Option<u32>,
+    pub pairing_ttl_minutes: Option<u32>,
+    pub reminder_lead_minutes: Option<Vec<u32>>,
+    pub overdue_reminder_days: Option<u32>,
+    pub recycle_bin_retention_days: Option<u32>,
+    pub archive_keep_daily: Option<u32>,
+    pub archive_keep_weekly: Option<u32>,
+    pub wal_archive_interval_minutes: Option<u32>,
+    pub wal_retention_days: Option<u32>,
+    pub peer_sync_secret: Option<String>,
+    pub mdns_hostname: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingsInfo {
+    pub settings: Settings,
+    pub path: String,
+    pub errors: Vec<String>,
+    /// The saved settings differ from what the running app uses
+    pub restart_required: bool,
+}
+
+fn check_data_dir(dir: &str) -> std::result::Result<PathBuf, String> {
+    let path = PathBuf::from(dir);
+    if !path.is_absolute() {
+        return Err(format!("Data folder must be an absolute path: {dir}"));
+    }
+
+    if !filesystem::is_writable(&path) {
+        return Err(format!("Data folder is not writable: {dir}"));
+    }
+
+    Ok(path)
+}
+
+fn check_port(port: u16) -> std::result::Result<u16, String> {
+    if port < 1024 {
+        return Err(format!("Port must be between 1024 and 65535, got {port}"));
+    }
+
+    Ok(port)
+}
+
+fn check_port_range(range: u16) -> std::result::Result<u16, String> {
+    if range > MAX_PORT_RANGE {
+        return Err(format!(
+            "Port range must be at most {MAX_PORT_RANGE}, got {range}"
+        ));
+    }
+
+    Ok(range)
+}
+
+fn check_bind_address(address: &str) -> std::result::Result<IpAddr, String> {
+    IpAddr::from_str(address.trim())
+        .map_err(|_| format!("Bind address must be an IP address, got \"{address}\""))
+}
+
+fn check_minutes(minutes: u32, name: &str) -> std::result::Result<u32, String> {
+    if minutes == 0 || minutes > MAX_INTERVAL_MINUTES {
+        return Err(format!(
+            "{name} must be between 1 and {MAX_INTERVAL_MINUTES} minutes, got {minutes}"
+        ));
+    }
+
+    Ok(minutes)
+}
+
+// An empty list is allowed and turns appointment reminders off
+pub(crate) fn check_lead_minutes(minutes: Vec<u32>) -> std::result::Result<Vec<u32>, String> {
+    if let Some(bad) = minutes
+        .iter()
+        .find(|&&lead| lead == 0 || lead > MAX_REMINDER_LEAD_MINUTES)
+    {
+        return Err(format!(
+            "Reminder lead times must be between 1 and {MAX_REMINDER_LEAD_MINUTES} minutes, got {bad}"
+        ));
+    }
+
+    let mut minutes = minutes;
+    minutes.sort_unstable_by(|a, b| b.cmp(a));
+    minutes.dedup();
+    Ok(minutes)
+}
+
+fn check_overdue_days(days: u32) -> std::result::Result<u32, String> {
+    if days > MAX_OVERDUE_REMINDER_DAYS {
+        return Err(format!(
+            "Overdue reminder must be at most {MAX_OVERDUE_REMINDER_DAYS} days, got {days}"
+        ));
+    }
+
+    Ok(days)
+}
+
+fn check_retention_days(days: u32) -> std::result::Result<u32, String> {
+    if days > MAX_RECYCLE_BIN_RETENTION_DAYS {
+        return Err(format!(
+            "Recycle bin retention must be at most {MAX_RECYCLE_BIN_RETENTION_DAYS} days, got {days}"
+        ));
+    }
+
+    Ok(days)
+}
+
+fn check_archive_keep(count: u32, name: &str) -> std::result::Result<u32, String> {
+    if count > MAX_ARCHIVE_KEEP {
+        return Err(format!(
+            "{name} must be at most {MAX_ARCHIVE_KEEP}, got {count}"
+        ));
+    }
+
+    Ok(count)
+}
+
+// 0 is allowed and turns WAL archiving off
+fn check_wal_interval(minutes: u32) -> std::result::Result<u32, String> {
+    if minutes == 0 {
+        return Ok(0);
+    }
+
+    check_minutes(minutes, "WAL archive interval")
+}
+
+fn check_wal_retention_days(days: u32) -> std::result::Result<u32, String> {
+    if days == 0 || days > MAX_WAL_RETENTION_DAYS {
+        return Err(format!(
+            "WAL retention must be between 1 and {MAX_WAL_RETENTION_DAYS} days, got {days}"
+        ));
+    }
+
+    Ok(days)
+}
+
+fn check_peer_sync_secret(secret: &str) -> std::result::Result<Option<String>, String> {
+    let secret = secret.trim();
+    if secret.chars().count() < MIN_PEER_SYNC_SECRET_LEN {
+        return Err(format!(
+            "Peer sync secret must be at least {MIN_PEER_SYNC_SECRET_LEN} characters"
+        ));
+    }
+
+    Ok(Some(secret.to_string()))
+}
+
+fn check_mdns_hostname(name: &str) -> std::result::Result<String, String> {
+    let name = name.trim().to_lowercase();
+    let valid = (1..=63).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-');
+
+    if !valid {
+        return Err(format!(
+            "Network name must be 1 to 63 letters, digits or dashes, got \"{name}\""
+        ));
+    }
+
+    Ok(name)
+}
+
+// A bad value is reported and replaced by the fallback instead of aborting startup
+fn resolve<T, V>(
+    value: Option<V>,
+    check: impl FnOnce(V) -> std::result::Result<T, String>,
+    fallback: impl FnOnce() -> T,
+    errors: &mut Vec<String>,
+) -> T {
+    match value.map(check) {
+        Some(Ok(value)) => value,
+        Some(Err(err)) => {
+            errors.push(err);
+            fallback()
+        }
+        None => fallback(),
+    }
+}
+
+fn env_override<T: FromStr>(name: &str, target: &mut Option<T>, errors: &mut Vec<String>) {
+    let Ok(value) = env::var(name) else {
+        return;
+    };
+
+    match value.parse() {
+        Ok(parsed) => *target = Some(parsed),
+        Err(_) => errors.push(format!("Ignoring {name}, invalid value \"{value}\"")),
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, reporting a malformed file instead of failing.
+    pub fn load(path: &Path) -> (Self, Vec<String>) {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(_) => return (Self::default(), Vec::new()),
+        };
+
+        match serde_json::from_slice(&data) {
+            Ok(settings) => (settings, Vec::new()),
+            Err(err) => (
+                Self::default(),
+                vec![format!("Ignoring {}: {}", path.display(), err)],
+            ),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn with_env_overrides(mut self, errors: &mut Vec<String>) -> Self {
+        env_override("SGMC_DATA_DIR", &mut self.data_dir, errors);
+        env_override("SGMC_PORT", &mut self.port, errors);
+        env_override("SGMC_PORT_RANGE", &mut self.port_range, errors);
+        env_override("SGMC_BIND_ADDRESS", &mut self.bind_address, errors);
+        env_override(
+            "SYNC_INTERVAL_MINUTES",
+            &mut self.sync_interval_minutes,
+            errors,
+        );
+        env_override("PAIRING_TTL_MINUTES", &mut self.pairing_ttl_minutes, errors);
+        self
+    }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        AppConfig::from_settings(self, &mut errors);
+        errors
+    }
+}
+
+impl AppConfig {
+    pub fn from_settings(settings: &Settings, errors: &mut Vec<String>) -> Self {
+        let data_dir = resolve(
+            settings.data_dir.as_deref(),
+            check_data_dir,
+            filesystem::default_data_dir,
+            errors,
+        );
+        let port = resolve(settings.port, check_port, || DEFAULT_PORT, errors);
+        let port_range = resolve(
+            settings.port_range,
+            check_port_range,
+            || DEFAULT_PORT_RANGE,
+            errors,
+        );
+        let bind_address = resolve(
+            settings.bind_address.as_deref(),
+            check_bind_address,
+            || IpAddr::from_str(DEFAULT_BIND_ADDRESS).unwrap(),
+            errors,
+        );
+        let sync_interval_minutes = resolve(
+            settings.sync_interval_minutes,
+            |minutes| check_minutes(minutes, "Sync interval"),
+            || DEFAULT_SYNC_INTERVAL_MINUTES,
+            errors,
+        );
+        let pairing_ttl_minutes = resolve(
+            settings.pairing_ttl_minutes,
+            |minutes| check_minutes(minutes, "Pairing token lifetime"),
+            || DEFAULT_PAIRING_TTL_MINUTES,
+            errors,
+        );
+        let reminder_lead_minutes = resolve(
+            settings.reminder_lead_minutes.clone(),
+            check_lead_minutes,
+            || DEFAULT_REMINDER_LEAD_MINUTES.to_vec(),
+            errors,
+        );
+        let overdue_reminder_days = resolve(
+            settings.overdue_reminder_days,
+            check_overdue_days,
+            || DEFAULT_OVERDUE_REMINDER_DAYS,
+            errors,
+        );
+        let recycle_bin_retention_days = resolve(
+            settings.recycle_bin_retention_days,
+            check_retention_days,
+            || DEFAULT_RECYCLE_BIN_RETENTION_DAYS,
+            errors,
+        );
+        let archive_keep_daily = resolve(
+            settings.archive_keep_daily,
+            |count| check_archive_keep(count, "Daily archives kept"),
+            || DEFAULT_ARCHIVE_KEEP_DAILY,
+            errors,
+        );
+        let archive_keep_weekly = resolve(
+            settings.archive_keep_weekly,
+            |count| check_archive_keep(count, "Weekly archives kept"),
+            || DEFAULT_ARCHIVE_KEEP_WEEKLY,
+            errors,
+        );
+        let wal_archive_interval_minutes = resolve(
+            settings.wal_archive_interval_minutes,
+            check_wal_interval,
+            || DEFAULT_WAL_ARCHIVE_INTERVAL_MINUTES,
+            errors,
+        );
+        let wal_retention_days = resolve(
+            settings.wal_retention_days,
+            check_wal_retention_days,
+            || DEFAULT_WAL_RETENTION_DAYS,
+            errors,
+        );
+        // An empty box in the settings form means no secret
+        let peer_sync_secret = resolve(
+            settings
+                .peer_sync_secret
+                .as_deref()
+                .filter(|secret| !secret.trim().is_empty()),
+            check_peer_sync_secret,
+            || None,
+            errors,
+        );
+        let mdns_hostname = resolve(
+            settings.mdns_hostname.as_deref(),
+            check_mdns_hostname,
+            discovery::default_host_name,
+            errors,
+        );
+
+        // Phones need a reachable address, which a wildcard bind is not
+        let ip_address = if bind_address.is_unspecified() {
+            local_ip_address::local_ip()
+                .map(|ip| ip.to_string())
+                .unwrap_or_else(|_| "127.0.0.1".to_string())
+        } else {
+            bind_address.to_string()
+        };
+
+        let db_path = data_dir.join("database.db").display().to_string();
+
+        AppConfig {
+            db_url: format!("sqlite:{}", db_path),
+            db_path,
+            data_dir: data_dir.display().to_string(),
+            sync_interval_minutes,
+            pairing_ttl_minutes,
+            ip_address,
+            bind_address: bind_address.to_string(),
+            port,
+            port_range,
+            reminder_lead_minutes,
+            overdue_reminder_days,
+            recycle_bin_retention_days,
+            archive_keep_daily,
+            archive_keep_weekly,
+            wal_archive_interval_minutes,
+            wal_retention_days,
+            peer_sync_secret,
+            mdns_hostname,
+        }
+    }
+
+    /// Whether the two configs differ in anything that is only read at startup.
+    fn differs_from(&self, other: &AppConfig) -> bool {
+        self.data_dir != other.data_dir
+            || self.port != other.port
+            || self.port_range != other.port_range
+            || self.bind_address != other.bind_address
+            || self.sync_interval_minutes != other.sync_interval_minutes
+            || self.pairing_ttl_minutes != other.pairing_ttl_minutes
+            || self.reminder_lead_minutes != other.reminder_lead_minutes
+            || self.overdue_reminder_days != other.overdue_reminder_days
+            || self.recycle_bin_retention_days != other.recycle_bin_retention_days
+            || self.archive_keep_daily != other.archive_keep_daily
+            || self.archive_keep_weekly != other.archive_keep_weekly
+            || self.wal_archive_interval_minutes != other.wal_archive_interval_minutes
+            || self.wal_retention_days != other.wal_retention_days
+            || self.peer_sync_secret != other.peer_sync_secret
+            || self.mdns_hostname != other.mdns_hostname
+    }
+}
+
+fn settings_info(state: &AppState, settings: Settings, mut errors: Vec<String>) -> SettingsInfo {
+    let saved = AppConfig::from_settings(
+        &settings.clone().with_env_overrides(&mut errors),
+        &mut errors,
+    );
+
+    SettingsInfo {
+        restart_required: saved.differs_from(&state.config),
+        path: filesystem::settings_path().display().to_string(),
+        settings,
+        errors,
+    }
 }
 
 #[tauri::command]
-pub fn get_app_config(state: State<'_, AppState>) -> AppConfig {
+pub fn get_app_config(state: State<'_, AppState>, server: State<'_, ServerInfo>) -> AppConfig {
     let mut config = state.config.clone();
 
+    // The server may have fallen back to another port in the range
+    if let Some(port) = server.port() {
+        config.port = port;
+    }
+
     // Dynamically update IP address if possible
-    if let Ok(ip) = local_ip_address::local_ip() {
-        config.ip_address = ip.to_string();
+    let wildcard = IpAddr::from_str(&config.bind_address).map_or(true, |ip| ip.is_unspecified());
+    if wildcard {
+        if let Ok(ip) = local_ip_address::local_ip() {
+            config.ip_address = ip.to_string();
+        }
     }
 
     config
 }
+
+/// The saved settings, peer sync secret included, so only for those who may change them.
+#[tauri::command]
+pub fn get_settings(state: State<'_, AppState>) -> Result<SettingsInfo> {
+    state.require(Permission::ManageSettings)?;
+
+    let (settings, errors) = Settings::load(&filesystem::settings_path());
+    Ok(settings_info(&state, settings, errors))
+}
+
+/// Validates and saves the settings; they take effect after a restart.
+#[tauri::command]
+pub fn set_app_config(state: State<'_, AppState>, settings: Settings) -> Result<SettingsInfo> {
+    state.require(Permission::ManageSettings)?;
+
+    let errors = settings.validate();
+    if !errors.is_empty() {
+        return Err(Error::Validation(errors.join("\n")));
+    }
+
+    settings.save(&filesystem::settings_path())?;
+    // Not the settings themselves, they hold the peer sync secret
+    log::info!("Saved settings to {}", filesystem::settings_path().display());
+
+    Ok(settings_info(&state, settings, Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::new_id;
+
+    #[test]
+    fn checks_the_data_folder_without_creating_it() {
+        let root = env::temp_dir().join(format!("sgmc-config-{}", new_id()));
+        let dir = root.join("clinic").join("data");
+
+        assert_eq!(check_data_dir(&dir.display().to_string()), Ok(dir));
+        assert!(!root.exists());
+        assert!(check_data_dir("data").is_err());
+
+        // A file where a folder would have to be made
+        fs::write(&root, "").unwrap();
+        let blocked = root.join("data").display().to_string();
+        assert!(check_data_dir(&blocked).is_err());
+        fs::remove_file(&root).unwrap();
+    }
+
+    #[test]
+    fn checks_ports_and_addresses() {
+        assert!(check_port(80).is_err());
+        assert_eq!(check_port(1024), Ok(1024));
+        assert_eq!(check_port(65535), Ok(65535));
+
+        assert_eq!(check_port_range(MAX_PORT_RANGE), Ok(MAX_PORT_RANGE));
+        assert!(check_port_range(MAX_PORT_RANGE + 1).is_err());
+
+        assert_eq!(
+            check_bind_address(" 192.168.1.5 "),
+            Ok(IpAddr::from([192, 168, 1, 5]))
+        );
+        assert!(check_bind_address("::").is_ok());
+        assert!(check_bind_address("localhost").is_err());
+    }
+
+    #[test]
+    fn checks_intervals_and_retention() {
+        assert!(check_minutes(0, "Sync interval").is_err());
+        assert_eq!(check_minutes(MAX_INTERVAL_MINUTES, "Sync interval"), Ok(MAX_INTERVAL_MINUTES));
+        assert!(check_minutes(MAX_INTERVAL_MINUTES + 1, "Sync interval")
+            .unwrap_err()
+            .starts_with("Sync interval"));
+
+        assert_eq!(check_wal_interval(0), Ok(0));
+        assert!(check_wal_interval(MAX_INTERVAL_MINUTES + 1).is_err());
+        assert!(check_wal_retention_days(0).is_err());
+        assert!(check_wal_retention_days(MAX_WAL_RETENTION_DAYS + 1).is_err());
+
+        assert_eq!(check_overdue_days(0), Ok(0));
+        assert!(check_overdue_days(MAX_OVERDUE_REMINDER_DAYS + 1).is_err());
+        assert_eq!(check_retention_days(0), Ok(0));
+        assert!(check_retention_days(MAX_RECYCLE_BIN_RETENTION_DAYS + 1).is_err());
+        assert_eq!(check_archive_keep(0, "Daily archives kept"), Ok(0));
+        assert!(check_archive_keep(MAX_ARCHIVE_KEEP + 1, "Daily archives kept").is_err());
+    }
+
+    #[test]
+    fn sorts_reminder_lead_times_longest_first() {
+        assert_eq!(check_lead_minutes(vec![60, 1440, 60, 15]), Ok(vec![1440, 60, 15]));
+        assert_eq!(check_lead_minutes(Vec::new()), Ok(Vec::new()));
+        assert!(check_lead_minutes(vec![60, 0]).is_err());
+        assert!(check_lead_minutes(vec![MAX_REMINDER_LEAD_MINUTES + 1]).is_err());
+    }
+
+    #[test]
+    fn checks_the_peer_secret_and_network_name() {
+        assert!(check_peer_sync_secret("  too short  ").is_err());
+        assert_eq!(
+            check_peer_sync_secret("  0123456789abcdef  "),
+            Ok(Some("0123456789abcdef".to_string()))
+        );
+
+        assert_eq!(check_mdns_hostname(" Front-Desk "), Ok("front-desk".to_string()));
+        assert!(check_mdns_hostname(&"a".repeat(63)).is_ok());
+        for bad in ["", "-desk", "desk-", "front desk", "desk.local", &"a".repeat(64)] {
+            assert!(check_mdns_hostname(bad).is_err(), "{bad:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn reports_bad_settings_and_falls_back_to_defaults() {
+        let data_dir = env::temp_dir().join(format!("sgmc-config-{}", new_id()));
+        let settings = Settings {
+            data_dir: Some(data_dir.display().to_string()),
+            port: Some(80),
+            wal_retention_days: Some(0),
+            peer_sync_secret: Some("   ".to_string()),
+            mdns_hostname: Some("front desk".to_string()),
+            ..Settings::default()
+        };
+
+        let mut errors = Vec::new();
+        let config = AppConfig::from_settings(&settings, &mut errors);
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert_eq!(settings.validate(), errors);
+
+        assert_eq!(config.data_dir, data_dir.display().to_string());
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(config.wal_retention_days, DEFAULT_WAL_RETENTION_DAYS);
+        // An empty secret turns peer sync off rather than failing
+        assert_eq!(config.peer_sync_secret, None);
+        assert!(!data_dir.exists());
+    }
+}
@@ -1,6 +1,106 @@
-use tauri::plugin::TauriPlugin;
-use tauri::Runtime;
-use tauri_plugin_sql::{Migration, MigrationKind};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use sqlx::{
+    migrate::{Migration as SqlxMigration, MigrationType, Migrator},
+    sqlite::{
+        SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool, SqlitePoolOptions,
+    },
+    Connection, Row,
+};
+
+use crate::{
+    encryption::Key,
+    error::{Error, Result},
+    filesystem,
+};
+
+// Folds Arabic spelling variants so "أحمد", "احمد" and "أَحْمَد" index the same.
+// Used by migration 6, editing it changes that migration's checksum.
+macro_rules! search_fold {
+    ($column:literal) => {
+        concat!(
+            "replace(replace(replace(replace(replace(replace(replace(replace(replace(replace(",
+            "replace(replace(replace(replace(replace(replace(replace(replace(replace(replace(",
+            "replace(replace(replace(replace(replace(replace(",
+            $column,
+            ", char(0x064B), ''), char(0x064C), ''), char(0x064D), ''), char(0x064E), '')",
+            ", char(0x064F), ''), char(0x0650), ''), char(0x0651), ''), char(0x0652), '')",
+            ", char(0x0670), ''), char(0x0640), '')",
+            ", 'أ', 'ا'), 'إ', 'ا'), 'آ', 'ا'), 'ٱ', 'ا'), 'ى', 'ي'), 'ة', 'ه')",
+            ", '٠', '0'), '١', '1'), '٢', '2'), '٣', '3'), '٤', '4')",
+            ", '٥', '5'), '٦', '6'), '٧', '7'), '٨', '8'), '٩', '9')",
+        )
+    };
+}
+
+// `json_object('id', NEW.id, ...)` over the given columns of `$row`.
+macro_rules! audit_json {
+    ($row:literal; $first:ident $(, $column:ident)*) => {
+        concat!(
+            "json_object('", stringify!($first), "', ", $row, ".", stringify!($first),
+            $(", '", stringify!($column), "', ", $row, ".", stringify!($column),)*
+            ")"
+        )
+    };
+}
+
+// Copies every insert, update and delete on `$table` into `audit_log`. The
+// patient and statement expressions are evaluated against NEW and OLD rows so
+// history can be looked up per patient or statement after the row is gone.
+// Used by migrations 8 to 10, editing it changes their checksums.
+macro_rules! audit_triggers {
+    (
+        $table:literal,
+        patient: ($new_patient:literal, $old_patient:literal),
+        statement: ($new_statement:literal, $old_statement:literal),
+        columns: [$($column:ident),+ $(,)?]
+    ) => {
+        concat!(
+            "CREATE TRIGGER IF NOT EXISTS audit_", $table, "_insert AFTER INSERT ON ", $table, " BEGIN\n",
+            "    INSERT INTO audit_log (table_name, row_id, operation, patient_id, statement_id, new_values)\n",
+            "    VALUES ('", $table, "', NEW.id, 'insert', ", $new_patient, ", ", $new_statement, ", ",
+            audit_json!("NEW"; $($column),+), ");\n",
+            "END;\n",
+            "CREATE TRIGGER IF NOT EXISTS audit_", $table, "_update AFTER UPDATE ON ", $table, "\n",
+            "WHEN ", audit_json!("OLD"; $($column),+), " IS NOT ", audit_json!("NEW"; $($column),+), " BEGIN\n",
+            "    INSERT INTO audit_log (table_name, row_id, operation, patient_id, statement_id, old_values, new_values)\n",
+            "    VALUES ('", $table, "', NEW.id, 'update', ", $new_patient, ", ", $new_statement, ", ",
+            audit_json!("OLD"; $($column),+), ", ", audit_json!("NEW"; $($column),+), ");\n",
+            "END;\n",
+            "CREATE TRIGGER IF NOT EXISTS audit_", $table, "_delete AFTER DELETE ON ", $table, " BEGIN\n",
+            "    INSERT INTO audit_log (table_name, row_id, operation, patient_id, statement_id, old_values)\n",
+            "    VALUES ('", $table, "', OLD.id, 'delete', ", $old_patient, ", ", $old_statement, ", ",
+            audit_json!("OLD"; $($column),+), ");\n",
+            "END;\n",
+        )
+    };
+}
+
+macro_rules! drop_audit_triggers {
+    ($table:literal) => {
+        concat!(
+            "DROP TRIGGER IF EXISTS audit_", $table, "_insert;\n",
+            "DROP TRIGGER IF EXISTS audit_", $table, "_update;\n",
+            "DROP TRIGGER IF EXISTS audit_", $table, "_delete;\n",
+        )
+    };
+}
+
+enum MigrationKind {
+    Up,
+    Down,
+}
+
+struct Migration {
+    version: i64,
+    kind: MigrationKind,
+    description: &'static str,
+    sql: &'static str,
+}
 
 fn db_migrations() -> Vec<Migration> {
     vec![
@@ -49,6 +149,17 @@ fn db_migrations() -> Vec<Migration> {
             CREATE INDEX IF NOT EXISTS idx_sessions_statement_id ON sessions (statement_id);
         "#,
         },
+        Migration {
+            version: 1,
+            kind: MigrationKind::Down,
+            description: "initial_schema",
+            sql: r#"
+            DROP TABLE IF EXISTS sessions;
+            DROP TABLE IF EXISTS payments;
+            DROP TABLE IF EXISTS statements;
+            DROP TABLE IF EXISTS patients;
+        "#,
+        },
         Migration {
             version: 2,
             kind: MigrationKind::Up,
@@ -76,6 +187,32 @@ fn db_migrations() -> Vec<Migration> {
             CREATE INDEX IF NOT EXISTS idx_statements_clinic_id ON statements (clinic_id);
         "#,
         },
+        Migration {
+            version: 2,
+            kind: MigrationKind::Down,
+            description: "add_doctors_and_clinics",
+            sql: r#"
+            CREATE TABLE statements_old (
+                id TEXT PRIMARY KEY,
+                patient_id TEXT NOT NULL,
+                total INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY(patient_id) REFERENCES patients(id)
+            );
+
+            INSERT INTO statements_old (id, patient_id, total, created_at, updated_at)
+            SELECT id, patient_id, total, created_at, updated_at FROM statements;
+
+            DROP TABLE statements;
+            ALTER TABLE statements_old RENAME TO statements;
+
+            CREATE INDEX IF NOT EXISTS idx_statements_patient_id ON statements (patient_id);
+
+            DROP TABLE IF EXISTS clinics;
+            DROP TABLE IF EXISTS doctors;
+        "#,
+        },
         Migration {
             version: 3,
             kind: MigrationKind::Up,
@@ -105,6 +242,33 @@ fn db_migrations() -> Vec<Migration> {
             CREATE INDEX IF NOT EXISTS idx_statements_clinic_id ON statements (clinic_id);
         "#,
         },
+        Migration {
+            version: 3,
+            kind: MigrationKind::Down,
+            description: "cascade_delete_patients",
+            sql: r#"
+            CREATE TABLE statements_old (
+                id TEXT PRIMARY KEY,
+                patient_id TEXT NOT NULL,
+                total INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                doctor_id TEXT REFERENCES doctors(id) ON DELETE SET NULL,
+                clinic_id TEXT REFERENCES clinics(id) ON DELETE SET NULL,
+                FOREIGN KEY(patient_id) REFERENCES patients(id)
+            );
+
+            INSERT INTO statements_old (id, patient_id, total, created_at, updated_at, doctor_id, clinic_id)
+            SELECT id, patient_id, total, created_at, updated_at, doctor_id, clinic_id FROM statements;
+
+            DROP TABLE statements;
+            ALTER TABLE statements_old RENAME TO statements;
+
+            CREATE INDEX IF NOT EXISTS idx_statements_patient_id ON statements (patient_id);
+            CREATE INDEX IF NOT EXISTS idx_statements_doctor_id ON statements (doctor_id);
+            CREATE INDEX IF NOT EXISTS idx_statements_clinic_id ON statements (clinic_id);
+        "#,
+        },
         Migration {
             version: 4,
             kind: MigrationKind::Up,
@@ -127,6 +291,42 @@ fn db_migrations() -> Vec<Migration> {
             CREATE INDEX IF NOT EXISTS idx_patients_phone ON patients (phone);
         "#,
         },
+        Migration {
+            version: 4,
+            kind: MigrationKind::Down,
+            description: "remove_unique_phone_constraint",
+            sql: r#"
+            CREATE TABLE patients_old (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                phone TEXT NOT NULL UNIQUE,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            -- Patients added since share phones, so every copy after the first
+            -- gets its id appended rather than being dropped
+            INSERT INTO patients_old (id, name, phone, created_at, updated_at)
+            SELECT
+                id,
+                name,
+                CASE
+                    WHEN EXISTS (
+                        SELECT 1 FROM patients AS earlier
+                        WHERE earlier.phone = patients.phone AND earlier.rowid < patients.rowid
+                    ) THEN phone || ' #' || id
+                    ELSE phone
+                END,
+                created_at,
+                updated_at
+            FROM patients;
+
+            DROP TABLE patients;
+            ALTER TABLE patients_old RENAME TO patients;
+
+            CREATE INDEX IF NOT EXISTS idx_patients_phone ON patients (phone);
+        "#,
+        },
         Migration {
             version: 5,
             kind: MigrationKind::Up,
@@ -146,14 +346,792 @@ fn db_migrations() -> Vec<Migration> {
             CREATE INDEX IF NOT EXISTS idx_attachments_statement_id ON attachments (statement_id);
         "#,
         },
+        Migration {
+            version: 5,
+            kind: MigrationKind::Down,
+            description: "create_attachments_table",
+            sql: r#"
+            DROP TABLE IF EXISTS attachments;
+        "#,
+        },
+        Migration {
+            version: 6,
+            kind: MigrationKind::Up,
+            description: "create_patient_search_index",
+            sql: concat!(
+                r#"
+            CREATE VIEW patients_fts_source AS
+            SELECT
+                p.id AS patient_id,
+                "#,
+                search_fold!("p.name"),
+                r#" AS name,
+                "#,
+                search_fold!("p.phone"),
+                r#" AS phone,
+                "#,
+                search_fold!(
+                    "COALESCE((SELECT group_concat(DISTINCT d.name) FROM statements s JOIN doctors d ON d.id = s.doctor_id WHERE s.patient_id = p.id), '')"
+                ),
+                r#" AS doctors,
+                "#,
+                search_fold!(
+                    "COALESCE((SELECT group_concat(se.procedure, ' ') FROM sessions se JOIN statements s ON s.id = se.statement_id WHERE s.patient_id = p.id), '')"
+                ),
+                r#" AS procedures
+            FROM patients p;
+
+            CREATE VIRTUAL TABLE patients_fts USING fts5 (
+                patient_id UNINDEXED,
+                name,
+                phone,
+                doctors,
+                procedures,
+                tokenize = 'unicode61 remove_diacritics 2',
+                prefix = '2 3'
+            );
+
+            INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+            SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source;
+
+            CREATE TRIGGER patients_fts_patient_insert AFTER INSERT ON patients BEGIN
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source WHERE patient_id = NEW.id;
+            END;
+
+            CREATE TRIGGER patients_fts_patient_update AFTER UPDATE OF name, phone ON patients BEGIN
+                DELETE FROM patients_fts WHERE patient_id = OLD.id;
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source WHERE patient_id = NEW.id;
+            END;
+
+            CREATE TRIGGER patients_fts_patient_delete AFTER DELETE ON patients BEGIN
+                DELETE FROM patients_fts WHERE patient_id = OLD.id;
+            END;
+
+            CREATE TRIGGER patients_fts_statement_insert AFTER INSERT ON statements BEGIN
+                DELETE FROM patients_fts WHERE patient_id = NEW.patient_id;
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source WHERE patient_id = NEW.patient_id;
+            END;
+
+            CREATE TRIGGER patients_fts_statement_update AFTER UPDATE OF patient_id, doctor_id ON statements BEGIN
+                DELETE FROM patients_fts WHERE patient_id IN (OLD.patient_id, NEW.patient_id);
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source WHERE patient_id IN (OLD.patient_id, NEW.patient_id);
+            END;
+
+            CREATE TRIGGER patients_fts_statement_delete AFTER DELETE ON statements BEGIN
+                DELETE FROM patients_fts WHERE patient_id = OLD.patient_id;
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source WHERE patient_id = OLD.patient_id;
+            END;
+
+            CREATE TRIGGER patients_fts_session_insert AFTER INSERT ON sessions BEGIN
+                DELETE FROM patients_fts WHERE patient_id = (SELECT patient_id FROM statements WHERE id = NEW.statement_id);
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source
+                WHERE patient_id = (SELECT patient_id FROM statements WHERE id = NEW.statement_id);
+            END;
+
+            CREATE TRIGGER patients_fts_session_update AFTER UPDATE OF statement_id, procedure ON sessions BEGIN
+                DELETE FROM patients_fts WHERE patient_id IN (SELECT patient_id FROM statements WHERE id IN (OLD.statement_id, NEW.statement_id));
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source
+                WHERE patient_id IN (SELECT patient_id FROM statements WHERE id IN (OLD.statement_id, NEW.statement_id));
+            END;
+
+            CREATE TRIGGER patients_fts_session_delete AFTER DELETE ON sessions BEGIN
+                DELETE FROM patients_fts WHERE patient_id = (SELECT patient_id FROM statements WHERE id = OLD.statement_id);
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source
+                WHERE patient_id = (SELECT patient_id FROM statements WHERE id = OLD.statement_id);
+            END;
+
+            CREATE TRIGGER patients_fts_doctor_update AFTER UPDATE OF name ON doctors BEGIN
+                DELETE FROM patients_fts WHERE patient_id IN (SELECT patient_id FROM statements WHERE doctor_id = NEW.id);
+                INSERT INTO patients_fts (patient_id, name, phone, doctors, procedures)
+                SELECT patient_id, name, phone, doctors, procedures FROM patients_fts_source
+                WHERE patient_id IN (SELECT patient_id FROM statements WHERE doctor_id = NEW.id);
+            END;
+        "#
+            ),
+        },
+        Migration {
+            version: 6,
+            kind: MigrationKind::Down,
+            description: "create_patient_search_index",
+            sql: r#"
+            DROP TRIGGER IF EXISTS patients_fts_doctor_update;
+            DROP TRIGGER IF EXISTS patients_fts_session_delete;
+            DROP TRIGGER IF EXISTS patients_fts_session_update;
+            DROP TRIGGER IF EXISTS patients_fts_session_insert;
+            DROP TRIGGER IF EXISTS patients_fts_statement_delete;
+            DROP TRIGGER IF EXISTS patients_fts_statement_update;
+            DROP TRIGGER IF EXISTS patients_fts_statement_insert;
+            DROP TRIGGER IF EXISTS patients_fts_patient_delete;
+            DROP TRIGGER IF EXISTS patients_fts_patient_update;
+            DROP TRIGGER IF EXISTS patients_fts_patient_insert;
+            DROP TABLE IF EXISTS patients_fts;
+            DROP VIEW IF EXISTS patients_fts_source;
+        "#,
+        },
+        Migration {
+            version: 7,
+            kind: MigrationKind::Up,
+            description: "create_appointments_table",
+            sql: r#"
+            CREATE TABLE IF NOT EXISTS appointments (
+                id TEXT PRIMARY KEY,
+                patient_id TEXT NOT NULL,
+                doctor_id TEXT,
+                clinic_id TEXT,
+                starts_at TEXT NOT NULL,
+                ends_at TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'scheduled'
+                    CHECK (status IN ('scheduled', 'completed', 'cancelled', 'no_show')),
+                notes TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                CHECK (ends_at > starts_at),
+                FOREIGN KEY(patient_id) REFERENCES patients(id) ON DELETE CASCADE,
+                FOREIGN KEY(doctor_id) REFERENCES doctors(id) ON DELETE SET NULL,
+                FOREIGN KEY(clinic_id) REFERENCES clinics(id) ON DELETE SET NULL
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_appointments_starts_at ON appointments (starts_at);
+            CREATE INDEX IF NOT EXISTS idx_appointments_patient_id ON appointments (patient_id);
+            CREATE INDEX IF NOT EXISTS idx_appointments_doctor_id ON appointments (doctor_id, starts_at);
+            CREATE INDEX IF NOT EXISTS idx_appointments_clinic_id ON appointments (clinic_id, starts_at);
+        "#,
+        },
+        Migration {
+            version: 7,
+            kind: MigrationKind::Down,
+            description: "create_appointments_table",
+            sql: r#"
+            DROP TABLE IF EXISTS appointments;
+        "#,
+        },
+        Migration {
+            version: 8,
+            kind: MigrationKind::Up,
+            description: "create_audit_log",
+            sql: concat!(
+                r#"
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                table_name TEXT NOT NULL,
+                row_id TEXT NOT NULL,
+                operation TEXT NOT NULL CHECK (operation IN ('insert', 'update', 'delete')),
+                patient_id TEXT,
+                statement_id TEXT,
+                old_values TEXT,
+                new_values TEXT,
+                changed_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_audit_log_row ON audit_log (table_name, row_id);
+            CREATE INDEX IF NOT EXISTS idx_audit_log_patient_id ON audit_log (patient_id);
+            CREATE INDEX IF NOT EXISTS idx_audit_log_statement_id ON audit_log (statement_id);
+            "#,
+                audit_triggers!(
+                    "patients",
+                    patient: ("NEW.id", "OLD.id"),
+                    statement: ("NULL", "NULL"),
+                    columns: [id, name, phone, created_at, updated_at]
+                ),
+                audit_triggers!(
+                    "statements",
+                    patient: ("NEW.patient_id", "OLD.patient_id"),
+                    statement: ("NEW.id", "OLD.id"),
+                    columns: [id, patient_id, doctor_id, clinic_id, total, created_at, updated_at]
+                ),
+                audit_triggers!(
+                    "payments",
+                    patient: (
+                        "(SELECT patient_id FROM statements WHERE id = NEW.statement_id)",
+                        "(SELECT patient_id FROM statements WHERE id = OLD.statement_id)"
+                    ),
+                    statement: ("NEW.statement_id", "OLD.statement_id"),
+                    columns: [id, statement_id, amount, created_at, updated_at]
+                ),
+                audit_triggers!(
+                    "sessions",
+                    patient: (
+                        "(SELECT patient_id FROM statements WHERE id = NEW.statement_id)",
+                        "(SELECT patient_id FROM statements WHERE id = OLD.statement_id)"
+                    ),
+                    statement: ("NEW.statement_id", "OLD.statement_id"),
+                    columns: [id, statement_id, procedure, created_at, updated_at]
+                ),
+                audit_triggers!(
+                    "attachments",
+                    patient: (
+                        "(SELECT patient_id FROM statements WHERE id = NEW.statement_id)",
+                        "(SELECT patient_id FROM statements WHERE id = OLD.statement_id)"
+                    ),
+                    statement: ("NEW.statement_id", "OLD.statement_id"),
+                    columns: [id, statement_id, file_name, file_path, file_type, file_size, created_at]
+                ),
+                audit_triggers!(
+                    "appointments",
+                    patient: ("NEW.patient_id", "OLD.patient_id"),
+                    statement: ("NULL", "NULL"),
+                    columns: [
+                        id, patient_id, doctor_id, clinic_id, starts_at, ends_at, status, notes,
+                        created_at, updated_at
+                    ]
+                ),
+                audit_triggers!(
+                    "doctors",
+                    patient: ("NULL", "NULL"),
+                    statement: ("NULL", "NULL"),
+                    columns: [id, name, phone, created_at, updated_at]
+                ),
+                audit_triggers!(
+                    "clinics",
+                    patient: ("NULL", "NULL"),
+                    statement: ("NULL", "NULL"),
+                    columns: [id, name, created_at, updated_at]
+                ),
+            ),
+        },
+        Migration {
+            version: 8,
+            kind: MigrationKind::Down,
+            description: "create_audit_log",
+            sql: concat!(
+                drop_audit_triggers!("clinics"),
+                drop_audit_triggers!("doctors"),
+                drop_audit_triggers!("appointments"),
+                drop_audit_triggers!("attachments"),
+                drop_audit_triggers!("sessions"),
+                drop_audit_triggers!("payments"),
+                drop_audit_triggers!("statements"),
+                drop_audit_triggers!("patients"),
+                "DROP TABLE IF EXISTS audit_log;\n",
+            ),
+        },
+        Migration {
+            version: 9,
+            kind: MigrationKind::Up,
+            description: "soft_delete_patients_and_statements",
+            sql: concat!(
+                r#"
+            ALTER TABLE patients ADD COLUMN deleted_at TEXT;
+            ALTER TABLE statements ADD COLUMN deleted_at TEXT;
+
+            CREATE INDEX IF NOT EXISTS idx_patients_deleted_at ON patients (deleted_at);
+            CREATE INDEX IF NOT EXISTS idx_statements_deleted_at ON statements (deleted_at);
+            "#,
+                drop_audit_triggers!("patients"),
+                drop_audit_triggers!("statements"),
+                audit_triggers!(
+                    "patients",
+                    patient: ("NEW.id", "OLD.id"),
+                    statement: ("NULL", "NULL"),
+                    columns: [id, name, phone, created_at, updated_at, deleted_at]
+                ),
+                audit_triggers!(
+                    "statements",
+                    patient: ("NEW.patient_id", "OLD.patient_id"),
+                    statement: ("NEW.id", "OLD.id"),
+                    columns: [
+                        id, patient_id, doctor_id, clinic_id, total, created_at, updated_at,
+                        deleted_at
+                    ]
+                ),
+            ),
+        },
+        Migration {
+            version: 9,
+            kind: MigrationKind::Down,
+            description: "soft_delete_patients_and_statements",
+            sql: concat!(
+                drop_audit_triggers!("statements"),
+                drop_audit_triggers!("patients"),
+                audit_triggers!(
+                    "patients",
+                    patient: ("NEW.id", "OLD.id"),
+                    statement: ("NULL", "NULL"),
+                    columns: [id, name, phone, created_at, updated_at]
+                ),
+                audit_triggers!(
+                    "statements",
+                    patient: ("NEW.patient_id", "OLD.patient_id"),
+                    statement: ("NEW.id", "OLD.id"),
+                    columns: [id, patient_id, doctor_id, clinic_id, total, created_at, updated_at]
+                ),
+                r#"
+            DROP INDEX IF EXISTS idx_statements_deleted_at;
+            DROP INDEX IF EXISTS idx_patients_deleted_at;
+            ALTER TABLE statements DROP COLUMN deleted_at;
+            ALTER TABLE patients DROP COLUMN deleted_at;
+            "#,
+            ),
+        },
+        Migration {
+            version: 10,
+            kind: MigrationKind::Up,
+            description: "create_users",
+            sql: concat!(
+                r#"
+            CREATE TABLE IF NOT EXISTS users (
+                id TEXT PRIMARY KEY,
+                username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                display_name TEXT NOT NULL,
+                password_hash TEXT NOT NULL,
+                role TEXT NOT NULL CHECK (role IN ('admin', 'doctor', 'receptionist', 'accountant')),
+                active INTEGER NOT NULL DEFAULT 1,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+
+            -- The user signed in at this desk. Writes made straight from the
+            -- webview go through another connection pool, so the audit trigger
+            -- reads the acting user from here rather than from the app.
+            CREATE TABLE IF NOT EXISTS app_session (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                user_id TEXT REFERENCES users(id) ON DELETE SET NULL
+            );
+
+            ALTER TABLE audit_log ADD COLUMN user_id TEXT;
+            CREATE INDEX IF NOT EXISTS idx_audit_log_user_id ON audit_log (user_id);
+
+            CREATE TRIGGER IF NOT EXISTS audit_log_user AFTER INSERT ON audit_log BEGIN
+                UPDATE audit_log SET user_id = (SELECT user_id FROM app_session WHERE id = 1)
+                WHERE id = NEW.id;
+            END;
+            "#,
+                audit_triggers!(
+                    "users",
+                    patient: ("NULL", "NULL"),
+                    statement: ("NULL", "NULL"),
+                    columns: [id, username, display_name, role, active, created_at, updated_at]
+                ),
+            ),
+        },
+        Migration {
+            version: 10,
+            kind: MigrationKind::Down,
+            description: "create_users",
+            sql: concat!(
+                drop_audit_triggers!("users"),
+                r#"
+            DROP TRIGGER IF EXISTS audit_log_user;
+            DROP INDEX IF EXISTS idx_audit_log_user_id;
+            ALTER TABLE audit_log DROP COLUMN user_id;
+            DROP TABLE IF EXISTS app_session;
+            DROP TABLE IF EXISTS users;
+            "#,
+            ),
+        },
+        Migration {
+            version: 11,
+            kind: MigrationKind::Up,
+            description: "create_peer_sync",
+            sql: r#"
+            -- How far each workstation we sync with has got, as audit log ids:
+            -- theirs for what we pulled, ours for what we pushed
+            CREATE TABLE IF NOT EXISTS peer_sync_state (
+                peer TEXT PRIMARY KEY,
+                pulled_through INTEGER NOT NULL DEFAULT 0,
+                pushed_through INTEGER NOT NULL DEFAULT 0,
+                synced_at TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS payment_conflicts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                payment_id TEXT NOT NULL,
+                peer TEXT NOT NULL,
+                local_values TEXT,
+                remote_values TEXT,
+                kept TEXT NOT NULL CHECK (kept IN ('local', 'remote')),
+                detected_at TEXT NOT NULL DEFAULT (datetime('now')),
+                resolved_at TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_payment_conflicts_resolved_at ON payment_conflicts (resolved_at);
+            "#,
+        },
+        Migration {
+            version: 11,
+            kind: MigrationKind::Down,
+            description: "create_peer_sync",
+            sql: r#"
+            DROP INDEX IF EXISTS idx_payment_conflicts_resolved_at;
+            DROP TABLE IF EXISTS payment_conflicts;
+            DROP TABLE IF EXISTS peer_sync_state;
+            "#,
+        },
+        Migration {
+            version: 12,
+            kind: MigrationKind::Up,
+            description: "add_user_reminder_lead_minutes",
+            sql: r#"
+            -- JSON list of minutes before an appointment; NULL follows the app setting
+            ALTER TABLE users ADD COLUMN reminder_lead_minutes TEXT;
+            "#,
+        },
+        Migration {
+            version: 12,
+            kind: MigrationKind::Down,
+            description: "add_user_reminder_lead_minutes",
+            sql: r#"
+            ALTER TABLE users DROP COLUMN reminder_lead_minutes;
+            "#,
+        },
     ]
 }
 
-pub fn get_db_plugin<R>(db_url: &str) -> TauriPlugin<R, Option<tauri_plugin_sql::PluginConfig>>
-where
-    R: Runtime,
-{
-    tauri_plugin_sql::Builder::new()
-        .add_migrations(db_url, db_migrations())
-        .build()
-}
\ No newline at end of file
+// Every connection to an encrypted file has to send the key before anything else
+fn with_key(options: SqliteConnectOptions, key: Option<&Key>) -> SqliteConnectOptions {
+    match key {
+        Some(key) => options.pragma("key", format!("\"{}\"", key.sqlcipher_key())),
+        None => options,
+    }
+}
+
+/// Opens a pool over the database file.
+///
+/// The pool is lazy so that the first connection happens after
+/// [`prepare`] has checked and migrated the file. With `archive_wal` the
+/// pool never checkpoints on its own, the WAL archiver does it after
+/// copying the log.
+pub fn connect(db_url: &str, key: Option<&Key>, archive_wal: bool) -> Result<SqlitePool> {
+    let mut options = SqliteConnectOptions::from_str(db_url)?
+        .create_if_missing(true)
+        .foreign_keys(true)
+        .journal_mode(SqliteJournalMode::Wal);
+    let mut pool = SqlitePoolOptions::new();
+
+    if archive_wal {
+        options = options.pragma("wal_autocheckpoint", "0");
+        // SQLite checkpoints and deletes the WAL when the last connection
+        // closes, which would lose commits the archiver has not copied yet
+        pool = pool
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None);
+    }
+
+    Ok(pool.connect_lazy_with(with_key(options, key)))
+}
+
+/// Opens one connection to the WAL mode database at `path` that leaves
+/// checkpoints to the caller.
+pub async fn open_wal(path: &Path, key: Option<&Key>) -> Result<SqliteConnection> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .foreign_keys(false)
+        .journal_mode(SqliteJournalMode::Wal)
+        .pragma("wal_autocheckpoint", "0");
+    Ok(SqliteConnection::connect_with(&with_key(options, key)).await?)
+}
+
+/// Opens the database file at `path` without writing to it, for checking
+/// snapshots before they replace the live database.
+pub async fn open_read_only(path: &Path, key: Option<&Key>) -> Result<SqliteConnection> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .foreign_keys(false);
+    Ok(SqliteConnection::connect_with(&with_key(options, key)).await?)
+}
+
+/// Copies the database at `source` into a new file at `target`, encrypted
+/// with `target_key` or plain when it is `None`, and checks that the copy
+/// opens with it.
+pub async fn export(
+    source: &Path,
+    source_key: Option<&Key>,
+    target: &Path,
+    target_key: Option<&Key>,
+) -> Result<()> {
+    // ATTACH opens without SQLITE_OPEN_CREATE like the connection it runs
+    // on, an empty file counts as a new database
+    tokio::fs::File::create(target).await?;
+
+    let options = SqliteConnectOptions::new()
+        .filename(source)
+        .foreign_keys(false);
+    let mut conn = SqliteConnection::connect_with(&with_key(options, source_key)).await?;
+
+    sqlx::query("ATTACH DATABASE ? AS exported KEY ?")
+        .bind(target.display().to_string())
+        .bind(target_key.map(Key::sqlcipher_key).unwrap_or_default())
+        .execute(&mut conn)
+        .await?;
+    sqlx::query("SELECT sqlcipher_export('exported')")
+        .execute(&mut conn)
+        .await?;
+    sqlx::query("DETACH DATABASE exported")
+        .execute(&mut conn)
+        .await?;
+    conn.close().await?;
+
+    let options = SqliteConnectOptions::new()
+        .filename(target)
+        .foreign_keys(false);
+    let mut conn = SqliteConnection::connect_with(&with_key(options, target_key)).await?;
+    let checked = check_integrity(&mut conn).await;
+    conn.close().await?;
+
+    if checked.is_err() {
+        let _ = tokio::fs::remove_file(target).await;
+    }
+    checked
+}
+
+/// The newest schema version this build can read.
+pub fn latest_version() -> i64 {
+    db_migrations()
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+// The scripts are never edited once released, so the checksums in
+// `_sqlx_migrations` keep matching
+fn migrator() -> Migrator {
+    let mut migrations: Vec<SqlxMigration> = db_migrations()
+        .into_iter()
+        .map(|migration| {
+            let kind = match migration.kind {
+                MigrationKind::Up => MigrationType::ReversibleUp,
+                MigrationKind::Down => MigrationType::ReversibleDown,
+            };
+            SqlxMigration::new(
+                migration.version,
+                migration.description.into(),
+                kind,
+                migration.sql.into(),
+                false,
+            )
+        })
+        .collect();
+    migrations.sort_by_key(|migration| migration.version);
+
+    Migrator {
+        migrations: Cow::Owned(migrations),
+        ..Migrator::DEFAULT
+    }
+}
+
+/// Applies every pending migration.
+pub async fn migrate(conn: &mut SqliteConnection) -> Result<()> {
+    Ok(migrator().run_direct(conn).await?)
+}
+
+/// Reverts applied migrations newer than `target`, newest first.
+///
+/// The app never downgrades in place, a newer database is refused instead,
+/// so only the migration tests walk the Down scripts.
+#[cfg(test)]
+async fn rollback(conn: &mut SqliteConnection, target: i64) -> Result<()> {
+    Ok(migrator().undo(conn, target).await?)
+}
+
+/// Highest successfully applied migration, 0 for a new database.
+pub async fn schema_version(conn: &mut SqliteConnection) -> Result<i64> {
+    let tracked: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !tracked {
+        return Ok(0);
+    }
+
+    let version = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success = 1",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(version)
+}
+
+/// Fails if SQLite reports corruption or rows that reference missing parents.
+pub async fn check_integrity(conn: &mut SqliteConnection) -> Result<()> {
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if problems.iter().any(|problem| problem != "ok") {
+        return Err(Error::Integrity(format!(
+            "Database integrity check failed: {}",
+            problems.join("; ")
+        )));
+    }
+
+    let violations = sqlx::query("PRAGMA foreign_key_check")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    if !violations.is_empty() {
+        let mut tables: Vec<String> = violations
+            .iter()
+            .filter_map(|row| row.try_get::<String, _>(0).ok())
+            .collect();
+        tables.sort();
+        tables.dedup();
+
+        return Err(Error::Integrity(format!(
+            "Database has {} rows with broken references in {}",
+            violations.len(),
+            tables.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks the database file and brings its schema up to date.
+///
+/// This runs before the pool is opened, so every command finds the schema
+/// already up to date. Foreign keys are off on this connection
+/// because the copy-and-rename migrations would otherwise cascade the
+/// implicit delete of `DROP TABLE` into child tables.
+pub async fn prepare(db_url: &str, data_dir: &Path, key: Option<&Key>) -> Result<()> {
+    let options = SqliteConnectOptions::from_str(db_url)?
+        .create_if_missing(true)
+        .foreign_keys(false);
+    let mut conn = SqliteConnection::connect_with(&with_key(options, key)).await?;
+
+    check_integrity(&mut conn).await?;
+
+    let current = schema_version(&mut conn).await?;
+    let latest = latest_version();
+
+    if current > latest {
+        return Err(Error::Integrity(format!(
+            "The database was created by a newer version of the app (schema {current}, this build supports {latest})"
+        )));
+    }
+
+    if current > 0 && current < latest {
+        let snapshot = snapshot(&mut conn, data_dir, current).await?;
+        log::info!(
+            "Migrating database from schema {} to {}, snapshot saved to {}",
+            current,
+            latest,
+            snapshot.display()
+        );
+    }
+
+    migrate(&mut conn).await?;
+    if current < latest {
+        check_integrity(&mut conn).await?;
+    }
+
+    conn.close().await?;
+    Ok(())
+}
+
+async fn snapshot(conn: &mut SqliteConnection, data_dir: &Path, version: i64) -> Result<PathBuf> {
+    let dir = filesystem::backups_dir(data_dir);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let path = dir.join(format!(
+        "pre_migration_v{}_{}.db",
+        version,
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+
+    // An encrypted database gives a snapshot encrypted with the same key
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.display().to_string())
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(path)
+}
+
+/// A fully migrated database that lives as long as the pool, for tests.
+///
+/// Every connection to `sqlite::memory:` gets its own database, so the pool
+/// keeps exactly one connection open for its whole life.
+#[cfg(test)]
+pub(crate) async fn memory_pool() -> SqlitePool {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")
+        .unwrap()
+        .foreign_keys(false);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(options)
+        .await
+        .unwrap();
+
+    let mut conn = pool.acquire().await.unwrap();
+    migrate(&mut conn).await.unwrap();
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+
+    drop(conn);
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn table_names(conn: &mut SqliteConnection) -> Vec<String> {
+        sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(&mut *conn)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrations_round_trip() {
+        let path = std::env::temp_dir().join(format!("sgmc-migrations-{}.db", uuid::Uuid::new_v4()));
+        let options = SqliteConnectOptions::new()
+            .filename(&path)
+            .create_if_missing(true)
+            .foreign_keys(false);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+
+        migrate(&mut conn).await.unwrap();
+        assert_eq!(schema_version(&mut conn).await.unwrap(), latest_version());
+        let migrated = table_names(&mut conn).await;
+
+        // Two patients sharing a phone, which migration 3 did not allow
+        sqlx::query(
+            "INSERT INTO patients (id, name, phone, created_at, updated_at)
+             VALUES ('a', 'Ahmad', '0791111111', 1, 1), ('b', 'Sara', '0791111111', 2, 2)",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+        rollback(&mut conn, 3).await.unwrap();
+        assert_eq!(schema_version(&mut conn).await.unwrap(), 3);
+        let phones: Vec<String> = sqlx::query_scalar("SELECT phone FROM patients ORDER BY id")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(phones, ["0791111111", "0791111111 #b"]);
+
+        rollback(&mut conn, 0).await.unwrap();
+        assert_eq!(schema_version(&mut conn).await.unwrap(), 0);
+        assert_eq!(table_names(&mut conn).await, ["_sqlx_migrations"]);
+
+        migrate(&mut conn).await.unwrap();
+        assert_eq!(schema_version(&mut conn).await.unwrap(), latest_version());
+        assert_eq!(table_names(&mut conn).await, migrated);
+        check_integrity(&mut conn).await.unwrap();
+
+        conn.close().await.unwrap();
+        let _ = std::fs::remove_file(&path);
+    }
+}
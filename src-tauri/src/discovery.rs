@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+use tokio::task;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+/// What every copy of the app advertises, and browses for.
+pub const SERVICE_TYPE: &str = "_sgmc._tcp.local.";
+const APP_PROPERTY: &str = "app";
+const APP_NAME: &str = "sgmc";
+const VERSION_PROPERTY: &str = "version";
+// Instances answer a query within a second or so on a quiet LAN
+const BROWSE_WINDOW: Duration = Duration::from_secs(2);
+
+struct Advertised {
+    fullname: String,
+    /// e.g. `sgmc-frontdesk.local`, without the trailing dot
+    host_name: String,
+}
+
+/// The mDNS responder, started once the server is listening.
+///
+/// Phones reach the scanner through the advertised host name, which keeps
+/// resolving after DHCP hands the PC a new address.
+#[derive(Default)]
+pub struct Discovery {
+    daemon: Mutex<Option<ServiceDaemon>>,
+    advertised: Mutex<Option<Advertised>>,
+}
+
+fn discovery_error(err: mdns_sd::Error) -> Error {
+    Error::Discovery(err.to_string())
+}
+
+impl Discovery {
+    fn daemon(&self) -> Result<ServiceDaemon> {
+        let mut daemon = self.daemon.lock().unwrap();
+        if let Some(daemon) = daemon.as_ref() {
+            return Ok(daemon.clone());
+        }
+
+        let started = ServiceDaemon::new().map_err(discovery_error)?;
+        *daemon = Some(started.clone());
+        Ok(started)
+    }
+
+    /// The host name phones can use instead of the IP address, once advertised.
+    pub fn host_name(&self) -> Option<String> {
+        self.advertised
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|advertised| advertised.host_name.clone())
+    }
+
+    /// Announces `host_name.local` and the `_sgmc._tcp` service on `port`.
+    ///
+    /// A wildcard bind follows every interface, including addresses picked
+    /// up later; otherwise only the bound address is announced.
+    pub fn advertise(&self, host_name: &str, bind_address: IpAddr, port: u16) -> Result<()> {
+        let daemon = self.daemon()?;
+        let instance = machine_name();
+        let properties = [
+            (APP_PROPERTY, APP_NAME),
+            (VERSION_PROPERTY, env!("CARGO_PKG_VERSION")),
+        ];
+        let mdns_host = format!("{host_name}.local.");
+
+        let service = if bind_address.is_unspecified() {
+            ServiceInfo::new(
+                SERVICE_TYPE,
+                &instance,
+                &mdns_host,
+                "",
+                port,
+                &properties[..],
+            )
+            .map(ServiceInfo::enable_addr_auto)
+        } else {
+            ServiceInfo::new(
+                SERVICE_TYPE,
+                &instance,
+                &mdns_host,
+                bind_address,
+                port,
+                &properties[..],
+            )
+        }
+        .map_err(discovery_error)?;
+
+        let fullname = service.get_fullname().to_string();
+        daemon.register(service).map_err(discovery_error)?;
+
+        *self.advertised.lock().unwrap() = Some(Advertised {
+            fullname,
+            host_name: format!("{host_name}.local"),
+        });
+        Ok(())
+    }
+
+    /// Other copies of the app that answer within [`BROWSE_WINDOW`].
+    pub async fn browse(&self) -> Result<Vec<PeerInfo>> {
+        let daemon = self.daemon()?;
+        let own = self
+            .advertised
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|advertised| advertised.fullname.clone());
+        let events = daemon.browse(SERVICE_TYPE).map_err(discovery_error)?;
+
+        let peers = task::spawn_blocking(move || {
+            let deadline = Instant::now() + BROWSE_WINDOW;
+            let mut peers = HashMap::new();
+            while let Ok(event) = events.recv_deadline(deadline) {
+                let ServiceEvent::ServiceResolved(service) = event else {
+                    continue;
+                };
+
+                if own.as_deref() == Some(service.get_fullname())
+                    || service.get_property_val_str(APP_PROPERTY) != Some(APP_NAME)
+                {
+                    continue;
+                }
+
+                peers.insert(service.get_fullname().to_string(), PeerInfo::from(&service));
+            }
+            peers
+        })
+        .await
+        .map_err(|err| Error::Discovery(err.to_string()))?;
+
+        if let Err(err) = daemon.stop_browse(SERVICE_TYPE) {
+            log::warn!("Failed to stop browsing for peers: {}", err);
+        }
+
+        let mut peers: Vec<PeerInfo> = peers.into_values().collect();
+        peers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(peers)
+    }
+}
+
+/// Another SGMC instance found on the LAN.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo {
+    /// The PC's name
+    pub name: String,
+    pub host_name: String,
+    pub port: u16,
+    pub addresses: Vec<String>,
+    pub version: Option<String>,
+    /// Base URL for syncing with it
+    pub url: String,
+}
+
+impl From<&ServiceInfo> for PeerInfo {
+    fn from(service: &ServiceInfo) -> Self {
+        let host_name = service.get_hostname().trim_end_matches('.').to_string();
+        let name = service
+            .get_fullname()
+            .strip_suffix(SERVICE_TYPE)
+            .unwrap_or(service.get_fullname())
+            .trim_end_matches('.')
+            .to_string();
+
+        let mut addresses: Vec<String> = service
+            .get_addresses()
+            .iter()
+            .map(IpAddr::to_string)
+            .collect();
+        addresses.sort();
+
+        Self {
+            url: format!("http://{}:{}", host_name, service.get_port()),
+            name,
+            host_name,
+            port: service.get_port(),
+            addresses,
+            version: service
+                .get_property_val_str(VERSION_PROPERTY)
+                .map(str::to_string),
+        }
+    }
+}
+
+/// This PC's name as the OS reports it.
+pub fn machine_name() -> String {
+    gethostname::gethostname()
+        .to_string_lossy()
+        .trim()
+        .to_string()
+}
+
+/// `sgmc-<machine name>` as a DNS label, kept apart from the name the OS
+/// may already announce for itself.
+pub fn default_host_name() -> String {
+    let machine: String = machine_name()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let machine = machine.trim_matches('-');
+
+    let mut name = if machine.is_empty() {
+        APP_NAME.to_string()
+    } else {
+        format!("{APP_NAME}-{machine}")
+    };
+    name.truncate(63);
+    name.trim_end_matches('-').to_string()
+}
+
+/// Starts advertising once the server has a port. Failing only costs phones
+/// the host name, so it is logged and the scan URL falls back to the IP.
+pub fn advertise(app: &AppHandle, port: u16) {
+    let state = app.state::<AppState>();
+    let config = &state.config;
+    let Ok(bind_address) = config.bind_address.parse::<IpAddr>() else {
+        return;
+    };
+
+    // Nobody else on the LAN could connect anyway
+    if bind_address.is_loopback() {
+        return;
+    }
+
+    match app
+        .state::<Discovery>()
+        .advertise(&config.mdns_hostname, bind_address, port)
+    {
+        Ok(()) => log::info!(
+            "Advertising {} as {}.local on port {}",
+            SERVICE_TYPE,
+            config.mdns_hostname,
+            port
+        ),
+        Err(err) => log::error!("Failed to advertise over mDNS: {}", err),
+    }
+}
+
+#[tauri::command]
+pub async fn discover_peers(
+    state: State<'_, AppState>,
+    discovery: State<'_, Discovery>,
+) -> Result<Vec<PeerInfo>> {
+    state.require(Permission::ManageBackups)?;
+    discovery.browse().await
+}
@@ -0,0 +1,1092 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager, State};
+use tokio::fs;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    backup::{self, wal},
+    database,
+    error::{Error, Result},
+    filesystem,
+    repository::attachments::attachments_dir,
+    sync::SyncScheduler,
+};
+
+const KEY_FILE: &str = "encryption.json";
+const BACKUP_KEY_FILE: &str = "backup-key.json";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const MIN_PASSPHRASE_LENGTH: usize = 12;
+
+/// Starts every encrypted file. Attachments without it were written before
+/// encryption was enabled, or restored from an older backup.
+const FILE_MAGIC: &[u8] = b"SGMCENC1";
+
+/// Starts every backup sealed with the backup passphrase, followed by the
+/// length of its key file header, the header and the sealed payload.
+const BACKUP_MAGIC: &[u8] = b"SGMCBAK1";
+
+// Sealed into the key file, so a wrong passphrase is caught before SQLCipher
+// reports the database as corrupt
+const CHECK_TEXT: &[u8] = b"SGMC encryption check";
+
+// Re-encrypted copies wait under this suffix until everything is converted
+const PENDING_SUFFIX: &str = "rekey";
+
+// Argon2id cost for new key files, older files keep the cost they were made with
+const KDF_MEMORY_KIB: u32 = 64 * 1024;
+const KDF_ITERATIONS: u32 = 3;
+const KDF_PARALLELISM: u32 = 1;
+
+/// The data key, derived from the passphrase with Argon2id.
+///
+/// One key covers the database (as a raw SQLCipher key) and every attachment.
+#[derive(Clone)]
+pub struct Key([u8; KEY_LEN]);
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+impl Key {
+    /// The value for SQLCipher's `PRAGMA key` and `ATTACH ... KEY`. Passing
+    /// the raw key skips SQLCipher's own passphrase derivation.
+    pub fn sqlcipher_key(&self) -> String {
+        let hex: String = self.0.iter().map(|byte| format!("{byte:02x}")).collect();
+        format!("x'{hex}'")
+    }
+
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new(&self.0.into())
+    }
+
+    /// Encrypts `plaintext` as `FILE_MAGIC`, a random nonce and the ciphertext.
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: FILE_MAGIC,
+                },
+            )
+            .map_err(|_| Error::Encryption("Failed to encrypt data".to_string()))?;
+
+        let mut sealed = Vec::with_capacity(FILE_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(FILE_MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        Ok(sealed)
+    }
+
+    /// Decrypts [`Key::seal`] output, failing if it was sealed with another key or altered.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let invalid =
+            || Error::Encryption("Encrypted data is damaged or uses another key".to_string());
+
+        let body = sealed.strip_prefix(FILE_MAGIC).ok_or_else(invalid)?;
+        if body.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+
+        self.cipher()
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: FILE_MAGIC,
+                },
+            )
+            .map_err(|_| invalid())
+    }
+}
+
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(FILE_MAGIC)
+}
+
+/// Encrypts file contents for disk, or passes them through when encryption is off.
+pub fn seal_file(key: Option<&Key>, data: Vec<u8>) -> Result<Vec<u8>> {
+    match key {
+        Some(key) => key.seal(&data),
+        None => Ok(data),
+    }
+}
+
+/// Reverses [`seal_file`]. Plain files stay readable with encryption on.
+pub fn open_file(key: Option<&Key>, data: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_sealed(&data) {
+        return Ok(data);
+    }
+
+    key.ok_or(Error::Locked)?.open(&data)
+}
+
+pub async fn read_file(path: &Path, key: Option<&Key>) -> Result<Vec<u8>> {
+    open_file(key, fs::read(path).await?)
+}
+
+fn key_file_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(KEY_FILE)
+}
+
+fn backup_key_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(BACKUP_KEY_FILE)
+}
+
+/// Lives next to the database as `encryption.json`. Its presence is what
+/// marks the data folder as encrypted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    /// `CHECK_TEXT` sealed with the key
+    check: String,
+}
+
+impl KeyFile {
+    pub async fn load(data_dir: &Path) -> Result<Option<Self>> {
+        match fs::read(key_file_path(data_dir)).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn remove(data_dir: &Path) -> Result<()> {
+        match fs::remove_file(key_file_path(data_dir)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// A fresh salt and key for `passphrase`.
+    pub async fn create(passphrase: &str) -> Result<(Self, Key)> {
+        if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+            return Err(Error::Validation(format!(
+                "Passphrase must be at least {MIN_PASSPHRASE_LENGTH} characters"
+            )));
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let mut key_file = KeyFile {
+            salt: STANDARD.encode(salt),
+            memory_kib: KDF_MEMORY_KIB,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+            check: String::new(),
+        };
+        let key = key_file.derive(passphrase).await?;
+        key_file.check = STANDARD.encode(key.seal(CHECK_TEXT)?);
+
+        Ok((key_file, key))
+    }
+
+    /// The key for `passphrase`, or `WrongPassphrase`.
+    pub async fn unlock(&self, passphrase: &str) -> Result<Key> {
+        let key = self.derive(passphrase).await?;
+        let check = STANDARD
+            .decode(&self.check)
+            .map_err(|err| Error::Encryption(format!("Invalid key file: {err}")))?;
+
+        match key.open(&check) {
+            Ok(text) if text == CHECK_TEXT => Ok(key),
+            _ => Err(Error::WrongPassphrase),
+        }
+    }
+
+    async fn derive(&self, passphrase: &str) -> Result<Key> {
+        let salt = STANDARD
+            .decode(&self.salt)
+            .map_err(|err| Error::Encryption(format!("Invalid key file: {err}")))?;
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|err| Error::Encryption(format!("Invalid key file: {err}")))?;
+
+        // Derivation is deliberately slow, keep it off the async workers
+        let passphrase = passphrase.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut key = [0u8; KEY_LEN];
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+                .map_err(|err| Error::Encryption(format!("Failed to derive key: {err}")))?;
+            Ok(Key(key))
+        })
+        .await
+        .map_err(|err| Error::Encryption(err.to_string()))?
+    }
+}
+
+/// Seals what goes to Google Drive or a backup folder.
+///
+/// Separate from the database key so backups can be restored on another
+/// machine with the backup passphrase alone. Every sealed file carries the
+/// salt it was made with. The key itself is only kept in `backup-key.json`
+/// when the database key encrypts that file, otherwise the passphrase is
+/// entered once per session, see [`StoredBackupKey`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BackupKey {
+    key_file: KeyFile,
+    #[serde(with = "base64_key")]
+    key: Key,
+}
+
+mod base64_key {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    use super::{Key, KEY_LEN};
+
+    pub fn serialize<S: Serializer>(key: &Key, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(key.0))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Key, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let bytes = STANDARD.decode(encoded).map_err(D::Error::custom)?;
+        let key: [u8; KEY_LEN] = bytes
+            .try_into()
+            .map_err(|_| D::Error::custom("invalid key length"))?;
+        Ok(Key(key))
+    }
+}
+
+impl fmt::Debug for BackupKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BackupKey(..)")
+    }
+}
+
+/// What `backup-key.json` holds. Without a database key to encrypt it the
+/// file would hand the backup key to anyone who can read the data folder,
+/// so only the salt and check are written and the key stays in memory.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredBackupKey {
+    Unlocked(BackupKey),
+    Locked { key_file: KeyFile },
+}
+
+impl StoredBackupKey {
+    async fn load(data_dir: &Path, local: Option<&Key>) -> Result<Option<Self>> {
+        match fs::read(backup_key_path(data_dir)).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&open_file(local, data)?)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn key_file(&self) -> &KeyFile {
+        match self {
+            Self::Unlocked(backup_key) => &backup_key.key_file,
+            Self::Locked { key_file } => key_file,
+        }
+    }
+
+    /// The same file with the key left out, for when the database key goes away.
+    fn locked(data: &[u8]) -> Result<Vec<u8>> {
+        let stored: Self = serde_json::from_slice(data)?;
+        let key_file = stored.key_file().clone();
+        Ok(serde_json::to_vec(&Self::Locked { key_file })?)
+    }
+}
+
+impl BackupKey {
+    pub async fn create(passphrase: &str) -> Result<Self> {
+        let (key_file, key) = KeyFile::create(passphrase).await?;
+        Ok(Self { key_file, key })
+    }
+
+    /// The key backups are sealed with, or `None` when they are uploaded as
+    /// is. Without a database key that is the `unlocked` one from earlier
+    /// in the session, or `BackupLocked` until the passphrase is entered.
+    pub async fn load(
+        data_dir: &Path,
+        local: Option<&Key>,
+        unlocked: Option<&BackupKey>,
+    ) -> Result<Option<Self>> {
+        match StoredBackupKey::load(data_dir, local).await? {
+            None => Ok(None),
+            Some(StoredBackupKey::Unlocked(backup_key)) => {
+                if local.is_none() {
+                    // Written in the clear by an older version
+                    backup_key.save(data_dir, None).await?;
+                    log::info!("Removed the plain backup key from the data folder");
+                }
+                Ok(Some(backup_key))
+            }
+            Some(StoredBackupKey::Locked { key_file }) => unlocked
+                .filter(|backup_key| backup_key.key_file.check == key_file.check)
+                .cloned()
+                .map(Some)
+                .ok_or(Error::BackupLocked),
+        }
+    }
+
+    /// The key for `passphrase`, or `WrongPassphrase`. Kept on disk from
+    /// now on if the database key can encrypt it.
+    pub async fn unlock(data_dir: &Path, local: Option<&Key>, passphrase: &str) -> Result<Self> {
+        let stored = StoredBackupKey::load(data_dir, local)
+            .await?
+            .ok_or_else(|| Error::Conflict("Backups are not encrypted".to_string()))?;
+        let key_file = stored.key_file().clone();
+        let key = key_file.unlock(passphrase).await?;
+        let backup_key = Self { key_file, key };
+
+        if local.is_some() && matches!(stored, StoredBackupKey::Locked { .. }) {
+            backup_key.save(data_dir, local).await?;
+        }
+
+        Ok(backup_key)
+    }
+
+    pub async fn save(&self, data_dir: &Path, local: Option<&Key>) -> Result<()> {
+        let stored = match local {
+            Some(_) => StoredBackupKey::Unlocked(self.clone()),
+            None => StoredBackupKey::Locked {
+                key_file: self.key_file.clone(),
+            },
+        };
+
+        let path = backup_key_path(data_dir);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, seal_file(local, serde_json::to_vec(&stored)?)?).await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(data_dir: &Path) -> Result<()> {
+        match fs::remove_file(backup_key_path(data_dir)).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn seal(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let header = serde_json::to_vec(&self.key_file)?;
+        let sealed = self.key.seal(data)?;
+
+        let mut backup = Vec::with_capacity(BACKUP_MAGIC.len() + 4 + header.len() + sealed.len());
+        backup.extend_from_slice(BACKUP_MAGIC);
+        backup.extend_from_slice(&(header.len() as u32).to_le_bytes());
+        backup.extend_from_slice(&header);
+        backup.extend_from_slice(&sealed);
+
+        Ok(backup)
+    }
+}
+
+pub fn is_backup_sealed(data: &[u8]) -> bool {
+    data.starts_with(BACKUP_MAGIC)
+}
+
+/// Opens sealed backups with the passphrase entered for a restore.
+///
+/// Changing the passphrase makes a new salt, so each salt seen is derived
+/// once and kept for the rest of the restore.
+pub struct BackupOpener {
+    passphrase: String,
+    keys: HashMap<String, Key>,
+}
+
+impl BackupOpener {
+    pub fn new(passphrase: &str) -> Self {
+        Self {
+            passphrase: passphrase.to_string(),
+            keys: HashMap::new(),
+        }
+    }
+
+    pub async fn open(&mut self, backup: &[u8]) -> Result<Vec<u8>> {
+        let invalid = || Error::Backup("Encrypted backup is damaged".to_string());
+
+        let body = backup.strip_prefix(BACKUP_MAGIC).ok_or_else(invalid)?;
+        let (length, body) = body.split_first_chunk::<4>().ok_or_else(invalid)?;
+        let length = u32::from_le_bytes(*length) as usize;
+        if body.len() < length {
+            return Err(invalid());
+        }
+        let (header, sealed) = body.split_at(length);
+        let key_file: KeyFile = serde_json::from_slice(header).map_err(|_| invalid())?;
+
+        let key = match self.keys.get(&key_file.salt) {
+            Some(key) => key.clone(),
+            None => {
+                let key = key_file.unlock(&self.passphrase).await?;
+                self.keys.insert(key_file.salt.clone(), key.clone());
+                key
+            }
+        };
+
+        key.open(sealed)
+    }
+}
+
+fn pending_path(path: &Path) -> PathBuf {
+    let mut pending = path.as_os_str().to_owned();
+    pending.push(".");
+    pending.push(PENDING_SUFFIX);
+    PathBuf::from(pending)
+}
+
+fn is_pending(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == PENDING_SUFFIX)
+}
+
+async fn files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !fs::try_exists(dir).await? {
+        return Ok(files);
+    }
+
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() && !is_pending(&entry.path()) {
+            files.push(entry.path());
+        }
+    }
+
+    Ok(files)
+}
+
+async fn discard(pending: &[(PathBuf, PathBuf)]) {
+    for (temp_path, _) in pending {
+        let _ = fs::remove_file(temp_path).await;
+    }
+}
+
+// A rename only survives a crash once the data it points at is on disk
+async fn sync_file(path: &Path) -> Result<()> {
+    fs::File::open(path).await?.sync_all().await?;
+    Ok(())
+}
+
+async fn write_synced(path: &Path, data: &[u8]) -> Result<()> {
+    fs::write(path, data).await?;
+    sync_file(path).await
+}
+
+/// `encryption.json.rekey`, the key a rekey switches to or `null` when it
+/// turns encryption off. It is written once every side file is complete, so
+/// from then on the rekey can always be finished.
+fn pending_key_path(data_dir: &Path) -> PathBuf {
+    pending_path(&key_file_path(data_dir))
+}
+
+/// Side files left by a rekey next to the file each one replaces, not
+/// counting the database.
+async fn pending_files(data_dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut found = Vec::new();
+    for dir in [filesystem::backups_dir(data_dir), attachments_dir(data_dir)] {
+        if !fs::try_exists(&dir).await? {
+            continue;
+        }
+
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() && is_pending(&entry.path()) {
+                found.push(entry.path());
+            }
+        }
+    }
+
+    let backup_key = pending_path(&backup_key_path(data_dir));
+    if fs::try_exists(&backup_key).await? {
+        found.push(backup_key);
+    }
+
+    Ok(found
+        .into_iter()
+        .map(|temp_path| {
+            let path = temp_path.with_extension("");
+            (temp_path, path)
+        })
+        .collect())
+}
+
+/// Everything after the database is swapped: the other files, then the key
+/// file last. Safe to run again if it is interrupted.
+async fn roll_forward(data_dir: &Path) -> Result<usize> {
+    let pending = pending_files(data_dir).await?;
+    for (temp_path, path) in &pending {
+        fs::rename(temp_path, path).await?;
+    }
+
+    // WAL chains hold raw pages under the old key and cannot be re-sealed,
+    // the archiver starts a fresh chain once the app is relaunched
+    let chains = wal::wal_dir(data_dir);
+    if fs::try_exists(&chains).await? {
+        fs::remove_dir_all(&chains).await?;
+    }
+
+    let pending_key = pending_key_path(data_dir);
+    let next: Option<KeyFile> = serde_json::from_slice(&fs::read(&pending_key).await?)?;
+    match next {
+        Some(_) => fs::rename(&pending_key, key_file_path(data_dir)).await?,
+        None => {
+            KeyFile::remove(data_dir).await?;
+            fs::remove_file(&pending_key).await?;
+        }
+    }
+
+    Ok(pending.len())
+}
+
+/// Finishes or undoes a rekey the app was closed in the middle of. Runs at
+/// startup before the key file is read.
+///
+/// With the pending key written and the database already swapped it is
+/// finished; otherwise the originals still match the key file and the side
+/// files are removed.
+pub async fn recover_rekey(db_path: &Path, data_dir: &Path) -> Result<()> {
+    let pending_key = pending_key_path(data_dir);
+    let db_pending = pending_path(db_path);
+
+    if fs::try_exists(&pending_key).await? && !fs::try_exists(&db_pending).await? {
+        let finished = roll_forward(data_dir).await?;
+        log::warn!("Finished an interrupted re-encryption of {} files", finished);
+        return Ok(());
+    }
+
+    // The pending key goes first, without it nothing left can be rolled forward
+    let mut leftovers = vec![pending_key, db_pending];
+    leftovers.extend(
+        pending_files(data_dir)
+            .await?
+            .into_iter()
+            .map(|(temp_path, _)| temp_path),
+    );
+
+    let mut removed = 0;
+    for path in leftovers {
+        if fs::remove_file(&path).await.is_ok() {
+            removed += 1;
+        }
+    }
+    if removed > 0 {
+        log::warn!("Rolled back an interrupted re-encryption, removed {} files", removed);
+    }
+
+    Ok(())
+}
+
+/// Re-encrypts the database, pre-migration snapshots, attachments and the
+/// backup key from `from` to `to`, where `None` means plain files, then
+/// saves or removes the key file to match. Point-in-time recovery history
+/// is dropped, it only opens with the old key.
+///
+/// Everything is written to side files first, then the new key under a
+/// pending name, then the database is swapped and the key file committed
+/// last. A crash at any point is finished or undone by [`recover_rekey`] on
+/// the next launch. The pool is closed before the database is read, so the
+/// app has to be relaunched afterwards either way. Copies already in Google
+/// Drive or a backup folder keep the encryption they were made with.
+pub async fn rekey(
+    pool: &SqlitePool,
+    db_path: &Path,
+    data_dir: &Path,
+    from: Option<&Key>,
+    to: Option<(&KeyFile, &Key)>,
+) -> Result<()> {
+    let to_key = to.map(|(_, key)| key);
+
+    pool.close().await;
+
+    let db_pending = pending_path(db_path);
+    let pending_key = pending_key_path(data_dir);
+
+    let mut pending = Vec::new();
+    let prepared = async {
+        database::export(db_path, from, &db_pending, to_key).await?;
+        sync_file(&db_pending).await?;
+
+        for snapshot in files_in(&filesystem::backups_dir(data_dir)).await? {
+            if snapshot
+                .extension()
+                .is_none_or(|extension| extension != "db")
+            {
+                continue;
+            }
+
+            // Snapshots from before an earlier rotation no longer open with `from`
+            let temp_path = pending_path(&snapshot);
+            let exported = async {
+                database::export(&snapshot, from, &temp_path, to_key).await?;
+                sync_file(&temp_path).await
+            };
+            match exported.await {
+                Ok(()) => pending.push((temp_path, snapshot)),
+                Err(err) => {
+                    let _ = fs::remove_file(&temp_path).await;
+                    log::warn!("Leaving snapshot {} as is: {}", snapshot.display(), err);
+                }
+            }
+        }
+
+        let mut files = files_in(&attachments_dir(data_dir)).await?;
+        let backup_key = backup_key_path(data_dir);
+        if fs::try_exists(&backup_key).await? {
+            files.push(backup_key.clone());
+        }
+
+        for file in files {
+            let data = match read_file(&file, from).await {
+                Ok(data) => data,
+                Err(err) => {
+                    log::warn!("Leaving {} as is: {}", file.display(), err);
+                    continue;
+                }
+            };
+
+            // Without a database key the backup key itself can't stay on disk
+            let data = if file == backup_key && to_key.is_none() {
+                StoredBackupKey::locked(&data)?
+            } else {
+                data
+            };
+
+            let temp_path = pending_path(&file);
+            write_synced(&temp_path, &seal_file(to_key, data)?).await?;
+            pending.push((temp_path, file));
+        }
+
+        let next = to.map(|(key_file, _)| key_file);
+        write_synced(&pending_key, &serde_json::to_vec_pretty(&next)?).await
+    }
+    .await;
+
+    if let Err(err) = prepared {
+        let _ = fs::remove_file(&pending_key).await;
+        let _ = fs::remove_file(&db_pending).await;
+        discard(&pending).await;
+        return Err(err);
+    }
+
+    // The point of no return, from here on a crash is rolled forward
+    backup::replace_database(pool, &db_pending, db_path).await?;
+    roll_forward(data_dir).await?;
+
+    log::info!(
+        "Re-encrypted the database, {} attachments and snapshots",
+        pending.len()
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    /// False until the passphrase is entered, or while the plain database is being checked
+    pub database_open: bool,
+    /// Backups are sealed with a backup passphrase before they are uploaded
+    pub backups_encrypted: bool,
+    /// The backup passphrase has to be entered before the next backup
+    pub backups_locked: bool,
+}
+
+// No Debug, it holds passphrases
+#[derive(Clone, Deserialize)]
+#[serde(
+    tag = "action",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum EncryptionChange {
+    Enable {
+        passphrase: String,
+    },
+    /// A new passphrase and key, every file is re-encrypted
+    Rotate {
+        current_passphrase: String,
+        new_passphrase: String,
+    },
+    Disable {
+        current_passphrase: String,
+    },
+}
+
+#[tauri::command]
+pub async fn get_encryption_status(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<EncryptionStatus> {
+    let data_dir = Path::new(&state.config.data_dir);
+    let key_file = KeyFile::load(data_dir).await?;
+
+    Ok(EncryptionStatus {
+        enabled: key_file.is_some(),
+        database_open: app.try_state::<SqlitePool>().is_some(),
+        backups_encrypted: fs::try_exists(backup_key_path(data_dir)).await?,
+        backups_locked: matches!(state.backup_key().await, Err(Error::BackupLocked)),
+    })
+}
+
+/// Opens an encrypted database with the passphrase entered at startup.
+#[tauri::command]
+pub async fn unlock_database(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    passphrase: String,
+) -> Result<()> {
+    if app.try_state::<SqlitePool>().is_some() {
+        return Ok(());
+    }
+
+    let key_file = KeyFile::load(Path::new(&state.config.data_dir))
+        .await?
+        .ok_or_else(|| Error::Encryption("Encryption is not enabled".to_string()))?;
+
+    let key = key_file.unlock(&passphrase).await.inspect_err(|err| {
+        log::warn!("Failed to unlock the database: {}", err);
+    })?;
+
+    crate::open_database(&app, Some(key)).await
+}
+
+/// Enables, rotates or disables encryption. The app has to be relaunched afterwards.
+#[tauri::command]
+pub async fn change_encryption(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    change: EncryptionChange,
+) -> Result<()> {
+    state.require(Permission::ManageSettings)?;
+    // A backup running alongside would read files as they are re-encrypted
+    let _guard = scheduler.try_begin().ok_or_else(|| {
+        Error::Conflict("A backup is running, try again once it finishes".to_string())
+    })?;
+
+    let config = &state.config;
+    let data_dir = Path::new(&config.data_dir);
+    let key_file = KeyFile::load(data_dir).await?;
+
+    let current = match (&change, &key_file) {
+        (EncryptionChange::Enable { .. }, None) => None,
+        (EncryptionChange::Enable { .. }, Some(_)) => {
+            return Err(Error::Conflict("Encryption is already enabled".to_string()))
+        }
+        (_, None) => return Err(Error::Conflict("Encryption is not enabled".to_string())),
+        (
+            EncryptionChange::Rotate {
+                current_passphrase, ..
+            }
+            | EncryptionChange::Disable { current_passphrase },
+            Some(key_file),
+        ) => Some(key_file.unlock(current_passphrase).await?),
+    };
+
+    let next = match &change {
+        EncryptionChange::Enable { passphrase } => Some(KeyFile::create(passphrase).await?),
+        EncryptionChange::Rotate { new_passphrase, .. } => {
+            Some(KeyFile::create(new_passphrase).await?)
+        }
+        EncryptionChange::Disable { .. } => None,
+    };
+
+    rekey(
+        &pool,
+        Path::new(&config.db_path),
+        data_dir,
+        current.as_ref(),
+        next.as_ref().map(|(key_file, key)| (key_file, key)),
+    )
+    .await?;
+
+    log::info!(
+        "Encryption {}",
+        match change {
+            EncryptionChange::Enable { .. } => "enabled",
+            EncryptionChange::Rotate { .. } => "key rotated",
+            EncryptionChange::Disable { .. } => "disabled",
+        }
+    );
+
+    Ok(())
+}
+
+/// Sets the passphrase new backups are sealed with, or uploads them as is
+/// again when `passphrase` is `None`. Existing backups keep the passphrase
+/// they were made with.
+#[tauri::command]
+pub async fn set_backup_passphrase(
+    state: State<'_, AppState>,
+    passphrase: Option<String>,
+) -> Result<()> {
+    state.require(Permission::ManageBackups)?;
+    let data_dir = Path::new(&state.config.data_dir);
+
+    match passphrase {
+        Some(passphrase) => {
+            let backup_key = BackupKey::create(&passphrase).await?;
+            backup_key
+                .save(data_dir, state.encryption_key().as_ref())
+                .await?;
+            *state.backup_key.write().unwrap() = Some(backup_key);
+            log::info!("Backup encryption enabled");
+        }
+        None => {
+            BackupKey::remove(data_dir).await?;
+            *state.backup_key.write().unwrap() = None;
+            log::info!("Backup encryption disabled");
+        }
+    }
+
+    Ok(())
+}
+
+/// Enters the backup passphrase for this session, needed when the database
+/// isn't encrypted and so can't keep the backup key on disk.
+#[tauri::command]
+pub async fn unlock_backups(state: State<'_, AppState>, passphrase: String) -> Result<()> {
+    state.require(Permission::ManageBackups)?;
+
+    let backup_key = BackupKey::unlock(
+        Path::new(&state.config.data_dir),
+        state.encryption_key().as_ref(),
+        &passphrase,
+    )
+    .await
+    .inspect_err(|err| log::warn!("Failed to unlock backups: {}", err))?;
+    *state.backup_key.write().unwrap() = Some(backup_key);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::new_id;
+
+    struct Folder {
+        data_dir: PathBuf,
+        db_path: PathBuf,
+    }
+
+    impl Drop for Folder {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.data_dir);
+        }
+    }
+
+    // A data folder partway through a rekey: the new key pending, an
+    // attachment converted and the database either swapped or not
+    async fn interrupted(db_swapped: bool) -> Folder {
+        let data_dir = std::env::temp_dir().join(format!("sgmc-rekey-{}", new_id()));
+        let attachments = attachments_dir(&data_dir);
+        fs::create_dir_all(&attachments).await.unwrap();
+        let db_path = data_dir.join("app.db");
+
+        let (key_file, _) = KeyFile::create("correct horse battery").await.unwrap();
+        fs::write(&db_path, "old database").await.unwrap();
+        fs::write(attachments.join("scan.pdf"), "old scan").await.unwrap();
+        let chain = wal::wal_dir(&data_dir).join("20260101T000000.000Z");
+        fs::create_dir_all(&chain).await.unwrap();
+        fs::write(chain.join("base.db"), "old pages").await.unwrap();
+        fs::write(pending_path(&attachments.join("scan.pdf")), "new scan")
+            .await
+            .unwrap();
+        fs::write(
+            pending_key_path(&data_dir),
+            serde_json::to_vec(&Some(&key_file)).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        if db_swapped {
+            fs::write(&db_path, "new database").await.unwrap();
+        } else {
+            fs::write(pending_path(&db_path), "new database").await.unwrap();
+        }
+
+        Folder { data_dir, db_path }
+    }
+
+    async fn read(path: PathBuf) -> String {
+        String::from_utf8(fs::read(path).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rolls_back_before_the_database_is_swapped() {
+        let folder = interrupted(false).await;
+
+        recover_rekey(&folder.db_path, &folder.data_dir).await.unwrap();
+
+        assert_eq!(read(folder.db_path.clone()).await, "old database");
+        assert_eq!(read(attachments_dir(&folder.data_dir).join("scan.pdf")).await, "old scan");
+        assert!(KeyFile::load(&folder.data_dir).await.unwrap().is_none());
+        assert!(!fs::try_exists(pending_key_path(&folder.data_dir)).await.unwrap());
+        assert!(!fs::try_exists(pending_path(&folder.db_path)).await.unwrap());
+        assert!(fs::try_exists(wal::wal_dir(&folder.data_dir)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn rolls_forward_once_the_database_is_swapped() {
+        let folder = interrupted(true).await;
+
+        recover_rekey(&folder.db_path, &folder.data_dir).await.unwrap();
+
+        assert_eq!(read(folder.db_path.clone()).await, "new database");
+        assert_eq!(read(attachments_dir(&folder.data_dir).join("scan.pdf")).await, "new scan");
+        assert!(KeyFile::load(&folder.data_dir).await.unwrap().is_some());
+        assert!(!fs::try_exists(pending_key_path(&folder.data_dir)).await.unwrap());
+        // Recovery points under the old key are gone
+        assert!(!fs::try_exists(wal::wal_dir(&folder.data_dir)).await.unwrap());
+
+        // Nothing is left for a second launch to do
+        recover_rekey(&folder.db_path, &folder.data_dir).await.unwrap();
+        assert!(KeyFile::load(&folder.data_dir).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn removes_the_key_file_when_disabling() {
+        let folder = interrupted(true).await;
+        fs::write(key_file_path(&folder.data_dir), "{}").await.unwrap();
+        fs::write(pending_key_path(&folder.data_dir), "null")
+            .await
+            .unwrap();
+
+        recover_rekey(&folder.db_path, &folder.data_dir).await.unwrap();
+
+        assert!(!fs::try_exists(key_file_path(&folder.data_dir)).await.unwrap());
+        assert!(!fs::try_exists(pending_key_path(&folder.data_dir)).await.unwrap());
+    }
+
+    async fn empty_folder() -> Folder {
+        let data_dir = std::env::temp_dir().join(format!("sgmc-backup-key-{}", new_id()));
+        fs::create_dir_all(&data_dir).await.unwrap();
+        Folder {
+            db_path: data_dir.join("app.db"),
+            data_dir,
+        }
+    }
+
+    async fn key_on_disk(data_dir: &Path, backup_key: &BackupKey) -> bool {
+        let stored = fs::read(backup_key_path(data_dir)).await.unwrap();
+        String::from_utf8_lossy(&stored).contains(&STANDARD.encode(backup_key.key.0))
+    }
+
+    #[tokio::test]
+    async fn keeps_the_backup_key_off_disk_without_a_database_key() {
+        let folder = empty_folder().await;
+        let data_dir = &folder.data_dir;
+        let backup_key = BackupKey::create("correct horse battery").await.unwrap();
+
+        backup_key.save(data_dir, None).await.unwrap();
+        assert!(!key_on_disk(data_dir, &backup_key).await);
+        assert!(matches!(
+            BackupKey::load(data_dir, None, None).await,
+            Err(Error::BackupLocked)
+        ));
+
+        assert!(matches!(
+            BackupKey::unlock(data_dir, None, "wrong passphrase").await,
+            Err(Error::WrongPassphrase)
+        ));
+        let unlocked = BackupKey::unlock(data_dir, None, "correct horse battery")
+            .await
+            .unwrap();
+        assert_eq!(unlocked.key.0, backup_key.key.0);
+        assert!(!key_on_disk(data_dir, &backup_key).await);
+
+        let loaded = BackupKey::load(data_dir, None, Some(&unlocked))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.key.0, backup_key.key.0);
+
+        // A key unlocked before the passphrase changed seals nothing new
+        let replaced = BackupKey::create("another passphrase").await.unwrap();
+        assert!(matches!(
+            BackupKey::load(data_dir, None, Some(&replaced)).await,
+            Err(Error::BackupLocked)
+        ));
+    }
+
+    #[tokio::test]
+    async fn removes_a_plain_backup_key_left_by_older_versions() {
+        let folder = empty_folder().await;
+        let data_dir = &folder.data_dir;
+        let backup_key = BackupKey::create("correct horse battery").await.unwrap();
+        fs::write(
+            backup_key_path(data_dir),
+            serde_json::to_vec(&backup_key).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let loaded = BackupKey::load(data_dir, None, None).await.unwrap().unwrap();
+        assert_eq!(loaded.key.0, backup_key.key.0);
+        assert!(!key_on_disk(data_dir, &backup_key).await);
+        assert!(matches!(
+            BackupKey::load(data_dir, None, None).await,
+            Err(Error::BackupLocked)
+        ));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_backup_key_sealed_with_the_database_key() {
+        let folder = empty_folder().await;
+        let data_dir = &folder.data_dir;
+        let (_, local) = KeyFile::create("database passphrase").await.unwrap();
+        let backup_key = BackupKey::create("correct horse battery").await.unwrap();
+
+        // Entering the passphrase once stores the key for later sessions
+        backup_key.save(data_dir, None).await.unwrap();
+        let sealed = fs::read(backup_key_path(data_dir)).await.unwrap();
+        fs::write(backup_key_path(data_dir), seal_file(Some(&local), sealed).unwrap())
+            .await
+            .unwrap();
+        BackupKey::unlock(data_dir, Some(&local), "correct horse battery")
+            .await
+            .unwrap();
+
+        let loaded = BackupKey::load(data_dir, Some(&local), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.key.0, backup_key.key.0);
+        assert!(!key_on_disk(data_dir, &backup_key).await);
+
+        // Disabling database encryption leaves only the salt behind
+        let opened = read_file(&backup_key_path(data_dir), Some(&local))
+            .await
+            .unwrap();
+        let locked = String::from_utf8(StoredBackupKey::locked(&opened).unwrap()).unwrap();
+        assert!(!locked.contains(&STANDARD.encode(backup_key.key.0)));
+        assert!(locked.contains(&backup_key.key_file.salt));
+    }
+}
@@ -0,0 +1,61 @@
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+    #[error("{0}")]
+    Integrity(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    #[error("{0}")]
+    Validation(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("Not signed in to Google Drive")]
+    Unauthenticated,
+    #[error("Sign in to continue")]
+    SignedOut,
+    #[error("Wrong username or password")]
+    InvalidCredentials,
+    #[error("Your role does not allow this")]
+    Forbidden,
+    #[error("Wrong encryption passphrase")]
+    WrongPassphrase,
+    #[error("The database is locked, enter the encryption passphrase")]
+    Locked,
+    #[error("{0}")]
+    Encryption(String),
+    #[error("{0}")]
+    Backup(String),
+    #[error("Enter the backup passphrase in the encryption settings to seal new backups")]
+    BackupLocked,
+    #[error("Sync with the other workstation failed: {0}")]
+    PeerSync(String),
+    #[error("Network discovery failed: {0}")]
+    Discovery(String),
+    #[error("Failed to generate report: {0}")]
+    Report(String),
+    #[error("SGMC is already running on port {0}, switch to the open window instead")]
+    AlreadyRunning(u16),
+}
+
+// Commands return errors to the webview, which only needs the message
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -1,4 +1,10 @@
-use std::{env, fs, path::PathBuf, sync::LazyLock};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const APP_IDENTIFIER: &str = "com.mina.hospital-tauri";
+const SETTINGS_FILE: &str = "settings.json";
 
 fn exe_dir() -> PathBuf {
     // AppImage (real location, not /tmp mount)
@@ -17,10 +23,46 @@ fn exe_dir() -> PathBuf {
         .to_path_buf()
 }
 
-pub static DATA_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
-    let dir = exe_dir().join("data");
+/// Checks that files can be written to `dir`, or to the folder it would be
+/// created in. Only a probe file is written, and removed again.
+pub fn is_writable(dir: &Path) -> bool {
+    let Some(existing) = dir.ancestors().find(|ancestor| ancestor.exists()) else {
+        return false;
+    };
+    if !existing.is_dir() {
+        return false;
+    }
+
+    let probe = existing.join(".write_test");
+    let writable = fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(probe);
 
-    fs::create_dir_all(&dir).expect("Data directory is not writable");
+    writable
+}
+
+/// `<exe dir>/data` as before, or the per-user data folder when the app is
+/// installed somewhere read-only like `Program Files`.
+pub fn default_data_dir() -> PathBuf {
+    let portable = exe_dir().join("data");
+    if is_writable(&portable) {
+        return portable;
+    }
 
-    dir
-});
+    dirs::data_dir()
+        .expect("No user data directory")
+        .join(APP_IDENTIFIER)
+        .join("data")
+}
+
+/// Lives outside the data folder because it is what points at the data folder.
+pub fn settings_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(exe_dir)
+        .join(APP_IDENTIFIER)
+        .join(SETTINGS_FILE)
+}
+
+/// Pre-migration snapshots and local backups.
+pub fn backups_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("backups")
+}
@@ -0,0 +1,493 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+// Statements are aged from the day they were opened
+const BALANCE_QUERY: &str = r#"
+    SELECT
+        s.id AS statement_id,
+        s.patient_id,
+        p.name AS patient_name,
+        s.total,
+        (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id) AS total_paid,
+        CAST(COALESCE(julianday('now') - julianday(s.created_at), 0) AS INTEGER) AS age_days,
+        s.created_at
+    FROM statements s
+    JOIN patients p ON s.patient_id = p.id
+    WHERE
+        s.deleted_at IS NULL AND
+        p.deleted_at IS NULL AND
+        (?1 IS NULL OR s.id = ?1) AND
+        (?2 IS NULL OR s.patient_id = ?2)
+    ORDER BY s.created_at
+"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AgingBucket {
+    #[serde(rename = "0-30")]
+    Current,
+    #[serde(rename = "31-60")]
+    Days31To60,
+    #[serde(rename = "61-90")]
+    Days61To90,
+    #[serde(rename = "90+")]
+    Over90,
+}
+
+impl AgingBucket {
+    pub fn from_age(days: i64) -> Self {
+        match days {
+            ..=30 => AgingBucket::Current,
+            31..=60 => AgingBucket::Days31To60,
+            61..=90 => AgingBucket::Days61To90,
+            _ => AgingBucket::Over90,
+        }
+    }
+}
+
+/// Outstanding amounts per aging bucket.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgingSummary {
+    pub current: i64,
+    pub days_31_to_60: i64,
+    pub days_61_to_90: i64,
+    pub over_90: i64,
+}
+
+impl AgingSummary {
+    fn add(&mut self, bucket: AgingBucket, amount: i64) {
+        match bucket {
+            AgingBucket::Current => self.current += amount,
+            AgingBucket::Days31To60 => self.days_31_to_60 += amount,
+            AgingBucket::Days61To90 => self.days_61_to_90 += amount,
+            AgingBucket::Over90 => self.over_90 += amount,
+        }
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct BalanceRow {
+    statement_id: String,
+    patient_id: String,
+    patient_name: String,
+    total: i64,
+    total_paid: i64,
+    age_days: i64,
+    created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementBalance {
+    pub statement_id: String,
+    pub patient_id: String,
+    pub patient_name: String,
+    pub total: i64,
+    pub total_paid: i64,
+    /// `total - total_paid`, negative when the patient paid too much
+    pub balance: i64,
+    pub outstanding: i64,
+    pub overpaid: i64,
+    pub age_days: i64,
+    pub bucket: AgingBucket,
+    pub created_at: String,
+}
+
+impl From<BalanceRow> for StatementBalance {
+    fn from(row: BalanceRow) -> Self {
+        let balance = row.total - row.total_paid;
+        let age_days = row.age_days.max(0);
+
+        Self {
+            statement_id: row.statement_id,
+            patient_id: row.patient_id,
+            patient_name: row.patient_name,
+            total: row.total,
+            total_paid: row.total_paid,
+            balance,
+            outstanding: balance.max(0),
+            overpaid: (-balance).max(0),
+            age_days,
+            bucket: AgingBucket::from_age(age_days),
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientBalance {
+    pub patient_id: String,
+    pub patient_name: String,
+    pub statement_count: i64,
+    /// Statements with anything left to pay, however recent
+    pub unpaid_count: i64,
+    pub total_required: i64,
+    pub total_paid: i64,
+    /// `total_required - total_paid`; overpayments on one statement offset debt on another
+    pub balance: i64,
+    pub outstanding: i64,
+    pub overpaid: i64,
+    pub aging: AgingSummary,
+    pub statements: Vec<StatementBalance>,
+}
+
+impl PatientBalance {
+    fn new(patient_id: String, patient_name: String) -> Self {
+        Self {
+            patient_id,
+            patient_name,
+            statement_count: 0,
+            unpaid_count: 0,
+            total_required: 0,
+            total_paid: 0,
+            balance: 0,
+            outstanding: 0,
+            overpaid: 0,
+            aging: AgingSummary::default(),
+            statements: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, statement: StatementBalance) {
+        self.statement_count += 1;
+        if statement.outstanding > 0 {
+            self.unpaid_count += 1;
+        }
+
+        self.total_required += statement.total;
+        self.total_paid += statement.total_paid;
+        self.balance += statement.balance;
+        self.outstanding += statement.outstanding;
+        self.overpaid += statement.overpaid;
+        self.aging.add(statement.bucket, statement.outstanding);
+        self.statements.push(statement);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgingReport {
+    pub aging: AgingSummary,
+    pub outstanding: i64,
+    pub overpaid: i64,
+    /// Patients with an outstanding balance, largest first
+    pub patients: Vec<PatientBalance>,
+}
+
+async fn balances(
+    pool: &SqlitePool,
+    statement_id: Option<&str>,
+    patient_id: Option<&str>,
+) -> Result<Vec<StatementBalance>> {
+    let rows = sqlx::query_as::<_, BalanceRow>(BALANCE_QUERY)
+        .bind(statement_id)
+        .bind(patient_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows.into_iter().map(StatementBalance::from).collect())
+}
+
+pub async fn statement_balance(pool: &SqlitePool, id: &str) -> Result<StatementBalance> {
+    balances(pool, Some(id), None)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or(Error::NotFound("Statement"))
+}
+
+pub async fn patient_balance(pool: &SqlitePool, patient_id: &str) -> Result<PatientBalance> {
+    let name: String =
+        sqlx::query_scalar("SELECT name FROM patients WHERE id = ? AND deleted_at IS NULL")
+            .bind(patient_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(Error::NotFound("Patient"))?;
+
+    let mut patient = PatientBalance::new(patient_id.to_string(), name);
+    for statement in balances(pool, None, Some(patient_id)).await? {
+        patient.push(statement);
+    }
+
+    Ok(patient)
+}
+
+pub async fn aging_report(pool: &SqlitePool) -> Result<AgingReport> {
+    let mut patients: HashMap<String, PatientBalance> = HashMap::new();
+    let mut aging = AgingSummary::default();
+    let mut outstanding = 0;
+    let mut overpaid = 0;
+
+    for statement in balances(pool, None, None).await? {
+        aging.add(statement.bucket, statement.outstanding);
+        outstanding += statement.outstanding;
+        overpaid += statement.overpaid;
+
+        patients
+            .entry(statement.patient_id.clone())
+            .or_insert_with(|| {
+                PatientBalance::new(statement.patient_id.clone(), statement.patient_name.clone())
+            })
+            .push(statement);
+    }
+
+    let mut patients: Vec<PatientBalance> = patients
+        .into_values()
+        .filter(|patient| patient.outstanding > 0)
+        .collect();
+    patients.sort_by_key(|patient| Reverse(patient.outstanding));
+
+    Ok(AgingReport {
+        aging,
+        outstanding,
+        overpaid,
+        patients,
+    })
+}
+
+/// Statements with money still owed, oldest first.
+pub async fn outstanding_balances(pool: &SqlitePool) -> Result<Vec<StatementBalance>> {
+    Ok(balances(pool, None, None)
+        .await?
+        .into_iter()
+        .filter(|statement| statement.outstanding > 0)
+        .collect())
+}
+
+/// Statements whose payments exceed their total.
+pub async fn overpayments(pool: &SqlitePool) -> Result<Vec<StatementBalance>> {
+    let mut statements: Vec<StatementBalance> = balances(pool, None, None)
+        .await?
+        .into_iter()
+        .filter(|statement| statement.overpaid > 0)
+        .collect();
+    statements.sort_by_key(|statement| Reverse(statement.overpaid));
+
+    Ok(statements)
+}
+
+#[tauri::command]
+pub async fn get_statement_balance(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<StatementBalance> {
+    state.require(Permission::ViewFinancials)?;
+    statement_balance(&pool, &id).await
+}
+
+#[tauri::command]
+pub async fn get_patient_balance(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    patient_id: String,
+) -> Result<PatientBalance> {
+    state.require(Permission::ViewFinancials)?;
+    patient_balance(&pool, &patient_id).await
+}
+
+#[tauri::command]
+pub async fn get_aging_report(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<AgingReport> {
+    state.require(Permission::ViewFinancials)?;
+    aging_report(&pool).await
+}
+
+#[tauri::command]
+pub async fn get_overpayments(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<StatementBalance>> {
+    state.require(Permission::ViewFinancials)?;
+    overpayments(&pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_pool;
+
+    async fn add_patient(pool: &SqlitePool, id: &str) {
+        sqlx::query(
+            "INSERT INTO patients (id, name, phone, created_at, updated_at)
+             VALUES (?, ?, '0790000000', datetime('now'), datetime('now'))",
+        )
+        .bind(id)
+        .bind(format!("Patient {id}"))
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    /// A statement opened `age_days` ago with one payment per amount in `paid`.
+    async fn add_statement(
+        pool: &SqlitePool,
+        id: &str,
+        patient_id: &str,
+        total: i64,
+        age_days: i64,
+        paid: &[i64],
+    ) {
+        sqlx::query(
+            "INSERT INTO statements (id, patient_id, total, created_at, updated_at)
+             VALUES (?1, ?2, ?3, datetime('now', ?4), datetime('now'))",
+        )
+        .bind(id)
+        .bind(patient_id)
+        .bind(total)
+        .bind(format!("-{age_days} days"))
+        .execute(pool)
+        .await
+        .unwrap();
+
+        for (index, amount) in paid.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO payments (id, statement_id, amount, created_at, updated_at)
+                 VALUES (?, ?, ?, datetime('now'), datetime('now'))",
+            )
+            .bind(format!("{id}-payment-{index}"))
+            .bind(id)
+            .bind(amount)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn buckets_split_on_their_last_day() {
+        let cases = [
+            (0, AgingBucket::Current),
+            (30, AgingBucket::Current),
+            (31, AgingBucket::Days31To60),
+            (60, AgingBucket::Days31To60),
+            (61, AgingBucket::Days61To90),
+            (90, AgingBucket::Days61To90),
+            (91, AgingBucket::Over90),
+            (-1, AgingBucket::Current),
+        ];
+
+        for (days, bucket) in cases {
+            assert_eq!(AgingBucket::from_age(days), bucket, "{days} days");
+        }
+    }
+
+    #[tokio::test]
+    async fn balance_query_sums_payments_of_live_statements() {
+        let pool = memory_pool().await;
+        add_patient(&pool, "p1").await;
+        add_patient(&pool, "p2").await;
+        add_statement(&pool, "s1", "p1", 100, 0, &[30, 20]).await;
+        add_statement(&pool, "s2", "p1", 50, 0, &[]).await;
+        add_statement(&pool, "s3", "p2", 70, 0, &[70]).await;
+        add_statement(&pool, "deleted", "p1", 500, 0, &[]).await;
+        sqlx::query("UPDATE statements SET deleted_at = datetime('now') WHERE id = 'deleted'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let s1 = statement_balance(&pool, "s1").await.unwrap();
+        assert_eq!((s1.total_paid, s1.balance, s1.outstanding), (50, 50, 50));
+        assert_eq!(s1.patient_name, "Patient p1");
+
+        let all = balances(&pool, None, None).await.unwrap();
+        let mut ids: Vec<&str> = all.iter().map(|s| s.statement_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, ["s1", "s2", "s3"]);
+
+        let for_p2 = balances(&pool, None, Some("p2")).await.unwrap();
+        assert_eq!(for_p2.len(), 1);
+        assert_eq!(for_p2[0].outstanding, 0);
+
+        assert!(matches!(
+            statement_balance(&pool, "deleted").await,
+            Err(Error::NotFound("Statement"))
+        ));
+
+        sqlx::query("UPDATE patients SET deleted_at = datetime('now') WHERE id = 'p2'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(balances(&pool, None, Some("p2")).await.unwrap().is_empty());
+        assert!(matches!(
+            patient_balance(&pool, "p2").await,
+            Err(Error::NotFound("Patient"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn ages_statements_from_the_day_they_were_opened() {
+        let pool = memory_pool().await;
+        add_patient(&pool, "p1").await;
+        for (id, age) in [("a30", 30), ("a31", 31), ("a60", 60), ("a61", 61), ("a91", 91)] {
+            add_statement(&pool, id, "p1", 10, age, &[]).await;
+        }
+
+        let report = aging_report(&pool).await.unwrap();
+        assert_eq!(report.aging.current, 10);
+        assert_eq!(report.aging.days_31_to_60, 20);
+        assert_eq!(report.aging.days_61_to_90, 10);
+        assert_eq!(report.aging.over_90, 10);
+        assert_eq!(report.outstanding, 50);
+
+        let a31 = statement_balance(&pool, "a31").await.unwrap();
+        assert_eq!((a31.age_days, a31.bucket), (31, AgingBucket::Days31To60));
+
+        // Paid statements are not owed, however old
+        add_statement(&pool, "paid", "p1", 10, 200, &[10]).await;
+        assert_eq!(aging_report(&pool).await.unwrap().aging.over_90, 10);
+    }
+
+    #[tokio::test]
+    async fn overpayments_offset_debt_but_are_not_owed() {
+        let pool = memory_pool().await;
+        add_patient(&pool, "p1").await;
+        add_patient(&pool, "p2").await;
+        add_statement(&pool, "over", "p1", 100, 0, &[100, 30]).await;
+        add_statement(&pool, "owed", "p1", 50, 0, &[10]).await;
+        add_statement(&pool, "only-over", "p2", 20, 0, &[25]).await;
+
+        let over = statement_balance(&pool, "over").await.unwrap();
+        assert_eq!((over.balance, over.outstanding, over.overpaid), (-30, 0, 30));
+
+        let patient = patient_balance(&pool, "p1").await.unwrap();
+        assert_eq!(patient.statement_count, 2);
+        assert_eq!(patient.unpaid_count, 1);
+        assert_eq!((patient.total_required, patient.total_paid), (150, 140));
+        assert_eq!((patient.balance, patient.outstanding, patient.overpaid), (10, 40, 30));
+        assert_eq!(patient.aging.current, 40);
+
+        let listed: Vec<String> = overpayments(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|statement| statement.statement_id)
+            .collect();
+        assert_eq!(listed, ["over", "only-over"]);
+
+        let report = aging_report(&pool).await.unwrap();
+        assert_eq!((report.outstanding, report.overpaid), (40, 35));
+        assert_eq!(report.patients.len(), 1);
+        assert_eq!(report.patients[0].patient_id, "p1");
+
+        let owed: Vec<String> = outstanding_balances(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|statement| statement.statement_id)
+            .collect();
+        assert_eq!(owed, ["owed"]);
+    }
+}
@@ -1,14 +1,41 @@
-use log;
-use tauri::Manager;
+use std::{path::Path, sync::Arc};
 
-use crate::app_state::AppState;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{
+    app_state::AppState,
+    discovery::Discovery,
+    encryption::{Key, KeyFile},
+    error::{Error, Result},
+    pairing::PairingTokens,
+    reminders::ReminderScheduler,
+    server::{ServerInfo, ServerStatus},
+    sync::SyncScheduler,
+};
 
 mod app_state;
+mod audit;
+mod auth;
+mod backup;
 mod config;
 mod database;
+mod discovery;
+mod encryption;
+mod error;
 mod filesystem;
+mod ledger;
 mod logging;
+mod pairing;
+mod peer_sync;
+mod qr;
+mod recycle_bin;
+mod reminders;
+mod reports;
+mod repository;
+mod search;
 mod server;
+mod sync;
 
 
 
@@ -18,33 +45,190 @@ pub fn run() {
 
 
     let app_state = AppState::default();
+    // Bound before touching the database so a second instance can back off first
+    let binding = tauri::async_runtime::block_on(server::bind(&app_state.config));
+    let startup = match &binding {
+        Err(ServerStatus::AlreadyRunning { port }) => Err(Error::AlreadyRunning(*port)),
+        _ => tauri::async_runtime::block_on(async {
+            let data_dir = Path::new(&app_state.config.data_dir);
+            // A rekey cut short leaves the key file and database out of step
+            encryption::recover_rekey(Path::new(&app_state.config.db_path), data_dir).await?;
+            KeyFile::load(data_dir).await
+        })
+        .map(|key_file| key_file.is_some()),
+    };
+    let sync_scheduler = SyncScheduler::load(Path::new(&app_state.config.data_dir));
+    let reminder_scheduler = ReminderScheduler::load(Path::new(&app_state.config.data_dir));
 
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(logging::get_logging_plugin())
-        .plugin(database::get_db_plugin(&app_state.config.db_url))
         .plugin(tauri_plugin_opener::init())
         .manage(app_state)
-        .setup(|app| {
-            let handle = app.handle().clone();
-            let config = handle.state::<AppState>().config.clone();
-            log::info!("{:#?}", &config);
+        .manage(Arc::new(PairingTokens::default()))
+        .manage(sync_scheduler)
+        .manage(reminder_scheduler)
+        .manage(ServerInfo::default())
+        .manage(Discovery::default())
+        .register_asynchronous_uri_scheme_protocol("attachment", |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
             tauri::async_runtime::spawn(async move {
-                let _ = server::start_server(handle).await;
-                log::info!("Scanner/OAuth server started on port {}", config.port);
+                responder.respond(repository::attachments::serve(&app, request.uri().path()).await);
             });
+        })
+        .setup(move |app| {
+            // An encrypted database waits for the passphrase from the unlock screen
+            let opened = startup.and_then(|encrypted| {
+                if encrypted {
+                    log::info!("Database is encrypted, waiting for the passphrase");
+                    Ok(())
+                } else {
+                    tauri::async_runtime::block_on(open_database(app.handle(), None))
+                }
+            });
+
+            if let Err(err) = opened {
+                log::error!("Refusing to start: {}", err);
+                let handle = app.handle().clone();
+                app.dialog()
+                    .message(err.to_string())
+                    .title("Cannot start SGMC")
+                    .kind(MessageDialogKind::Error)
+                    .show(move |_| handle.exit(1));
+                return Ok(());
+            }
+
+            let config = app.state::<AppState>().config.clone();
+            log::info!("{:#?}", &config);
+            match server::start_server(app.handle().clone(), binding) {
+                ServerStatus::Listening { port } => {
+                    log::info!("Scanner/OAuth server started on port {}", port);
+                    discovery::advertise(app.handle(), port);
+                }
+                status => log::error!("Scanner/OAuth server not running: {:?}", status),
+            }
             
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            config::get_app_config
+            auth::get_session,
+            auth::login,
+            auth::logout,
+            auth::create_first_admin,
+            auth::change_password,
+            encryption::get_encryption_status,
+            encryption::unlock_database,
+            encryption::change_encryption,
+            encryption::set_backup_passphrase,
+            encryption::unlock_backups,
+            config::get_app_config,
+            config::get_settings,
+            config::set_app_config,
+            backup::create_backup,
+            backup::list_backups,
+            backup::verify_backup,
+            backup::restore_backup,
+            backup::archive::create_archive,
+            backup::archive::list_archives,
+            backup::archive::verify_archive,
+            backup::archive::restore_archive,
+            backup::wal::get_recovery_points,
+            backup::wal::restore_to_point,
+            server::get_server_status,
+            peer_sync::sync_with_peer,
+            discovery::discover_peers,
+            peer_sync::get_payment_conflicts,
+            peer_sync::resolve_payment_conflict,
+            ledger::get_statement_balance,
+            ledger::get_patient_balance,
+            ledger::get_aging_report,
+            ledger::get_overpayments,
+            audit::get_history,
+            recycle_bin::get_recycle_bin,
+            recycle_bin::restore_deleted,
+            recycle_bin::purge_deleted,
+            reports::export_statement_pdf,
+            pairing::create_pairing_token,
+            pairing::get_scan_qr,
+            pairing::revoke_pairing_token,
+            repository::patients::get_patients,
+            repository::patients::get_patient_details,
+            repository::patients::add_patient,
+            repository::patients::update_patient,
+            repository::patients::delete_patient,
+            search::search,
+            repository::statements::get_statements,
+            repository::statements::get_statement_details,
+            repository::statements::add_statement,
+            repository::statements::update_statement,
+            repository::statements::delete_statement,
+            repository::payments::add_payment,
+            repository::payments::update_payment,
+            repository::payments::delete_payment,
+            repository::sessions::add_session,
+            repository::sessions::update_session,
+            repository::sessions::delete_session,
+            repository::doctors::get_doctors,
+            repository::doctors::add_doctor,
+            repository::doctors::update_doctor,
+            repository::doctors::delete_doctor,
+            repository::clinics::get_clinics,
+            repository::clinics::add_clinic,
+            repository::clinics::update_clinic,
+            repository::clinics::delete_clinic,
+            repository::attachments::get_attachments,
+            repository::attachments::add_attachment,
+            repository::attachments::delete_attachment,
+            repository::appointments::get_agenda,
+            repository::appointments::get_appointment,
+            repository::appointments::get_appointment_conflicts,
+            repository::appointments::book_appointment,
+            repository::appointments::reschedule_appointment,
+            repository::appointments::cancel_appointment,
+            repository::appointments::set_appointment_status,
+            repository::users::get_users,
+            repository::users::add_user,
+            repository::users::update_user,
+            repository::users::reset_user_password,
+            repository::users::get_reminder_lead_times,
+            repository::users::set_reminder_lead_times,
+            sync::get_sync_status,
+            sync::set_auto_sync,
+            reminders::set_reminder_language
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
+}
+
+/// Checks and migrates the database, then hands the pool to the commands
+/// and the background tasks.
+pub(crate) async fn open_database(app: &AppHandle, key: Option<Key>) -> Result<()> {
+    let state = app.state::<AppState>();
+    let config = &state.config;
+
+    database::prepare(&config.db_url, Path::new(&config.data_dir), key.as_ref()).await?;
+    let archive_wal = config.wal_archive_interval_minutes > 0;
+    let pool = database::connect(&config.db_url, key.as_ref(), archive_wal)?;
+
+    *state.encryption_key.write().unwrap() = key;
+    app.manage(pool.clone());
+
+    // Everyone signs in again after a restart, or a crash
+    if let Err(err) = auth::end_session(&pool, &state).await {
+        log::error!("Failed to clear the previous session: {}", err);
+    }
+
+    sync::spawn(app.clone());
+    backup::wal::spawn(app.clone());
+    reminders::spawn(app.clone());
+    recycle_bin::spawn(app.clone());
+
+    Ok(())
 }
\ No newline at end of file
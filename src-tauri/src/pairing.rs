@@ -0,0 +1,212 @@
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::Url;
+use serde::Serialize;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    config::AppConfig,
+    discovery::Discovery,
+    error::{Error, Result},
+    qr::{self, QrFormat},
+    server::ServerInfo,
+};
+
+#[derive(Debug, Clone)]
+struct PairingSession {
+    token: String,
+    statement_id: String,
+    expires_at: SystemTime,
+}
+
+/// The token phones must present to use the scanner endpoints.
+///
+/// Only one token is live at a time; issuing a new one invalidates the
+/// previous QR code. Each token only uploads to the statement it was issued for.
+#[derive(Debug, Default)]
+pub struct PairingTokens {
+    current: Mutex<Option<PairingSession>>,
+}
+
+impl PairingTokens {
+    /// Replaces the current token with a fresh one for `statement_id`, valid for `ttl`.
+    pub fn issue(&self, statement_id: &str, ttl: Duration) -> (String, SystemTime) {
+        let session = PairingSession {
+            token: uuid::Uuid::new_v4().simple().to_string(),
+            statement_id: statement_id.to_string(),
+            expires_at: SystemTime::now() + ttl,
+        };
+
+        let issued = (session.token.clone(), session.expires_at);
+        *self.current.lock().unwrap() = Some(session);
+
+        issued
+    }
+
+    pub fn revoke(&self) {
+        *self.current.lock().unwrap() = None;
+    }
+
+    /// The statement `token` was issued for, while it is live.
+    pub fn validate(&self, token: &str) -> Option<String> {
+        let mut current = self.current.lock().unwrap();
+        let session = current.as_ref()?;
+
+        if SystemTime::now() >= session.expires_at {
+            *current = None;
+            return None;
+        }
+
+        constant_time_eq(session.token.as_bytes(), token.as_bytes())
+            .then(|| session.statement_id.clone())
+    }
+}
+
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairingInfo {
+    pub token: String,
+    pub scan_url: String,
+    /// `scan_url` by IP address, when it uses the advertised `.local` name
+    /// that Android phones cannot resolve
+    pub fallback_url: Option<String>,
+    /// Unix time in milliseconds
+    pub expires_at: u64,
+}
+
+fn scan_url(host: &str, port: u16, token: &str, statement_id: &str) -> Result<String> {
+    let mut url = Url::parse(&format!("http://{}:{}/scan", host, port))
+        .map_err(|err| Error::Validation(format!("Invalid scanner address {host}: {err}")))?;
+
+    // Both end up in the query as given, whatever characters they hold
+    url.query_pairs_mut()
+        .append_pair("token", token)
+        .append_pair("statement_id", statement_id);
+
+    Ok(url.into())
+}
+
+/// Issues a fresh token for the statement the upload goes to, and the scan
+/// URL that carries both.
+fn issue(
+    config: &AppConfig,
+    pairing: &PairingTokens,
+    server: &ServerInfo,
+    discovery: &Discovery,
+    statement_id: &str,
+) -> Result<PairingInfo> {
+    let ip = if config
+        .bind_address
+        .parse()
+        .is_ok_and(|ip: IpAddr| ip.is_unspecified())
+    {
+        local_ip_address::local_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|_| config.ip_address.clone())
+    } else {
+        config.ip_address.clone()
+    };
+
+    let port = server.port().unwrap_or(config.port);
+    let ttl = Duration::from_secs(u64::from(config.pairing_ttl_minutes) * 60);
+    let (token, expires_at) = pairing.issue(statement_id, ttl);
+    let by_ip = scan_url(&ip, port, &token, statement_id)?;
+
+    // The advertised name survives DHCP handing out a new address, the IP
+    // stays on hand for phones that do not look up `.local` names
+    let (scan_url, fallback_url) = match discovery.host_name() {
+        Some(host_name) => (
+            scan_url(&host_name, port, &token, statement_id)?,
+            Some(by_ip),
+        ),
+        None => (by_ip, None),
+    };
+
+    Ok(PairingInfo {
+        scan_url,
+        fallback_url,
+        token,
+        expires_at: unix_millis(expires_at),
+    })
+}
+
+#[tauri::command]
+pub fn create_pairing_token(
+    state: State<'_, AppState>,
+    pairing: State<'_, Arc<PairingTokens>>,
+    server: State<'_, ServerInfo>,
+    discovery: State<'_, Discovery>,
+    statement_id: String,
+) -> Result<PairingInfo> {
+    state.require(Permission::EditStatements)?;
+
+    issue(&state.config, &pairing, &server, &discovery, &statement_id)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanQr {
+    #[serde(flatten)]
+    pub pairing: PairingInfo,
+    pub format: QrFormat,
+    /// The QR code of `scan_url` as a `data:` URL
+    pub image: String,
+    /// The QR code of `fallback_url`, when there is one
+    pub fallback_image: Option<String>,
+}
+
+/// Like `create_pairing_token`, with the scan URL already drawn as a QR code
+/// (SVG unless PNG is asked for).
+#[tauri::command]
+pub fn get_scan_qr(
+    state: State<'_, AppState>,
+    pairing: State<'_, Arc<PairingTokens>>,
+    server: State<'_, ServerInfo>,
+    discovery: State<'_, Discovery>,
+    statement_id: String,
+    format: Option<QrFormat>,
+) -> Result<ScanQr> {
+    state.require(Permission::EditStatements)?;
+
+    let format = format.unwrap_or_default();
+    let pairing = issue(&state.config, &pairing, &server, &discovery, &statement_id)?;
+    let image = qr::data_url(&pairing.scan_url, format)?;
+    let fallback_image = pairing
+        .fallback_url
+        .as_deref()
+        .map(|url| qr::data_url(url, format))
+        .transpose()?;
+
+    Ok(ScanQr {
+        pairing,
+        format,
+        image,
+        fallback_image,
+    })
+}
+
+#[tauri::command]
+pub fn revoke_pairing_token(
+    state: State<'_, AppState>,
+    pairing: State<'_, Arc<PairingTokens>>,
+) -> Result<()> {
+    state.require(Permission::EditStatements)?;
+    pairing.revoke();
+    Ok(())
+}
@@ -0,0 +1,1378 @@
+use std::{ops::AddAssign, sync::Arc, time::Duration};
+
+use axum::{
+    extract::{DefaultBodyLimit, Query, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{sqlite::SqliteConnection, Row, SqlitePool};
+use tauri::{AppHandle, Manager, State as TauriState};
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+    pairing,
+};
+
+const PEER_SECRET_HEADER: &str = "x-peer-secret";
+const CHANGES_PATH: &str = "/peer/changes";
+// Audit entries per request, so a long stretch offline is sent in pieces
+const PAGE_SIZE: i64 = 1000;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const PAYMENTS: &str = "payments";
+// What a payment conflict is about; a new `updated_at` alone is not one
+const PAYMENT_FIELDS: [&str; 2] = ["statement_id", "amount"];
+
+struct SyncedTable {
+    name: &'static str,
+    columns: &'static [&'static str],
+}
+
+// Parents come before children so foreign keys resolve when rows are applied
+// in this order, and deletions go the other way. Users stay on the desk they
+// were created at, and attachment files are not in the database to send.
+const TABLES: [SyncedTable; 7] = [
+    SyncedTable {
+        name: "clinics",
+        columns: &["id", "name", "created_at", "updated_at"],
+    },
+    SyncedTable {
+        name: "doctors",
+        columns: &["id", "name", "phone", "created_at", "updated_at"],
+    },
+    SyncedTable {
+        name: "patients",
+        columns: &[
+            "id",
+            "name",
+            "phone",
+            "created_at",
+            "updated_at",
+            "deleted_at",
+        ],
+    },
+    SyncedTable {
+        name: "statements",
+        columns: &[
+            "id",
+            "patient_id",
+            "doctor_id",
+            "clinic_id",
+            "total",
+            "created_at",
+            "updated_at",
+            "deleted_at",
+        ],
+    },
+    SyncedTable {
+        name: "sessions",
+        columns: &[
+            "id",
+            "statement_id",
+            "procedure",
+            "created_at",
+            "updated_at",
+        ],
+    },
+    SyncedTable {
+        name: PAYMENTS,
+        columns: &["id", "statement_id", "amount", "created_at", "updated_at"],
+    },
+    SyncedTable {
+        name: "appointments",
+        columns: &[
+            "id",
+            "patient_id",
+            "doctor_id",
+            "clinic_id",
+            "starts_at",
+            "ends_at",
+            "status",
+            "notes",
+            "created_at",
+            "updated_at",
+        ],
+    },
+];
+
+fn synced_table(name: &str) -> Option<(usize, &'static SyncedTable)> {
+    TABLES
+        .iter()
+        .enumerate()
+        .find(|(_, table)| table.name == name)
+}
+
+impl SyncedTable {
+    /// `json_object('id', t.id, ...)` over the synced columns.
+    fn json(&self, alias: &str) -> String {
+        let pairs: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("'{column}', {alias}.{column}"))
+            .collect();
+
+        format!("json_object({})", pairs.join(", "))
+    }
+
+    /// Inserts or overwrites the row given as a JSON object in `?1`.
+    fn upsert(&self) -> String {
+        let values: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("json_extract(?1, '$.{column}')"))
+            .collect();
+        let updates: Vec<String> = self
+            .columns
+            .iter()
+            .filter(|column| **column != "id")
+            .map(|column| format!("{column} = excluded.{column}"))
+            .collect();
+
+        format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+            self.name,
+            self.columns.join(", "),
+            values.join(", "),
+            updates.join(", ")
+        )
+    }
+}
+
+// Older rows hold Unix millis and newer ones `datetime('now')` text, so every
+// timestamp is compared as millis
+fn stamp(expr: &str) -> String {
+    format!(
+        "(CASE WHEN typeof({expr}) = 'text' \
+         THEN CAST(round((julianday({expr}) - 2440587.5) * 86400000) AS INTEGER) \
+         ELSE {expr} END)"
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedRow {
+    pub table: String,
+    /// The row as a JSON object keyed by column
+    pub values: Value,
+    /// Who last changed the row, on the desk it was changed at
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deletion {
+    pub table: String,
+    pub id: String,
+    /// Unix time in milliseconds
+    pub deleted_at: i64,
+    #[serde(default)]
+    pub user_id: Option<String>,
+}
+
+/// Where the next page of a full dump starts.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpCursor {
+    /// Position in the synced tables, parents first
+    pub table: usize,
+    /// The last rowid sent from that table
+    pub after: i64,
+}
+
+/// One page of rows changed on a workstation, in the order they can be applied.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Changes {
+    /// The last audit log entry covered, where the next page starts
+    pub through: i64,
+    /// Entries after `through` are waiting for another request
+    pub more: bool,
+    /// Set while a full dump has rows left, ask again with it and the same `through`
+    #[serde(default)]
+    pub dump: Option<DumpCursor>,
+    pub rows: Vec<ChangedRow>,
+    pub deletions: Vec<Deletion>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Applied {
+    pub applied: u32,
+    /// Older than what is already here, or rejected by a constraint
+    pub skipped: u32,
+    /// Payments changed on both sides, also counted as applied or skipped
+    pub conflicts: u32,
+}
+
+impl AddAssign for Applied {
+    fn add_assign(&mut self, other: Self) {
+        self.applied += other.applied;
+        self.skipped += other.skipped;
+        self.conflicts += other.conflicts;
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerSyncReport {
+    pub peer: String,
+    /// Their changes applied here
+    pub pulled: Applied,
+    /// Our changes applied there
+    pub pushed: Applied,
+}
+
+/// The workstation whose changes are being applied, and the last audit log
+/// entry of ours it has already received.
+#[derive(Debug, Clone, Copy)]
+pub struct PulledFrom<'a> {
+    pub peer: &'a str,
+    pub pushed_through: i64,
+}
+
+enum Outcome {
+    Applied,
+    Skipped,
+}
+
+impl Applied {
+    fn count(&mut self, outcome: Outcome, conflict: bool) {
+        match outcome {
+            Outcome::Applied => self.applied += 1,
+            Outcome::Skipped => self.skipped += 1,
+        }
+        if conflict {
+            self.conflicts += 1;
+        }
+    }
+}
+
+fn parse_rows(table: &SyncedTable, rows: Vec<(String, Option<String>)>) -> Result<Vec<ChangedRow>> {
+    rows.into_iter()
+        .map(|(row, user_id)| {
+            Ok(ChangedRow {
+                table: table.name.to_string(),
+                values: serde_json::from_str(&row)?,
+                user_id,
+            })
+        })
+        .collect()
+}
+
+// Who made the latest audit entry of row `t` up to `through`, both given as
+// query parameters
+fn author(table_name: &str, through: &str) -> String {
+    format!(
+        "(SELECT user_id FROM audit_log \
+         WHERE table_name = {table_name} AND row_id = t.id AND id <= {through} \
+         ORDER BY id DESC LIMIT 1)"
+    )
+}
+
+/// Rows touched after audit log entry `since`, at most [`PAGE_SIZE`] entries' worth.
+///
+/// A peer that has seen nothing yet, or whose cursor is past the end because
+/// this database was restored, gets every row instead, including rows from
+/// before the audit log existed. That dump is paged by rowid too, and `dump`
+/// continues it from where the previous page stopped.
+pub async fn changes(pool: &SqlitePool, since: i64, dump: Option<DumpCursor>) -> Result<Changes> {
+    // One read transaction, so the rows and the cursor come from the same snapshot
+    let mut tx = pool.begin().await?;
+    let latest: i64 = sqlx::query_scalar("SELECT COALESCE(max(id), 0) FROM audit_log")
+        .fetch_one(&mut *tx)
+        .await?;
+
+    if since > latest {
+        // Restored from a backup while dumping, or since the last sync
+        return full_dump(&mut tx, latest, latest, DumpCursor::default()).await;
+    }
+    if let Some(dump) = dump {
+        // Edits made since the dump began come after `since` in the audit log
+        return full_dump(&mut tx, since, latest, dump).await;
+    }
+    if since <= 0 {
+        return full_dump(&mut tx, latest, latest, DumpCursor::default()).await;
+    }
+
+    let through: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(max(id), ?1)
+        FROM (SELECT id FROM audit_log WHERE id > ?1 ORDER BY id LIMIT ?2)
+        "#,
+    )
+    .bind(since)
+    .bind(PAGE_SIZE)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut changes = Changes {
+        through,
+        more: through < latest,
+        ..Default::default()
+    };
+
+    for table in &TABLES {
+        let sql = format!(
+            r#"
+            SELECT {json}, {author} FROM {name} t
+            WHERE t.id IN (
+                SELECT row_id FROM audit_log WHERE table_name = ?1 AND id > ?2 AND id <= ?3
+            )
+            "#,
+            json = table.json("t"),
+            author = author("?1", "?3"),
+            name = table.name,
+        );
+        let rows = sqlx::query_as(&sql)
+            .bind(table.name)
+            .bind(since)
+            .bind(through)
+            .fetch_all(&mut *tx)
+            .await?;
+        changes.rows.extend(parse_rows(table, rows)?);
+
+        // Rows that are gone by now are sent as deletions, dated by their last
+        // delete; SQLite takes the bare `user_id` from the row with the max
+        let sql = format!(
+            r#"
+            SELECT a.row_id, max({deleted_at}), a.user_id
+            FROM audit_log a
+            WHERE a.table_name = ?1 AND a.operation = 'delete' AND a.id > ?2 AND a.id <= ?3
+                AND NOT EXISTS (SELECT 1 FROM {name} t WHERE t.id = a.row_id)
+            GROUP BY a.row_id
+            "#,
+            deleted_at = stamp("a.changed_at"),
+            name = table.name,
+        );
+        let deleted: Vec<(String, i64, Option<String>)> = sqlx::query_as(&sql)
+            .bind(table.name)
+            .bind(since)
+            .bind(through)
+            .fetch_all(&mut *tx)
+            .await?;
+        changes
+            .deletions
+            .extend(
+                deleted
+                    .into_iter()
+                    .map(|(id, deleted_at, user_id)| Deletion {
+                        table: table.name.to_string(),
+                        id,
+                        deleted_at,
+                        user_id,
+                    }),
+            );
+    }
+
+    Ok(changes)
+}
+
+/// Up to [`PAGE_SIZE`] rows of every synced table, starting at `from`.
+///
+/// `through` stays where the dump began, so what changed while it was paged
+/// through is sent again afterwards.
+async fn full_dump(
+    conn: &mut SqliteConnection,
+    through: i64,
+    latest: i64,
+    from: DumpCursor,
+) -> Result<Changes> {
+    let mut changes = Changes {
+        through,
+        ..Default::default()
+    };
+    let mut cursor = from;
+    let mut left = PAGE_SIZE;
+
+    while let Some(table) = TABLES.get(cursor.table) {
+        let sql = format!(
+            "SELECT t.rowid, {}, {} FROM {} t WHERE t.rowid > ?1 ORDER BY t.rowid LIMIT ?2",
+            table.json("t"),
+            author("?3", "?4"),
+            table.name
+        );
+        let rows: Vec<(i64, String, Option<String>)> = sqlx::query_as(&sql)
+            .bind(cursor.after)
+            .bind(left)
+            .bind(table.name)
+            .bind(through)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        left -= rows.len() as i64;
+        if let Some((rowid, ..)) = rows.last() {
+            cursor.after = *rowid;
+        }
+        changes.rows.extend(parse_rows(
+            table,
+            rows.into_iter()
+                .map(|(_, row, user_id)| (row, user_id))
+                .collect(),
+        )?);
+
+        if left == 0 {
+            changes.more = true;
+            changes.dump = Some(cursor);
+            return Ok(changes);
+        }
+        cursor = DumpCursor {
+            table: cursor.table + 1,
+            after: 0,
+        };
+    }
+
+    changes.more = through < latest;
+    Ok(changes)
+}
+
+/// Applies a peer's changes, keeping whichever side of each row was updated last.
+///
+/// With `pulled_from`, a payment changed here since our last push to that peer
+/// is recorded in `payment_conflicts` when the peer has a different version,
+/// whichever side wins. Changes pushed to us skip this: the peer pulled ours
+/// and settled the conflicts on its side first.
+pub async fn apply(
+    pool: &SqlitePool,
+    changes: &Changes,
+    pulled_from: Option<PulledFrom<'_>>,
+) -> Result<Applied> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+
+    match apply_locked(&mut conn, changes, pulled_from).await {
+        Ok(applied) => {
+            sqlx::query("COMMIT").execute(&mut *conn).await?;
+            Ok(applied)
+        }
+        Err(err) => {
+            // Never hand a connection stuck in a transaction back to the pool
+            if sqlx::query("ROLLBACK").execute(&mut *conn).await.is_err() {
+                let _ = conn.close().await;
+            }
+            Err(err)
+        }
+    }
+}
+
+async fn apply_locked(
+    conn: &mut SqliteConnection,
+    changes: &Changes,
+    pulled_from: Option<PulledFrom<'_>>,
+) -> Result<Applied> {
+    let mut applied = Applied::default();
+
+    let mut rows = Vec::with_capacity(changes.rows.len());
+    for row in &changes.rows {
+        match synced_table(&row.table) {
+            Some((order, table)) => rows.push((order, table, row)),
+            None => {
+                log::warn!("Ignoring a change to unknown table {}", row.table);
+                applied.skipped += 1;
+            }
+        }
+    }
+    rows.sort_by_key(|(order, ..)| *order);
+
+    for (_, table, row) in rows {
+        let (outcome, conflict) = apply_row(conn, table, row, pulled_from).await?;
+        applied.count(outcome, conflict);
+    }
+
+    let mut deletions = Vec::with_capacity(changes.deletions.len());
+    for deletion in &changes.deletions {
+        match synced_table(&deletion.table) {
+            Some((order, table)) => deletions.push((order, table, deletion)),
+            None => {
+                log::warn!("Ignoring a deletion from unknown table {}", deletion.table);
+                applied.skipped += 1;
+            }
+        }
+    }
+    deletions.sort_by_key(|(order, ..)| std::cmp::Reverse(*order));
+
+    for (_, table, deletion) in deletions {
+        let (outcome, conflict) = apply_deletion(conn, table, deletion, pulled_from).await?;
+        applied.count(outcome, conflict);
+    }
+
+    Ok(applied)
+}
+
+/// The local side of a row a peer sent.
+struct LocalRow {
+    /// `updated_at` as millis, missing when the row is not here
+    stamp: Option<i64>,
+    values: Option<Value>,
+    /// When the row was last deleted here, in millis
+    deleted_at: Option<i64>,
+    /// Changed here after the last audit log entry the peer received
+    unsynced: bool,
+}
+
+async fn local_row(
+    conn: &mut SqliteConnection,
+    table: &SyncedTable,
+    id: &str,
+    pulled_from: Option<PulledFrom<'_>>,
+) -> Result<LocalRow> {
+    let sql = format!(
+        r#"
+        SELECT
+            (SELECT {stamp} FROM {name} WHERE id = ?1) AS stamp,
+            (SELECT {json} FROM {name} t WHERE t.id = ?1) AS local_values,
+            (SELECT max({deleted_at}) FROM audit_log
+                WHERE table_name = ?2 AND row_id = ?1 AND operation = 'delete') AS deleted_at,
+            EXISTS (SELECT 1 FROM audit_log WHERE table_name = ?2 AND row_id = ?1 AND id > ?3) AS unsynced
+        "#,
+        stamp = stamp("updated_at"),
+        json = table.json("t"),
+        deleted_at = stamp("changed_at"),
+        name = table.name,
+    );
+
+    let row = sqlx::query(&sql)
+        .bind(id)
+        .bind(table.name)
+        .bind(pulled_from.map_or(i64::MAX, |from| from.pushed_through))
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let values: Option<String> = row.try_get("local_values")?;
+    Ok(LocalRow {
+        stamp: row.try_get("stamp")?,
+        values: values
+            .map(|values| serde_json::from_str(&values))
+            .transpose()?,
+        deleted_at: row.try_get("deleted_at")?,
+        unsynced: row.try_get("unsynced")?,
+    })
+}
+
+fn payment_differs(local: Option<&Value>, remote: Option<&Value>) -> bool {
+    PAYMENT_FIELDS.iter().any(|field| {
+        local.and_then(|values| values.get(field)) != remote.and_then(|values| values.get(field))
+    })
+}
+
+async fn record_conflict(
+    conn: &mut SqliteConnection,
+    peer: &str,
+    payment_id: &str,
+    local: Option<&Value>,
+    remote: Option<&Value>,
+    kept_remote: bool,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO payment_conflicts (payment_id, peer, local_values, remote_values, kept)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(payment_id)
+    .bind(peer)
+    .bind(local.map(Value::to_string))
+    .bind(remote.map(Value::to_string))
+    .bind(if kept_remote { "remote" } else { "local" })
+    .execute(&mut *conn)
+    .await?;
+
+    log::warn!(
+        "Payment {} was changed here and on {}, kept the {} version",
+        payment_id,
+        peer,
+        if kept_remote { "remote" } else { "local" }
+    );
+    Ok(())
+}
+
+// Whether the peer and this desk both changed a payment since they last synced
+fn payment_conflict<'a>(
+    table: &SyncedTable,
+    local: &LocalRow,
+    remote: Option<&Value>,
+    pulled_from: Option<PulledFrom<'a>>,
+) -> Option<&'a str> {
+    let peer = pulled_from?.peer;
+    let conflict = table.name == PAYMENTS
+        && local.unsynced
+        && (local.values.is_some() || remote.is_some())
+        && payment_differs(local.values.as_ref(), remote);
+
+    conflict.then_some(peer)
+}
+
+// The latest audit entry, so the entries a synced change adds can be found
+async fn last_audit_id(conn: &mut SqliteConnection) -> Result<i64> {
+    Ok(sqlx::query_scalar("SELECT COALESCE(max(id), 0) FROM audit_log")
+        .fetch_one(&mut *conn)
+        .await?)
+}
+
+// The audit trigger credits whoever is signed in here, while a synced change
+// was made by someone on the other desk
+async fn credit_author(
+    conn: &mut SqliteConnection,
+    after: i64,
+    user_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query("UPDATE audit_log SET user_id = ? WHERE id > ?")
+        .bind(user_id)
+        .bind(after)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn apply_row(
+    conn: &mut SqliteConnection,
+    table: &SyncedTable,
+    row: &ChangedRow,
+    pulled_from: Option<PulledFrom<'_>>,
+) -> Result<(Outcome, bool)> {
+    let values = &row.values;
+    let id = values.get("id").and_then(Value::as_str);
+    let text = values.to_string();
+    let remote_stamp: Option<i64> = sqlx::query_scalar(&format!(
+        "SELECT {}",
+        stamp("json_extract(?1, '$.updated_at')")
+    ))
+    .bind(&text)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let (Some(id), Some(remote_stamp)) = (id, remote_stamp) else {
+        log::warn!("Ignoring a {} row without an id or updated_at", table.name);
+        return Ok((Outcome::Skipped, false));
+    };
+
+    let local = local_row(conn, table, id, pulled_from).await?;
+    let newer = match (local.stamp, local.deleted_at) {
+        (Some(stamp), _) => remote_stamp > stamp,
+        // Deleted here after the peer's last edit stays deleted
+        (None, Some(deleted_at)) => remote_stamp > deleted_at,
+        (None, None) => true,
+    };
+
+    let conflict = payment_conflict(table, &local, Some(values), pulled_from);
+    if let Some(peer) = conflict {
+        record_conflict(conn, peer, id, local.values.as_ref(), Some(values), newer).await?;
+    }
+
+    if !newer {
+        return Ok((Outcome::Skipped, conflict.is_some()));
+    }
+
+    let audited = last_audit_id(conn).await?;
+    match sqlx::query(&table.upsert())
+        .bind(&text)
+        .execute(&mut *conn)
+        .await
+    {
+        Ok(_) => {
+            credit_author(conn, audited, row.user_id.as_deref()).await?;
+            Ok((Outcome::Applied, conflict.is_some()))
+        }
+        // A parent deleted here, or a check the peer's row fails, should not
+        // hold back the rest of the sync
+        Err(sqlx::Error::Database(err)) => {
+            log::warn!("Skipped {} {} from a peer: {}", table.name, id, err);
+            Ok((Outcome::Skipped, conflict.is_some()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn apply_deletion(
+    conn: &mut SqliteConnection,
+    table: &SyncedTable,
+    deletion: &Deletion,
+    pulled_from: Option<PulledFrom<'_>>,
+) -> Result<(Outcome, bool)> {
+    let local = local_row(conn, table, &deletion.id, pulled_from).await?;
+    let Some(stamp) = local.stamp else {
+        return Ok((Outcome::Skipped, false));
+    };
+
+    // An edit made here after the peer deleted the row keeps it
+    let newer = deletion.deleted_at >= stamp;
+
+    let conflict = payment_conflict(table, &local, None, pulled_from);
+    if let Some(peer) = conflict {
+        record_conflict(conn, peer, &deletion.id, local.values.as_ref(), None, newer).await?;
+    }
+
+    if !newer {
+        return Ok((Outcome::Skipped, conflict.is_some()));
+    }
+
+    let audited = last_audit_id(conn).await?;
+    sqlx::query(&format!("DELETE FROM {} WHERE id = ?", table.name))
+        .bind(&deletion.id)
+        .execute(&mut *conn)
+        .await?;
+    credit_author(conn, audited, deletion.user_id.as_deref()).await?;
+
+    Ok((Outcome::Applied, conflict.is_some()))
+}
+
+async fn cursors(pool: &SqlitePool, peer: &str) -> Result<(i64, i64)> {
+    let cursors =
+        sqlx::query_as("SELECT pulled_through, pushed_through FROM peer_sync_state WHERE peer = ?")
+            .bind(peer)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(cursors.unwrap_or_default())
+}
+
+async fn save_cursors(pool: &SqlitePool, peer: &str, pulled: i64, pushed: i64) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO peer_sync_state (peer, pulled_through, pushed_through, synced_at)
+        VALUES (?, ?, ?, datetime('now'))
+        ON CONFLICT(peer) DO UPDATE SET
+            pulled_through = excluded.pulled_through,
+            pushed_through = excluded.pushed_through,
+            synced_at = excluded.synced_at
+        "#,
+    )
+    .bind(peer)
+    .bind(pulled)
+    .bind(pushed)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn send<T: DeserializeOwned>(request: reqwest::RequestBuilder, secret: &str) -> Result<T> {
+    let response = request.header(PEER_SECRET_HEADER, secret).send().await?;
+    let status = response.status();
+
+    if !status.is_success() {
+        let message = response.text().await.unwrap_or_default();
+        return Err(Error::PeerSync(if message.trim().is_empty() {
+            status.to_string()
+        } else {
+            message
+        }));
+    }
+
+    Ok(response.json().await?)
+}
+
+/// Pulls the changes made on `peer`, e.g. `http://192.168.1.20:14200`, then
+/// pushes ours, carrying on from where the last sync with it stopped.
+///
+/// Pulling first means conflicting payments are noticed here, and what gets
+/// pushed is already settled.
+pub async fn sync_with(pool: &SqlitePool, peer: &str, secret: &str) -> Result<PeerSyncReport> {
+    let peer = peer.trim().trim_end_matches('/');
+    if !(peer.starts_with("http://") || peer.starts_with("https://")) {
+        return Err(Error::Validation(format!(
+            "Workstation address must start with http://, got \"{peer}\""
+        )));
+    }
+
+    let url = format!("{peer}{CHANGES_PATH}");
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()?;
+    let (mut pulled_through, mut pushed_through) = cursors(pool, peer).await?;
+    let mut report = PeerSyncReport {
+        peer: peer.to_string(),
+        ..Default::default()
+    };
+
+    let mut dump = None;
+    loop {
+        let mut request = client.get(&url).query(&[("since", pulled_through)]);
+        if let Some(dump) = &dump {
+            request = request.query(dump);
+        }
+        let theirs: Changes = send(request, secret).await?;
+        report.pulled += apply(
+            pool,
+            &theirs,
+            Some(PulledFrom {
+                peer,
+                pushed_through,
+            }),
+        )
+        .await?;
+
+        pulled_through = theirs.through;
+        dump = theirs.dump;
+        // A dump cut short starts over, the cursor only moves once it is complete
+        if dump.is_none() {
+            save_cursors(pool, peer, pulled_through, pushed_through).await?;
+        }
+        if !theirs.more {
+            break;
+        }
+    }
+
+    let mut dump = None;
+    loop {
+        let ours = changes(pool, pushed_through, dump).await?;
+        report.pushed += send::<Applied>(client.post(&url).json(&ours), secret).await?;
+
+        pushed_through = ours.through;
+        dump = ours.dump;
+        if dump.is_none() {
+            save_cursors(pool, peer, pulled_through, pushed_through).await?;
+        }
+        if !ours.more {
+            break;
+        }
+    }
+
+    Ok(report)
+}
+
+type PoolSource = Arc<dyn Fn() -> Option<SqlitePool> + Send + Sync>;
+
+/// What the peer routes need, without the Tauri app, so several instances
+/// can serve from one process.
+#[derive(Clone)]
+pub struct PeerState {
+    pool: PoolSource,
+    /// Unset turns the routes off
+    secret: Option<Arc<str>>,
+}
+
+impl PeerState {
+    /// `pool` is asked on every request, as the database may still be locked.
+    pub fn new(
+        secret: Option<String>,
+        pool: impl Fn() -> Option<SqlitePool> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            pool: Arc::new(pool),
+            secret: secret.map(Arc::from),
+        }
+    }
+
+    /// Uses the app's database once it is unlocked, with the configured secret.
+    pub fn for_app(app: &AppHandle) -> Self {
+        let secret = app.state::<AppState>().config.peer_sync_secret.clone();
+        let app = app.clone();
+        Self::new(secret, move || {
+            app.try_state::<SqlitePool>()
+                .map(|pool| pool.inner().clone())
+        })
+    }
+
+    fn pool(&self) -> std::result::Result<SqlitePool, (StatusCode, String)> {
+        (self.pool)().ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Error::Locked.to_string()))
+    }
+}
+
+/// `GET /peer/changes?since=` hands out changes, `POST /peer/changes` takes them.
+///
+/// Both need the shared secret in the `x-peer-secret` header.
+pub fn router(state: PeerState) -> Router {
+    Router::new()
+        .route(CHANGES_PATH, get(get_changes).post(post_changes))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_peer_secret,
+        ))
+        .with_state(state)
+        .layer(DefaultBodyLimit::disable())
+}
+
+async fn require_peer_secret(
+    State(state): State<PeerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(secret) = &state.secret else {
+        return (
+            StatusCode::NOT_FOUND,
+            "Sync between workstations is turned off on this PC".to_string(),
+        )
+            .into_response();
+    };
+
+    let authorized = request
+        .headers()
+        .get(PEER_SECRET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|given| pairing::constant_time_eq(secret.as_bytes(), given.as_bytes()));
+
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "The sync secret does not match this PC's".to_string(),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+#[derive(Deserialize)]
+struct ChangesQuery {
+    #[serde(default)]
+    since: i64,
+    /// Both set while paging through a full dump
+    table: Option<usize>,
+    after: Option<i64>,
+}
+
+type PeerResponse<T> = std::result::Result<Json<T>, (StatusCode, String)>;
+
+fn internal_error(err: Error) -> (StatusCode, String) {
+    log::error!("Peer sync request failed: {}", err);
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+}
+
+async fn get_changes(
+    State(state): State<PeerState>,
+    Query(query): Query<ChangesQuery>,
+) -> PeerResponse<Changes> {
+    let pool = state.pool()?;
+    let dump = query
+        .table
+        .map(|table| DumpCursor {
+            table,
+            after: query.after.unwrap_or_default(),
+        });
+    changes(&pool, query.since, dump)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+async fn post_changes(
+    State(state): State<PeerState>,
+    Json(changes): Json<Changes>,
+) -> PeerResponse<Applied> {
+    let pool = state.pool()?;
+    apply(&pool, &changes, None)
+        .await
+        .map(Json)
+        .map_err(internal_error)
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct ConflictRow {
+    id: i64,
+    payment_id: String,
+    peer: String,
+    local_values: Option<String>,
+    remote_values: Option<String>,
+    kept: String,
+    detected_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentConflict {
+    pub id: i64,
+    pub payment_id: String,
+    pub peer: String,
+    /// This desk's version, missing when it had deleted the payment
+    pub local_values: Option<Value>,
+    /// The peer's version, missing when it had deleted the payment
+    pub remote_values: Option<Value>,
+    /// `local` or `remote`, whichever was updated last
+    pub kept: String,
+    pub detected_at: String,
+}
+
+fn parse_values(values: Option<String>) -> Option<Value> {
+    values.and_then(|values| serde_json::from_str(&values).ok())
+}
+
+impl From<ConflictRow> for PaymentConflict {
+    fn from(row: ConflictRow) -> Self {
+        Self {
+            id: row.id,
+            payment_id: row.payment_id,
+            peer: row.peer,
+            local_values: parse_values(row.local_values),
+            remote_values: parse_values(row.remote_values),
+            kept: row.kept,
+            detected_at: row.detected_at,
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn sync_with_peer(
+    pool: TauriState<'_, SqlitePool>,
+    state: TauriState<'_, AppState>,
+    url: String,
+) -> Result<PeerSyncReport> {
+    state.require(Permission::ManageBackups)?;
+
+    let secret = state.config.peer_sync_secret.as_deref().ok_or_else(|| {
+        Error::Validation("Set a peer sync secret in the settings first".to_string())
+    })?;
+
+    let report = sync_with(&pool, &url, secret).await?;
+    log::info!("Synced with {}: {:?}", report.peer, report);
+    Ok(report)
+}
+
+/// Payment conflicts nobody has looked at yet, newest first.
+#[tauri::command]
+pub async fn get_payment_conflicts(
+    pool: TauriState<'_, SqlitePool>,
+    state: TauriState<'_, AppState>,
+) -> Result<Vec<PaymentConflict>> {
+    state.require(Permission::EditPayments)?;
+
+    let rows = sqlx::query_as::<_, ConflictRow>(
+        r#"
+        SELECT id, payment_id, peer, local_values, remote_values, kept, detected_at
+        FROM payment_conflicts
+        WHERE resolved_at IS NULL
+        ORDER BY id DESC
+        "#,
+    )
+    .fetch_all(&*pool)
+    .await?;
+
+    Ok(rows.into_iter().map(PaymentConflict::from).collect())
+}
+
+#[tauri::command]
+pub async fn resolve_payment_conflict(
+    pool: TauriState<'_, SqlitePool>,
+    state: TauriState<'_, AppState>,
+    id: i64,
+) -> Result<()> {
+    state.require(Permission::EditPayments)?;
+
+    let result = sqlx::query(
+        "UPDATE payment_conflicts SET resolved_at = datetime('now') WHERE id = ? AND resolved_at IS NULL",
+    )
+    .bind(id)
+    .execute(&*pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Payment conflict"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::Role,
+        database::memory_pool,
+        repository::{new_id, payments, test_statement, users},
+    };
+
+    const SECRET: &str = "test-secret";
+
+    /// A workstation serving its in-memory database on a free local port.
+    struct Desk {
+        pool: SqlitePool,
+        url: String,
+    }
+
+    impl Desk {
+        async fn start() -> Self {
+            let pool = memory_pool().await;
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+
+            let served = pool.clone();
+            let app = router(PeerState::new(Some(SECRET.to_string()), move || {
+                Some(served.clone())
+            }));
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+            Self { pool, url }
+        }
+
+        async fn sync_with(&self, peer: &Desk) -> PeerSyncReport {
+            sync_with(&self.pool, &peer.url, SECRET).await.unwrap()
+        }
+
+        async fn edit(&self, table: &str, column: &str, id: &str, value: &str, at: &str) {
+            sqlx::query(&format!(
+                "UPDATE {table} SET {column} = ?, updated_at = ? WHERE id = ?"
+            ))
+            .bind(value)
+            .bind(at)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .unwrap();
+        }
+
+        /// Signs a new account in at this desk, for the audit trigger to credit.
+        async fn sign_in(&self, username: &str) -> String {
+            let user = users::create(
+                &self.pool,
+                &users::AddUser {
+                    user: users::SaveUser {
+                        username: username.to_string(),
+                        display_name: username.to_string(),
+                        role: Role::Receptionist,
+                        active: true,
+                    },
+                    password: "correct horse battery".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+
+            sqlx::query("INSERT OR REPLACE INTO app_session (id, user_id) VALUES (1, ?)")
+                .bind(&user.id)
+                .execute(&self.pool)
+                .await
+                .unwrap();
+            user.id
+        }
+
+        /// Who the audit log credits with the latest change to a row.
+        async fn author(&self, table: &str, id: &str) -> Option<String> {
+            sqlx::query_scalar(
+                "SELECT user_id FROM audit_log WHERE table_name = ? AND row_id = ? ORDER BY id DESC LIMIT 1",
+            )
+            .bind(table)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .unwrap()
+        }
+
+        async fn value(&self, table: &str, column: &str, id: &str) -> Option<String> {
+            sqlx::query_scalar(&format!(
+                "SELECT CAST({column} AS TEXT) FROM {table} WHERE id = ?"
+            ))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_the_last_edit_of_a_row_changed_on_both_desks() {
+        let a = Desk::start().await;
+        let b = Desk::start().await;
+        let statement_id = test_statement(&a.pool, 500).await;
+        let patient_id = a
+            .value("statements", "patient_id", &statement_id)
+            .await
+            .unwrap();
+
+        a.sync_with(&b).await;
+        assert_eq!(
+            b.value("patients", "name", &patient_id).await.as_deref(),
+            Some("Test Patient")
+        );
+
+        a.edit("patients", "name", &patient_id, "Edited on A", "2030-01-01 10:00:00")
+            .await;
+        b.edit("patients", "name", &patient_id, "Edited on B", "2030-01-01 11:00:00")
+            .await;
+
+        // Synced from the other side this time, through the first desk's router
+        let report = b.sync_with(&a).await;
+        assert!(report.pulled.skipped > 0);
+        assert!(report.pushed.applied > 0);
+
+        for desk in [&a, &b] {
+            assert_eq!(
+                desk.value("patients", "name", &patient_id).await.as_deref(),
+                Some("Edited on B")
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_the_author_of_synced_changes() {
+        let a = Desk::start().await;
+        let b = Desk::start().await;
+        let sara = a.sign_in("sara").await;
+        let omar = b.sign_in("omar").await;
+
+        let statement_id = test_statement(&a.pool, 500).await;
+        let patient_id = a
+            .value("statements", "patient_id", &statement_id)
+            .await
+            .unwrap();
+        let payment = payments::create(
+            &a.pool,
+            &payments::AddPayment {
+                statement_id,
+                amount: 50,
+            },
+        )
+        .await
+        .unwrap();
+
+        // Pushed to the other desk
+        a.sync_with(&b).await;
+        assert_eq!(b.author("patients", &patient_id).await, Some(sara.clone()));
+
+        // Pulled from the other desk, an edit and a deletion
+        b.edit("patients", "name", &patient_id, "Edited on B", "2030-01-01 10:00:00")
+            .await;
+        payments::delete(&b.pool, &payment.id).await.unwrap();
+        a.sync_with(&b).await;
+
+        assert_eq!(
+            a.value("patients", "name", &patient_id).await.as_deref(),
+            Some("Edited on B")
+        );
+        assert_eq!(a.author("patients", &patient_id).await, Some(omar.clone()));
+        assert_eq!(a.value("payments", "amount", &payment.id).await, None);
+        assert_eq!(a.author("payments", &payment.id).await, Some(omar));
+    }
+
+    #[tokio::test]
+    async fn settles_a_deletion_racing_an_edit() {
+        let a = Desk::start().await;
+        let b = Desk::start().await;
+        let statement_id = test_statement(&a.pool, 500).await;
+        let (kept, deleted) = (new_id(), new_id());
+        for id in [&kept, &deleted] {
+            sqlx::query(
+                r#"
+                INSERT INTO sessions (id, statement_id, procedure, created_at, updated_at)
+                VALUES (?, ?, 'Cleaning', '2020-01-01 00:00:00', '2020-01-01 00:00:00')
+                "#,
+            )
+            .bind(id)
+            .bind(&statement_id)
+            .execute(&a.pool)
+            .await
+            .unwrap();
+        }
+        a.sync_with(&b).await;
+
+        sqlx::query("DELETE FROM sessions WHERE statement_id = ?")
+            .bind(&statement_id)
+            .execute(&a.pool)
+            .await
+            .unwrap();
+        // One edit lands after the deletion, the other before it
+        b.edit("sessions", "procedure", &kept, "Filling", "2099-01-01 00:00:00")
+            .await;
+        b.edit("sessions", "procedure", &deleted, "Filling", "2021-01-01 00:00:00")
+            .await;
+
+        a.sync_with(&b).await;
+
+        for desk in [&a, &b] {
+            assert_eq!(
+                desk.value("sessions", "procedure", &kept).await.as_deref(),
+                Some("Filling")
+            );
+            assert_eq!(desk.value("sessions", "procedure", &deleted).await, None);
+        }
+    }
+
+    #[tokio::test]
+    async fn records_a_payment_changed_on_both_desks() {
+        let a = Desk::start().await;
+        let b = Desk::start().await;
+        let statement_id = test_statement(&a.pool, 500).await;
+        let payment = payments::create(
+            &a.pool,
+            &payments::AddPayment {
+                statement_id,
+                amount: 50,
+            },
+        )
+        .await
+        .unwrap();
+        a.sync_with(&b).await;
+
+        a.edit("payments", "amount", &payment.id, "100", "2030-01-01 10:00:00")
+            .await;
+        b.edit("payments", "amount", &payment.id, "200", "2030-01-01 11:00:00")
+            .await;
+
+        let report = a.sync_with(&b).await;
+        assert_eq!(report.pulled.conflicts, 1);
+
+        for desk in [&a, &b] {
+            assert_eq!(
+                desk.value("payments", "amount", &payment.id).await.as_deref(),
+                Some("200")
+            );
+        }
+
+        let conflicts: Vec<ConflictRow> = sqlx::query_as(
+            r#"
+            SELECT id, payment_id, peer, local_values, remote_values, kept, detected_at
+            FROM payment_conflicts
+            "#,
+        )
+        .fetch_all(&a.pool)
+        .await
+        .unwrap();
+        let conflicts: Vec<PaymentConflict> =
+            conflicts.into_iter().map(PaymentConflict::from).collect();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].payment_id, payment.id);
+        assert_eq!(conflicts[0].peer, b.url);
+        assert_eq!(conflicts[0].kept, "remote");
+        assert_eq!(conflicts[0].local_values.as_ref().unwrap()["amount"], 100);
+        assert_eq!(conflicts[0].remote_values.as_ref().unwrap()["amount"], 200);
+
+        // The pushing side had nothing left to settle
+        let theirs: i64 = sqlx::query_scalar("SELECT count(*) FROM payment_conflicts")
+            .fetch_one(&b.pool)
+            .await
+            .unwrap();
+        assert_eq!(theirs, 0);
+    }
+
+    #[tokio::test]
+    async fn pages_the_full_dump() {
+        let pool = memory_pool().await;
+        sqlx::query(
+            r#"
+            WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 1500)
+            INSERT INTO clinics (id, name, created_at, updated_at)
+            SELECT 'clinic-' || i, 'Clinic ' || i, datetime('now'), datetime('now') FROM n
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let first = changes(&pool, 0, None).await.unwrap();
+        assert_eq!(first.rows.len(), PAGE_SIZE as usize);
+        assert!(first.more);
+        let dump = first.dump.unwrap();
+
+        let second = changes(&pool, first.through, Some(dump)).await.unwrap();
+        assert_eq!(second.rows.len(), 500);
+        assert_eq!(second.through, first.through);
+        assert!(second.dump.is_none());
+        assert!(!second.more);
+
+        let mut ids: Vec<&str> = first
+            .rows
+            .iter()
+            .chain(&second.rows)
+            .map(|row| row.values["id"].as_str().unwrap())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 1500);
+    }
+}
@@ -0,0 +1,159 @@
+use std::io::{self, Cursor};
+
+use base64::Engine;
+use image::{ImageFormat, Luma};
+use qrcode::{render::svg, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+// Readable from a phone held at arm's length, and sharp enough to print
+const MIN_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QrFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl QrFormat {
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            QrFormat::Svg => "image/svg+xml",
+            QrFormat::Png => "image/png",
+        }
+    }
+}
+
+/// `text` as a QR code image, with the quiet zone scanners need around it.
+pub fn render(text: &str, format: QrFormat) -> Result<Vec<u8>> {
+    // Medium error correction survives screen glare without growing the code much
+    let code = QrCode::with_error_correction_level(text, EcLevel::M)
+        .map_err(|err| Error::Validation(format!("Cannot make a QR code: {err}")))?;
+
+    match format {
+        QrFormat::Svg => Ok(code
+            .render::<svg::Color<'_>>()
+            .min_dimensions(MIN_SIZE, MIN_SIZE)
+            .quiet_zone(true)
+            .build()
+            .into_bytes()),
+        QrFormat::Png => {
+            let image = code
+                .render::<Luma<u8>>()
+                .min_dimensions(MIN_SIZE, MIN_SIZE)
+                .quiet_zone(true)
+                .build();
+
+            let mut png = Cursor::new(Vec::new());
+            image
+                .write_to(&mut png, ImageFormat::Png)
+                .map_err(io::Error::other)?;
+            Ok(png.into_inner())
+        }
+    }
+}
+
+/// [`render`] as a `data:` URL an `<img>` can show directly.
+pub fn data_url(text: &str, format: QrFormat) -> Result<String> {
+    let image = render(text, format)?;
+
+    Ok(format!(
+        "data:{};base64,{}",
+        format.mime_type(),
+        base64::engine::general_purpose::STANDARD.encode(image)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GenericImageView;
+    use qrcode::Color;
+
+    use super::*;
+    use crate::pairing::{PairingInfo, ScanQr};
+
+    const URL: &str = "http://sgmc-desk.local:3000/scan?token=abc123&statement_id=42";
+
+    /// Whether the PNG shows exactly the modules of `text`'s QR code.
+    fn shows(png: &[u8], text: &str) -> bool {
+        let code = QrCode::with_error_correction_level(text, EcLevel::M).unwrap();
+        let image = image::load_from_memory(png).unwrap();
+        // Four modules of quiet zone on each side
+        let modules = code.width() as u32 + 8;
+        let module_size = image.width() / modules;
+        assert_eq!(image.width(), module_size * modules);
+
+        let colors = code.to_colors();
+        (0..code.width()).all(|y| {
+            (0..code.width()).all(|x| {
+                let center = |at: usize| (at as u32 + 4) * module_size + module_size / 2;
+                let dark = image.get_pixel(center(x), center(y))[0] < 128;
+                dark == (colors[y * code.width() + x] == Color::Dark)
+            })
+        })
+    }
+
+    #[test]
+    fn draws_the_scan_url() {
+        let png = render(URL, QrFormat::Png).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+        assert!(image.width() >= MIN_SIZE && image.height() >= MIN_SIZE);
+
+        assert!(shows(&png, URL));
+        assert!(!shows(&png, &URL.replace("abc123", "abc124")));
+    }
+
+    #[test]
+    fn embeds_the_image_in_a_data_url() {
+        let url = data_url(URL, QrFormat::Png).unwrap();
+        let encoded = url.strip_prefix("data:image/png;base64,").unwrap();
+        let png = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        assert!(shows(&png, URL));
+
+        let svg = data_url(URL, QrFormat::Svg).unwrap();
+        let encoded = svg.strip_prefix("data:image/svg+xml;base64,").unwrap();
+        let svg = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        assert!(String::from_utf8(svg).unwrap().contains("<svg"));
+    }
+
+    #[test]
+    fn serializes_the_scan_qr_for_the_webview() {
+        let qr = ScanQr {
+            pairing: PairingInfo {
+                token: "abc123".to_string(),
+                scan_url: URL.to_string(),
+                fallback_url: Some(URL.replace("sgmc-desk.local", "192.168.1.20")),
+                expires_at: 1_700_000_000_000,
+            },
+            format: QrFormat::Png,
+            image: "data:image/png;base64,".to_string(),
+            fallback_image: None,
+        };
+
+        let json = serde_json::to_value(&qr).unwrap();
+        let mut keys: Vec<_> = json.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "expiresAt",
+                "fallbackImage",
+                "fallbackUrl",
+                "format",
+                "image",
+                "scanUrl",
+                "token"
+            ]
+        );
+        assert_eq!(json["scanUrl"], URL);
+        assert_eq!(json["format"], "png");
+        assert!(json["fallbackImage"].is_null());
+    }
+}
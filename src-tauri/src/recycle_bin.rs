@@ -0,0 +1,333 @@
+use std::{fs, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager, State};
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+    repository::{attachments::attachments_dir, search_term, PagedList, PagingParams},
+};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum DeletedKind {
+    Patient,
+    Statement,
+}
+
+impl DeletedKind {
+    fn table(self) -> &'static str {
+        match self {
+            DeletedKind::Patient => "patients",
+            DeletedKind::Statement => "statements",
+        }
+    }
+
+    fn not_found(self) -> Error {
+        match self {
+            DeletedKind::Patient => Error::NotFound("Patient"),
+            DeletedKind::Statement => Error::NotFound("Statement"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecycleBinParams {
+    #[serde(flatten)]
+    pub paging: PagingParams,
+    /// Patient name or phone
+    pub search: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedItem {
+    pub kind: DeletedKind,
+    pub id: String,
+    pub patient_id: String,
+    pub patient_name: String,
+    pub patient_phone: String,
+    /// Statement total, missing for patients
+    pub total: Option<i64>,
+    pub deleted_at: String,
+    /// When the item will be purged, missing when the bin is kept forever
+    pub purge_at: Option<String>,
+}
+
+// A deleted patient takes their statements along, so those are not listed
+// separately and come back when the patient is restored
+const DELETED_ITEMS: &str = r#"
+    SELECT
+        'patient' AS kind,
+        p.id,
+        p.id AS patient_id,
+        p.name AS patient_name,
+        p.phone AS patient_phone,
+        NULL AS total,
+        p.deleted_at
+    FROM patients p
+    WHERE p.deleted_at IS NOT NULL
+    UNION ALL
+    SELECT
+        'statement' AS kind,
+        s.id,
+        p.id AS patient_id,
+        p.name AS patient_name,
+        p.phone AS patient_phone,
+        s.total,
+        s.deleted_at
+    FROM statements s
+    JOIN patients p ON s.patient_id = p.id
+    WHERE s.deleted_at IS NOT NULL AND p.deleted_at IS NULL
+"#;
+
+const SEARCH_FILTER: &str = r#"
+    WHERE ?1 IS NULL OR patient_name LIKE '%' || ?1 || '%' OR patient_phone LIKE '%' || ?1 || '%'
+"#;
+
+/// Deleted patients and statements, most recently deleted first.
+pub async fn list(
+    pool: &SqlitePool,
+    params: &RecycleBinParams,
+    retention_days: u32,
+) -> Result<PagedList<DeletedItem>> {
+    let search = search_term(params.search.as_deref());
+
+    let sql = format!(
+        r#"
+        SELECT
+            *,
+            CASE WHEN ?2 > 0 THEN datetime(deleted_at, '+' || ?2 || ' days') END AS purge_at
+        FROM ({DELETED_ITEMS})
+        {SEARCH_FILTER}
+        ORDER BY deleted_at DESC, id
+        LIMIT ?3 OFFSET ?4
+        "#
+    );
+
+    let items = sqlx::query_as::<_, DeletedItem>(&sql)
+        .bind(search)
+        .bind(retention_days)
+        .bind(params.paging.limit())
+        .bind(params.paging.offset())
+        .fetch_all(pool)
+        .await?;
+
+    let total: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM ({DELETED_ITEMS}) {SEARCH_FILTER}"
+    ))
+    .bind(search)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(PagedList::new(items, &params.paging, total))
+}
+
+/// Takes a patient or statement back out of the recycle bin.
+pub async fn restore(pool: &SqlitePool, kind: DeletedKind, id: &str) -> Result<()> {
+    if kind == DeletedKind::Statement {
+        let patient_deleted: Option<bool> = sqlx::query_scalar(
+            r#"
+            SELECT p.deleted_at IS NOT NULL
+            FROM statements s
+            JOIN patients p ON s.patient_id = p.id
+            WHERE s.id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        if patient_deleted == Some(true) {
+            return Err(Error::Conflict(
+                "Restore the patient before restoring their statement".to_string(),
+            ));
+        }
+    }
+
+    let sql = format!(
+        "UPDATE {} SET deleted_at = NULL, updated_at = datetime('now') WHERE id = ? AND deleted_at IS NOT NULL",
+        kind.table()
+    );
+    let result = sqlx::query(&sql).bind(id).execute(pool).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(kind.not_found());
+    }
+
+    Ok(())
+}
+
+// Rows are matched by id or, for the retention sweep, by age
+const PURGE_FILTER: &str = r#"
+    deleted_at IS NOT NULL AND
+    (?1 IS NULL OR id = ?1) AND
+    (?2 IS NULL OR deleted_at <= datetime('now', '-' || ?2 || ' days'))
+"#;
+
+/// Deletes matching rows from the recycle bin for good, along with the
+/// attachment files of every statement that goes with them.
+///
+/// Returns how many rows of `kind` were removed, not counting cascades.
+async fn purge_where(
+    pool: &SqlitePool,
+    data_dir: &Path,
+    kind: DeletedKind,
+    id: Option<&str>,
+    older_than_days: Option<u32>,
+) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+
+    let statements = match kind {
+        DeletedKind::Patient => format!(
+            "SELECT id FROM statements WHERE patient_id IN (SELECT id FROM patients WHERE {PURGE_FILTER})"
+        ),
+        DeletedKind::Statement => format!("SELECT id FROM statements WHERE {PURGE_FILTER}"),
+    };
+    let files: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT file_path FROM attachments WHERE statement_id IN ({statements})"
+    ))
+    .bind(id)
+    .bind(older_than_days)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Statements, payments, sessions, attachments and appointments cascade
+    let result = sqlx::query(&format!(
+        "DELETE FROM {} WHERE {PURGE_FILTER}",
+        kind.table()
+    ))
+    .bind(id)
+    .bind(older_than_days)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let dir = attachments_dir(data_dir);
+    for file in files {
+        remove_attachment_file(&dir, &file);
+    }
+
+    Ok(result.rows_affected())
+}
+
+// Only ever touches the attachments folder, even if the stored path points
+// elsewhere, e.g. after the data folder was moved
+fn remove_attachment_file(dir: &Path, file_path: &str) {
+    let Some(name) = Path::new(file_path).file_name() else {
+        return;
+    };
+
+    let path = dir.join(name);
+    if let Err(err) = fs::remove_file(&path) {
+        log::warn!("Failed to delete file {}: {}", path.display(), err);
+    }
+}
+
+/// Permanently deletes one item that is in the recycle bin.
+pub async fn purge(pool: &SqlitePool, data_dir: &Path, kind: DeletedKind, id: &str) -> Result<()> {
+    if purge_where(pool, data_dir, kind, Some(id), None).await? == 0 {
+        return Err(kind.not_found());
+    }
+
+    Ok(())
+}
+
+/// Permanently deletes everything that has been in the recycle bin for
+/// longer than `retention_days`. Zero keeps deleted items forever.
+pub async fn purge_expired(pool: &SqlitePool, data_dir: &Path, retention_days: u32) -> Result<u64> {
+    if retention_days == 0 {
+        return Ok(0);
+    }
+
+    let patients = purge_where(
+        pool,
+        data_dir,
+        DeletedKind::Patient,
+        None,
+        Some(retention_days),
+    )
+    .await?;
+    let statements = purge_where(
+        pool,
+        data_dir,
+        DeletedKind::Statement,
+        None,
+        Some(retention_days),
+    )
+    .await?;
+
+    Ok(patients + statements)
+}
+
+/// Empties expired items from the recycle bin at startup and then daily.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = time::interval(PURGE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+
+            let pool = app.state::<SqlitePool>();
+            let config = &app.state::<AppState>().config;
+            match purge_expired(
+                &pool,
+                Path::new(&config.data_dir),
+                config.recycle_bin_retention_days,
+            )
+            .await
+            {
+                Ok(0) => {}
+                Ok(count) => log::info!("Purged {} items from the recycle bin", count),
+                Err(err) => log::error!("Failed to purge the recycle bin: {}", err),
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_recycle_bin(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    params: Option<RecycleBinParams>,
+) -> Result<PagedList<DeletedItem>> {
+    state.require(Permission::ManageRecycleBin)?;
+    list(
+        &pool,
+        &params.unwrap_or_default(),
+        state.config.recycle_bin_retention_days,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn restore_deleted(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    kind: DeletedKind,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::ManageRecycleBin)?;
+    restore(&pool, kind, &id).await
+}
+
+#[tauri::command]
+pub async fn purge_deleted(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    kind: DeletedKind,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::ManageRecycleBin)?;
+    purge(&pool, Path::new(&state.config.data_dir), kind, &id).await
+}
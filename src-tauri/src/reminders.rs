@@ -0,0 +1,670 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+use tokio::{
+    sync::Mutex as AsyncMutex,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    app_state::AppState,
+    config::AppConfig,
+    error::{Error, Result},
+    ledger::{self, StatementBalance},
+    reports::{statement::format_currency, Language},
+    repository::{
+        appointments::{self, Appointment, TIME_FORMAT},
+        users,
+    },
+};
+
+const LOG_FILE: &str = "reminders.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Unpaid statements are brought up again weekly until they are settled
+const OVERDUE_REPEAT: TimeDelta = TimeDelta::days(7);
+// Older entries can no longer match anything that is due
+const LOG_RETENTION: TimeDelta = TimeDelta::days(30);
+
+/// Where the scheduler gets the time from, so it can be driven by hand.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A notification that is due, with the log keys it settles.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub keys: Vec<String>,
+    pub title: String,
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReminderLog {
+    /// Last language the webview reported, notifications are written in it
+    #[serde(default)]
+    language: Language,
+    /// Reminder key to when it was shown, in Unix milliseconds
+    #[serde(default)]
+    notified: HashMap<String, i64>,
+}
+
+impl ReminderLog {
+    // Strictly after, so a weekly repeat is due again on the same minute
+    fn notified_after(&self, key: &str, since: DateTime<Local>) -> bool {
+        self.notified
+            .get(key)
+            .is_some_and(|&at| at > since.timestamp_millis())
+    }
+}
+
+// Appointments are stored in local time, statements in UTC
+fn parse_time(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value, TIME_FORMAT).ok()
+}
+
+fn appointment_text(appointment: &Appointment, language: Language) -> (String, String) {
+    let time = parse_time(&appointment.starts_at)
+        .map(|start| start.format("%H:%M").to_string())
+        .unwrap_or_else(|| appointment.starts_at.clone());
+    let doctor = appointment.doctor_name.as_deref();
+    let clinic = appointment.clinic_name.as_deref();
+
+    match language {
+        Language::En => {
+            let mut body = format!("{} at {time}", appointment.patient_name);
+            if let Some(doctor) = doctor {
+                body.push_str(&format!(" with {doctor}"));
+            }
+            if let Some(clinic) = clinic {
+                body.push_str(&format!(" in {clinic}"));
+            }
+            ("Upcoming appointment".to_string(), body)
+        }
+        Language::Ar => {
+            let mut body = format!("{} الساعة {time}", appointment.patient_name);
+            if let Some(doctor) = doctor {
+                body.push_str(&format!(" مع {doctor}"));
+            }
+            if let Some(clinic) = clinic {
+                body.push_str(&format!(" في {clinic}"));
+            }
+            ("موعد قادم".to_string(), body)
+        }
+    }
+}
+
+fn overdue_text(statements: &[&StatementBalance], language: Language) -> (String, String) {
+    let body = match (statements, language) {
+        ([statement], Language::En) => format!(
+            "{} owes {}",
+            statement.patient_name,
+            format_currency(statement.outstanding, language)
+        ),
+        ([statement], Language::Ar) => format!(
+            "{} عليه {}",
+            statement.patient_name,
+            format_currency(statement.outstanding, language)
+        ),
+        (_, Language::En) => format!("{} statements have overdue balances", statements.len()),
+        (_, Language::Ar) => format!("{} فواتير عليها أرصدة متأخرة", statements.len()),
+    };
+
+    let title = match language {
+        Language::En => "Overdue balance",
+        Language::Ar => "رصيد متأخر",
+    };
+
+    (title.to_string(), body)
+}
+
+/// One reminder per appointment whose lead time has been reached.
+///
+/// When several lead times are reached at once, e.g. after the app was
+/// closed, only one notification is shown and all of them are settled.
+fn appointment_reminders(
+    now: DateTime<Local>,
+    lead_minutes: &[u32],
+    appointments: &[Appointment],
+    log: &ReminderLog,
+) -> Vec<Reminder> {
+    let local_now = now.naive_local();
+
+    appointments
+        .iter()
+        .filter_map(|appointment| {
+            let start = parse_time(&appointment.starts_at)?;
+            if start <= local_now {
+                return None;
+            }
+
+            // Rescheduling changes the key, so the new time is announced again
+            let keys: Vec<String> = lead_minutes
+                .iter()
+                .filter(|&&lead| local_now >= start - TimeDelta::minutes(i64::from(lead)))
+                .map(|lead| {
+                    format!(
+                        "appointment:{}:{}:{lead}",
+                        appointment.id, appointment.starts_at
+                    )
+                })
+                .collect();
+
+            if keys.iter().all(|key| log.notified.contains_key(key)) {
+                return None;
+            }
+
+            let (title, body) = appointment_text(appointment, log.language);
+            Some(Reminder { keys, title, body })
+        })
+        .collect()
+}
+
+/// A single reminder covering every statement that is newly overdue.
+fn overdue_reminder(
+    now: DateTime<Local>,
+    days: u32,
+    balances: &[StatementBalance],
+    log: &ReminderLog,
+) -> Option<Reminder> {
+    if days == 0 {
+        return None;
+    }
+
+    let cutoff = now.naive_utc() - TimeDelta::days(i64::from(days));
+    let statements: Vec<&StatementBalance> = balances
+        .iter()
+        .filter(|statement| statement.outstanding > 0)
+        .filter(|statement| parse_time(&statement.created_at).is_some_and(|at| at <= cutoff))
+        .filter(|statement| {
+            !log.notified_after(
+                &format!("overdue:{}", statement.statement_id),
+                now - OVERDUE_REPEAT,
+            )
+        })
+        .collect();
+
+    if statements.is_empty() {
+        return None;
+    }
+
+    let (title, body) = overdue_text(&statements, log.language);
+    Some(Reminder {
+        keys: statements
+            .iter()
+            .map(|statement| format!("overdue:{}", statement.statement_id))
+            .collect(),
+        title,
+        body,
+    })
+}
+
+/// Decides which reminders are due and remembers the ones already shown.
+///
+/// The log is kept in `DATA_DIR/reminders.json` so restarting the app does
+/// not repeat notifications.
+pub struct ReminderScheduler {
+    path: PathBuf,
+    log: Mutex<ReminderLog>,
+    saving: AsyncMutex<()>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ReminderScheduler {
+    pub fn load(data_dir: &Path) -> Self {
+        Self::with_clock(data_dir, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(data_dir: &Path, clock: Arc<dyn Clock>) -> Self {
+        let path = data_dir.join(LOG_FILE);
+        let log = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            log: Mutex::new(log),
+            saving: AsyncMutex::new(()),
+            clock,
+        }
+    }
+
+    pub async fn set_language(&self, language: Language) {
+        self.update(|log| log.language = language).await;
+    }
+
+    async fn update(&self, f: impl FnOnce(&mut ReminderLog)) {
+        f(&mut self.log.lock().unwrap());
+
+        // Saves queue up and each writes the log as it is by then, so a
+        // slow save never overwrites a newer one
+        let _saving = self.saving.lock().await;
+        let log = self.log.lock().unwrap().clone();
+        let result = match serde_json::to_vec_pretty(&log) {
+            Ok(data) => tokio::fs::write(&self.path, data).await.map_err(Error::from),
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to save reminder log: {}", err);
+        }
+    }
+
+    /// Returns the reminders that are due now and marks them as shown.
+    ///
+    /// Appointments are announced at the lead times `user_id` picked, or the
+    /// app setting when they have none or nobody is signed in.
+    pub async fn due(
+        &self,
+        pool: &SqlitePool,
+        config: &AppConfig,
+        user_id: Option<&str>,
+    ) -> Result<Vec<Reminder>> {
+        let now = self.clock.now();
+        let local_now = now.naive_local();
+        let lead_minutes = match user_id {
+            Some(id) => users::reminder_lead_minutes(pool, id).await?,
+            None => None,
+        }
+        .unwrap_or_else(|| config.reminder_lead_minutes.clone());
+        let longest_lead = lead_minutes.iter().max().copied().unwrap_or(0);
+
+        let upcoming = if longest_lead > 0 {
+            // The range is half-open, and an appointment exactly the longest
+            // lead time away is due too
+            appointments::scheduled_between(
+                pool,
+                local_now,
+                local_now + TimeDelta::minutes(i64::from(longest_lead)) + TimeDelta::seconds(1),
+            )
+            .await?
+        } else {
+            Vec::new()
+        };
+
+        let balances = if config.overdue_reminder_days > 0 {
+            ledger::outstanding_balances(pool).await?
+        } else {
+            Vec::new()
+        };
+
+        let reminders = {
+            let log = self.log.lock().unwrap();
+            let mut reminders =
+                appointment_reminders(now, &lead_minutes, &upcoming, &log);
+            reminders.extend(overdue_reminder(
+                now,
+                config.overdue_reminder_days,
+                &balances,
+                &log,
+            ));
+            reminders
+        };
+
+        let shown_at = now.timestamp_millis();
+        let expired = (now - LOG_RETENTION).timestamp_millis();
+        self.update(|log| {
+            log.notified.retain(|_, &mut at| at >= expired);
+            for key in reminders.iter().flat_map(|reminder| &reminder.keys) {
+                log.notified.insert(key.clone(), shown_at);
+            }
+        })
+        .await;
+
+        Ok(reminders)
+    }
+}
+
+/// Checks for due reminders every minute while the app is open.
+pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = time::interval(CHECK_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            notify_due(&app).await;
+        }
+    });
+}
+
+async fn notify_due(app: &AppHandle) {
+    let scheduler = app.state::<ReminderScheduler>();
+    let pool = app.state::<SqlitePool>();
+    let state = app.state::<AppState>();
+    let user = state.current_user();
+
+    let reminders = match scheduler
+        .due(&pool, &state.config, user.as_ref().map(|user| user.id.as_str()))
+        .await
+    {
+        Ok(reminders) => reminders,
+        Err(err) => {
+            log::error!("Failed to check reminders: {}", err);
+            return;
+        }
+    };
+
+    for reminder in reminders {
+        log::info!("Reminder: {} - {}", reminder.title, reminder.body);
+
+        let shown = app
+            .notification()
+            .builder()
+            .title(&reminder.title)
+            .body(&reminder.body)
+            .show();
+        if let Err(err) = shown {
+            log::warn!("Failed to show notification: {}", err);
+        }
+    }
+}
+
+/// Called by the webview whenever the UI language changes.
+#[tauri::command]
+pub async fn set_reminder_language(
+    scheduler: State<'_, ReminderScheduler>,
+    language: Language,
+) -> Result<()> {
+    scheduler.set_language(language).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auth::Role,
+        config::Settings,
+        database::memory_pool,
+        repository::{
+            appointments::{AppointmentSlot, BookAppointment},
+            new_id,
+            patients::{self, SavePatient},
+            test_statement,
+            users::{AddUser, SaveUser},
+        },
+    };
+
+    struct FixedClock(Mutex<DateTime<Local>>);
+
+    impl FixedClock {
+        fn set(&self, time: &str) {
+            *self.0.lock().unwrap() = at(time);
+        }
+    }
+
+    impl Clock for FixedClock {
+        fn now(&self) -> DateTime<Local> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Local> {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap()
+    }
+
+    struct Fixture {
+        pool: SqlitePool,
+        config: AppConfig,
+        data_dir: PathBuf,
+        clock: Arc<FixedClock>,
+        scheduler: ReminderScheduler,
+        patient_id: String,
+    }
+
+    impl Fixture {
+        // Each call is the scheduler waking up at the clock's time
+        async fn due(&self) -> Vec<Reminder> {
+            self.scheduler.due(&self.pool, &self.config, None).await.unwrap()
+        }
+
+        async fn book(&self, starts_at: &str) -> Appointment {
+            appointments::book(
+                &self.pool,
+                &BookAppointment {
+                    patient_id: self.patient_id.clone(),
+                    slot: slot(starts_at),
+                    notes: None,
+                },
+            )
+            .await
+            .unwrap()
+        }
+
+        fn reload(&mut self) {
+            self.scheduler = ReminderScheduler::with_clock(&self.data_dir, self.clock.clone());
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.data_dir);
+        }
+    }
+
+    fn slot(starts_at: &str) -> AppointmentSlot {
+        let start = NaiveDateTime::parse_from_str(starts_at, "%Y-%m-%d %H:%M").unwrap();
+        AppointmentSlot {
+            doctor_id: None,
+            clinic_id: None,
+            starts_at: start.format(TIME_FORMAT).to_string(),
+            ends_at: (start + TimeDelta::minutes(30)).format(TIME_FORMAT).to_string(),
+        }
+    }
+
+    // A day and an hour ahead, overdue after 30 days
+    async fn fixture(now: &str) -> Fixture {
+        let pool = memory_pool().await;
+        let data_dir = std::env::temp_dir().join(format!("sgmc-reminders-{}", new_id()));
+        fs::create_dir_all(&data_dir).unwrap();
+
+        let config = AppConfig::from_settings(
+            &Settings {
+                data_dir: Some(data_dir.to_string_lossy().into_owned()),
+                reminder_lead_minutes: Some(vec![60, 1440]),
+                overdue_reminder_days: Some(30),
+                ..Default::default()
+            },
+            &mut Vec::new(),
+        );
+
+        let patient_id = patients::create(
+            &pool,
+            &SavePatient {
+                name: "Huda".to_string(),
+                phone: "0791111111".to_string(),
+            },
+        )
+        .await
+        .unwrap()
+        .patient
+        .id;
+
+        let clock = Arc::new(FixedClock(Mutex::new(at(now))));
+        let scheduler = ReminderScheduler::with_clock(&data_dir, clock.clone());
+
+        Fixture {
+            pool,
+            config,
+            data_dir,
+            clock,
+            scheduler,
+            patient_id,
+        }
+    }
+
+    fn keys(reminders: &[Reminder]) -> Vec<&str> {
+        reminders
+            .iter()
+            .flat_map(|reminder| &reminder.keys)
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn announces_each_lead_time_once_it_is_reached() {
+        let fixture = fixture("2025-04-01 08:59").await;
+        let appointment = fixture.book("2025-04-02 09:00").await;
+
+        assert!(fixture.due().await.is_empty());
+
+        fixture.clock.set("2025-04-01 09:00");
+        let reminders = fixture.due().await;
+        assert_eq!(
+            keys(&reminders),
+            [format!("appointment:{}:2025-04-02 09:00:00:1440", appointment.id)]
+        );
+        assert_eq!(reminders[0].title, "Upcoming appointment");
+        assert_eq!(reminders[0].body, "Huda at 09:00");
+
+        fixture.clock.set("2025-04-01 09:01");
+        assert!(fixture.due().await.is_empty());
+
+        fixture.clock.set("2025-04-02 08:00");
+        let reminders = fixture.due().await;
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(
+            reminders[0].keys.last().unwrap(),
+            &format!("appointment:{}:2025-04-02 09:00:00:60", appointment.id)
+        );
+
+        // Nothing once it has started
+        fixture.clock.set("2025-04-02 09:00");
+        assert!(fixture.due().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn settles_lead_times_reached_together_with_one_notification() {
+        // The app was closed until half an hour before the appointment
+        let fixture = fixture("2025-04-02 08:30").await;
+        let appointment = fixture.book("2025-04-02 09:00").await;
+
+        let reminders = fixture.due().await;
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(
+            keys(&reminders),
+            [
+                format!("appointment:{}:2025-04-02 09:00:00:1440", appointment.id),
+                format!("appointment:{}:2025-04-02 09:00:00:60", appointment.id),
+            ]
+        );
+
+        fixture.clock.set("2025-04-02 08:45");
+        assert!(fixture.due().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn does_not_repeat_after_a_restart() {
+        let mut fixture = fixture("2025-04-01 09:00").await;
+        fixture.book("2025-04-02 09:00").await;
+        fixture.config.overdue_reminder_days = 0;
+
+        assert_eq!(fixture.due().await.len(), 1);
+        assert!(fixture.data_dir.join(LOG_FILE).exists());
+
+        fixture.reload();
+        fixture.clock.set("2025-04-01 09:05");
+        assert!(fixture.due().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn announces_a_rescheduled_appointment_again() {
+        let fixture = fixture("2025-04-01 09:00").await;
+        let appointment = fixture.book("2025-04-02 09:00").await;
+        assert_eq!(fixture.due().await.len(), 1);
+
+        appointments::reschedule(&fixture.pool, &appointment.id, &slot("2025-04-02 10:00"))
+            .await
+            .unwrap();
+
+        fixture.clock.set("2025-04-01 09:30");
+        assert!(fixture.due().await.is_empty());
+
+        fixture.clock.set("2025-04-01 10:00");
+        let reminders = fixture.due().await;
+        assert_eq!(
+            keys(&reminders),
+            [format!("appointment:{}:2025-04-02 10:00:00:1440", appointment.id)]
+        );
+        assert_eq!(reminders[0].body, "Huda at 10:00");
+    }
+
+    #[tokio::test]
+    async fn repeats_overdue_balances_weekly() {
+        let fixture = fixture("2025-04-01 09:00").await;
+        let statement_id = test_statement(&fixture.pool, 500).await;
+        sqlx::query("UPDATE statements SET created_at = '2025-02-01 08:00:00' WHERE id = ?")
+            .bind(&statement_id)
+            .execute(&fixture.pool)
+            .await
+            .unwrap();
+
+        let reminders = fixture.due().await;
+        assert_eq!(keys(&reminders), [format!("overdue:{statement_id}")]);
+        assert_eq!(reminders[0].title, "Overdue balance");
+
+        fixture.clock.set("2025-04-07 09:00");
+        assert!(fixture.due().await.is_empty());
+
+        fixture.clock.set("2025-04-08 09:00");
+        assert_eq!(keys(&fixture.due().await), [format!("overdue:{statement_id}")]);
+    }
+
+    #[tokio::test]
+    async fn uses_the_signed_in_users_lead_times() {
+        let fixture = fixture("2025-04-01 09:00").await;
+        fixture.book("2025-04-02 09:00").await;
+
+        let user = users::create(
+            &fixture.pool,
+            &AddUser {
+                user: SaveUser {
+                    username: "sara".to_string(),
+                    display_name: "Sara".to_string(),
+                    role: Role::Receptionist,
+                    active: true,
+                },
+                password: "correct horse battery".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        users::set_reminder_lead_minutes(&fixture.pool, &user.id, Some(vec![30]))
+            .await
+            .unwrap();
+
+        let due = |clock: &str| {
+            fixture.clock.set(clock);
+            fixture
+                .scheduler
+                .due(&fixture.pool, &fixture.config, Some(&user.id))
+        };
+
+        // The app setting would announce it a day ahead
+        assert!(due("2025-04-01 09:00").await.unwrap().is_empty());
+        assert_eq!(due("2025-04-02 08:30").await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,326 @@
+use krilla::{
+    color::rgb,
+    geom::{PathBuilder, Rect, Size, Transform},
+    image::Image,
+    metadata::Metadata,
+    page::PageSettings,
+    paint::{Fill, Stroke},
+    Document,
+};
+
+use super::text::{Fonts, TextLine, Weight};
+use crate::error::{Error, Result};
+
+// A4 in points
+pub const PAGE_WIDTH: f32 = 595.28;
+pub const PAGE_HEIGHT: f32 = 841.89;
+// 15mm, like the print stylesheet
+pub const MARGIN: f32 = 42.52;
+
+pub type Color = (u8, u8, u8);
+
+pub const BLACK: Color = (0, 0, 0);
+pub const GRAY: Color = (107, 114, 128);
+pub const LIGHT_GRAY: Color = (249, 250, 251);
+pub const BORDER: Color = (212, 212, 216);
+pub const GREEN: Color = (21, 128, 61);
+pub const RED: Color = (220, 38, 38);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    End,
+}
+
+enum Item {
+    Text {
+        line: TextLine,
+        x: f32,
+        baseline: f32,
+        color: Color,
+    },
+    Rule {
+        y: f32,
+        width: f32,
+        color: Color,
+    },
+    Box {
+        rect: Rect,
+        color: Color,
+    },
+    Image {
+        image: Image,
+        rect: Rect,
+    },
+}
+
+/// Lays content out top to bottom, starting a new page when one fills up.
+///
+/// Everything is positioned by the logical start and end of a line, so the
+/// same code produces mirrored pages for right-to-left languages.
+pub struct Layout {
+    pub fonts: Fonts,
+    pub rtl: bool,
+    pages: Vec<Vec<Item>>,
+    y: f32,
+}
+
+impl Layout {
+    pub fn new(fonts: Fonts, rtl: bool) -> Self {
+        Self {
+            fonts,
+            rtl,
+            pages: vec![Vec::new()],
+            y: MARGIN,
+        }
+    }
+
+    pub fn content_width(&self) -> f32 {
+        PAGE_WIDTH - 2.0 * MARGIN
+    }
+
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    pub fn set_y(&mut self, y: f32) {
+        self.y = y;
+    }
+
+    pub fn gap(&mut self, height: f32) {
+        self.y += height;
+    }
+
+    pub fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+        self.y = MARGIN;
+    }
+
+    /// Moves to a new page unless `height` still fits on this one.
+    pub fn ensure(&mut self, height: f32) {
+        if self.y + height > PAGE_HEIGHT - MARGIN && self.y > MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn push(&mut self, item: Item) {
+        self.pages
+            .last_mut()
+            .expect("Layout always has a page")
+            .push(item);
+    }
+
+    /// Left edge of a box of `width` whose logical start is `inset` from the margin.
+    fn x_for(&self, inset: f32, width: f32, align: Align) -> f32 {
+        let start_side = matches!(
+            (align, self.rtl),
+            (Align::Start, false) | (Align::End, true)
+        );
+
+        if start_side {
+            MARGIN + inset
+        } else {
+            PAGE_WIDTH - MARGIN - inset - width
+        }
+    }
+
+    /// Draws one line at the current position without advancing.
+    pub fn text_at(
+        &mut self,
+        text: &str,
+        weight: Weight,
+        size: f32,
+        color: Color,
+        inset: f32,
+        align: Align,
+    ) {
+        let line = self.fonts.line(text, weight, size, self.rtl);
+        let x = self.x_for(inset, line.width, align);
+        let baseline = self.y + size;
+
+        self.push(Item::Text {
+            line,
+            x,
+            baseline,
+            color,
+        });
+    }
+
+    /// Draws wrapped text and advances past it.
+    pub fn paragraph(&mut self, text: &str, weight: Weight, size: f32, color: Color, align: Align) {
+        let width = self.content_width();
+        for line in self.fonts.wrap(text, weight, size, self.rtl, width) {
+            self.ensure(line_height(size));
+            let x = self.x_for(0.0, line.width, align);
+            let baseline = self.y + size;
+            self.push(Item::Text {
+                line,
+                x,
+                baseline,
+                color,
+            });
+            self.y += line_height(size);
+        }
+    }
+
+    /// A row with one cell at the start and one at the end, the start cell wrapping.
+    pub fn row(
+        &mut self,
+        start: &str,
+        end: &str,
+        end_weight: Weight,
+        size: f32,
+        end_color: Color,
+        padding: f32,
+    ) {
+        let end_line = self.fonts.line(end, end_weight, size, self.rtl);
+        let start_width = self.content_width() - end_line.width - 2.0 * padding - 12.0;
+        let start_lines = self
+            .fonts
+            .wrap(start, Weight::Regular, size, self.rtl, start_width);
+        let height = start_lines.len() as f32 * line_height(size) + 2.0 * padding;
+
+        self.ensure(height);
+        let top = self.y + padding;
+
+        for (index, line) in start_lines.into_iter().enumerate() {
+            let x = self.x_for(padding, line.width, Align::Start);
+            self.push(Item::Text {
+                line,
+                x,
+                baseline: top + index as f32 * line_height(size) + size,
+                color: BLACK,
+            });
+        }
+
+        let x = self.x_for(padding, end_line.width, Align::End);
+        self.push(Item::Text {
+            line: end_line,
+            x,
+            baseline: top + size,
+            color: end_color,
+        });
+
+        self.y += height;
+    }
+
+    /// A full width horizontal line at the current position.
+    pub fn rule(&mut self, width: f32, color: Color) {
+        let y = self.y;
+        self.push(Item::Rule { y, width, color });
+    }
+
+    /// A filled box behind content that is drawn afterwards.
+    pub fn shade(&mut self, height: f32, color: Color) {
+        if let Some(rect) = Rect::from_xywh(MARGIN, self.y, self.content_width(), height) {
+            self.push(Item::Box { rect, color });
+        }
+    }
+
+    /// Scales `image` to fit the page content area on a page of its own.
+    pub fn full_page_image(&mut self, image: Image) {
+        let (width, height) = image.size();
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let scale =
+            (self.content_width() / width as f32).min((PAGE_HEIGHT - 2.0 * MARGIN) / height as f32);
+        let (width, height) = (width as f32 * scale, height as f32 * scale);
+
+        self.new_page();
+        if let Some(rect) = Rect::from_xywh(
+            (PAGE_WIDTH - width) / 2.0,
+            (PAGE_HEIGHT - height) / 2.0,
+            width,
+            height,
+        ) {
+            self.push(Item::Image { image, rect });
+        }
+    }
+
+    /// Renders the pages into a PDF file.
+    pub fn finish(self, metadata: Metadata) -> Result<Vec<u8>> {
+        let mut document = Document::new();
+        document.set_metadata(metadata);
+
+        let settings =
+            PageSettings::from_wh(PAGE_WIDTH, PAGE_HEIGHT).expect("A4 is a valid page size");
+
+        for items in self.pages {
+            let mut page = document.start_page_with(settings.clone());
+            let mut surface = page.surface();
+
+            for item in items {
+                match item {
+                    Item::Text {
+                        line,
+                        x,
+                        baseline,
+                        color,
+                    } => {
+                        surface.set_fill(Some(fill(color)));
+                        line.draw(&mut surface, x, baseline);
+                    }
+                    Item::Rule { y, width, color } => {
+                        let mut builder = PathBuilder::new();
+                        builder.move_to(MARGIN, y);
+                        builder.line_to(PAGE_WIDTH - MARGIN, y);
+
+                        if let Some(path) = builder.finish() {
+                            surface.set_fill(None);
+                            surface.set_stroke(Some(Stroke {
+                                paint: to_rgb(color).into(),
+                                width,
+                                ..Default::default()
+                            }));
+                            surface.draw_path(&path);
+                            surface.set_stroke(None);
+                        }
+                    }
+                    Item::Box { rect, color } => {
+                        let mut builder = PathBuilder::new();
+                        builder.push_rect(rect);
+
+                        if let Some(path) = builder.finish() {
+                            surface.set_fill(Some(fill(color)));
+                            surface.draw_path(&path);
+                        }
+                    }
+                    Item::Image { image, rect } => {
+                        if let Some(size) = Size::from_wh(rect.width(), rect.height()) {
+                            surface.push_transform(&Transform::from_translate(
+                                rect.left(),
+                                rect.top(),
+                            ));
+                            surface.draw_image(image, size);
+                            surface.pop();
+                        }
+                    }
+                }
+            }
+
+            surface.finish();
+            page.finish();
+        }
+
+        document
+            .finish()
+            .map_err(|err| Error::Report(format!("{err:?}")))
+    }
+}
+
+pub fn line_height(size: f32) -> f32 {
+    size * 1.5
+}
+
+fn to_rgb((red, green, blue): Color) -> rgb::Color {
+    rgb::Color::new(red, green, blue)
+}
+
+fn fill(color: Color) -> Fill {
+    Fill {
+        paint: to_rgb(color).into(),
+        ..Default::default()
+    }
+}
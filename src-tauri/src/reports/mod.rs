@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+use tokio::fs;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    encryption::Key,
+    error::{Error, Result},
+    repository::{attachments, statements},
+};
+
+mod layout;
+pub mod statement;
+mod text;
+
+pub use statement::{ReportImage, StatementReport};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Ar,
+}
+
+impl Language {
+    pub fn is_rtl(self) -> bool {
+        self == Language::Ar
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Ar => "ar",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementPdfOptions {
+    /// Where to write the PDF, usually picked with a save dialog
+    pub path: String,
+    #[serde(default)]
+    pub language: Language,
+    /// Attachments to print, in order; all image attachments when missing
+    pub attachment_ids: Option<Vec<String>>,
+}
+
+/// Loads a statement with its image attachments, ready for [`statement::render`].
+pub async fn load_statement(
+    pool: &SqlitePool,
+    key: Option<&Key>,
+    statement_id: &str,
+    attachment_ids: Option<&[String]>,
+) -> Result<StatementReport> {
+    let details = statements::find(pool, statement_id).await?;
+
+    let attachments: Vec<_> = match attachment_ids {
+        Some(ids) => ids
+            .iter()
+            .filter_map(|id| details.attachments.iter().find(|a| &a.id == id))
+            .collect(),
+        None => details.attachments.iter().collect(),
+    };
+
+    let mut images = Vec::new();
+    for attachment in attachments {
+        if !attachment.file_type.starts_with("image/") {
+            continue;
+        }
+
+        match attachments::read(attachment, key).await {
+            Ok(data) => images.push(ReportImage {
+                file_name: attachment.file_name.clone(),
+                file_type: attachment.file_type.clone(),
+                data,
+            }),
+            Err(err) => log::warn!(
+                "Skipping unreadable attachment {}: {}",
+                attachment.file_path,
+                err
+            ),
+        }
+    }
+
+    Ok(StatementReport {
+        details,
+        images,
+        generated_at: chrono::Local::now().naive_local(),
+    })
+}
+
+/// Renders a statement to a PDF file and returns its path.
+#[tauri::command]
+pub async fn export_statement_pdf(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    statement_id: String,
+    options: StatementPdfOptions,
+) -> Result<String> {
+    state.require(Permission::ViewRecords)?;
+    let report = load_statement(
+        &pool,
+        state.encryption_key().as_ref(),
+        &statement_id,
+        options.attachment_ids.as_deref(),
+    )
+    .await?;
+    let language = options.language;
+
+    let pdf = tokio::task::spawn_blocking(move || statement::render(&report, language))
+        .await
+        .map_err(|err| Error::Report(err.to_string()))??;
+
+    fs::write(&options.path, pdf).await?;
+    log::info!("Saved statement {} to {}", statement_id, options.path);
+
+    Ok(options.path)
+}
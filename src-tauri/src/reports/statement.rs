@@ -0,0 +1,963 @@
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use krilla::{image::Image, metadata::Metadata, Data};
+
+use super::{
+    layout::{Align, Layout, BLACK, BORDER, GRAY, GREEN, LIGHT_GRAY, RED},
+    text::{Fonts, Weight},
+    Language,
+};
+use crate::{error::Result, repository::statements::StatementDetails};
+
+/// An image attachment already read from disk, in print order.
+pub struct ReportImage {
+    pub file_name: String,
+    pub file_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Everything printed on a statement, gathered before rendering starts.
+pub struct StatementReport {
+    pub details: StatementDetails,
+    pub images: Vec<ReportImage>,
+    /// Printed as the generation date, passed in so output is reproducible
+    pub generated_at: NaiveDateTime,
+}
+
+// Same wording as the print preview in translation.json
+struct Labels {
+    title: &'static str,
+    generated_by: &'static str,
+    date: &'static str,
+    id: &'static str,
+    doctor: &'static str,
+    clinic: &'static str,
+    summary: &'static str,
+    total_required: &'static str,
+    total_paid: &'static str,
+    total_remaining: &'static str,
+    sessions: &'static str,
+    procedure: &'static str,
+    no_sessions: &'static str,
+    payment_history: &'static str,
+    amount: &'static str,
+    no_payments: &'static str,
+    months: [&'static str; 12],
+}
+
+const ENGLISH: Labels = Labels {
+    title: "Statement Details",
+    generated_by: "Generated by",
+    date: "Date",
+    id: "ID",
+    doctor: "Doctor",
+    clinic: "Clinic",
+    summary: "Financial Summary",
+    total_required: "Total Required",
+    total_paid: "Total Paid",
+    total_remaining: "Total Remaining",
+    sessions: "Sessions",
+    procedure: "Procedure",
+    no_sessions: "No sessions found.",
+    payment_history: "Payment History",
+    amount: "Amount",
+    no_payments: "No payments recorded yet.",
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+};
+
+const ARABIC: Labels = Labels {
+    title: "تفاصيل الفاتورة",
+    generated_by: "تم الإنشاء بواسطة",
+    date: "التاريخ",
+    id: "المعرف",
+    doctor: "الطبيب",
+    clinic: "العيادة",
+    summary: "الملخص المالي",
+    total_required: "إجمالي المطلوب",
+    total_paid: "إجمالي المدفوع",
+    total_remaining: "إجمالي المتبقي",
+    sessions: "الجلسات",
+    procedure: "الإجراء",
+    no_sessions: "لم يتم العثور على جلسات.",
+    payment_history: "سجل الدفع",
+    amount: "المبلغ",
+    no_payments: "لم يتم تسجيل أي مدفوعات بعد.",
+    months: [
+        "يناير",
+        "فبراير",
+        "مارس",
+        "أبريل",
+        "مايو",
+        "يونيو",
+        "يوليو",
+        "أغسطس",
+        "سبتمبر",
+        "أكتوبر",
+        "نوفمبر",
+        "ديسمبر",
+    ],
+};
+
+const APP_NAME: &str = "SGMC";
+
+impl Language {
+    fn labels(self) -> &'static Labels {
+        match self {
+            Language::En => &ENGLISH,
+            Language::Ar => &ARABIC,
+        }
+    }
+}
+
+/// Amounts are stored in piastres, printed like `formatCurrency` in the webview.
+pub(crate) fn format_currency(amount: i64, language: Language) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let amount = amount.unsigned_abs();
+
+    let pounds = (amount / 100).to_string();
+    let mut grouped = String::new();
+    for (index, digit) in pounds.chars().enumerate() {
+        if index > 0 && (pounds.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    let number = format!("{sign}{grouped}.{:02}", amount % 100);
+    match language {
+        Language::En => format!("EGP {number}"),
+        Language::Ar => format!("{number} ج.م."),
+    }
+}
+
+fn format_date(date: &NaiveDateTime, language: Language) -> String {
+    let month = language.labels().months[date.month0() as usize];
+    let time = format!("{:02}:{:02}", date.hour(), date.minute());
+
+    match language {
+        Language::En => format!("{month} {}, {}, {time}", date.day(), date.year()),
+        Language::Ar => format!("{} {month} {}، {time}", date.day(), date.year()),
+    }
+}
+
+// Rows store `datetime('now')` text; anything else is printed as is
+fn format_timestamp(value: &str, language: Language) -> String {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|date| format_date(&date, language))
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn load_image(image: &ReportImage) -> Option<Image> {
+    let data = Data::from(image.data.clone());
+    let extension = image
+        .file_name
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let decoded = match (image.file_type.as_str(), extension.as_str()) {
+        ("image/png", _) | (_, "png") => Image::from_png(data, true),
+        ("image/jpeg", _) | (_, "jpg" | "jpeg") => Image::from_jpeg(data, true),
+        ("image/webp", _) | (_, "webp") => Image::from_webp(data, true),
+        ("image/gif", _) | (_, "gif") => Image::from_gif(data, true),
+        _ => return None,
+    };
+
+    match decoded {
+        Ok(decoded) => Some(decoded),
+        Err(err) => {
+            log::warn!("Skipping attachment {} in report: {}", image.file_name, err);
+            None
+        }
+    }
+}
+
+fn section_heading(layout: &mut Layout, title: &str) {
+    layout.ensure(40.0);
+    layout.text_at(title, Weight::Medium, 11.0, BLACK, 0.0, Align::Start);
+    layout.gap(18.0);
+    layout.rule(0.75, BORDER);
+    layout.gap(8.0);
+}
+
+fn table_header(layout: &mut Layout, start: &str, end: &str) {
+    layout.ensure(40.0);
+    layout.text_at(start, Weight::Medium, 9.0, BLACK, 0.0, Align::Start);
+    layout.text_at(end, Weight::Medium, 9.0, BLACK, 0.0, Align::End);
+    layout.gap(16.0);
+    layout.rule(1.5, (39, 39, 42));
+}
+
+fn table_row(layout: &mut Layout, start: &str, end: &str) {
+    layout.row(start, end, Weight::Regular, 9.0, BLACK, 6.0);
+    layout.rule(0.5, BORDER);
+}
+
+fn header(layout: &mut Layout, report: &StatementReport, language: Language) {
+    let labels = language.labels();
+    let statement = &report.details.statement;
+    let top = layout.y();
+
+    layout.text_at(APP_NAME, Weight::Medium, 20.0, BLACK, 0.0, Align::Start);
+    layout.gap(30.0);
+    for (text, size) in [
+        (format!("{} {APP_NAME}", labels.generated_by), 8.0),
+        (
+            format!(
+                "{}: {}",
+                labels.date,
+                format_date(&report.generated_at, language)
+            ),
+            8.0,
+        ),
+        (format!("{}: {}", labels.id, statement.id), 7.0),
+    ] {
+        layout.text_at(&text, Weight::Regular, size, GRAY, 0.0, Align::Start);
+        layout.gap(12.0);
+    }
+    let start_bottom = layout.y();
+
+    layout.set_y(top);
+    layout.text_at(labels.title, Weight::Medium, 13.0, BLACK, 0.0, Align::End);
+    layout.gap(20.0);
+    layout.text_at(
+        &statement.patient.name,
+        Weight::Medium,
+        11.0,
+        BLACK,
+        0.0,
+        Align::End,
+    );
+    layout.gap(16.0);
+    layout.text_at(
+        &statement.patient.phone,
+        Weight::Regular,
+        9.0,
+        GRAY,
+        0.0,
+        Align::End,
+    );
+    layout.gap(14.0);
+
+    layout.set_y(layout.y().max(start_bottom) + 4.0);
+    layout.rule(0.75, BORDER);
+    layout.gap(14.0);
+}
+
+fn care_team(layout: &mut Layout, report: &StatementReport, language: Language) {
+    let labels = language.labels();
+    let statement = &report.details.statement;
+    if statement.doctor.is_none() && statement.clinic.is_none() {
+        return;
+    }
+
+    let column = layout.content_width() / 2.0;
+    let padding = 8.0;
+
+    layout.ensure(50.0);
+    layout.shade(48.0, LIGHT_GRAY);
+    let top = layout.y();
+
+    let mut inset = padding;
+    if let Some(doctor) = &statement.doctor {
+        layout.set_y(top + padding);
+        layout.text_at(
+            labels.doctor,
+            Weight::Regular,
+            7.0,
+            GRAY,
+            inset,
+            Align::Start,
+        );
+        layout.gap(12.0);
+        layout.text_at(
+            &doctor.name,
+            Weight::Medium,
+            9.0,
+            BLACK,
+            inset,
+            Align::Start,
+        );
+        layout.gap(13.0);
+        if let Some(phone) = &doctor.phone {
+            layout.text_at(phone, Weight::Regular, 7.0, GRAY, inset, Align::Start);
+        }
+        inset += column;
+    }
+
+    if let Some(clinic) = &statement.clinic {
+        layout.set_y(top + padding);
+        layout.text_at(
+            labels.clinic,
+            Weight::Regular,
+            7.0,
+            GRAY,
+            inset,
+            Align::Start,
+        );
+        layout.gap(12.0);
+        layout.text_at(
+            &clinic.name,
+            Weight::Medium,
+            9.0,
+            BLACK,
+            inset,
+            Align::Start,
+        );
+    }
+
+    layout.set_y(top + 48.0 + 18.0);
+}
+
+fn financial_summary(layout: &mut Layout, report: &StatementReport, language: Language) {
+    let labels = language.labels();
+    let statement = &report.details.statement;
+
+    section_heading(layout, labels.summary);
+    let remaining_color = if statement.total_remaining > 0 {
+        RED
+    } else {
+        BLACK
+    };
+
+    for (label, amount, color) in [
+        (labels.total_required, statement.total, BLACK),
+        (labels.total_paid, statement.total_paid, GREEN),
+        (
+            labels.total_remaining,
+            statement.total_remaining,
+            remaining_color,
+        ),
+    ] {
+        layout.ensure(24.0);
+        layout.shade(20.0, LIGHT_GRAY);
+        layout.row(
+            label,
+            &format_currency(amount, language),
+            Weight::Medium,
+            9.0,
+            color,
+            3.5,
+        );
+        layout.gap(4.0);
+    }
+    layout.gap(14.0);
+}
+
+fn sessions(layout: &mut Layout, report: &StatementReport, language: Language) {
+    let labels = language.labels();
+    section_heading(layout, labels.sessions);
+
+    if report.details.sessions.is_empty() {
+        layout.paragraph(labels.no_sessions, Weight::Regular, 8.5, GRAY, Align::Start);
+    } else {
+        table_header(layout, labels.procedure, labels.date);
+        for session in &report.details.sessions {
+            table_row(
+                layout,
+                &session.procedure,
+                &format_timestamp(&session.created_at, language),
+            );
+        }
+    }
+    layout.gap(18.0);
+}
+
+fn payments(layout: &mut Layout, report: &StatementReport, language: Language) {
+    let labels = language.labels();
+    section_heading(layout, labels.payment_history);
+
+    if report.details.payments.is_empty() {
+        layout.paragraph(labels.no_payments, Weight::Regular, 8.5, GRAY, Align::Start);
+    } else {
+        table_header(layout, labels.date, labels.amount);
+        for payment in &report.details.payments {
+            table_row(
+                layout,
+                &format_timestamp(&payment.created_at, language),
+                &format_currency(payment.amount, language),
+            );
+        }
+    }
+}
+
+/// Renders a statement as an A4 PDF: the summary page followed by one page per image.
+pub fn render(report: &StatementReport, language: Language) -> Result<Vec<u8>> {
+    let mut layout = Layout::new(Fonts::load(), language.is_rtl());
+
+    header(&mut layout, report, language);
+    care_team(&mut layout, report, language);
+    financial_summary(&mut layout, report, language);
+    sessions(&mut layout, report, language);
+    payments(&mut layout, report, language);
+
+    for image in report.images.iter().filter_map(load_image) {
+        layout.full_page_image(image);
+    }
+
+    let statement = &report.details.statement;
+    let metadata = Metadata::new()
+        .title(format!(
+            "{} - {}",
+            language.labels().title,
+            statement.patient.name
+        ))
+        .creator(APP_NAME.to_string())
+        .language(language.code().to_string());
+
+    layout.finish(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::repository::{
+        clinics::Clinic, doctors::Doctor, patients::Patient, payments::Payment, sessions::Session,
+        statements::Statement,
+    };
+
+    fn report() -> StatementReport {
+        let statement_id = "0190a3c4-5b6d-7e8f-9a0b-1c2d3e4f5a6b".to_string();
+        let created_at = "2025-03-14 09:30:00".to_string();
+
+        StatementReport {
+            details: StatementDetails {
+                statement: Statement {
+                    id: statement_id.clone(),
+                    patient: Patient {
+                        id: "patient".to_string(),
+                        name: "أحمد سمير".to_string(),
+                        phone: "0790000000".to_string(),
+                        created_at: created_at.clone(),
+                        updated_at: created_at.clone(),
+                    },
+                    doctor: Some(Doctor {
+                        id: "doctor".to_string(),
+                        name: "Dr. Sara Haddad".to_string(),
+                        phone: Some("0791111111".to_string()),
+                        created_at: created_at.clone(),
+                        updated_at: created_at.clone(),
+                    }),
+                    clinic: Some(Clinic {
+                        id: "clinic".to_string(),
+                        name: "Downtown Clinic".to_string(),
+                        created_at: created_at.clone(),
+                        updated_at: created_at.clone(),
+                    }),
+                    total: 1_250_000,
+                    total_paid: 500_050,
+                    total_remaining: 749_950,
+                    created_at: created_at.clone(),
+                    updated_at: created_at.clone(),
+                },
+                sessions: vec![Session {
+                    id: "session".to_string(),
+                    statement_id: statement_id.clone(),
+                    procedure: "Root canal".to_string(),
+                    created_at: created_at.clone(),
+                    updated_at: created_at.clone(),
+                }],
+                payments: vec![Payment {
+                    id: "payment".to_string(),
+                    statement_id,
+                    amount: 500_050,
+                    created_at: "2025-03-15 14:05:00".to_string(),
+                    updated_at: created_at,
+                }],
+                attachments: Vec::new(),
+            },
+            images: Vec::new(),
+            generated_at: NaiveDate::from_ymd_opt(2025, 4, 1)
+                .unwrap()
+                .and_hms_opt(10, 15, 0)
+                .unwrap(),
+        }
+    }
+
+    /// The text of a PDF written by krilla, one printed line per line.
+    ///
+    /// Glyphs are mapped back through each font's `ToUnicode` map, or taken from
+    /// the `ActualText` krilla wraps around clusters it cannot map one to one.
+    /// Runs that touch on the same baseline are one line, and separate texts
+    /// on a baseline, like table cells, are joined with ` | `. Everything is
+    /// drawn in visual order, so right-to-left text is put back in reading order.
+    mod extract {
+        use std::{collections::HashMap, io::Read};
+
+        struct Object<'a> {
+            dict: &'a [u8],
+            stream: Option<Vec<u8>>,
+        }
+
+        fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+            haystack
+                .get(from..)?
+                .windows(needle.len())
+                .position(|window| window == needle)
+                .map(|at| at + from)
+        }
+
+        fn integer_after(bytes: &[u8], key: &[u8]) -> Option<u32> {
+            let start = find(bytes, key, 0)? + key.len();
+            let digits: String = bytes[start..]
+                .iter()
+                .map(|&byte| byte as char)
+                .skip_while(|c| c.is_whitespace())
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().ok()
+        }
+
+        fn objects(pdf: &[u8]) -> HashMap<u32, Object<'_>> {
+            let mut objects = HashMap::new();
+            let mut position = 0;
+
+            while let Some(at) = find(pdf, b" 0 obj", position) {
+                let digits = pdf[..at]
+                    .iter()
+                    .rev()
+                    .take_while(|byte| byte.is_ascii_digit())
+                    .count();
+                let number: u32 = std::str::from_utf8(&pdf[at - digits..at])
+                    .unwrap()
+                    .parse()
+                    .unwrap();
+
+                let body = at + b" 0 obj".len();
+                let end = find(pdf, b"endobj", body).unwrap();
+                let stream_at = find(pdf, b"stream", body).filter(|&start| start < end);
+                let dict = &pdf[body..stream_at.unwrap_or(end)];
+
+                let mut next = end;
+                let stream = stream_at.map(|start| {
+                    let mut data = start + b"stream".len();
+                    while matches!(pdf[data], b'\r' | b'\n') {
+                        data += 1;
+                    }
+                    let length = integer_after(dict, b"/Length").unwrap() as usize;
+                    let raw = &pdf[data..data + length];
+                    next = data + length;
+
+                    if find(dict, b"/FlateDecode", 0).is_some() {
+                        let mut inflated = Vec::new();
+                        flate2::read::ZlibDecoder::new(raw)
+                            .read_to_end(&mut inflated)
+                            .unwrap();
+                        inflated
+                    } else {
+                        raw.to_vec()
+                    }
+                });
+
+                objects.insert(number, Object { dict, stream });
+                position = find(pdf, b"endobj", next).unwrap() + b"endobj".len();
+            }
+
+            objects
+        }
+
+        fn hex_bytes(hex: &[u8]) -> Vec<u8> {
+            let digits: Vec<u8> = hex.iter().copied().filter(u8::is_ascii_hexdigit).collect();
+            digits
+                .chunks(2)
+                .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+                .collect()
+        }
+
+        fn utf16(bytes: &[u8]) -> String {
+            let units: Vec<u16> = bytes
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .filter(|&unit| unit != 0xFEFF)
+                .collect();
+            String::from_utf16(&units).unwrap()
+        }
+
+        fn to_unicode(cmap: &[u8]) -> HashMap<u16, String> {
+            let text = String::from_utf8_lossy(cmap);
+            let mut map = HashMap::new();
+
+            for section in text.split("beginbfchar").skip(1) {
+                let section = section.split("endbfchar").next().unwrap();
+                let codes: Vec<&str> = section
+                    .split(['<', '>'])
+                    .map(str::trim)
+                    .filter(|part| !part.is_empty())
+                    .collect();
+
+                for pair in codes.chunks(2) {
+                    let code = u16::from_str_radix(pair[0], 16).unwrap();
+                    map.insert(code, utf16(&hex_bytes(pair[1].as_bytes())));
+                }
+            }
+
+            map
+        }
+
+        struct Font {
+            text: HashMap<u16, String>,
+            /// Advance per glyph, in thousandths of the font size
+            widths: HashMap<u16, f32>,
+        }
+
+        /// Both `c [w1 w2 ...]` and `c_first c_last w` entries of a `/W` array.
+        fn widths(font: &[u8]) -> HashMap<u16, f32> {
+            let start = find(font, b"/W [", 0).unwrap() + b"/W [".len();
+            let list = String::from_utf8_lossy(&font[start..])
+                .replace('[', " [ ")
+                .replace(']', " ] ");
+            let mut words = list.split_whitespace();
+            let mut widths = HashMap::new();
+
+            while let Some(first) = words.next().filter(|word| *word != "]") {
+                let first: u16 = first.parse().unwrap();
+                match words.next().unwrap() {
+                    "[" => {
+                        for (code, width) in (first..).zip(words.by_ref().take_while(|w| *w != "]"))
+                        {
+                            widths.insert(code, width.parse().unwrap());
+                        }
+                    }
+                    last => {
+                        let width: f32 = words.next().unwrap().parse().unwrap();
+                        for code in first..=last.parse().unwrap() {
+                            widths.insert(code, width);
+                        }
+                    }
+                }
+            }
+
+            widths
+        }
+
+        /// `/f0 2 0 R` entries of the page's font resources.
+        fn page_fonts(page: &[u8], objects: &HashMap<u32, Object<'_>>) -> HashMap<String, Font> {
+            let start = find(page, b"/Font", 0).unwrap();
+            let open = find(page, b"<<", start).unwrap() + 2;
+            let close = find(page, b">>", open).unwrap();
+            let entries = String::from_utf8_lossy(&page[open..close]).into_owned();
+            let words: Vec<&str> = entries.split_whitespace().collect();
+
+            words
+                .chunks(4)
+                .map(|entry| {
+                    let font = &objects[&entry[1].parse::<u32>().unwrap()];
+                    let cmap = integer_after(font.dict, b"/ToUnicode").unwrap();
+                    let cmap = objects[&cmap].stream.as_deref().unwrap();
+                    let glyphs = integer_after(font.dict, b"/DescendantFonts [").unwrap();
+                    let font = Font {
+                        text: to_unicode(cmap),
+                        widths: widths(objects[&glyphs].dict),
+                    };
+                    (entry[0].trim_start_matches('/').to_string(), font)
+                })
+                .collect()
+        }
+
+        enum Token {
+            Number(f32),
+            Name(String),
+            Text(Vec<u8>),
+            Hex(Vec<u8>),
+            Operator(String),
+        }
+
+        fn literal(content: &[u8], position: &mut usize) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            let mut depth = 0;
+
+            loop {
+                let byte = content[*position];
+                *position += 1;
+                match byte {
+                    b'(' => {
+                        depth += 1;
+                        bytes.push(byte);
+                    }
+                    b')' if depth == 0 => return bytes,
+                    b')' => {
+                        depth -= 1;
+                        bytes.push(byte);
+                    }
+                    b'\\' => {
+                        let escaped = content[*position];
+                        *position += 1;
+                        match escaped {
+                            b'n' => bytes.push(b'\n'),
+                            b'r' => bytes.push(b'\r'),
+                            b't' => bytes.push(b'\t'),
+                            b'b' => bytes.push(0x08),
+                            b'f' => bytes.push(0x0C),
+                            b'0'..=b'7' => {
+                                let mut value = u32::from(escaped - b'0');
+                                for _ in 0..2 {
+                                    match content[*position] {
+                                        digit @ b'0'..=b'7' => {
+                                            value = value * 8 + u32::from(digit - b'0');
+                                            *position += 1;
+                                        }
+                                        _ => break,
+                                    }
+                                }
+                                bytes.push(value as u8);
+                            }
+                            other => bytes.push(other),
+                        }
+                    }
+                    _ => bytes.push(byte),
+                }
+            }
+        }
+
+        fn tokens(content: &[u8]) -> Vec<Token> {
+            let mut tokens = Vec::new();
+            let mut position = 0;
+            let is_delimiter = |byte: u8| b"()<>[]{}/%".contains(&byte) || byte.is_ascii_whitespace();
+
+            while position < content.len() {
+                let byte = content[position];
+                position += 1;
+                match byte {
+                    b'(' => tokens.push(Token::Text(literal(content, &mut position))),
+                    b'<' if content[position] == b'<' => position += 1,
+                    b'>' if content[position] == b'>' => position += 1,
+                    b'<' => {
+                        let end = find(content, b">", position).unwrap();
+                        tokens.push(Token::Hex(hex_bytes(&content[position..end])));
+                        position = end + 1;
+                    }
+                    b'/' => {
+                        let start = position;
+                        while position < content.len() && !is_delimiter(content[position]) {
+                            position += 1;
+                        }
+                        let name = String::from_utf8_lossy(&content[start..position]);
+                        tokens.push(Token::Name(name.into_owned()));
+                    }
+                    byte if byte.is_ascii_alphabetic() || byte == b'\'' || byte == b'"' => {
+                        let start = position - 1;
+                        while position < content.len() && !is_delimiter(content[position]) {
+                            position += 1;
+                        }
+                        let operator = String::from_utf8_lossy(&content[start..position]);
+                        tokens.push(Token::Operator(operator.into_owned()));
+                    }
+                    byte if byte.is_ascii_digit() || byte == b'-' || byte == b'.' => {
+                        let start = position - 1;
+                        while position < content.len() && !is_delimiter(content[position]) {
+                            position += 1;
+                        }
+                        let number = std::str::from_utf8(&content[start..position]).unwrap();
+                        tokens.push(Token::Number(number.parse().unwrap()));
+                    }
+                    // Array brackets and whitespace
+                    _ => {}
+                }
+            }
+
+            tokens
+        }
+
+        fn is_arabic(piece: &str) -> bool {
+            piece.chars().any(|c| ('\u{0600}'..='\u{06FF}').contains(&c))
+        }
+
+        /// One `BT ... ET` block, which krilla writes per shaped run.
+        struct Run {
+            x: f32,
+            y: f32,
+            end: f32,
+            text: String,
+        }
+
+        fn runs(content: &[u8], fonts: &HashMap<String, Font>) -> Vec<Run> {
+            let mut runs = Vec::new();
+            let mut operands = Vec::new();
+            let mut font = None;
+            let mut size = 0.0;
+            let mut origin = None;
+            let mut cursor = 0.0;
+            let mut pieces: Vec<String> = Vec::new();
+            let mut spans: Vec<bool> = Vec::new();
+
+            for token in tokens(content) {
+                let Token::Operator(operator) = token else {
+                    operands.push(token);
+                    continue;
+                };
+
+                match operator.as_str() {
+                    "BT" => {
+                        pieces.clear();
+                        origin = None;
+                    }
+                    "Tf" => {
+                        for token in &operands {
+                            match token {
+                                Token::Name(name) => font = fonts.get(name),
+                                Token::Number(number) => size = *number,
+                                _ => {}
+                            }
+                        }
+                    }
+                    "Tm" => {
+                        let numbers: Vec<f32> = operands
+                            .iter()
+                            .filter_map(|token| match token {
+                                Token::Number(number) => Some(*number),
+                                _ => None,
+                            })
+                            .collect();
+                        let (x, y) = (numbers[4], numbers[5]);
+                        origin.get_or_insert((x, y));
+                        cursor = x;
+                    }
+                    "Tj" | "TJ" => {
+                        let font: &Font = font.unwrap();
+                        let mapped = !spans.contains(&true);
+                        for token in &operands {
+                            match token {
+                                Token::Text(codes) => {
+                                    for code in codes.chunks(2) {
+                                        let code = u16::from_be_bytes([code[0], code[1]]);
+                                        cursor += font.widths.get(&code).copied().unwrap_or(0.0)
+                                            * size
+                                            / 1000.0;
+                                        if mapped {
+                                            pieces.push(
+                                                font.text.get(&code).cloned().unwrap_or_default(),
+                                            );
+                                        }
+                                    }
+                                }
+                                Token::Number(adjustment) => cursor -= adjustment * size / 1000.0,
+                                _ => {}
+                            }
+                        }
+                    }
+                    "BDC" => {
+                        let actual = operands.windows(2).find_map(|pair| match pair {
+                            [Token::Name(name), Token::Hex(text)] if name == "ActualText" => {
+                                Some(utf16(text))
+                            }
+                            _ => None,
+                        });
+                        spans.push(actual.is_some());
+                        pieces.extend(actual);
+                    }
+                    "BMC" => spans.push(false),
+                    "EMC" => {
+                        spans.pop();
+                    }
+                    "ET" => {
+                        if let Some((x, y)) = origin {
+                            // Right-to-left runs are drawn last character first
+                            if pieces.iter().any(|piece| is_arabic(piece)) {
+                                pieces.reverse();
+                            }
+                            runs.push(Run {
+                                x,
+                                y,
+                                end: cursor,
+                                text: pieces.concat(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+                operands.clear();
+            }
+
+            runs
+        }
+
+        /// `rtl` is the paragraph direction the report was laid out in.
+        pub fn text(pdf: &[u8], rtl: bool) -> String {
+            let objects = objects(pdf);
+            let mut pages: Vec<u32> = objects
+                .iter()
+                .filter(|(_, object)| find(object.dict, b"/Type /Page\n", 0).is_some())
+                .map(|(&number, _)| number)
+                .collect();
+            pages.sort();
+
+            let mut lines = Vec::new();
+            for page in pages {
+                let page = &objects[&page];
+                let fonts = page_fonts(page.dict, &objects);
+                let contents = integer_after(page.dict, b"/Contents").unwrap();
+                let mut runs = runs(objects[&contents].stream.as_deref().unwrap(), &fonts);
+                // Marks hang a little off the baseline, lines are further apart
+                runs.sort_by(|a, b| {
+                    (a.y.round() as i64)
+                        .cmp(&(b.y.round() as i64))
+                        .then(a.x.total_cmp(&b.x))
+                });
+
+                let mut runs = runs.into_iter().peekable();
+                while let Some(first) = runs.next() {
+                    let mut cells = vec![vec![first.text]];
+                    let mut end = first.end;
+                    while let Some(next) = runs.next_if(|next| (next.y - first.y).abs() < 1.0) {
+                        if next.x - end > 0.5 {
+                            cells.push(Vec::new());
+                        }
+                        cells.last_mut().unwrap().push(next.text);
+                        end = next.end;
+                    }
+
+                    if rtl {
+                        cells.reverse();
+                        cells.iter_mut().for_each(|cell| cell.reverse());
+                    }
+                    let cells: Vec<String> = cells.iter().map(|cell| cell.concat()).collect();
+                    lines.push(cells.join(" | "));
+                }
+            }
+
+            lines.join("\n") + "\n"
+        }
+    }
+
+    /// Renders `report` and compares its text to `fixtures/<name>`; run with
+    /// `UPDATE_FIXTURES=1` to rewrite the fixture after an intended change.
+    fn assert_matches_fixture(language: Language, name: &str) {
+        let pdf = render(&report(), language).unwrap();
+        let text = extract::text(&pdf, language.is_rtl());
+
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/reports/fixtures")
+            .join(name);
+        if std::env::var_os("UPDATE_FIXTURES").is_some() {
+            std::fs::write(&path, &text).unwrap();
+        }
+
+        let expected = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, expected, "{name} no longer matches, see {}", path.display());
+    }
+
+    #[test]
+    fn renders_english_statement() {
+        assert_matches_fixture(Language::En, "statement_en.txt");
+    }
+
+    #[test]
+    fn renders_arabic_statement() {
+        assert_matches_fixture(Language::Ar, "statement_ar.txt");
+    }
+}
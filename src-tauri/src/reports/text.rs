@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use krilla::{
+    geom::Point,
+    surface::Surface,
+    text::{Font, GlyphId, KrillaGlyph},
+    Data,
+};
+use rustybuzz::{Direction, UnicodeBuffer};
+use unicode_bidi::{BidiInfo, Level};
+
+// The same files the webview uses, so printouts match the screen
+static INTER_REGULAR: &[u8] = include_bytes!("../../../src/assets/fonts/Inter-Regular.ttf");
+static INTER_MEDIUM: &[u8] = include_bytes!("../../../src/assets/fonts/Inter-Medium.ttf");
+static ARABIC_REGULAR: &[u8] =
+    include_bytes!("../../../src/assets/fonts/NotoSansArabic-Regular.ttf");
+static ARABIC_MEDIUM: &[u8] = include_bytes!("../../../src/assets/fonts/NotoSansArabic-Medium.ttf");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weight {
+    Regular,
+    Medium,
+}
+
+#[derive(Clone)]
+struct Face {
+    data: &'static [u8],
+    font: Font,
+}
+
+impl Face {
+    fn load(data: &'static [u8]) -> Self {
+        let shared: Arc<dyn AsRef<[u8]> + Send + Sync> = Arc::new(data);
+        let font = Font::new(Data::from(shared), 0).expect("Bundled font is invalid");
+
+        Self { data, font }
+    }
+}
+
+/// Inter for Latin text and digits, Noto Sans Arabic for Arabic script.
+#[derive(Clone)]
+struct FacePair {
+    latin: Face,
+    arabic: Face,
+}
+
+#[derive(Clone)]
+pub struct Fonts {
+    regular: FacePair,
+    medium: FacePair,
+}
+
+impl Fonts {
+    pub fn load() -> Self {
+        Self {
+            regular: FacePair {
+                latin: Face::load(INTER_REGULAR),
+                arabic: Face::load(ARABIC_REGULAR),
+            },
+            medium: FacePair {
+                latin: Face::load(INTER_MEDIUM),
+                arabic: Face::load(ARABIC_MEDIUM),
+            },
+        }
+    }
+
+    fn pair(&self, weight: Weight) -> &FacePair {
+        match weight {
+            Weight::Regular => &self.regular,
+            Weight::Medium => &self.medium,
+        }
+    }
+
+    /// Shapes `text` as a single line in visual order.
+    ///
+    /// `rtl` is the paragraph direction; embedded runs in the other direction,
+    /// like phone numbers in Arabic text, are still laid out by the bidi algorithm.
+    pub fn line(&self, text: &str, weight: Weight, size: f32, rtl: bool) -> TextLine {
+        let text = text.replace(['\n', '\r', '\t'], " ");
+        let pair = self.pair(weight);
+        let base = if rtl { Level::rtl() } else { Level::ltr() };
+        let info = BidiInfo::new(&text, Some(base));
+
+        let mut runs = Vec::new();
+        for paragraph in &info.paragraphs {
+            let (levels, visual) = info.visual_runs(paragraph, paragraph.range.clone());
+
+            for range in visual {
+                let rtl = levels[range.start].is_rtl();
+                let mut segments = script_segments(&text[range]);
+                if rtl {
+                    segments.reverse();
+                }
+
+                for (arabic, segment) in segments {
+                    let face = if arabic { &pair.arabic } else { &pair.latin };
+                    runs.push(Run::shape(face, segment, rtl, size));
+                }
+            }
+        }
+
+        TextLine {
+            width: runs.iter().map(|run| run.width).sum(),
+            runs,
+            size,
+        }
+    }
+
+    /// Breaks `text` on spaces into lines no wider than `max_width`.
+    pub fn wrap(
+        &self,
+        text: &str,
+        weight: Weight,
+        size: f32,
+        rtl: bool,
+        max_width: f32,
+    ) -> Vec<TextLine> {
+        let mut lines = Vec::new();
+        let mut current = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = if current.is_empty() {
+                word.to_string()
+            } else {
+                format!("{current} {word}")
+            };
+
+            if !current.is_empty() && self.line(&candidate, weight, size, rtl).width > max_width {
+                lines.push(self.line(&current, weight, size, rtl));
+                current = word.to_string();
+            } else {
+                current = candidate;
+            }
+        }
+
+        if !current.is_empty() || lines.is_empty() {
+            lines.push(self.line(&current, weight, size, rtl));
+        }
+
+        lines
+    }
+}
+
+fn is_arabic(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0600}'..='\u{06FF}'
+            | '\u{0750}'..='\u{077F}'
+            | '\u{08A0}'..='\u{08FF}'
+            | '\u{FB50}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+    )
+}
+
+// Spaces and punctuation stay with the preceding script, both fonts cover them
+fn script_segments(text: &str) -> Vec<(bool, &str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut current = None;
+
+    for (index, c) in text.char_indices() {
+        let arabic = is_arabic(c);
+        if !arabic && !c.is_alphanumeric() {
+            continue;
+        }
+
+        match current {
+            None => current = Some(arabic),
+            Some(script) if script != arabic => {
+                segments.push((script, &text[start..index]));
+                start = index;
+                current = Some(arabic);
+            }
+            Some(_) => {}
+        }
+    }
+
+    segments.push((current.unwrap_or(false), &text[start..]));
+    segments
+}
+
+struct Run {
+    font: Font,
+    text: String,
+    glyphs: Vec<KrillaGlyph>,
+    width: f32,
+}
+
+impl Run {
+    fn shape(face: &Face, text: &str, rtl: bool, size: f32) -> Self {
+        let rb_face = rustybuzz::Face::from_slice(face.data, 0).expect("Bundled font is invalid");
+        let units_per_em = rb_face.units_per_em() as f32;
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        buffer.set_direction(if rtl {
+            Direction::RightToLeft
+        } else {
+            Direction::LeftToRight
+        });
+
+        let output = rustybuzz::shape(&rb_face, &[], buffer);
+
+        // Each glyph maps back to its cluster so the PDF text layer stays searchable
+        let mut clusters: Vec<usize> = output
+            .glyph_infos()
+            .iter()
+            .map(|info| info.cluster as usize)
+            .collect();
+        clusters.sort_unstable();
+        clusters.dedup();
+
+        let glyphs: Vec<KrillaGlyph> = output
+            .glyph_infos()
+            .iter()
+            .zip(output.glyph_positions())
+            .map(|(info, position)| {
+                let start = info.cluster as usize;
+                let end = clusters
+                    .iter()
+                    .copied()
+                    .find(|&cluster| cluster > start)
+                    .unwrap_or(text.len());
+
+                KrillaGlyph::new(
+                    GlyphId::new(info.glyph_id),
+                    position.x_advance as f32 / units_per_em,
+                    position.x_offset as f32 / units_per_em,
+                    position.y_offset as f32 / units_per_em,
+                    position.y_advance as f32 / units_per_em,
+                    start..end,
+                    None,
+                )
+            })
+            .collect();
+
+        let width = output
+            .glyph_positions()
+            .iter()
+            .map(|position| position.x_advance as f32 / units_per_em * size)
+            .sum();
+
+        Self {
+            font: face.font.clone(),
+            text: text.to_string(),
+            glyphs,
+            width,
+        }
+    }
+}
+
+/// A shaped single line of text, ready to draw left to right.
+pub struct TextLine {
+    runs: Vec<Run>,
+    size: f32,
+    pub width: f32,
+}
+
+impl TextLine {
+    pub fn draw(&self, surface: &mut Surface, x: f32, baseline: f32) {
+        let mut x = x;
+        for run in &self.runs {
+            surface.draw_glyphs(
+                Point::from_xy(x, baseline),
+                &run.glyphs,
+                run.font.clone(),
+                &run.text,
+                self.size,
+                false,
+            );
+            x += run.width;
+        }
+    }
+}
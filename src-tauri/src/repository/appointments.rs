@@ -0,0 +1,752 @@
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::State;
+
+use super::{new_id, require};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+// Local wall clock time, which sorts and compares correctly as text
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// What the webview sends: `datetime-local` inputs or values read back from here
+const INPUT_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum AppointmentStatus {
+    Scheduled,
+    Completed,
+    Cancelled,
+    NoShow,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Appointment {
+    pub id: String,
+    pub patient_id: String,
+    pub patient_name: String,
+    pub patient_phone: String,
+    pub doctor_id: Option<String>,
+    pub doctor_name: Option<String>,
+    pub clinic_id: Option<String>,
+    pub clinic_name: Option<String>,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub status: AppointmentStatus,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Who and when, shared by booking, rescheduling and the conflict check.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppointmentSlot {
+    pub doctor_id: Option<String>,
+    pub clinic_id: Option<String>,
+    pub starts_at: String,
+    pub ends_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookAppointment {
+    pub patient_id: String,
+    #[serde(flatten)]
+    pub slot: AppointmentSlot,
+    pub notes: Option<String>,
+}
+
+impl BookAppointment {
+    fn notes(&self) -> Option<&str> {
+        self.notes
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgendaRange {
+    #[default]
+    Day,
+    Week,
+}
+
+impl AgendaRange {
+    fn days(self) -> u64 {
+        match self {
+            AgendaRange::Day => 1,
+            AgendaRange::Week => 7,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgendaParams {
+    /// First day shown, `YYYY-MM-DD`
+    pub date: String,
+    #[serde(default)]
+    pub range: AgendaRange,
+    pub doctor_id: Option<String>,
+    pub clinic_id: Option<String>,
+    #[serde(default)]
+    pub include_cancelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgendaDay {
+    pub date: String,
+    pub appointments: Vec<Appointment>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Agenda {
+    pub from: String,
+    /// Last day shown, inclusive
+    pub to: String,
+    /// Every day in the range, including days without appointments
+    pub days: Vec<AgendaDay>,
+}
+
+const APPOINTMENT_QUERY: &str = r#"
+    SELECT
+        a.id,
+        a.patient_id,
+        p.name AS patient_name,
+        p.phone AS patient_phone,
+        a.doctor_id,
+        d.name AS doctor_name,
+        a.clinic_id,
+        c.name AS clinic_name,
+        a.starts_at,
+        a.ends_at,
+        a.status,
+        a.notes,
+        a.created_at,
+        a.updated_at
+    FROM appointments a
+    JOIN patients p ON a.patient_id = p.id
+    LEFT JOIN doctors d ON a.doctor_id = d.id
+    LEFT JOIN clinics c ON a.clinic_id = c.id
+"#;
+
+// Back to back appointments touch but do not overlap
+const CONFLICT_FILTER: &str = r#"
+    WHERE
+        a.status IN ('scheduled', 'completed') AND
+        (?1 IS NULL OR a.id <> ?1) AND
+        ((?2 IS NOT NULL AND a.doctor_id = ?2) OR (?3 IS NOT NULL AND a.clinic_id = ?3)) AND
+        a.starts_at < ?5 AND
+        a.ends_at > ?4
+    ORDER BY a.starts_at
+"#;
+
+fn parse_time(value: &str, field: &str) -> Result<NaiveDateTime> {
+    INPUT_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+        .ok_or_else(|| Error::Validation(format!("{field} must be a date and time")))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), DATE_FORMAT)
+        .map_err(|_| Error::Validation("Date must look like 2025-01-31".to_string()))
+}
+
+// Select boxes send "" for "none"
+fn optional_id(id: &Option<String>) -> Option<&str> {
+    id.as_deref().map(str::trim).filter(|id| !id.is_empty())
+}
+
+/// A validated slot with normalized times.
+struct Slot<'a> {
+    doctor_id: Option<&'a str>,
+    clinic_id: Option<&'a str>,
+    starts_at: String,
+    ends_at: String,
+}
+
+impl AppointmentSlot {
+    fn validate(&self) -> Result<Slot<'_>> {
+        let starts_at = parse_time(&self.starts_at, "Start")?;
+        let ends_at = parse_time(&self.ends_at, "End")?;
+
+        if ends_at <= starts_at {
+            return Err(Error::Validation("End must be after start".to_string()));
+        }
+
+        Ok(Slot {
+            doctor_id: optional_id(&self.doctor_id),
+            clinic_id: optional_id(&self.clinic_id),
+            starts_at: starts_at.format(TIME_FORMAT).to_string(),
+            ends_at: ends_at.format(TIME_FORMAT).to_string(),
+        })
+    }
+}
+
+fn short_time(value: &str) -> String {
+    NaiveDateTime::parse_from_str(value, TIME_FORMAT)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn conflict_error(slot: &Slot, conflict: &Appointment) -> Error {
+    let doctor_busy = slot.doctor_id.is_some() && conflict.doctor_id.as_deref() == slot.doctor_id;
+    let resource = if doctor_busy {
+        conflict.doctor_name.as_deref().unwrap_or("The doctor")
+    } else {
+        conflict.clinic_name.as_deref().unwrap_or("The clinic")
+    };
+
+    Error::Conflict(format!(
+        "{} is already booked with {} from {} to {}",
+        resource,
+        conflict.patient_name,
+        short_time(&conflict.starts_at),
+        short_time(&conflict.ends_at),
+    ))
+}
+
+async fn conflicts_in(
+    conn: &mut SqliteConnection,
+    slot: &Slot<'_>,
+    exclude_id: Option<&str>,
+) -> Result<Vec<Appointment>> {
+    if slot.doctor_id.is_none() && slot.clinic_id.is_none() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!("{APPOINTMENT_QUERY} {CONFLICT_FILTER}");
+    let conflicts = sqlx::query_as::<_, Appointment>(&sql)
+        .bind(exclude_id)
+        .bind(slot.doctor_id)
+        .bind(slot.clinic_id)
+        .bind(&slot.starts_at)
+        .bind(&slot.ends_at)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(conflicts)
+}
+
+async fn ensure_free(
+    conn: &mut SqliteConnection,
+    slot: &Slot<'_>,
+    exclude_id: Option<&str>,
+) -> Result<()> {
+    match conflicts_in(conn, slot, exclude_id).await?.first() {
+        Some(conflict) => Err(conflict_error(slot, conflict)),
+        None => Ok(()),
+    }
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Appointment> {
+    let sql = format!("{APPOINTMENT_QUERY} WHERE a.id = ?");
+
+    sqlx::query_as::<_, Appointment>(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(Error::NotFound("Appointment"))
+}
+
+/// Appointments that would overlap `slot` for the same doctor or clinic.
+pub async fn find_conflicts(
+    pool: &SqlitePool,
+    slot: &AppointmentSlot,
+    exclude_id: Option<&str>,
+) -> Result<Vec<Appointment>> {
+    let slot = slot.validate()?;
+    let mut conn = pool.acquire().await?;
+
+    conflicts_in(&mut conn, &slot, exclude_id).await
+}
+
+pub async fn agenda(pool: &SqlitePool, params: &AgendaParams) -> Result<Agenda> {
+    let from = parse_date(&params.date)?;
+    let dates: Vec<NaiveDate> = from
+        .iter_days()
+        .take(params.range.days() as usize)
+        .collect();
+    let until = from
+        .checked_add_days(Days::new(params.range.days()))
+        .ok_or_else(|| Error::Validation("Date is out of range".to_string()))?;
+
+    let sql = format!(
+        r#"
+        {APPOINTMENT_QUERY}
+        WHERE
+            p.deleted_at IS NULL AND
+            a.starts_at >= ?1 AND
+            a.starts_at < ?2 AND
+            (?3 IS NULL OR a.doctor_id = ?3) AND
+            (?4 IS NULL OR a.clinic_id = ?4) AND
+            (?5 OR a.status <> 'cancelled')
+        ORDER BY a.starts_at, d.name, c.name
+        "#
+    );
+
+    let appointments = sqlx::query_as::<_, Appointment>(&sql)
+        .bind(from.format(DATE_FORMAT).to_string())
+        .bind(until.format(DATE_FORMAT).to_string())
+        .bind(optional_id(&params.doctor_id))
+        .bind(optional_id(&params.clinic_id))
+        .bind(params.include_cancelled)
+        .fetch_all(pool)
+        .await?;
+
+    let mut days: Vec<AgendaDay> = dates
+        .iter()
+        .map(|date| AgendaDay {
+            date: date.format(DATE_FORMAT).to_string(),
+            appointments: Vec::new(),
+        })
+        .collect();
+
+    // Grouped by the day they start on, the query already sorted them
+    for appointment in appointments {
+        if let Some(day) = days
+            .iter_mut()
+            .find(|day| appointment.starts_at.starts_with(&day.date))
+        {
+            day.appointments.push(appointment);
+        }
+    }
+
+    Ok(Agenda {
+        from: from.format(DATE_FORMAT).to_string(),
+        to: dates
+            .last()
+            .unwrap_or(&from)
+            .format(DATE_FORMAT)
+            .to_string(),
+        days,
+    })
+}
+
+/// Scheduled appointments starting in `[from, until)`, soonest first.
+pub async fn scheduled_between(
+    pool: &SqlitePool,
+    from: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<Vec<Appointment>> {
+    let sql = format!(
+        "{APPOINTMENT_QUERY} WHERE p.deleted_at IS NULL AND a.status = 'scheduled' AND a.starts_at >= ? AND a.starts_at < ? ORDER BY a.starts_at"
+    );
+
+    let appointments = sqlx::query_as::<_, Appointment>(&sql)
+        .bind(from.format(TIME_FORMAT).to_string())
+        .bind(until.format(TIME_FORMAT).to_string())
+        .fetch_all(pool)
+        .await?;
+
+    Ok(appointments)
+}
+
+pub async fn book(pool: &SqlitePool, data: &BookAppointment) -> Result<Appointment> {
+    require(&data.patient_id, "Patient")?;
+    let slot = data.slot.validate()?;
+
+    let id = new_id();
+
+    // IMMEDIATE takes the write lock up front, so two bookings cannot both pass the check
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+    ensure_free(&mut tx, &slot, None).await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, clinic_id, starts_at, ends_at, status, notes, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, 'scheduled', ?, datetime('now'), datetime('now'))
+        "#,
+    )
+    .bind(&id)
+    .bind(data.patient_id.trim())
+    .bind(slot.doctor_id)
+    .bind(slot.clinic_id)
+    .bind(&slot.starts_at)
+    .bind(&slot.ends_at)
+    .bind(data.notes())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    find(pool, &id).await
+}
+
+pub async fn reschedule(
+    pool: &SqlitePool,
+    id: &str,
+    data: &AppointmentSlot,
+) -> Result<Appointment> {
+    let slot = data.validate()?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+    let status: AppointmentStatus =
+        sqlx::query_scalar("SELECT status FROM appointments WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(Error::NotFound("Appointment"))?;
+
+    if status != AppointmentStatus::Scheduled {
+        return Err(Error::Validation(
+            "Only scheduled appointments can be rescheduled".to_string(),
+        ));
+    }
+
+    ensure_free(&mut tx, &slot, Some(id)).await?;
+
+    sqlx::query(
+        r#"
+        UPDATE appointments
+        SET doctor_id = ?, clinic_id = ?, starts_at = ?, ends_at = ?, updated_at = datetime('now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(slot.doctor_id)
+    .bind(slot.clinic_id)
+    .bind(&slot.starts_at)
+    .bind(&slot.ends_at)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    find(pool, id).await
+}
+
+/// Closes a scheduled appointment as completed, missed or cancelled.
+///
+/// Closed appointments are kept for the patient's history; book a new one
+/// instead of reopening them.
+pub async fn set_status(
+    pool: &SqlitePool,
+    id: &str,
+    status: AppointmentStatus,
+) -> Result<Appointment> {
+    if status == AppointmentStatus::Scheduled {
+        return Err(Error::Validation(
+            "Closed appointments cannot be reopened, book a new one instead".to_string(),
+        ));
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE appointments
+        SET status = ?, updated_at = datetime('now')
+        WHERE id = ? AND status = 'scheduled'
+        "#,
+    )
+    .bind(status)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    let appointment = find(pool, id).await?;
+    if result.rows_affected() == 0 && appointment.status != status {
+        return Err(Error::Validation(
+            "Only scheduled appointments can be changed".to_string(),
+        ));
+    }
+
+    Ok(appointment)
+}
+
+#[tauri::command]
+pub async fn get_agenda(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    params: AgendaParams,
+) -> Result<Agenda> {
+    state.require(Permission::ViewRecords)?;
+    agenda(&pool, &params).await
+}
+
+#[tauri::command]
+pub async fn get_appointment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Appointment> {
+    state.require(Permission::ViewRecords)?;
+    find(&pool, &id).await
+}
+
+#[tauri::command]
+pub async fn get_appointment_conflicts(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    slot: AppointmentSlot,
+    exclude_id: Option<String>,
+) -> Result<Vec<Appointment>> {
+    state.require(Permission::ViewRecords)?;
+    find_conflicts(&pool, &slot, exclude_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn book_appointment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    appointment: BookAppointment,
+) -> Result<Appointment> {
+    state.require(Permission::ManageAppointments)?;
+    book(&pool, &appointment).await
+}
+
+#[tauri::command]
+pub async fn reschedule_appointment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    slot: AppointmentSlot,
+) -> Result<Appointment> {
+    state.require(Permission::ManageAppointments)?;
+    reschedule(&pool, &id, &slot).await
+}
+
+#[tauri::command]
+pub async fn cancel_appointment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Appointment> {
+    state.require(Permission::ManageAppointments)?;
+    set_status(&pool, &id, AppointmentStatus::Cancelled).await
+}
+
+#[tauri::command]
+pub async fn set_appointment_status(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    status: AppointmentStatus,
+) -> Result<Appointment> {
+    state.require(Permission::ManageAppointments)?;
+    set_status(&pool, &id, status).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_pool,
+        repository::{
+            doctors::{self, SaveDoctor},
+            patients::{self, SavePatient},
+        },
+    };
+
+    struct Fixture {
+        pool: SqlitePool,
+        patient_id: String,
+        doctor_id: String,
+    }
+
+    async fn fixture() -> Fixture {
+        let pool = memory_pool().await;
+        let patient_id = patients::create(
+            &pool,
+            &SavePatient {
+                name: "Huda".to_string(),
+                phone: "0791111111".to_string(),
+            },
+        )
+        .await
+        .unwrap()
+        .patient
+        .id;
+        let doctor_id = doctors::create(
+            &pool,
+            &SaveDoctor {
+                name: "Dr. Salem".to_string(),
+                phone: None,
+            },
+        )
+        .await
+        .unwrap()
+        .id;
+
+        Fixture {
+            pool,
+            patient_id,
+            doctor_id,
+        }
+    }
+
+    fn slot(doctor_id: &str, starts_at: &str, ends_at: &str) -> AppointmentSlot {
+        AppointmentSlot {
+            doctor_id: Some(doctor_id.to_string()),
+            clinic_id: Some(String::new()),
+            starts_at: starts_at.to_string(),
+            ends_at: ends_at.to_string(),
+        }
+    }
+
+    async fn book_at(fixture: &Fixture, starts_at: &str, ends_at: &str) -> Result<Appointment> {
+        book(
+            &fixture.pool,
+            &BookAppointment {
+                patient_id: fixture.patient_id.clone(),
+                slot: slot(&fixture.doctor_id, starts_at, ends_at),
+                notes: Some("  ".to_string()),
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn books_with_normalized_times() {
+        let fixture = fixture().await;
+
+        let booked = book_at(&fixture, "2025-03-01T09:00", "2025-03-01T09:30")
+            .await
+            .unwrap();
+        assert_eq!(booked.starts_at, "2025-03-01 09:00:00");
+        assert_eq!(booked.ends_at, "2025-03-01 09:30:00");
+        assert_eq!(booked.status, AppointmentStatus::Scheduled);
+        assert_eq!(booked.clinic_id, None);
+        assert_eq!(booked.notes, None);
+        assert_eq!(booked.doctor_name.as_deref(), Some("Dr. Salem"));
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_times() {
+        let fixture = fixture().await;
+
+        assert!(matches!(
+            book_at(&fixture, "2025-03-01 10:00", "2025-03-01 09:00").await,
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            book_at(&fixture, "tomorrow", "2025-03-01 09:00").await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn refuses_overlaps_but_allows_back_to_back() {
+        let fixture = fixture().await;
+        let booked = book_at(&fixture, "2025-03-01 09:00", "2025-03-01 09:30")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            book_at(&fixture, "2025-03-01 09:15", "2025-03-01 09:45").await,
+            Err(Error::Conflict(_))
+        ));
+        let conflicts = find_conflicts(
+            &fixture.pool,
+            &slot(&fixture.doctor_id, "2025-03-01 09:15", "2025-03-01 09:45"),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].id, booked.id);
+
+        book_at(&fixture, "2025-03-01 09:30", "2025-03-01 10:00")
+            .await
+            .unwrap();
+
+        // A cancelled appointment frees its slot
+        set_status(&fixture.pool, &booked.id, AppointmentStatus::Cancelled)
+            .await
+            .unwrap();
+        book_at(&fixture, "2025-03-01 09:00", "2025-03-01 09:30")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reschedules_only_open_appointments() {
+        let fixture = fixture().await;
+        let booked = book_at(&fixture, "2025-03-01 09:00", "2025-03-01 09:30")
+            .await
+            .unwrap();
+
+        // Moving within its own slot does not conflict with itself
+        let moved = reschedule(
+            &fixture.pool,
+            &booked.id,
+            &slot(&fixture.doctor_id, "2025-03-01 09:15", "2025-03-01 09:45"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(moved.starts_at, "2025-03-01 09:15:00");
+
+        set_status(&fixture.pool, &booked.id, AppointmentStatus::Completed)
+            .await
+            .unwrap();
+        assert!(matches!(
+            reschedule(
+                &fixture.pool,
+                &booked.id,
+                &slot(&fixture.doctor_id, "2025-03-02 09:00", "2025-03-02 09:30"),
+            )
+            .await,
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            set_status(&fixture.pool, &booked.id, AppointmentStatus::Scheduled).await,
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            set_status(&fixture.pool, &booked.id, AppointmentStatus::NoShow).await,
+            Err(Error::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn agenda_lists_every_day_of_the_week() {
+        let fixture = fixture().await;
+        book_at(&fixture, "2025-03-01 09:00", "2025-03-01 09:30")
+            .await
+            .unwrap();
+        book_at(&fixture, "2025-03-03 11:00", "2025-03-03 11:30")
+            .await
+            .unwrap();
+        book_at(&fixture, "2025-03-08 09:00", "2025-03-08 09:30")
+            .await
+            .unwrap();
+
+        let week = agenda(
+            &fixture.pool,
+            &AgendaParams {
+                date: "2025-03-01".to_string(),
+                range: AgendaRange::Week,
+                doctor_id: None,
+                clinic_id: None,
+                include_cancelled: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(week.from, "2025-03-01");
+        assert_eq!(week.to, "2025-03-07");
+        assert_eq!(week.days.len(), 7);
+        let counts: Vec<usize> = week.days.iter().map(|day| day.appointments.len()).collect();
+        assert_eq!(counts, [1, 0, 1, 0, 0, 0, 0]);
+    }
+}
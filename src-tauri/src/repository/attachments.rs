@@ -0,0 +1,305 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{
+    http::{header::CONTENT_TYPE, Response, StatusCode},
+    AppHandle, Manager, State,
+};
+use tokio::fs;
+
+use super::{new_id, require};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    encryption::{self, Key},
+    error::{Error, Result},
+};
+
+/// A file stored for a statement, sealed on disk when encryption is on.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub statement_id: String,
+    pub file_name: String,
+    pub file_path: String,
+    pub file_type: String,
+    /// Size of the plaintext in bytes, not of the sealed file on disk
+    pub file_size: i64,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddAttachment {
+    pub statement_id: String,
+    pub file_name: String,
+    pub file_type: String,
+    /// Base64 encoded file contents
+    pub file_data: String,
+}
+
+pub fn attachments_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join("attachments")
+}
+
+// Attachments store their creation time in milliseconds, like `Date.now()`
+pub(crate) fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+// Files on disk are named after the attachment id, keeping the original extension
+pub(crate) fn stored_file_name(id: &str, file_name: &str) -> String {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .filter(|e| !e.is_empty())
+        .unwrap_or("jpg");
+
+    format!("{id}.{extension}")
+}
+
+pub async fn list_for_statement(pool: &SqlitePool, statement_id: &str) -> Result<Vec<Attachment>> {
+    let attachments = sqlx::query_as::<_, Attachment>(
+        r#"
+        SELECT id, statement_id, file_name, file_path, file_type, file_size, created_at
+        FROM attachments
+        WHERE statement_id = ?
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(statement_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(attachments)
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Attachment> {
+    sqlx::query_as::<_, Attachment>(
+        r#"
+        SELECT id, statement_id, file_name, file_path, file_type, file_size, created_at
+        FROM attachments
+        WHERE id = ?
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(Error::NotFound("Attachment"))
+}
+
+/// Inserts the row for a file that has already been written to `file_path`.
+pub async fn insert(pool: &SqlitePool, attachment: &Attachment) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO attachments (id, statement_id, file_name, file_path, file_type, file_size, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&attachment.id)
+    .bind(&attachment.statement_id)
+    .bind(&attachment.file_name)
+    .bind(&attachment.file_path)
+    .bind(&attachment.file_type)
+    .bind(attachment.file_size)
+    .bind(attachment.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create(
+    pool: &SqlitePool,
+    data_dir: &Path,
+    key: Option<&Key>,
+    data: &AddAttachment,
+) -> Result<Attachment> {
+    require(&data.file_name, "File name")?;
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&data.file_data)
+        .map_err(|err| Error::Validation(format!("Invalid file data: {err}")))?;
+
+    let id = new_id();
+    let dir = attachments_dir(data_dir);
+    fs::create_dir_all(&dir).await?;
+
+    let file_size = bytes.len() as i64;
+    let file_path = dir.join(stored_file_name(&id, &data.file_name));
+    fs::write(&file_path, encryption::seal_file(key, bytes)?).await?;
+
+    let attachment = Attachment {
+        id,
+        statement_id: data.statement_id.clone(),
+        file_name: data.file_name.clone(),
+        file_path: file_path.display().to_string(),
+        file_type: data.file_type.clone(),
+        file_size,
+        created_at: now_millis(),
+    };
+
+    if let Err(err) = insert(pool, &attachment).await {
+        let _ = fs::remove_file(&file_path).await;
+        return Err(err);
+    }
+
+    Ok(attachment)
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    let attachment = find(pool, id).await?;
+
+    sqlx::query("DELETE FROM attachments WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    // The row is the source of truth; a missing file should not block deletion
+    if let Err(err) = fs::remove_file(&attachment.file_path).await {
+        log::warn!("Failed to delete file {}: {}", attachment.file_path, err);
+    }
+
+    Ok(())
+}
+
+/// The file contents, decrypted when encryption is on.
+pub async fn read(attachment: &Attachment, key: Option<&Key>) -> Result<Vec<u8>> {
+    encryption::read_file(Path::new(&attachment.file_path), key).await
+}
+
+/// Answers `attachment://localhost/<id>` for the webview, which cannot read
+/// encrypted files through the asset protocol.
+pub async fn serve(app: &AppHandle, path: &str) -> Response<Vec<u8>> {
+    let loaded = async {
+        let state = app.state::<AppState>();
+        state.require(Permission::ViewRecords)?;
+
+        let pool = app.try_state::<SqlitePool>().ok_or(Error::Locked)?;
+        let attachment = find(&pool, path.trim_start_matches('/')).await?;
+        let data = read(&attachment, state.encryption_key().as_ref()).await?;
+
+        Ok::<_, Error>((attachment.file_type, data))
+    }
+    .await;
+
+    let response = match loaded {
+        Ok((file_type, data)) => Response::builder()
+            .header(CONTENT_TYPE, file_type)
+            .body(data),
+        Err(err) => {
+            let status = match err {
+                Error::NotFound(_) => StatusCode::NOT_FOUND,
+                Error::SignedOut | Error::Forbidden | Error::Locked => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            log::warn!("Failed to serve attachment {}: {}", path, err);
+
+            Response::builder()
+                .status(status)
+                .body(err.to_string().into_bytes())
+        }
+    };
+
+    response.unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn get_attachments(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    statement_id: String,
+) -> Result<Vec<Attachment>> {
+    state.require(Permission::ViewRecords)?;
+    list_for_statement(&pool, &statement_id).await
+}
+
+#[tauri::command]
+pub async fn add_attachment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    attachment: AddAttachment,
+) -> Result<Attachment> {
+    state.require(Permission::EditStatements)?;
+    create(
+        &pool,
+        Path::new(&state.config.data_dir),
+        state.encryption_key().as_ref(),
+        &attachment,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_attachment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::EditStatements)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::memory_pool, repository::test_statement};
+
+    fn upload(statement_id: &str, file_data: &str) -> AddAttachment {
+        AddAttachment {
+            statement_id: statement_id.to_string(),
+            file_name: "referral.pdf".to_string(),
+            file_type: "application/pdf".to_string(),
+            file_data: file_data.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_reads_and_deletes_files() {
+        let pool = memory_pool().await;
+        let data_dir = std::env::temp_dir().join(format!("sgmc-test-{}", new_id()));
+        let statement_id = test_statement(&pool, 100).await;
+        let contents = base64::engine::general_purpose::STANDARD.encode(b"%PDF-1.7");
+
+        let created = create(&pool, &data_dir, None, &upload(&statement_id, &contents))
+            .await
+            .unwrap();
+        assert_eq!(created.file_size, 8);
+        assert!(created.file_path.ends_with(&format!("{}.pdf", created.id)));
+        assert_eq!(read(&created, None).await.unwrap(), b"%PDF-1.7");
+        assert_eq!(list_for_statement(&pool, &statement_id).await.unwrap().len(), 1);
+
+        delete(&pool, &created.id).await.unwrap();
+        assert!(!Path::new(&created.file_path).exists());
+        assert!(list_for_statement(&pool, &statement_id).await.unwrap().is_empty());
+        assert!(matches!(delete(&pool, &created.id).await, Err(Error::NotFound("Attachment"))));
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_uploads_without_leaving_files() {
+        let pool = memory_pool().await;
+        let data_dir = std::env::temp_dir().join(format!("sgmc-test-{}", new_id()));
+        let statement_id = test_statement(&pool, 100).await;
+
+        let invalid = create(&pool, &data_dir, None, &upload(&statement_id, "not base64!")).await;
+        assert!(matches!(invalid, Err(Error::Validation(_))));
+
+        // The row fails its foreign key, so the file written before it is removed
+        let orphan = create(&pool, &data_dir, None, &upload("missing", "JVBERg==")).await;
+        assert!(matches!(orphan, Err(Error::Database(_))));
+        assert_eq!(std::fs::read_dir(attachments_dir(&data_dir)).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
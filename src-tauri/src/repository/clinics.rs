@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::{new_id, require};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Clinic {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveClinic {
+    pub name: String,
+}
+
+impl SaveClinic {
+    fn validate(&self) -> Result<()> {
+        require(&self.name, "Name")
+    }
+}
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Clinic>> {
+    let clinics = sqlx::query_as::<_, Clinic>(
+        "SELECT id, name, created_at, updated_at FROM clinics ORDER BY name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(clinics)
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Clinic> {
+    sqlx::query_as::<_, Clinic>("SELECT id, name, created_at, updated_at FROM clinics WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(Error::NotFound("Clinic"))
+}
+
+pub async fn create(pool: &SqlitePool, data: &SaveClinic) -> Result<Clinic> {
+    data.validate()?;
+
+    let id = new_id();
+
+    sqlx::query(
+        "INSERT INTO clinics (id, name, created_at, updated_at) VALUES (?, ?, datetime('now'), datetime('now'))",
+    )
+    .bind(&id)
+    .bind(data.name.trim())
+    .execute(pool)
+    .await?;
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &SaveClinic) -> Result<Clinic> {
+    data.validate()?;
+
+    let result =
+        sqlx::query("UPDATE clinics SET name = ?, updated_at = datetime('now') WHERE id = ?")
+            .bind(data.name.trim())
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Clinic"));
+    }
+
+    find(pool, id).await
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    let result = sqlx::query("DELETE FROM clinics WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Clinic"));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_clinics(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Clinic>> {
+    state.require(Permission::ViewRecords)?;
+    list(&pool).await
+}
+
+#[tauri::command]
+pub async fn add_clinic(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    clinic: SaveClinic,
+) -> Result<Clinic> {
+    state.require(Permission::ManageDirectory)?;
+    create(&pool, &clinic).await
+}
+
+#[tauri::command]
+pub async fn update_clinic(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    clinic: SaveClinic,
+) -> Result<Clinic> {
+    state.require(Permission::ManageDirectory)?;
+    update(&pool, &id, &clinic).await
+}
+
+#[tauri::command]
+pub async fn delete_clinic(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::ManageDirectory)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_pool;
+
+    fn clinic(name: &str) -> SaveClinic {
+        SaveClinic {
+            name: name.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+
+        let created = create(&pool, &clinic("  Dental  ")).await.unwrap();
+        assert_eq!(created.name, "Dental");
+        create(&pool, &clinic("Cardiology")).await.unwrap();
+
+        let names: Vec<String> = list(&pool).await.unwrap().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["Cardiology", "Dental"]);
+
+        let updated = update(&pool, &created.id, &clinic("Orthodontics")).await.unwrap();
+        assert_eq!(updated.name, "Orthodontics");
+        assert_eq!(find(&pool, &created.id).await.unwrap().name, "Orthodontics");
+
+        delete(&pool, &created.id).await.unwrap();
+        assert!(matches!(find(&pool, &created.id).await, Err(Error::NotFound("Clinic"))));
+        assert!(matches!(delete(&pool, &created.id).await, Err(Error::NotFound("Clinic"))));
+    }
+
+    #[tokio::test]
+    async fn rejects_blank_names() {
+        let pool = memory_pool().await;
+
+        assert!(matches!(create(&pool, &clinic("   ")).await, Err(Error::Validation(_))));
+
+        let created = create(&pool, &clinic("Dental")).await.unwrap();
+        assert!(matches!(
+            update(&pool, &created.id, &clinic("")).await,
+            Err(Error::Validation(_))
+        ));
+        assert!(matches!(
+            update(&pool, "missing", &clinic("Dental")).await,
+            Err(Error::NotFound("Clinic"))
+        ));
+    }
+}
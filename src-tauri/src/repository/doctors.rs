@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::{new_id, require};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Doctor {
+    pub id: String,
+    pub name: String,
+    pub phone: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveDoctor {
+    pub name: String,
+    pub phone: Option<String>,
+}
+
+impl SaveDoctor {
+    fn validate(&self) -> Result<()> {
+        require(&self.name, "Name")
+    }
+
+    fn phone(&self) -> Option<&str> {
+        self.phone.as_deref().map(str::trim).filter(|p| !p.is_empty())
+    }
+}
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Doctor>> {
+    let doctors = sqlx::query_as::<_, Doctor>(
+        "SELECT id, name, phone, created_at, updated_at FROM doctors ORDER BY name ASC",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(doctors)
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Doctor> {
+    sqlx::query_as::<_, Doctor>(
+        "SELECT id, name, phone, created_at, updated_at FROM doctors WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(Error::NotFound("Doctor"))
+}
+
+pub async fn create(pool: &SqlitePool, data: &SaveDoctor) -> Result<Doctor> {
+    data.validate()?;
+
+    let id = new_id();
+
+    sqlx::query(
+        "INSERT INTO doctors (id, name, phone, created_at, updated_at) VALUES (?, ?, ?, datetime('now'), datetime('now'))",
+    )
+    .bind(&id)
+    .bind(data.name.trim())
+    .bind(data.phone())
+    .execute(pool)
+    .await?;
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &SaveDoctor) -> Result<Doctor> {
+    data.validate()?;
+
+    let result = sqlx::query(
+        "UPDATE doctors SET name = ?, phone = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(data.name.trim())
+    .bind(data.phone())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Doctor"));
+    }
+
+    find(pool, id).await
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    // Statements keep their history; migration 2 sets doctor_id to NULL
+    let result = sqlx::query("DELETE FROM doctors WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Doctor"));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_doctors(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Doctor>> {
+    state.require(Permission::ViewRecords)?;
+    list(&pool).await
+}
+
+#[tauri::command]
+pub async fn add_doctor(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    doctor: SaveDoctor,
+) -> Result<Doctor> {
+    state.require(Permission::ManageDirectory)?;
+    create(&pool, &doctor).await
+}
+
+#[tauri::command]
+pub async fn update_doctor(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    doctor: SaveDoctor,
+) -> Result<Doctor> {
+    state.require(Permission::ManageDirectory)?;
+    update(&pool, &id, &doctor).await
+}
+
+#[tauri::command]
+pub async fn delete_doctor(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::ManageDirectory)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_pool,
+        repository::{
+            patients::{self, SavePatient},
+            statements::{self, AddStatement},
+        },
+    };
+
+    fn doctor(name: &str, phone: Option<&str>) -> SaveDoctor {
+        SaveDoctor {
+            name: name.to_string(),
+            phone: phone.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+
+        let created = create(&pool, &doctor(" Dr. Salem ", Some("  "))).await.unwrap();
+        assert_eq!(created.name, "Dr. Salem");
+        assert_eq!(created.phone, None);
+
+        let updated = update(&pool, &created.id, &doctor("Dr. Salem", Some(" 0791234567 ")))
+            .await
+            .unwrap();
+        assert_eq!(updated.phone.as_deref(), Some("0791234567"));
+        assert_eq!(list(&pool).await.unwrap().len(), 1);
+
+        delete(&pool, &created.id).await.unwrap();
+        assert!(list(&pool).await.unwrap().is_empty());
+        assert!(matches!(find(&pool, &created.id).await, Err(Error::NotFound("Doctor"))));
+    }
+
+    #[tokio::test]
+    async fn rejects_blank_names() {
+        let pool = memory_pool().await;
+
+        assert!(matches!(create(&pool, &doctor("", None)).await, Err(Error::Validation(_))));
+        assert!(matches!(
+            update(&pool, "missing", &doctor("Dr. Salem", None)).await,
+            Err(Error::NotFound("Doctor"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn deleting_keeps_their_statements() {
+        let pool = memory_pool().await;
+        let created = create(&pool, &doctor("Dr. Salem", None)).await.unwrap();
+        let patient = patients::create(
+            &pool,
+            &SavePatient {
+                name: "Huda".to_string(),
+                phone: "0790000000".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        let statement = statements::create(
+            &pool,
+            &AddStatement {
+                patient_id: patient.patient.id,
+                total: 100,
+                doctor_id: Some(created.id.clone()),
+                clinic_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        delete(&pool, &created.id).await.unwrap();
+
+        let statement = statements::find(&pool, &statement.statement.id).await.unwrap();
+        assert!(statement.statement.doctor.is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+pub mod appointments;
+pub mod attachments;
+pub mod clinics;
+pub mod doctors;
+pub mod patients;
+pub mod payments;
+pub mod sessions;
+pub mod statements;
+pub mod users;
+
+fn default_page() -> i64 {
+    1
+}
+
+fn default_page_size() -> i64 {
+    20
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PagingParams {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_page_size")]
+    pub page_size: i64,
+}
+
+impl Default for PagingParams {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            page_size: default_page_size(),
+        }
+    }
+}
+
+impl PagingParams {
+    pub fn offset(&self) -> i64 {
+        (self.page.max(1) - 1) * self.limit()
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.page_size.max(1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PagingInfo {
+    pub has_next_page: bool,
+    pub has_previous_page: bool,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PagedList<T> {
+    pub items: Vec<T>,
+    pub paging_info: PagingInfo,
+}
+
+impl<T> PagedList<T> {
+    pub fn new(items: Vec<T>, params: &PagingParams, total: i64) -> Self {
+        Self {
+            items,
+            paging_info: PagingInfo {
+                has_next_page: params.page * params.page_size < total,
+                has_previous_page: params.page > 1,
+                total,
+                page: params.page,
+                page_size: params.page_size,
+            },
+        }
+    }
+}
+
+pub(crate) fn new_id() -> String {
+    uuid::Uuid::now_v7().to_string()
+}
+
+pub(crate) fn require(value: &str, field: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        return Err(Error::Validation(format!("{field} is required")));
+    }
+
+    Ok(())
+}
+
+pub(crate) fn require_positive(value: i64, field: &str) -> Result<()> {
+    if value < 1 {
+        return Err(Error::Validation(format!("{field} must be at least 1")));
+    }
+
+    Ok(())
+}
+
+// Empty search boxes come through as "", which should not filter anything
+pub(crate) fn search_term(search: Option<&str>) -> Option<&str> {
+    search.map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// A statement for `total` on a new patient, for tests that need something
+/// to hang sessions, payments or attachments on.
+#[cfg(test)]
+pub(crate) async fn test_statement(pool: &sqlx::SqlitePool, total: i64) -> String {
+    let patient = patients::create(
+        pool,
+        &patients::SavePatient {
+            name: "Test Patient".to_string(),
+            phone: "0790000000".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    statements::create(
+        pool,
+        &statements::AddStatement {
+            patient_id: patient.patient.id,
+            total,
+            doctor_id: None,
+            clinic_id: None,
+        },
+    )
+    .await
+    .unwrap()
+    .statement
+    .id
+}
@@ -0,0 +1,348 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::{new_id, require, search_term, PagedList, PagingParams};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+    ledger,
+};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Patient {
+    pub id: String,
+    pub name: String,
+    pub phone: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientDetails {
+    #[serde(flatten)]
+    pub patient: Patient,
+    pub statement_count: i64,
+    pub unpaid_count: i64,
+    pub total_required: i64,
+    pub total_paid: i64,
+    pub total_remaining: i64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPatientsParams {
+    #[serde(flatten)]
+    pub paging: PagingParams,
+    pub search: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavePatient {
+    pub name: String,
+    pub phone: String,
+}
+
+impl SavePatient {
+    fn validate(&self) -> Result<()> {
+        require(&self.name, "Name")?;
+        require(&self.phone, "Phone")
+    }
+}
+
+pub async fn list(pool: &SqlitePool, params: &GetPatientsParams) -> Result<PagedList<Patient>> {
+    let search = search_term(params.search.as_deref());
+
+    let patients = sqlx::query_as::<_, Patient>(
+        r#"
+        SELECT id, name, phone, created_at, updated_at
+        FROM patients
+        WHERE deleted_at IS NULL AND (?1 IS NULL OR name LIKE '%' || ?1 || '%' OR phone LIKE '%' || ?1 || '%')
+        ORDER BY created_at DESC
+        LIMIT ?2 OFFSET ?3
+        "#,
+    )
+    .bind(search)
+    .bind(params.paging.limit())
+    .bind(params.paging.offset())
+    .fetch_all(pool)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM patients
+        WHERE deleted_at IS NULL AND (?1 IS NULL OR name LIKE '%' || ?1 || '%' OR phone LIKE '%' || ?1 || '%')
+        "#,
+    )
+    .bind(search)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(PagedList::new(patients, &params.paging, total))
+}
+
+/// The patient with their totals, which come from the ledger so they match
+/// the balance and aging reports.
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<PatientDetails> {
+    let patient = sqlx::query_as::<_, Patient>(
+        "SELECT id, name, phone, created_at, updated_at FROM patients WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(Error::NotFound("Patient"))?;
+
+    let balance = ledger::patient_balance(pool, id).await?;
+
+    Ok(PatientDetails {
+        patient,
+        statement_count: balance.statement_count,
+        unpaid_count: balance.unpaid_count,
+        total_required: balance.total_required,
+        total_paid: balance.total_paid,
+        total_remaining: balance.balance,
+    })
+}
+
+pub async fn create(pool: &SqlitePool, data: &SavePatient) -> Result<PatientDetails> {
+    data.validate()?;
+
+    let id = new_id();
+
+    sqlx::query(
+        r#"
+        INSERT INTO patients (id, name, phone, created_at, updated_at)
+        VALUES (?, ?, ?, datetime('now'), datetime('now'))
+        "#,
+    )
+    .bind(&id)
+    .bind(data.name.trim())
+    .bind(data.phone.trim())
+    .execute(pool)
+    .await?;
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &SavePatient) -> Result<PatientDetails> {
+    data.validate()?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE patients
+        SET name = ?, phone = ?, updated_at = datetime('now')
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+    )
+    .bind(data.name.trim())
+    .bind(data.phone.trim())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Patient"));
+    }
+
+    find(pool, id).await
+}
+
+/// Moves the patient to the recycle bin, their statements go with them.
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    let result = sqlx::query(
+        "UPDATE patients SET deleted_at = datetime('now'), updated_at = datetime('now') WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Patient"));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_patients(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    params: Option<GetPatientsParams>,
+) -> Result<PagedList<Patient>> {
+    state.require(Permission::ViewRecords)?;
+    list(&pool, &params.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_patient_details(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<PatientDetails> {
+    state.require(Permission::ViewRecords)?;
+    find(&pool, &id).await
+}
+
+#[tauri::command]
+pub async fn add_patient(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    patient: SavePatient,
+) -> Result<PatientDetails> {
+    state.require(Permission::EditPatients)?;
+    create(&pool, &patient).await
+}
+
+#[tauri::command]
+pub async fn update_patient(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    patient: SavePatient,
+) -> Result<PatientDetails> {
+    state.require(Permission::EditPatients)?;
+    update(&pool, &id, &patient).await
+}
+
+#[tauri::command]
+pub async fn delete_patient(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::DeletePatients)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_pool,
+        repository::{
+            payments::{self, AddPayment},
+            statements::{self, AddStatement},
+        },
+    };
+
+    fn patient(name: &str, phone: &str) -> SavePatient {
+        SavePatient {
+            name: name.to_string(),
+            phone: phone.to_string(),
+        }
+    }
+
+    fn page(page: i64, page_size: i64, search: Option<&str>) -> GetPatientsParams {
+        GetPatientsParams {
+            paging: PagingParams { page, page_size },
+            search: search.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+
+        let created = create(&pool, &patient(" Huda ", " 0791111111 ")).await.unwrap();
+        assert_eq!(created.patient.name, "Huda");
+        assert_eq!(created.patient.phone, "0791111111");
+        assert_eq!(created.statement_count, 0);
+
+        let updated = update(&pool, &created.patient.id, &patient("Huda Saleh", "0791111111"))
+            .await
+            .unwrap();
+        assert_eq!(updated.patient.name, "Huda Saleh");
+
+        delete(&pool, &created.patient.id).await.unwrap();
+        assert!(matches!(
+            find(&pool, &created.patient.id).await,
+            Err(Error::NotFound("Patient"))
+        ));
+        assert!(matches!(
+            update(&pool, &created.patient.id, &patient("Huda", "0791111111")).await,
+            Err(Error::NotFound("Patient"))
+        ));
+        assert!(matches!(
+            delete(&pool, &created.patient.id).await,
+            Err(Error::NotFound("Patient"))
+        ));
+        assert_eq!(list(&pool, &page(1, 20, None)).await.unwrap().paging_info.total, 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_name_or_phone() {
+        let pool = memory_pool().await;
+
+        assert!(matches!(create(&pool, &patient(" ", "0791111111")).await, Err(Error::Validation(_))));
+        assert!(matches!(create(&pool, &patient("Huda", "")).await, Err(Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn pages_and_searches() {
+        let pool = memory_pool().await;
+        for (name, phone) in [("Huda", "0791111111"), ("Omar", "0792222222"), ("Hussein", "0793333333")] {
+            create(&pool, &patient(name, phone)).await.unwrap();
+        }
+
+        let first = list(&pool, &page(1, 2, None)).await.unwrap();
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.paging_info.total, 3);
+        assert!(first.paging_info.has_next_page);
+        assert!(!first.paging_info.has_previous_page);
+
+        let second = list(&pool, &page(2, 2, None)).await.unwrap();
+        assert_eq!(second.items.len(), 1);
+        assert!(!second.paging_info.has_next_page);
+        assert!(second.paging_info.has_previous_page);
+
+        let by_name = list(&pool, &page(1, 20, Some("hu"))).await.unwrap();
+        assert_eq!(by_name.paging_info.total, 2);
+
+        let by_phone = list(&pool, &page(1, 20, Some("2222"))).await.unwrap();
+        assert_eq!(by_phone.items[0].name, "Omar");
+
+        // A blank search box lists everyone
+        assert_eq!(list(&pool, &page(1, 20, Some("  "))).await.unwrap().paging_info.total, 3);
+    }
+
+    #[tokio::test]
+    async fn details_total_their_statements() {
+        let pool = memory_pool().await;
+        let created = create(&pool, &patient("Huda", "0791111111")).await.unwrap();
+
+        for (total, paid) in [(100, 100), (250, 50)] {
+            let statement = statements::create(
+                &pool,
+                &AddStatement {
+                    patient_id: created.patient.id.clone(),
+                    total,
+                    doctor_id: None,
+                    clinic_id: None,
+                },
+            )
+            .await
+            .unwrap();
+            payments::create(
+                &pool,
+                &AddPayment {
+                    statement_id: statement.statement.id,
+                    amount: paid,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let details = find(&pool, &created.patient.id).await.unwrap();
+        assert_eq!(details.statement_count, 2);
+        assert_eq!(details.unpaid_count, 1);
+        assert_eq!(details.total_required, 350);
+        assert_eq!(details.total_paid, 150);
+        assert_eq!(details.total_remaining, 200);
+    }
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::{new_id, require_positive};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Payment {
+    pub id: String,
+    pub statement_id: String,
+    pub amount: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddPayment {
+    pub statement_id: String,
+    pub amount: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePayment {
+    pub amount: i64,
+}
+
+pub async fn list_for_statement(pool: &SqlitePool, statement_id: &str) -> Result<Vec<Payment>> {
+    let payments = sqlx::query_as::<_, Payment>(
+        r#"
+        SELECT id, statement_id, amount, created_at, updated_at
+        FROM payments
+        WHERE statement_id = ?
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(statement_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(payments)
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Payment> {
+    sqlx::query_as::<_, Payment>(
+        "SELECT id, statement_id, amount, created_at, updated_at FROM payments WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(Error::NotFound("Payment"))
+}
+
+pub async fn create(pool: &SqlitePool, data: &AddPayment) -> Result<Payment> {
+    require_positive(data.amount, "Amount")?;
+
+    let id = new_id();
+
+    sqlx::query(
+        r#"
+        INSERT INTO payments (id, statement_id, amount, created_at, updated_at)
+        VALUES (?, ?, ?, datetime('now'), datetime('now'))
+        "#,
+    )
+    .bind(&id)
+    .bind(&data.statement_id)
+    .bind(data.amount)
+    .execute(pool)
+    .await?;
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &UpdatePayment) -> Result<Payment> {
+    require_positive(data.amount, "Amount")?;
+
+    let result = sqlx::query(
+        "UPDATE payments SET amount = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(data.amount)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Payment"));
+    }
+
+    find(pool, id).await
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    let result = sqlx::query("DELETE FROM payments WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Payment"));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_payment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    payment: AddPayment,
+) -> Result<Payment> {
+    state.require(Permission::TakePayments)?;
+    create(&pool, &payment).await
+}
+
+#[tauri::command]
+pub async fn update_payment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    payment: UpdatePayment,
+) -> Result<Payment> {
+    state.require(Permission::EditPayments)?;
+    update(&pool, &id, &payment).await
+}
+
+#[tauri::command]
+pub async fn delete_payment(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::DeletePayments)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::memory_pool, repository::test_statement};
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+        let statement_id = test_statement(&pool, 100).await;
+
+        let created = create(
+            &pool,
+            &AddPayment {
+                statement_id: statement_id.clone(),
+                amount: 40,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.amount, 40);
+
+        let updated = update(&pool, &created.id, &UpdatePayment { amount: 60 })
+            .await
+            .unwrap();
+        assert_eq!(updated.amount, 60);
+        assert_eq!(list_for_statement(&pool, &statement_id).await.unwrap().len(), 1);
+
+        delete(&pool, &created.id).await.unwrap();
+        assert!(list_for_statement(&pool, &statement_id).await.unwrap().is_empty());
+        assert!(matches!(find(&pool, &created.id).await, Err(Error::NotFound("Payment"))));
+    }
+
+    #[tokio::test]
+    async fn rejects_amounts_below_one() {
+        let pool = memory_pool().await;
+        let statement_id = test_statement(&pool, 100).await;
+
+        let added = create(
+            &pool,
+            &AddPayment {
+                statement_id,
+                amount: 0,
+            },
+        )
+        .await;
+        assert!(matches!(added, Err(Error::Validation(_))));
+        assert!(matches!(
+            update(&pool, "missing", &UpdatePayment { amount: 10 }).await,
+            Err(Error::NotFound("Payment"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_statements() {
+        let pool = memory_pool().await;
+
+        let added = create(
+            &pool,
+            &AddPayment {
+                statement_id: "missing".to_string(),
+                amount: 10,
+            },
+        )
+        .await;
+        assert!(matches!(added, Err(Error::Database(_))));
+    }
+}
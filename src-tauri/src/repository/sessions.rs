@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::{new_id, require};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub statement_id: String,
+    pub procedure: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddSession {
+    pub statement_id: String,
+    pub procedure: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSession {
+    pub procedure: String,
+}
+
+pub async fn list_for_statement(pool: &SqlitePool, statement_id: &str) -> Result<Vec<Session>> {
+    let sessions = sqlx::query_as::<_, Session>(
+        r#"
+        SELECT id, statement_id, "procedure", created_at, updated_at
+        FROM sessions
+        WHERE statement_id = ?
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(statement_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions)
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<Session> {
+    sqlx::query_as::<_, Session>(
+        r#"SELECT id, statement_id, "procedure", created_at, updated_at FROM sessions WHERE id = ?"#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(Error::NotFound("Session"))
+}
+
+pub async fn create(pool: &SqlitePool, data: &AddSession) -> Result<Session> {
+    require(&data.procedure, "Procedure")?;
+
+    let id = new_id();
+
+    sqlx::query(
+        r#"
+        INSERT INTO sessions (id, statement_id, "procedure", created_at, updated_at)
+        VALUES (?, ?, ?, datetime('now'), datetime('now'))
+        "#,
+    )
+    .bind(&id)
+    .bind(&data.statement_id)
+    .bind(data.procedure.trim())
+    .execute(pool)
+    .await?;
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &UpdateSession) -> Result<Session> {
+    require(&data.procedure, "Procedure")?;
+
+    let result = sqlx::query(
+        r#"UPDATE sessions SET "procedure" = ?, updated_at = datetime('now') WHERE id = ?"#,
+    )
+    .bind(data.procedure.trim())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Session"));
+    }
+
+    find(pool, id).await
+}
+
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    let result = sqlx::query("DELETE FROM sessions WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Session"));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_session(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    session: AddSession,
+) -> Result<Session> {
+    state.require(Permission::EditSessions)?;
+    create(&pool, &session).await
+}
+
+#[tauri::command]
+pub async fn update_session(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    session: UpdateSession,
+) -> Result<Session> {
+    state.require(Permission::EditSessions)?;
+    update(&pool, &id, &session).await
+}
+
+#[tauri::command]
+pub async fn delete_session(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::EditSessions)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::memory_pool, repository::test_statement};
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+        let statement_id = test_statement(&pool, 100).await;
+
+        let created = create(
+            &pool,
+            &AddSession {
+                statement_id: statement_id.clone(),
+                procedure: " Root canal ".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.procedure, "Root canal");
+
+        let updated = update(
+            &pool,
+            &created.id,
+            &UpdateSession {
+                procedure: "Crown".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.procedure, "Crown");
+
+        let listed = list_for_statement(&pool, &statement_id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].procedure, "Crown");
+
+        delete(&pool, &created.id).await.unwrap();
+        assert!(list_for_statement(&pool, &statement_id).await.unwrap().is_empty());
+        assert!(matches!(delete(&pool, &created.id).await, Err(Error::NotFound("Session"))));
+    }
+
+    #[tokio::test]
+    async fn rejects_blank_procedures() {
+        let pool = memory_pool().await;
+        let statement_id = test_statement(&pool, 100).await;
+
+        let added = create(
+            &pool,
+            &AddSession {
+                statement_id,
+                procedure: "  ".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(added, Err(Error::Validation(_))));
+
+        let updated = update(
+            &pool,
+            "missing",
+            &UpdateSession {
+                procedure: "Crown".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(updated, Err(Error::NotFound("Session"))));
+    }
+}
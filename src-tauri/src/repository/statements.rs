@@ -0,0 +1,584 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use tauri::State;
+
+use super::{
+    attachments::{self, Attachment},
+    clinics::Clinic,
+    doctors::Doctor,
+    new_id,
+    patients::Patient,
+    payments::{self, Payment},
+    require_positive, search_term,
+    sessions::{self, Session},
+    PagedList, PagingParams,
+};
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statement {
+    pub id: String,
+    pub patient: Patient,
+    pub doctor: Option<Doctor>,
+    pub clinic: Option<Clinic>,
+    pub total: i64,
+    pub total_paid: i64,
+    pub total_remaining: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementDetails {
+    #[serde(flatten)]
+    pub statement: Statement,
+    pub sessions: Vec<Session>,
+    pub payments: Vec<Payment>,
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemainingFilter {
+    #[default]
+    All,
+    Positive,
+    Negative,
+}
+
+impl RemainingFilter {
+    fn as_str(self) -> &'static str {
+        match self {
+            RemainingFilter::All => "all",
+            RemainingFilter::Positive => "positive",
+            RemainingFilter::Negative => "negative",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetStatementsParams {
+    #[serde(flatten)]
+    pub paging: PagingParams,
+    pub search: Option<String>,
+    pub patient_id: Option<String>,
+    #[serde(default)]
+    pub remaining_filter: RemainingFilter,
+    pub doctor_id: Option<String>,
+    pub clinic_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddStatement {
+    pub patient_id: String,
+    pub total: i64,
+    pub doctor_id: Option<String>,
+    pub clinic_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateStatement {
+    pub total: i64,
+    pub doctor_id: Option<String>,
+    pub clinic_id: Option<String>,
+}
+
+const STATEMENT_COLUMNS: &str = r#"
+    s.id,
+    s.total,
+    s.created_at,
+    s.updated_at,
+    (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id) AS total_paid,
+    p.id AS patient_id,
+    p.name AS patient_name,
+    p.phone AS patient_phone,
+    p.created_at AS patient_created_at,
+    p.updated_at AS patient_updated_at,
+    d.id AS doctor_id,
+    d.name AS doctor_name,
+    d.phone AS doctor_phone,
+    d.created_at AS doctor_created_at,
+    d.updated_at AS doctor_updated_at,
+    c.id AS clinic_id,
+    c.name AS clinic_name,
+    c.created_at AS clinic_created_at,
+    c.updated_at AS clinic_updated_at
+"#;
+
+const STATEMENT_JOINS: &str = r#"
+    FROM statements s
+    JOIN patients p ON s.patient_id = p.id
+    LEFT JOIN doctors d ON s.doctor_id = d.id
+    LEFT JOIN clinics c ON s.clinic_id = c.id
+"#;
+
+// Shared by the list and its count so the two never filter differently
+const STATEMENT_FILTERS: &str = r#"
+    WHERE
+        s.deleted_at IS NULL AND
+        p.deleted_at IS NULL AND
+        (?1 IS NULL OR s.patient_id = ?1) AND
+        (?2 IS NULL OR p.name LIKE '%' || ?2 || '%' OR p.phone LIKE '%' || ?2 || '%') AND
+        (?3 IS NULL OR s.doctor_id = ?3) AND
+        (?4 IS NULL OR s.clinic_id = ?4)
+"#;
+
+const REMAINING_FILTER: &str = r#"
+    WHERE
+        ?5 = 'all' OR
+        (?5 = 'positive' AND total - total_paid > 0) OR
+        (?5 = 'negative' AND total - total_paid < 0)
+"#;
+
+fn statement_from_row(row: &SqliteRow) -> sqlx::Result<Statement> {
+    let total: i64 = row.try_get("total")?;
+    let total_paid: i64 = row.try_get("total_paid")?;
+
+    let patient = Patient {
+        id: row.try_get("patient_id")?,
+        name: row.try_get("patient_name")?,
+        phone: row.try_get("patient_phone")?,
+        created_at: row.try_get("patient_created_at")?,
+        updated_at: row.try_get("patient_updated_at")?,
+    };
+
+    let doctor = match row.try_get::<Option<String>, _>("doctor_id")? {
+        Some(id) => Some(Doctor {
+            id,
+            name: row.try_get("doctor_name")?,
+            phone: row.try_get("doctor_phone")?,
+            created_at: row.try_get("doctor_created_at")?,
+            updated_at: row.try_get("doctor_updated_at")?,
+        }),
+        None => None,
+    };
+
+    let clinic = match row.try_get::<Option<String>, _>("clinic_id")? {
+        Some(id) => Some(Clinic {
+            id,
+            name: row.try_get("clinic_name")?,
+            created_at: row.try_get("clinic_created_at")?,
+            updated_at: row.try_get("clinic_updated_at")?,
+        }),
+        None => None,
+    };
+
+    Ok(Statement {
+        id: row.try_get("id")?,
+        patient,
+        doctor,
+        clinic,
+        total,
+        total_paid,
+        total_remaining: total - total_paid,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+fn validate_total(total: i64) -> Result<()> {
+    require_positive(total, "Total")
+}
+
+pub async fn list(pool: &SqlitePool, params: &GetStatementsParams) -> Result<PagedList<Statement>> {
+    let search = search_term(params.search.as_deref());
+    let remaining = params.remaining_filter.as_str();
+
+    let sql = format!(
+        "SELECT * FROM (SELECT {STATEMENT_COLUMNS} {STATEMENT_JOINS} {STATEMENT_FILTERS}) {REMAINING_FILTER} ORDER BY created_at DESC LIMIT ?6 OFFSET ?7"
+    );
+
+    let rows = sqlx::query(&sql)
+        .bind(params.patient_id.as_deref())
+        .bind(search)
+        .bind(params.doctor_id.as_deref())
+        .bind(params.clinic_id.as_deref())
+        .bind(remaining)
+        .bind(params.paging.limit())
+        .bind(params.paging.offset())
+        .fetch_all(pool)
+        .await?;
+
+    let statements = rows
+        .iter()
+        .map(statement_from_row)
+        .collect::<sqlx::Result<Vec<_>>>()?;
+
+    let count_sql = format!(
+        "SELECT COUNT(*) FROM (SELECT s.total, (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id) AS total_paid {STATEMENT_JOINS} {STATEMENT_FILTERS}) {REMAINING_FILTER}"
+    );
+
+    let total: i64 = sqlx::query_scalar(&count_sql)
+        .bind(params.patient_id.as_deref())
+        .bind(search)
+        .bind(params.doctor_id.as_deref())
+        .bind(params.clinic_id.as_deref())
+        .bind(remaining)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(PagedList::new(statements, &params.paging, total))
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<StatementDetails> {
+    let sql = format!(
+        "SELECT {STATEMENT_COLUMNS} {STATEMENT_JOINS} WHERE s.id = ? AND s.deleted_at IS NULL AND p.deleted_at IS NULL"
+    );
+
+    let row = sqlx::query(&sql)
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(Error::NotFound("Statement"))?;
+
+    Ok(StatementDetails {
+        statement: statement_from_row(&row)?,
+        sessions: sessions::list_for_statement(pool, id).await?,
+        payments: payments::list_for_statement(pool, id).await?,
+        attachments: attachments::list_for_statement(pool, id).await?,
+    })
+}
+
+pub async fn exists(pool: &SqlitePool, id: &str) -> Result<bool> {
+    let exists = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM statements s
+            JOIN patients p ON s.patient_id = p.id
+            WHERE s.id = ? AND s.deleted_at IS NULL AND p.deleted_at IS NULL
+        )
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+pub async fn create(pool: &SqlitePool, data: &AddStatement) -> Result<StatementDetails> {
+    validate_total(data.total)?;
+
+    let id = new_id();
+
+    sqlx::query(
+        r#"
+        INSERT INTO statements (id, patient_id, total, doctor_id, clinic_id, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))
+        "#,
+    )
+    .bind(&id)
+    .bind(&data.patient_id)
+    .bind(data.total)
+    .bind(data.doctor_id.as_deref())
+    .bind(data.clinic_id.as_deref())
+    .execute(pool)
+    .await?;
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &UpdateStatement) -> Result<StatementDetails> {
+    validate_total(data.total)?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE statements
+        SET total = ?, doctor_id = ?, clinic_id = ?, updated_at = datetime('now')
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+    )
+    .bind(data.total)
+    .bind(data.doctor_id.as_deref())
+    .bind(data.clinic_id.as_deref())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Statement"));
+    }
+
+    find(pool, id).await
+}
+
+/// Moves the statement to the recycle bin.
+pub async fn delete(pool: &SqlitePool, id: &str) -> Result<()> {
+    let result = sqlx::query(
+        "UPDATE statements SET deleted_at = datetime('now'), updated_at = datetime('now') WHERE id = ? AND deleted_at IS NULL",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("Statement"));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_statements(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    params: Option<GetStatementsParams>,
+) -> Result<PagedList<Statement>> {
+    state.require(Permission::ViewRecords)?;
+    list(&pool, &params.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_statement_details(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<StatementDetails> {
+    state.require(Permission::ViewRecords)?;
+    find(&pool, &id).await
+}
+
+#[tauri::command]
+pub async fn add_statement(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    statement: AddStatement,
+) -> Result<StatementDetails> {
+    state.require(Permission::EditStatements)?;
+    create(&pool, &statement).await
+}
+
+#[tauri::command]
+pub async fn update_statement(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    statement: UpdateStatement,
+) -> Result<StatementDetails> {
+    state.require(Permission::EditStatements)?;
+    update(&pool, &id, &statement).await
+}
+
+#[tauri::command]
+pub async fn delete_statement(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<()> {
+    state.require(Permission::DeleteStatements)?;
+    delete(&pool, &id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_pool,
+        repository::{
+            clinics::{self, SaveClinic},
+            patients::{self, SavePatient},
+            payments::AddPayment,
+            sessions::AddSession,
+        },
+    };
+
+    async fn patient(pool: &SqlitePool, name: &str) -> String {
+        patients::create(
+            pool,
+            &SavePatient {
+                name: name.to_string(),
+                phone: "0791111111".to_string(),
+            },
+        )
+        .await
+        .unwrap()
+        .patient
+        .id
+    }
+
+    async fn statement(pool: &SqlitePool, patient_id: &str, total: i64, paid: i64) -> String {
+        let id = create(
+            pool,
+            &AddStatement {
+                patient_id: patient_id.to_string(),
+                total,
+                doctor_id: None,
+                clinic_id: None,
+            },
+        )
+        .await
+        .unwrap()
+        .statement
+        .id;
+
+        if paid > 0 {
+            payments::create(
+                pool,
+                &AddPayment {
+                    statement_id: id.clone(),
+                    amount: paid,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        id
+    }
+
+    fn params(remaining_filter: RemainingFilter) -> GetStatementsParams {
+        GetStatementsParams {
+            remaining_filter,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+        let patient_id = patient(&pool, "Huda").await;
+        let clinic = clinics::create(
+            &pool,
+            &SaveClinic {
+                name: "Dental".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let id = statement(&pool, &patient_id, 300, 100).await;
+        sessions::create(
+            &pool,
+            &AddSession {
+                statement_id: id.clone(),
+                procedure: "Filling".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let details = find(&pool, &id).await.unwrap();
+        assert_eq!(details.statement.patient.name, "Huda");
+        assert_eq!(details.statement.total_paid, 100);
+        assert_eq!(details.statement.total_remaining, 200);
+        assert_eq!(details.sessions.len(), 1);
+        assert_eq!(details.payments.len(), 1);
+
+        let updated = update(
+            &pool,
+            &id,
+            &UpdateStatement {
+                total: 400,
+                doctor_id: None,
+                clinic_id: Some(clinic.id.clone()),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.statement.total_remaining, 300);
+        assert_eq!(updated.statement.clinic.map(|c| c.id), Some(clinic.id));
+
+        assert!(exists(&pool, &id).await.unwrap());
+        delete(&pool, &id).await.unwrap();
+        assert!(!exists(&pool, &id).await.unwrap());
+        assert!(matches!(find(&pool, &id).await, Err(Error::NotFound("Statement"))));
+        assert!(matches!(delete(&pool, &id).await, Err(Error::NotFound("Statement"))));
+    }
+
+    #[tokio::test]
+    async fn rejects_totals_below_one() {
+        let pool = memory_pool().await;
+        let patient_id = patient(&pool, "Huda").await;
+
+        let added = create(
+            &pool,
+            &AddStatement {
+                patient_id: patient_id.clone(),
+                total: 0,
+                doctor_id: None,
+                clinic_id: None,
+            },
+        )
+        .await;
+        assert!(matches!(added, Err(Error::Validation(_))));
+
+        let id = statement(&pool, &patient_id, 100, 0).await;
+        let updated = update(
+            &pool,
+            &id,
+            &UpdateStatement {
+                total: -5,
+                doctor_id: None,
+                clinic_id: None,
+            },
+        )
+        .await;
+        assert!(matches!(updated, Err(Error::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn filters_by_remaining_balance_and_pages() {
+        let pool = memory_pool().await;
+        let huda = patient(&pool, "Huda").await;
+        let omar = patient(&pool, "Omar").await;
+        statement(&pool, &huda, 100, 40).await;
+        statement(&pool, &huda, 100, 100).await;
+        statement(&pool, &omar, 100, 150).await;
+
+        assert_eq!(list(&pool, &params(RemainingFilter::All)).await.unwrap().paging_info.total, 3);
+        assert_eq!(list(&pool, &params(RemainingFilter::Positive)).await.unwrap().paging_info.total, 1);
+
+        let negative = list(&pool, &params(RemainingFilter::Negative)).await.unwrap();
+        assert_eq!(negative.paging_info.total, 1);
+        assert_eq!(negative.items[0].total_remaining, -50);
+
+        let for_huda = list(
+            &pool,
+            &GetStatementsParams {
+                patient_id: Some(huda.clone()),
+                paging: PagingParams {
+                    page: 1,
+                    page_size: 1,
+                },
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(for_huda.items.len(), 1);
+        assert_eq!(for_huda.paging_info.total, 2);
+        assert!(for_huda.paging_info.has_next_page);
+
+        let searched = list(
+            &pool,
+            &GetStatementsParams {
+                search: Some("oma".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(searched.paging_info.total, 1);
+    }
+
+    #[tokio::test]
+    async fn hides_statements_of_deleted_patients() {
+        let pool = memory_pool().await;
+        let patient_id = patient(&pool, "Huda").await;
+        let id = statement(&pool, &patient_id, 100, 0).await;
+
+        patients::delete(&pool, &patient_id).await.unwrap();
+
+        assert_eq!(list(&pool, &params(RemainingFilter::All)).await.unwrap().paging_info.total, 0);
+        assert!(matches!(find(&pool, &id).await, Err(Error::NotFound("Statement"))));
+    }
+}
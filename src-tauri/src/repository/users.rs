@@ -0,0 +1,490 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use super::{new_id, require};
+use crate::{
+    app_state::AppState,
+    auth::{self, Permission, Role},
+    config,
+    error::{Error, Result},
+};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    pub display_name: String,
+    pub role: Role,
+    pub active: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveUser {
+    pub username: String,
+    pub display_name: String,
+    pub role: Role,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddUser {
+    #[serde(flatten)]
+    pub user: SaveUser,
+    pub password: String,
+}
+
+impl SaveUser {
+    fn validate(&self) -> Result<()> {
+        require(&self.username, "Username")?;
+        require(&self.display_name, "Name")
+    }
+}
+
+// Usernames are unique regardless of case, so "Sara" and "sara" clash
+fn username_taken(err: sqlx::Error, username: &str) -> Error {
+    match &err {
+        sqlx::Error::Database(db) if db.is_unique_violation() => {
+            Error::Conflict(format!("The username {} is already taken", username.trim()))
+        }
+        _ => err.into(),
+    }
+}
+
+const USER_COLUMNS: &str = "id, username, display_name, role, active, created_at, updated_at";
+
+pub async fn list(pool: &SqlitePool) -> Result<Vec<User>> {
+    let users = sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users ORDER BY active DESC, display_name ASC"
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+pub async fn find(pool: &SqlitePool, id: &str) -> Result<User> {
+    sqlx::query_as::<_, User>(&format!("SELECT {USER_COLUMNS} FROM users WHERE id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(Error::NotFound("User"))
+}
+
+/// An active user and their password hash, looked up for signing in.
+pub async fn find_credentials(pool: &SqlitePool, username: &str) -> Result<Option<(User, String)>> {
+    let user = sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE username = ? AND active = 1"
+    ))
+    .bind(username.trim())
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(user) = user else {
+        return Ok(None);
+    };
+
+    let hash: String = sqlx::query_scalar("SELECT password_hash FROM users WHERE id = ?")
+        .bind(&user.id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(Some((user, hash)))
+}
+
+pub async fn count(pool: &SqlitePool) -> Result<i64> {
+    Ok(sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await?)
+}
+
+pub async fn create(pool: &SqlitePool, data: &AddUser) -> Result<User> {
+    insert(pool, data, false).await
+}
+
+/// Creates the account of a fresh install, failing once any user exists.
+pub async fn create_first(pool: &SqlitePool, data: &AddUser) -> Result<User> {
+    insert(pool, data, true).await
+}
+
+async fn insert(pool: &SqlitePool, data: &AddUser, first: bool) -> Result<User> {
+    data.user.validate()?;
+    let password_hash = auth::hash_password(&data.password).await?;
+
+    let id = new_id();
+
+    // Checked in the same statement, so two desks or two clicks cannot both
+    // create the first account
+    let result = sqlx::query(
+        r#"
+        INSERT INTO users (id, username, display_name, password_hash, role, active, created_at, updated_at)
+        SELECT ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now')
+        WHERE NOT ? OR NOT EXISTS (SELECT 1 FROM users)
+        "#,
+    )
+    .bind(&id)
+    .bind(data.user.username.trim())
+    .bind(data.user.display_name.trim())
+    .bind(&password_hash)
+    .bind(data.user.role)
+    .bind(data.user.active)
+    .bind(first)
+    .execute(pool)
+    .await
+    .map_err(|err| username_taken(err, &data.user.username))?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::Conflict(
+            "An admin account already exists".to_string(),
+        ));
+    }
+
+    find(pool, &id).await
+}
+
+pub async fn update(pool: &SqlitePool, id: &str, data: &SaveUser) -> Result<User> {
+    data.validate()?;
+
+    let mut tx = pool.begin_with("BEGIN IMMEDIATE").await?;
+
+    // Someone has to be able to manage users afterwards
+    let other_admins: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM users WHERE role = 'admin' AND active = 1 AND id <> ?",
+    )
+    .bind(id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if other_admins == 0 && (data.role != Role::Admin || !data.active) {
+        return Err(Error::Conflict(
+            "At least one active admin is required".to_string(),
+        ));
+    }
+
+    let result = sqlx::query(
+        r#"
+        UPDATE users
+        SET username = ?, display_name = ?, role = ?, active = ?, updated_at = datetime('now')
+        WHERE id = ?
+        "#,
+    )
+    .bind(data.username.trim())
+    .bind(data.display_name.trim())
+    .bind(data.role)
+    .bind(data.active)
+    .bind(id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| username_taken(err, &data.username))?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("User"));
+    }
+
+    tx.commit().await?;
+
+    find(pool, id).await
+}
+
+pub async fn set_password(pool: &SqlitePool, id: &str, password: &str) -> Result<()> {
+    let password_hash = auth::hash_password(password).await?;
+
+    let result = sqlx::query(
+        "UPDATE users SET password_hash = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(&password_hash)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("User"));
+    }
+
+    Ok(())
+}
+
+/// The lead times `id` picked for appointment reminders, `None` when they
+/// follow the app setting.
+pub async fn reminder_lead_minutes(pool: &SqlitePool, id: &str) -> Result<Option<Vec<u32>>> {
+    let minutes: Option<Option<String>> =
+        sqlx::query_scalar("SELECT reminder_lead_minutes FROM users WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+
+    match minutes.ok_or(Error::NotFound("User"))? {
+        Some(minutes) => Ok(Some(serde_json::from_str(&minutes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Saves `id`'s own lead times; `None` goes back to the app setting and an
+/// empty list turns their appointment reminders off.
+pub async fn set_reminder_lead_minutes(
+    pool: &SqlitePool,
+    id: &str,
+    minutes: Option<Vec<u32>>,
+) -> Result<Option<Vec<u32>>> {
+    let minutes = minutes
+        .map(config::check_lead_minutes)
+        .transpose()
+        .map_err(Error::Validation)?;
+    let json = minutes.as_ref().map(serde_json::to_string).transpose()?;
+
+    let result = sqlx::query("UPDATE users SET reminder_lead_minutes = ? WHERE id = ?")
+        .bind(json)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::NotFound("User"));
+    }
+
+    Ok(minutes)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderLeadTimes {
+    /// The signed-in user's own lead times, if they set any
+    pub lead_minutes: Option<Vec<u32>>,
+    /// What applies otherwise, from the app settings
+    pub default_lead_minutes: Vec<u32>,
+}
+
+#[tauri::command]
+pub async fn get_reminder_lead_times(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<ReminderLeadTimes> {
+    let user = state.current_user().ok_or(Error::SignedOut)?;
+
+    Ok(ReminderLeadTimes {
+        lead_minutes: reminder_lead_minutes(&pool, &user.id).await?,
+        default_lead_minutes: state.config.reminder_lead_minutes.clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn set_reminder_lead_times(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    lead_minutes: Option<Vec<u32>>,
+) -> Result<ReminderLeadTimes> {
+    let user = state.current_user().ok_or(Error::SignedOut)?;
+
+    Ok(ReminderLeadTimes {
+        lead_minutes: set_reminder_lead_minutes(&pool, &user.id, lead_minutes).await?,
+        default_lead_minutes: state.config.reminder_lead_minutes.clone(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_users(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<User>> {
+    state.require(Permission::ManageUsers)?;
+    list(&pool).await
+}
+
+#[tauri::command]
+pub async fn add_user(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    user: AddUser,
+) -> Result<User> {
+    state.require(Permission::ManageUsers)?;
+    create(&pool, &user).await
+}
+
+#[tauri::command]
+pub async fn update_user(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    user: SaveUser,
+) -> Result<User> {
+    let current = state.require(Permission::ManageUsers)?;
+    if current.id == id && !user.active {
+        return Err(Error::Conflict(
+            "You cannot deactivate your own account".to_string(),
+        ));
+    }
+
+    let user = update(&pool, &id, &user).await?;
+
+    // Changing your own role or name takes effect right away
+    state.refresh_session(&user);
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn reset_user_password(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    id: String,
+    password: String,
+) -> Result<()> {
+    state.require(Permission::ManageUsers)?;
+    set_password(&pool, &id, &password).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_pool;
+
+    fn user(username: &str, role: Role, active: bool) -> SaveUser {
+        SaveUser {
+            username: username.to_string(),
+            display_name: username.to_uppercase(),
+            role,
+            active,
+        }
+    }
+
+    async fn add(pool: &SqlitePool, username: &str, role: Role) -> Result<User> {
+        create(
+            pool,
+            &AddUser {
+                user: user(username, role, true),
+                password: "correct horse battery".to_string(),
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn creates_only_one_first_account() {
+        let pool = memory_pool().await;
+        let first = |username: &str| AddUser {
+            user: user(username, Role::Admin, true),
+            password: "correct horse battery".to_string(),
+        };
+
+        let (sara, omar) = (first("sara"), first("omar"));
+        let (sara, omar) = tokio::join!(create_first(&pool, &sara), create_first(&pool, &omar));
+        assert!(sara.is_ok() != omar.is_ok());
+        assert!(matches!(
+            create_first(&pool, &first("lina")).await,
+            Err(Error::Conflict(_))
+        ));
+        assert_eq!(count(&pool).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn crud_round_trip() {
+        let pool = memory_pool().await;
+
+        let admin = add(&pool, "sara", Role::Admin).await.unwrap();
+        let receptionist = add(&pool, "omar", Role::Receptionist).await.unwrap();
+        assert_eq!(count(&pool).await.unwrap(), 2);
+
+        let (found, hash) = find_credentials(&pool, " sara ").await.unwrap().unwrap();
+        assert_eq!(found.id, admin.id);
+        assert!(hash.starts_with("$argon2"));
+
+        let updated = update(&pool, &receptionist.id, &user("omar", Role::Accountant, false))
+            .await
+            .unwrap();
+        assert_eq!(updated.role, Role::Accountant);
+        assert!(!updated.active);
+        assert!(find_credentials(&pool, "omar").await.unwrap().is_none());
+
+        // Inactive users sort after active ones
+        let ids: Vec<String> = list(&pool).await.unwrap().into_iter().map(|u| u.id).collect();
+        assert_eq!(ids, [admin.id, receptionist.id]);
+    }
+
+    #[tokio::test]
+    async fn usernames_are_unique_regardless_of_case() {
+        let pool = memory_pool().await;
+        add(&pool, "sara", Role::Admin).await.unwrap();
+
+        assert!(matches!(add(&pool, "Sara", Role::Doctor).await, Err(Error::Conflict(_))));
+
+        let omar = add(&pool, "omar", Role::Doctor).await.unwrap();
+        assert!(matches!(
+            update(&pool, &omar.id, &user("SARA", Role::Doctor, true)).await,
+            Err(Error::Conflict(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn keeps_one_active_admin() {
+        let pool = memory_pool().await;
+        let admin = add(&pool, "sara", Role::Admin).await.unwrap();
+
+        assert!(matches!(
+            update(&pool, &admin.id, &user("sara", Role::Doctor, true)).await,
+            Err(Error::Conflict(_))
+        ));
+        assert!(matches!(
+            update(&pool, &admin.id, &user("sara", Role::Admin, false)).await,
+            Err(Error::Conflict(_))
+        ));
+
+        add(&pool, "huda", Role::Admin).await.unwrap();
+        update(&pool, &admin.id, &user("sara", Role::Doctor, true))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_blank_fields_and_weak_passwords() {
+        let pool = memory_pool().await;
+
+        assert!(matches!(add(&pool, " ", Role::Admin).await, Err(Error::Validation(_))));
+        let weak = create(
+            &pool,
+            &AddUser {
+                user: user("sara", Role::Admin, true),
+                password: "123".to_string(),
+            },
+        )
+        .await;
+        assert!(matches!(weak, Err(Error::Validation(_))));
+        assert!(matches!(
+            set_password(&pool, "missing", "correct horse battery").await,
+            Err(Error::NotFound("User"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reminder_lead_times_are_per_user() {
+        let pool = memory_pool().await;
+        let sara = add(&pool, "sara", Role::Admin).await.unwrap();
+        let omar = add(&pool, "omar", Role::Doctor).await.unwrap();
+
+        let saved = set_reminder_lead_minutes(&pool, &sara.id, Some(vec![30, 1440, 30]))
+            .await
+            .unwrap();
+        assert_eq!(saved, Some(vec![1440, 30]));
+        assert_eq!(
+            reminder_lead_minutes(&pool, &sara.id).await.unwrap(),
+            Some(vec![1440, 30])
+        );
+        assert_eq!(reminder_lead_minutes(&pool, &omar.id).await.unwrap(), None);
+
+        assert!(matches!(
+            set_reminder_lead_minutes(&pool, &omar.id, Some(vec![0])).await,
+            Err(Error::Validation(_))
+        ));
+
+        set_reminder_lead_minutes(&pool, &sara.id, None).await.unwrap();
+        assert_eq!(reminder_lead_minutes(&pool, &sara.id).await.unwrap(), None);
+    }
+}
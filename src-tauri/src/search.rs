@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::State;
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    error::Result,
+    repository::{patients::Patient, PagedList, PagingParams},
+};
+
+/// Wraps a matched term in `highlightedName`, `highlightedPhone` and `snippet`.
+/// Control characters cannot appear in typed text, so the UI can split on them
+/// instead of rendering markup.
+pub const MATCH_START: char = '\u{2}';
+pub const MATCH_END: char = '\u{3}';
+
+const SNIPPET_TOKENS: i64 = 8;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchParams {
+    #[serde(flatten)]
+    pub paging: PagingParams,
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatientSearchResult {
+    #[serde(flatten)]
+    pub patient: Patient,
+    pub highlighted_name: String,
+    pub highlighted_phone: String,
+    /// Matching doctor or procedure text, when the match was not only on the patient
+    pub snippet: Option<String>,
+    pub rank: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    patient: Patient,
+    doctors: String,
+    procedures: String,
+    rank: f64,
+}
+
+/// Mirrors `search_fold!` in the search index migration, `None` drops the character.
+fn fold_char(c: char) -> Option<char> {
+    match c {
+        '\u{064B}'..='\u{0652}' | '\u{0670}' | '\u{0640}' => None,
+        'أ' | 'إ' | 'آ' | 'ٱ' => Some('ا'),
+        'ى' => Some('ي'),
+        'ة' => Some('ه'),
+        '٠'..='٩' => char::from_digit(c as u32 - '٠' as u32, 10),
+        _ => Some(c),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter_map(fold_char)
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// Marks and tatweel sit inside words, so they must not split them
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || fold_char(c).is_none()
+}
+
+/// Splits the query the way the `unicode61` tokenizer does.
+fn terms(query: &str) -> Vec<String> {
+    normalize(query)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Every term has to match the start of a word, in any column
+fn match_expression(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{term}\"*"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Marks the words of the original `text` that start with one of the terms.
+///
+/// The index only holds the folded text, so the name and phone are
+/// highlighted here to keep the spelling the user entered.
+fn highlight(text: &str, terms: &[String]) -> String {
+    let mut highlighted = String::with_capacity(text.len());
+    let mut word = String::new();
+
+    let flush = |word: &mut String, out: &mut String| {
+        if word.is_empty() {
+            return;
+        }
+
+        let folded = normalize(word);
+        if terms.iter().any(|term| folded.starts_with(term.as_str())) {
+            out.push(MATCH_START);
+            out.push_str(word);
+            out.push(MATCH_END);
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+
+    for c in text.chars() {
+        if is_word_char(c) {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut highlighted);
+            highlighted.push(c);
+        }
+    }
+    flush(&mut word, &mut highlighted);
+
+    highlighted
+}
+
+fn has_match(snippet: &str) -> bool {
+    snippet.contains(MATCH_START)
+}
+
+/// Ranks patients by name, phone, doctor and procedure matches.
+pub async fn search_patients(
+    pool: &SqlitePool,
+    params: &SearchParams,
+) -> Result<PagedList<PatientSearchResult>> {
+    let terms = terms(&params.query);
+    if terms.is_empty() {
+        return Ok(PagedList::new(Vec::new(), &params.paging, 0));
+    }
+
+    let expression = match_expression(&terms);
+
+    let rows = sqlx::query_as::<_, SearchRow>(
+        r#"
+        SELECT
+            p.id,
+            p.name,
+            p.phone,
+            p.created_at,
+            p.updated_at,
+            snippet(patients_fts, 3, char(2), char(3), '…', ?2) AS doctors,
+            snippet(patients_fts, 4, char(2), char(3), '…', ?2) AS procedures,
+            bm25(patients_fts, 0.0, 10.0, 5.0, 2.0, 1.0) AS rank
+        FROM patients_fts
+        JOIN patients p ON p.id = patients_fts.patient_id
+        WHERE patients_fts MATCH ?1 AND p.deleted_at IS NULL
+        ORDER BY rank, p.created_at DESC
+        LIMIT ?3 OFFSET ?4
+        "#,
+    )
+    .bind(&expression)
+    .bind(SNIPPET_TOKENS)
+    .bind(params.paging.limit())
+    .bind(params.paging.offset())
+    .fetch_all(pool)
+    .await?;
+
+    let total: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM patients_fts
+        JOIN patients p ON p.id = patients_fts.patient_id
+        WHERE patients_fts MATCH ? AND p.deleted_at IS NULL
+        "#,
+    )
+    .bind(&expression)
+    .fetch_one(pool)
+    .await?;
+
+    let results = rows
+        .into_iter()
+        .map(|row| {
+            let snippet = [row.procedures, row.doctors]
+                .into_iter()
+                .find(|snippet| has_match(snippet));
+
+            PatientSearchResult {
+                highlighted_name: highlight(&row.patient.name, &terms),
+                highlighted_phone: highlight(&row.patient.phone, &terms),
+                snippet,
+                rank: row.rank,
+                patient: row.patient,
+            }
+        })
+        .collect();
+
+    Ok(PagedList::new(results, &params.paging, total))
+}
+
+#[tauri::command]
+pub async fn search(
+    pool: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
+    params: SearchParams,
+) -> Result<PagedList<PatientSearchResult>> {
+    state.require(Permission::ViewRecords)?;
+    search_patients(&pool, &params).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_pool,
+        repository::patients::{self, SavePatient},
+    };
+
+    fn marked(word: &str) -> String {
+        format!("{MATCH_START}{word}{MATCH_END}")
+    }
+
+    async fn add_patient(pool: &SqlitePool, name: &str, phone: &str) -> String {
+        let patient = patients::create(
+            pool,
+            &SavePatient {
+                name: name.to_string(),
+                phone: phone.to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        patient.patient.id
+    }
+
+    async fn search(pool: &SqlitePool, query: &str) -> Vec<PatientSearchResult> {
+        let params = SearchParams {
+            paging: PagingParams::default(),
+            query: query.to_string(),
+        };
+        search_patients(pool, &params).await.unwrap().items
+    }
+
+    #[test]
+    fn strips_tashkeel_and_tatweel() {
+        assert_eq!(normalize("مُحَمَّدٌ"), "محمد");
+        assert_eq!(normalize("مـــحمد"), "محمد");
+        assert_eq!(normalize("رحمٰن"), "رحمن");
+    }
+
+    #[test]
+    fn folds_alef_ya_and_ta_marbuta() {
+        assert_eq!(normalize("أحمد إبراهيم آمال ٱلله"), "احمد ابراهيم امال الله");
+        assert_eq!(normalize("مصطفى"), "مصطفي");
+        assert_eq!(normalize("فاطمة"), "فاطمه");
+    }
+
+    #[test]
+    fn folds_arabic_indic_digits_and_case() {
+        assert_eq!(normalize("٠٧٩١٢٣٤٥٦٨٩"), "07912345689");
+        assert_eq!(normalize("Sara ALI"), "sara ali");
+    }
+
+    #[test]
+    fn splits_terms_like_the_tokenizer() {
+        assert_eq!(terms("  مُحَمَّد,  أحمد-Ali "), ["محمد", "احمد", "ali"]);
+        assert!(terms(" - ، ").is_empty());
+        assert_eq!(match_expression(&terms("sara ali")), "\"sara\"* \"ali\"*");
+    }
+
+    #[test]
+    fn highlights_the_original_spelling() {
+        // Offsets come from the original text, marks and tatweel stay inside the match
+        assert_eq!(
+            highlight("مُحَمَّد أَحْمَد", &terms("محمد")),
+            format!("{} أَحْمَد", marked("مُحَمَّد"))
+        );
+        assert_eq!(
+            highlight("مـحـمد بن أحمد", &terms("احم")),
+            format!("مـحـمد بن {}", marked("أحمد"))
+        );
+        assert_eq!(
+            highlight("Sara Ali-Hassan", &terms("ha sa")),
+            format!("{} Ali-{}", marked("Sara"), marked("Hassan"))
+        );
+        assert_eq!(
+            highlight("٠٧٩١٢٣٤٥٦٧", &terms("079")),
+            marked("٠٧٩١٢٣٤٥٦٧")
+        );
+        assert_eq!(highlight("Sara", &terms("ali")), "Sara");
+    }
+
+    #[tokio::test]
+    async fn folds_like_the_search_index() {
+        let pool = memory_pool().await;
+        let mut sample: String = ('\u{064B}'..='\u{0652}').collect();
+        sample.push_str("\u{0670}\u{0640}أإآٱىة٠١٢٣٤٥٦٧٨٩ ابت Ab");
+        let id = add_patient(&pool, &sample, "0790000000").await;
+
+        let indexed: String =
+            sqlx::query_scalar("SELECT name FROM patients_fts_source WHERE patient_id = ?")
+                .bind(&id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let folded: String = sample.chars().filter_map(fold_char).collect();
+        assert_eq!(indexed, folded);
+    }
+
+    #[tokio::test]
+    async fn finds_diacritized_names_from_plain_queries() {
+        let pool = memory_pool().await;
+        let muhammad = add_patient(&pool, "مُحَمَّد أَحْمَد", "٠٧٩١٢٣٤٥٦٧").await;
+        let fatima = add_patient(&pool, "فاطِمة مصطفى", "0781112222").await;
+
+        let found = search(&pool, "محمد احمد").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].patient.id, muhammad);
+        assert_eq!(
+            found[0].highlighted_name,
+            format!("{} {}", marked("مُحَمَّد"), marked("أَحْمَد"))
+        );
+
+        let found = search(&pool, "079").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].patient.id, muhammad);
+        assert_eq!(found[0].highlighted_phone, marked("٠٧٩١٢٣٤٥٦٧"));
+
+        let found = search(&pool, "فاطمه مصطفي").await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].patient.id, fatima);
+
+        assert!(search(&pool, "خالد").await.is_empty());
+        assert!(search(&pool, "").await.is_empty());
+    }
+}
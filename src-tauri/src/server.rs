@@ -1,49 +1,375 @@
+use std::{
+    fmt::Display,
+    io::ErrorKind,
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
 use axum::{
-    extract::{DefaultBodyLimit, Multipart, Query, State},
+    extract::{multipart::Field, DefaultBodyLimit, FromRef, Multipart, Query, Request, State},
     http::StatusCode,
-    response::{Html, IntoResponse},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
     routing::{get, post},
-    Router,
+    Json, Router,
 };
-use base64::Engine;
-use serde::Deserialize;
-use tauri::{AppHandle, Emitter, Manager};
-use tokio::net::TcpListener;
-use crate::app_state::AppState;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager, State as TauriState};
+use tokio::{fs, io::AsyncWriteExt, net::TcpListener};
+use crate::{
+    app_state::AppState,
+    config::AppConfig,
+    encryption::Key,
+    error::Error,
+    pairing::PairingTokens,
+    peer_sync::{self, PeerState},
+    repository::{
+        self,
+        attachments::{self, Attachment},
+        statements,
+    },
+};
+
+const PAIRING_TOKEN_HEADER: &str = "x-pairing-token";
+const HEALTH_APP_NAME: &str = "sgmc";
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ServerStatus {
+    Starting,
+    Listening { port: u16 },
+    /// Another copy of the app owns the port; this one should not keep running
+    AlreadyRunning { port: u16 },
+    Failed { reason: String },
+}
+
+/// Where the scanner/OAuth server ended up, for commands that build URLs to it.
+pub struct ServerInfo {
+    status: Mutex<ServerStatus>,
+}
+
+impl Default for ServerInfo {
+    fn default() -> Self {
+        Self {
+            status: Mutex::new(ServerStatus::Starting),
+        }
+    }
+}
+
+impl ServerInfo {
+    pub fn status(&self) -> ServerStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// The port actually bound, which may differ from the configured one.
+    pub fn port(&self) -> Option<u16> {
+        match *self.status.lock().unwrap() {
+            ServerStatus::Listening { port } => Some(port),
+            _ => None,
+        }
+    }
 
-// AppHandle is Clone + Send + Sync, so we can use it directly as State
-type ServerState = AppHandle;
+    fn set(&self, app: &AppHandle, status: ServerStatus) {
+        *self.status.lock().unwrap() = status.clone();
+        let _ = app.emit("server-status", status);
+    }
+}
+
+#[derive(Deserialize)]
+struct HealthResponse {
+    app: String,
+}
+
+type PoolSource = Arc<dyn Fn() -> Option<SqlitePool> + Send + Sync>;
+type KeySource = Arc<dyn Fn() -> Option<Key> + Send + Sync>;
+/// Takes an event name and its payload
+type EventSink = Arc<dyn Fn(&'static str, String) + Send + Sync>;
+
+/// What the scanner and OAuth routes need, without the Tauri app, so the
+/// routes can be served on their own.
+#[derive(Clone)]
+pub struct ServerState {
+    /// Asked on every request, as the database may still be locked
+    pool: PoolSource,
+    key: KeySource,
+    events: EventSink,
+    data_dir: Arc<Path>,
+    version: Arc<str>,
+    pairing: Arc<PairingTokens>,
+}
+
+impl ServerState {
+    /// Reads the database and key from the app as requests come in and
+    /// emits its events to the webview.
+    pub fn for_app(app: &AppHandle, pairing: Arc<PairingTokens>) -> Self {
+        let pool_app = app.clone();
+        let key_app = app.clone();
+        let event_app = app.clone();
+
+        Self {
+            pool: Arc::new(move || {
+                pool_app
+                    .try_state::<SqlitePool>()
+                    .map(|pool| pool.inner().clone())
+            }),
+            key: Arc::new(move || key_app.state::<AppState>().encryption_key()),
+            events: Arc::new(move |event, payload| {
+                if let Err(err) = event_app.emit(event, payload) {
+                    log::warn!("Failed to emit {}: {}", event, err);
+                }
+            }),
+            data_dir: Path::new(&app.state::<AppState>().config.data_dir).into(),
+            version: app.package_info().version.to_string().into(),
+            pairing,
+        }
+    }
+}
+
+impl FromRef<ServerState> for Arc<PairingTokens> {
+    fn from_ref(state: &ServerState) -> Self {
+        state.pairing.clone()
+    }
+}
 
 #[derive(Deserialize)]
 struct OAuthQuery {
     code: String,
 }
 
-pub async fn start_server(app: AppHandle) -> u16 {
-    let app_state = app.clone();
+#[derive(Deserialize)]
+struct PairingQuery {
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScanQuery {
+    token: Option<String>,
+    statement_id: Option<String>,
+}
 
-    let router = Router::new()
-        .route("/scan", get(get_scan_page))
+/// A listener on the first free port, or why there is none.
+pub type Binding = std::result::Result<(TcpListener, u16), ServerStatus>;
+
+/// Serves on the bound listener and records the outcome in [`ServerInfo`],
+/// also emitted as `server-status`.
+pub fn start_server(app: AppHandle, binding: Binding) -> ServerStatus {
+    let status = match binding {
+        Ok((listener, port)) => {
+            let pairing = app.state::<Arc<PairingTokens>>().inner().clone();
+            let router = router(ServerState::for_app(&app, pairing), PeerState::for_app(&app));
+            let server_app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = axum::serve(listener, router).await {
+                    log::error!("Scanner/OAuth server stopped: {}", err);
+                    server_app.state::<ServerInfo>().set(
+                        &server_app,
+                        ServerStatus::Failed {
+                            reason: err.to_string(),
+                        },
+                    );
+                }
+            });
+            ServerStatus::Listening { port }
+        }
+        Err(status) => status,
+    };
+
+    app.state::<ServerInfo>().set(&app, status.clone());
+    status
+}
+
+/// Tries `port..=port + port_range`, stopping early if another copy of the
+/// app already holds one of them.
+pub async fn bind(config: &AppConfig) -> Binding {
+    let address: IpAddr = config
+        .bind_address
+        .parse()
+        .map_err(|_| ServerStatus::Failed {
+            reason: format!("Invalid bind address {}", config.bind_address),
+        })?;
+    let last_port = config.port.saturating_add(config.port_range);
+
+    for port in config.port..=last_port {
+        match TcpListener::bind((address, port)).await {
+            Ok(listener) => return Ok((listener, port)),
+            Err(err) if err.kind() == ErrorKind::AddrInUse => {
+                if is_app_running(address, port).await {
+                    return Err(ServerStatus::AlreadyRunning { port });
+                }
+                log::warn!("Port {} is in use, trying the next one", port);
+            }
+            Err(err) => {
+                return Err(ServerStatus::Failed {
+                    reason: format!("Cannot listen on {}:{}: {}", address, port, err),
+                })
+            }
+        }
+    }
+
+    Err(ServerStatus::Failed {
+        reason: format!("Ports {}-{} are all in use", config.port, last_port),
+    })
+}
+
+// A busy port may just be another window of this app, which answers /health
+async fn is_app_running(address: IpAddr, port: u16) -> bool {
+    let host = if address.is_unspecified() {
+        IpAddr::from([127, 0, 0, 1])
+    } else {
+        address
+    };
+
+    let Ok(client) = reqwest::Client::builder().timeout(HEALTH_TIMEOUT).build() else {
+        return false;
+    };
+
+    let response = client
+        .get(format!("http://{}/health", SocketAddr::new(host, port)))
+        .send()
+        .await;
+
+    match response {
+        Ok(response) => response
+            .json::<HealthResponse>()
+            .await
+            .is_ok_and(|health| health.app == HEALTH_APP_NAME),
+        Err(_) => false,
+    }
+}
+
+pub fn router(state: ServerState, peers: PeerState) -> Router {
+    Router::new()
         .route("/upload", post(handle_upload))
+        .route_layer(middleware::from_fn_with_state(
+            state.pairing.clone(),
+            require_pairing_token,
+        ))
+        .route("/scan", get(get_scan_page))
         .route("/oauth/callback", get(handle_oauth))
-        .with_state(app_state)
-        .layer(DefaultBodyLimit::disable());
+        .route("/health", get(health))
+        .with_state(state)
+        .merge(peer_sync::router(peers))
+        .layer(DefaultBodyLimit::disable())
+}
 
-    let port = app.state::<AppState>().config.port;
-    let listener = TcpListener::bind(format!("0.0.0.0:{}", port))
-        .await
-        .unwrap();
+/// Reads the pairing token from the `token` query parameter or the `x-pairing-token` header.
+fn pairing_token(request: &Request) -> Option<String> {
+    if let Some(token) = request
+        .headers()
+        .get(PAIRING_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        return Some(token.to_string());
+    }
 
-    tokio::spawn(async move {
-        axum::serve(listener, router).await.unwrap();
-    });
+    Query::<PairingQuery>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(query)| query.token)
+}
+
+async fn require_pairing_token(
+    State(pairing): State<Arc<PairingTokens>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(paired_statement) =
+        pairing_token(&request).and_then(|token| pairing.validate(&token))
+    else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            "Pairing expired. Scan the QR code on the PC again.".to_string(),
+        )
+            .into_response();
+    };
+
+    let statement_id = Query::<UploadQuery>::try_from_uri(request.uri())
+        .ok()
+        .map(|Query(query)| query.statement_id);
+
+    if statement_id.as_deref() != Some(paired_statement.as_str()) {
+        return (
+            StatusCode::FORBIDDEN,
+            "This QR code is for another statement. Scan the one on the PC again.".to_string(),
+        )
+            .into_response();
+    }
 
-    port
+    next.run(request).await
 }
 
-async fn get_scan_page() -> Html<&'static str> {
+async fn get_scan_page(
+    State(pairing): State<Arc<PairingTokens>>,
+    Query(query): Query<ScanQuery>,
+) -> Response {
+    let Some((token, paired_statement)) = query
+        .token
+        .and_then(|token| pairing.validate(&token).map(|statement| (token, statement)))
+    else {
+        return error_page(
+            StatusCode::UNAUTHORIZED,
+            "Link expired",
+            "Open the scanner on the PC and scan the new QR code.",
+        );
+    };
+
+    if query.statement_id.as_deref() != Some(paired_statement.as_str()) {
+        return error_page(
+            StatusCode::FORBIDDEN,
+            "Wrong statement",
+            "This link is for another statement. Scan the QR code on the PC again.",
+        );
+    }
+
+    let Some(statement_id) = uuid::Uuid::parse_str(&paired_statement).ok() else {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            "Invalid link",
+            "Open the scanner from a statement on the PC and scan its QR code.",
+        );
+    };
+
+    // Both values are hex, so they are safe to drop into the script as-is
     Html(
-        r##"
+        SCAN_PAGE
+            .replace("__PAIRING_TOKEN__", &token)
+            .replace("__STATEMENT_ID__", &statement_id.to_string()),
+    )
+    .into_response()
+}
+
+fn error_page(status: StatusCode, title: &str, message: &str) -> Response {
+    let page = ERROR_PAGE
+        .replace("__TITLE__", title)
+        .replace("__MESSAGE__", message);
+
+    (status, Html(page)).into_response()
+}
+
+const ERROR_PAGE: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Scanner</title>
+    <style>
+        body { background-color: #09090b; color: #fff; font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Helvetica, Arial, sans-serif; display: flex; flex-direction: column; align-items: center; justify-content: center; min-height: 100vh; margin: 0; padding: 20px; box-sizing: border-box; text-align: center; }
+        h1 { font-size: 1.5rem; font-weight: 700; }
+        p { color: #a1a1aa; }
+    </style>
+</head>
+<body>
+    <h1>__TITLE__</h1>
+    <p>__MESSAGE__</p>
+</body>
+</html>
+"#;
+
+const SCAN_PAGE: &str = r##"
 <!DOCTYPE html>
 <html lang="en">
 <head>
@@ -51,9 +377,9 @@ async fn get_scan_page() -> Html<&'static str> {
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>Scanner</title>
     <style>
-        :root { --primary: #2563eb; --primary-hover: #1d4ed8; --secondary: #3f3f46; --bg: #09090b; --text: #fff; --error: #ef4444; --success: #22c55e; }
+        :root { --primary: #2563eb; --primary-hover: #1d4ed8; --secondary: #3f3f46; --bg: #09090b; --text: #fff; --muted: #a1a1aa; --error: #ef4444; --success: #22c55e; --warning: #fbbf24; }
         body { background-color: var(--bg); color: var(--text); font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Helvetica, Arial, sans-serif; display: flex; flex-direction: column; align-items: center; justify-content: center; min-height: 100vh; margin: 0; padding: 20px; box-sizing: border-box; text-align: center; }
-        h1 { margin-bottom: 2.5rem; font-size: 2rem; font-weight: 800; letter-spacing: -0.025em; }
+        h1 { margin-bottom: 1.5rem; font-size: 2rem; font-weight: 800; letter-spacing: -0.025em; }
         .container { width: 100%; max-width: 360px; display: flex; flex-direction: column; align-items: center; gap: 1.5rem; }
         
         /* Main View */
@@ -63,12 +389,25 @@ async fn get_scan_page() -> Html<&'static str> {
         .btn-primary:active { background-color: var(--primary-hover); transform: scale(0.98); }
         .btn-secondary { background-color: var(--secondary); }
         .btn-secondary:active { transform: scale(0.98); opacity: 0.9; }
+        .btn-send { background-color: var(--success); display: none; }
+        .btn-send:active { transform: scale(0.98); opacity: 0.9; }
         .icon { width: 24px; height: 24px; }
-        #status { font-size: 1rem; color: #a1a1aa; min-height: 1.5rem; }
+        #status { font-size: 1rem; color: var(--muted); min-height: 1.5rem; }
         input { display: none; }
+
+        /* Queue */
+        #queue { list-style: none; margin: 0; padding: 0; width: 100%; display: flex; flex-direction: column; gap: 0.5rem; }
+        .item { display: flex; align-items: center; gap: 0.75rem; padding: 0.75rem 1rem; background: #18181b; border-radius: 12px; text-align: left; font-size: 0.9rem; }
+        .item .name { flex: 1; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; }
+        .item .state { color: var(--muted); font-size: 0.8rem; }
+        .item.uploading .state { color: var(--warning); }
+        .item.done .state { color: var(--success); }
+        .item.failed .state { color: var(--error); }
+        .item .error { display: block; color: var(--error); font-size: 0.75rem; white-space: normal; }
+        .remove { background: none; border: none; color: var(--muted); font-size: 1.1rem; cursor: pointer; padding: 0 0.25rem; }
         
         /* Progress Bar */
-        .progress-container { width: 100%; height: 6px; background: #27272a; border-radius: 99px; overflow: hidden; display: none; margin-top: 1rem; }
+        .progress-container { width: 100%; height: 6px; background: #27272a; border-radius: 99px; overflow: hidden; display: none; }
         .progress-bar { height: 100%; background: var(--primary); width: 0%; transition: width 0.1s linear; }
 
         /* Success View */
@@ -76,7 +415,7 @@ async fn get_scan_page() -> Html<&'static str> {
         .checkmark { width: 80px; height: 80px; background: var(--success); border-radius: 50%; display: flex; align-items: center; justify-content: center; box-shadow: 0 0 20px rgba(34, 197, 94, 0.4); margin-bottom: 1rem; }
         .checkmark svg { width: 40px; height: 40px; color: white; stroke-width: 3; }
         .success-text { font-size: 1.5rem; font-weight: 700; color: white; }
-        .success-sub { color: #a1a1aa; }
+        .success-sub { color: var(--muted); }
 
         @keyframes fadeIn { from { opacity: 0; transform: translateY(10px); } to { opacity: 1; transform: translateY(0); } }
     </style>
@@ -88,22 +427,26 @@ async fn get_scan_page() -> Html<&'static str> {
         <div class="btn-group" id="controls">
             <label class="btn btn-primary">
                 <svg class="icon" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><path d="M23 19a2 2 0 0 1-2 2H3a2 2 0 0 1-2-2V8a2 2 0 0 1 2-2h4l2-3h6l2 3h4a2 2 0 0 1 2 2z"/><circle cx="12" cy="13" r="4"/></svg>
-                <span>Take Photo</span>
+                <span id="cam-label">Take Photo</span>
                 <input type="file" id="cam" accept="image/*" capture="environment">
             </label>
 
             <label class="btn btn-secondary">
                 <svg class="icon" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><rect x="3" y="3" width="18" height="18" rx="2" ry="2"/><circle cx="8.5" cy="8.5" r="1.5"/><polyline points="21 15 16 10 5 21"/></svg>
-                <span>Upload from Gallery</span>
-                <input type="file" id="gallery" accept="image/*">
+                <span>Add from Gallery or Files</span>
+                <input type="file" id="gallery" accept="image/*,application/pdf" multiple>
             </label>
         </div>
 
+        <ul id="queue"></ul>
+
+        <button class="btn btn-send" id="send"></button>
+
         <div class="progress-container" id="progressContainer">
             <div class="progress-bar" id="progressBar"></div>
         </div>
 
-        <p id="status">Ready to scan</p>
+        <p id="status">Take a photo of each page, then send them together</p>
     </div>
 
     <div class="container" id="success-view">
@@ -112,143 +455,467 @@ async fn get_scan_page() -> Html<&'static str> {
         </div>
         <div>
             <div class="success-text">Upload Complete!</div>
-            <div class="success-sub">The image has been sent to your PC.</div>
+            <div class="success-sub" id="success-count"></div>
         </div>
-        <p class="success-sub" style="margin-top: 1rem; font-size: 0.9rem;">You can now close this tab.</p>
+        <button class="btn btn-secondary" id="scan-more">Scan More</button>
+        <p class="success-sub" style="font-size: 0.9rem;">Or close this tab when you are done.</p>
     </div>
 
     <script>
         const mainView = document.getElementById('main-view');
         const successView = document.getElementById('success-view');
+        const successCount = document.getElementById('success-count');
         const cam = document.getElementById('cam');
+        const camLabel = document.getElementById('cam-label');
         const gallery = document.getElementById('gallery');
+        const queueList = document.getElementById('queue');
+        const send = document.getElementById('send');
+        const scanMore = document.getElementById('scan-more');
         const status = document.getElementById('status');
         const controls = document.getElementById('controls');
         const progressContainer = document.getElementById('progressContainer');
         const progressBar = document.getElementById('progressBar');
+        const pairingToken = "__PAIRING_TOKEN__";
+        const statementId = "__STATEMENT_ID__";
+
+        // Every picked file waits here until the server confirms it was saved
+        let queue = [];
+        let uploading = false;
+
+        const plural = (count) => `${count} file${count === 1 ? '' : 's'}`;
+        const pending = () => queue.filter((item) => item.status === 'queued' || item.status === 'failed');
+        const stateLabel = {
+            queued: 'Waiting',
+            uploading: 'Sending...',
+            done: 'Saved',
+            failed: 'Failed',
+        };
+
+        const setStatus = (text, color = 'var(--muted)') => {
+            status.innerText = text;
+            status.style.color = color;
+        };
 
-        const uploadFile = (file) => {
-            if (!file) return;
-            
-            // UI Reset
-            status.innerText = "Starting upload...";
-            status.style.color = "#fbbf24"; // yellow
+        // File names come from the phone, so they only ever go in as text
+        const render = () => {
+            queueList.replaceChildren(...queue.map((item, index) => {
+                const row = document.createElement('li');
+                row.className = `item ${item.status}`;
+
+                const name = document.createElement('span');
+                name.className = 'name';
+                name.textContent = `${index + 1}. ${item.file.name || 'Photo'}`;
+                if (item.error) {
+                    const error = document.createElement('span');
+                    error.className = 'error';
+                    error.textContent = item.error;
+                    name.append(error);
+                }
+
+                const state = document.createElement('span');
+                state.className = 'state';
+                state.textContent = stateLabel[item.status];
+                row.append(name, state);
+
+                if (!uploading && item.status !== 'done') {
+                    const remove = document.createElement('button');
+                    remove.className = 'remove';
+                    remove.textContent = '✕';
+                    remove.setAttribute('aria-label', 'Remove');
+                    remove.onclick = () => {
+                        queue = queue.filter((other) => other !== item);
+                        render();
+                    };
+                    row.append(remove);
+                }
+
+                return row;
+            }));
+
+            const waiting = pending();
+            const retrying = waiting.some((item) => item.status === 'failed');
+            send.style.display = waiting.length && !uploading ? 'flex' : 'none';
+            send.textContent = retrying ? `Retry ${plural(waiting.length)}` : `Send ${plural(waiting.length)}`;
+            camLabel.textContent = queue.length ? 'Take Another Photo' : 'Take Photo';
+            controls.style.opacity = uploading ? '0.5' : '1';
+            controls.style.pointerEvents = uploading ? 'none' : 'auto';
+        };
+
+        const addFiles = (files) => {
+            for (const file of files) {
+                queue.push({ file, status: 'queued', error: null });
+            }
+            setStatus(`${plural(pending().length)} ready to send`);
+            render();
+        };
+
+        const finish = (batch) => {
+            uploading = false;
+            progressContainer.style.display = 'none';
+
+            const failed = batch.filter((item) => item.status === 'failed').length;
+            if (failed) {
+                setStatus(`❌ ${plural(failed)} not saved, tap retry`, 'var(--error)');
+            } else if (queue.every((item) => item.status === 'done')) {
+                successCount.innerText = `${plural(queue.length)} saved to the statement.`;
+                mainView.style.display = 'none';
+                successView.style.display = 'flex';
+            } else {
+                setStatus(`${plural(pending().length)} ready to send`);
+            }
+            render();
+        };
+
+        const failBatch = (batch, message) => {
+            for (const item of batch) {
+                item.status = 'failed';
+                item.error = message;
+            }
+        };
+
+        // The whole batch goes in one request; the server answers for each file in order
+        const uploadQueue = () => {
+            const batch = pending();
+            if (!batch.length || uploading) return;
+
+            uploading = true;
+            for (const item of batch) {
+                item.status = 'uploading';
+                item.error = null;
+            }
             progressContainer.style.display = 'block';
             progressBar.style.width = '0%';
-            progressBar.style.background = "var(--primary)";
-            controls.style.opacity = '0.5';
-            controls.style.pointerEvents = 'none';
+            setStatus('Starting upload...', 'var(--warning)');
+            render();
 
             const fd = new FormData();
-            fd.append('file', file);
+            for (const item of batch) {
+                fd.append('file', item.file, item.file.name || 'photo.jpg');
+            }
 
             const xhr = new XMLHttpRequest();
-            
+
             xhr.upload.addEventListener("progress", (e) => {
                 if (e.lengthComputable) {
                     const percent = (e.loaded / e.total) * 100;
                     progressBar.style.width = percent + '%';
-                    status.innerText = `Uploading... ${Math.round(percent)}%`;
+                    setStatus(`Uploading ${plural(batch.length)}... ${Math.round(percent)}%`, 'var(--warning)');
                 }
             });
 
             xhr.addEventListener("load", () => {
                 if (xhr.status >= 200 && xhr.status < 300) {
-                    // Show Success View
-                    mainView.style.display = 'none';
-                    successView.style.display = 'flex';
+                    let results = [];
+                    try {
+                        results = JSON.parse(xhr.responseText).results || [];
+                    } catch (_) {
+                        failBatch(batch, "Unexpected response from the PC");
+                        return finish(batch);
+                    }
+
+                    batch.forEach((item, index) => {
+                        const result = results[index];
+                        if (result && result.attachmentId) {
+                            item.status = 'done';
+                        } else {
+                            item.status = 'failed';
+                            item.error = (result && result.error) || "The PC did not save this file";
+                        }
+                    });
                 } else {
                     const serverMessage = xhr.responseText ? xhr.responseText.trim() : "";
-                    handleError(serverMessage || `Upload failed (status ${xhr.status || "unknown"})`);
+                    failBatch(batch, serverMessage || `Upload failed (status ${xhr.status || "unknown"})`);
                 }
+                finish(batch);
             });
 
-            xhr.addEventListener("error", () => handleError("Network error while uploading"));
-            xhr.open("POST", "/upload");
+            xhr.addEventListener("error", () => {
+                failBatch(batch, "Network error while uploading");
+                finish(batch);
+            });
+            xhr.open("POST", `/upload?statement_id=${statementId}`);
+            xhr.setRequestHeader("X-Pairing-Token", pairingToken);
             xhr.send(fd);
         };
 
-        const handleError = (message = "Upload failed") => {
-            status.innerText = `❌ ${message}`;
-            status.style.color = "var(--error)";
-            progressBar.style.background = "var(--error)";
-            controls.style.opacity = '1';
-            controls.style.pointerEvents = 'auto';
+        // Cleared after each pick so the same photo can be chosen again
+        cam.onchange = (e) => { addFiles(e.target.files); e.target.value = ''; };
+        gallery.onchange = (e) => { addFiles(e.target.files); e.target.value = ''; };
+        send.onclick = uploadQueue;
+        scanMore.onclick = () => {
+            queue = [];
+            successView.style.display = 'none';
+            mainView.style.display = 'flex';
+            setStatus('Take a photo of each page, then send them together');
+            render();
         };
 
-        cam.onchange = (e) => uploadFile(e.target.files[0]);
-        gallery.onchange = (e) => uploadFile(e.target.files[0]);
+        render();
     </script>
 </body>
 </html>
-"##,
-    )
+"##;
+
+#[derive(Deserialize)]
+struct UploadQuery {
+    statement_id: String,
 }
 
+type UploadError = (StatusCode, String);
+
+fn upload_error<E: Display>(status: StatusCode, context: &'static str) -> impl FnOnce(E) -> UploadError {
+    move |err| (status, format!("{context}: {err}"))
+}
+
+/// How one `file` field of an upload went, in the order the fields were sent.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadResult {
+    /// The name the phone gave the file, if any
+    file_name: Option<String>,
+    attachment_id: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UploadResponse {
+    results: Vec<UploadResult>,
+}
+
+/// Saves every `file` field to the statement. Problems with the request as a
+/// whole come back as plain text; once files are being read, each one gets
+/// its own result so the phone can retry just the ones that failed.
 async fn handle_upload(
-    State(app): State<ServerState>,
+    State(state): State<ServerState>,
+    Query(query): Query<UploadQuery>,
     multipart: Multipart,
-) -> impl IntoResponse {
-    match process_upload(&app, multipart).await {
-        Ok(_) => (StatusCode::OK, "Uploaded".to_string()),
-        Err((status, message)) => (status, message),
+) -> Response {
+    match process_upload(&state, &query.statement_id, multipart).await {
+        Ok(results) => Json(UploadResponse { results }).into_response(),
+        Err((status, message)) => (status, message).into_response(),
     }
 }
 
+// Phones send photos, and scanned or downloaded documents as PDFs
+fn is_accepted_type(file_type: &str) -> bool {
+    file_type.starts_with("image/") || file_type == "application/pdf"
+}
+
 async fn process_upload(
-    app: &AppHandle,
+    state: &ServerState,
+    statement_id: &str,
     mut multipart: Multipart,
-) -> Result<(), (StatusCode, String)> {
-    while let Some(field) = multipart
-        .next_field()
+) -> Result<Vec<UploadResult>, UploadError> {
+    // Not managed until an encrypted database is unlocked
+    let pool = (state.pool)()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, Error::Locked.to_string()))?;
+    let key = (state.key)();
+
+    let statement_exists = statements::exists(&pool, statement_id)
         .await
-        .map_err(|err| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Failed to read multipart data: {err}"),
-            )
-        })?
-    {
+        .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to look up statement"))?;
+
+    if !statement_exists {
+        return Err((StatusCode::NOT_FOUND, "Statement not found".to_string()));
+    }
+
+    let mut results = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            // The rest of the body is unreadable, but the files before it are saved
+            Err(err) if !results.is_empty() => {
+                results.push(UploadResult {
+                    file_name: None,
+                    attachment_id: None,
+                    error: Some(format!("Failed to read multipart data: {err}")),
+                });
+                break;
+            }
+            Err(err) => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("Failed to read multipart data: {err}"),
+                ))
+            }
+        };
+
         if field.name() != Some("file") {
             continue;
         }
 
-        let content_type = field.content_type().unwrap_or("image/jpeg").to_string();
-        let bytes = field.bytes().await.map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to read file bytes: {err}"),
-            )
-        })?;
+        let file_name = field.file_name().map(str::to_string);
+        let result = match store_upload(&pool, &state.data_dir, key.as_ref(), statement_id, field).await {
+            Ok(attachment) => {
+                // The webview only needs to know which attachment to refetch
+                (state.events)("scan-received", attachment.id.clone());
+
+                UploadResult {
+                    file_name,
+                    attachment_id: Some(attachment.id),
+                    error: None,
+                }
+            }
+            Err((_, message)) => {
+                log::warn!("Failed to save uploaded file {:?}: {}", file_name, message);
+                UploadResult {
+                    file_name,
+                    attachment_id: None,
+                    error: Some(message),
+                }
+            }
+        };
+        results.push(result);
+    }
 
-        let base64_string = base64::engine::general_purpose::STANDARD.encode(&bytes);
+    if results.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "No file field found in upload".to_string(),
+        ));
+    }
 
-        let payload = serde_json::json!({
-            "mime": content_type,
-            "data": base64_string,
-        });
+    Ok(results)
+}
 
-        app.emit("scan-received", payload).map_err(|err| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to notify application: {err}"),
-            )
-        })?;
+/// Streams one multipart file to the attachments folder and records it against the statement.
+async fn store_upload(
+    pool: &SqlitePool,
+    data_dir: &Path,
+    key: Option<&Key>,
+    statement_id: &str,
+    mut field: Field<'_>,
+) -> Result<Attachment, UploadError> {
+    let original_name = field
+        .file_name()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+
+    let file_type = field
+        .content_type()
+        .map(str::to_string)
+        .or_else(|| {
+            original_name
+                .as_deref()
+                .and_then(|name| mime_guess::from_path(name).first_raw())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "image/jpeg".to_string());
 
-        return Ok(());
+    if !is_accepted_type(&file_type) {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Only images and PDFs can be uploaded, got {file_type}"),
+        ));
     }
 
-    Err((
-        StatusCode::BAD_REQUEST,
-        "No file field found in upload".to_string(),
-    ))
+    let created_at = attachments::now_millis();
+    let file_name = original_name.unwrap_or_else(|| {
+        let extension = match file_type.as_str() {
+            "image/jpeg" => "jpg",
+            other => mime_guess::get_mime_extensions_str(other)
+                .and_then(|extensions| extensions.first().copied())
+                .unwrap_or("bin"),
+        };
+        format!("scan_{created_at}.{extension}")
+    });
+
+    let id = repository::new_id();
+    let dir = attachments::attachments_dir(data_dir);
+    fs::create_dir_all(&dir)
+        .await
+        .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create attachments folder"))?;
+
+    let file_path = dir.join(attachments::stored_file_name(&id, &file_name));
+
+    let written = async {
+        let mut file = fs::File::create(&file_path)
+            .await
+            .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to create file"))?;
+        let mut size = 0;
+        // Encrypted files are sealed whole, so they are buffered instead of streamed
+        let mut buffer = key.map(|_| Vec::new());
+
+        while let Some(chunk) = field
+            .chunk()
+            .await
+            .map_err(upload_error(StatusCode::BAD_REQUEST, "Failed to read file bytes"))?
+        {
+            match &mut buffer {
+                Some(buffer) => buffer.extend_from_slice(&chunk),
+                None => file
+                    .write_all(&chunk)
+                    .await
+                    .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to write file"))?,
+            }
+            size += chunk.len() as i64;
+        }
+
+        if let (Some(key), Some(buffer)) = (key, buffer) {
+            let sealed = key
+                .seal(&buffer)
+                .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to encrypt file"))?;
+            file.write_all(&sealed)
+                .await
+                .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to write file"))?;
+        }
+
+        file.flush()
+            .await
+            .map_err(upload_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to write file"))?;
+
+        Ok(size)
+    }
+    .await;
+
+    let file_size = match written {
+        Ok(size) => size,
+        Err(err) => {
+            let _ = fs::remove_file(&file_path).await;
+            return Err(err);
+        }
+    };
+
+    let attachment = Attachment {
+        id,
+        statement_id: statement_id.to_string(),
+        file_name,
+        file_path: file_path.display().to_string(),
+        file_type,
+        file_size,
+        created_at,
+    };
+
+    if let Err(err) = attachments::insert(pool, &attachment).await {
+        let _ = fs::remove_file(&file_path).await;
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to save attachment: {err}"),
+        ));
+    }
+
+    Ok(attachment)
+}
+
+async fn health(State(state): State<ServerState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "app": HEALTH_APP_NAME,
+        "version": &*state.version,
+    }))
+}
+
+#[tauri::command]
+pub fn get_server_status(info: TauriState<'_, ServerInfo>) -> ServerStatus {
+    info.status()
 }
 
 async fn handle_oauth(
-    State(app): State<ServerState>,
+    State(state): State<ServerState>,
     Query(params): Query<OAuthQuery>,
 ) -> impl IntoResponse {
-    let _ = app.emit("oauth-code-received", params.code);
+    (state.events)("oauth-code-received", params.code);
 
     Html(
         r#"
@@ -299,3 +966,329 @@ async fn handle_oauth(
     "#,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn test_state(pairing: Arc<PairingTokens>) -> ServerState {
+        ServerState {
+            pool: Arc::new(|| None),
+            key: Arc::new(|| None),
+            events: Arc::new(|_, _| {}),
+            data_dir: std::env::temp_dir().into(),
+            version: "0.0.0".into(),
+            pairing,
+        }
+    }
+
+    fn test_router(pairing: Arc<PairingTokens>) -> Router {
+        router(test_state(pairing), PeerState::new(None, || None))
+    }
+
+    async fn status_of(router: &Router, request: axum::http::Request<Body>) -> StatusCode {
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    fn upload(token: Option<&str>, statement_id: &str) -> axum::http::Request<Body> {
+        let mut request =
+            axum::http::Request::post(format!("/upload?statement_id={statement_id}"));
+        if let Some(token) = token {
+            request = request.header(PAIRING_TOKEN_HEADER, token);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    fn scan(token: Option<&str>, statement_id: &str) -> axum::http::Request<Body> {
+        let mut uri = format!("/scan?statement_id={statement_id}");
+        if let Some(token) = token {
+            uri.push_str(&format!("&token={token}"));
+        }
+        axum::http::Request::get(uri).body(Body::empty()).unwrap()
+    }
+
+    fn statement_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    #[tokio::test]
+    async fn refuses_requests_without_a_token() {
+        let pairing = Arc::new(PairingTokens::default());
+        let statement = statement_id();
+        pairing.issue(&statement, Duration::from_secs(60));
+        let router = test_router(pairing);
+
+        assert_eq!(status_of(&router, upload(None, &statement)).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status_of(&router, scan(None, &statement)).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            status_of(&router, upload(Some("not-the-token"), &statement)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_expired_tokens() {
+        let pairing = Arc::new(PairingTokens::default());
+        let statement = statement_id();
+        let (token, _) = pairing.issue(&statement, Duration::ZERO);
+        let router = test_router(pairing);
+
+        assert_eq!(
+            status_of(&router, upload(Some(&token), &statement)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_of(&router, scan(Some(&token), &statement)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn refuses_revoked_tokens() {
+        let pairing = Arc::new(PairingTokens::default());
+        let statement = statement_id();
+        let (token, _) = pairing.issue(&statement, Duration::from_secs(60));
+        let router = test_router(pairing.clone());
+
+        assert_eq!(status_of(&router, scan(Some(&token), &statement)).await, StatusCode::OK);
+
+        pairing.revoke();
+        assert_eq!(
+            status_of(&router, upload(Some(&token), &statement)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_of(&router, scan(Some(&token), &statement)).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn tokens_only_open_their_own_statement() {
+        let pairing = Arc::new(PairingTokens::default());
+        let (token, _) = pairing.issue(&statement_id(), Duration::from_secs(60));
+        let router = test_router(pairing);
+
+        let other = statement_id();
+        assert_eq!(
+            status_of(&router, upload(Some(&token), &other)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status_of(&router, scan(Some(&token), &other)).await, StatusCode::FORBIDDEN);
+    }
+
+    const BOUNDARY: &str = "sgmc-test-boundary";
+
+    /// One multipart field: name, file name, content type and data.
+    type Part<'a> = (&'a str, Option<&'a str>, Option<&'a str>, &'a [u8]);
+
+    /// A desk with one statement, serving uploads for it.
+    struct Desk {
+        data_dir: PathBuf,
+        pool: SqlitePool,
+        key: Option<Key>,
+        statement_id: String,
+        token: String,
+        events: Arc<Mutex<Vec<String>>>,
+        router: Router,
+    }
+
+    impl Desk {
+        async fn new(key: Option<Key>) -> Self {
+            let data_dir =
+                std::env::temp_dir().join(format!("sgmc-upload-{}", repository::new_id()));
+            let pool = crate::database::memory_pool().await;
+            let statement_id = repository::test_statement(&pool, 500).await;
+            let pairing = Arc::new(PairingTokens::default());
+            let (token, _) = pairing.issue(&statement_id, Duration::from_secs(60));
+            let events = Arc::new(Mutex::new(Vec::new()));
+
+            let state = ServerState {
+                pool: Arc::new({
+                    let pool = pool.clone();
+                    move || Some(pool.clone())
+                }),
+                key: Arc::new({
+                    let key = key.clone();
+                    move || key.clone()
+                }),
+                events: Arc::new({
+                    let events = events.clone();
+                    move |event, payload| {
+                        assert_eq!(event, "scan-received");
+                        events.lock().unwrap().push(payload);
+                    }
+                }),
+                data_dir: data_dir.as_path().into(),
+                ..test_state(pairing)
+            };
+
+            Self {
+                data_dir,
+                pool,
+                key,
+                statement_id,
+                token,
+                events,
+                router: router(state, PeerState::new(None, || None)),
+            }
+        }
+
+        async fn upload(&self, parts: &[Part<'_>]) -> (StatusCode, serde_json::Value) {
+            let mut body = Vec::new();
+            for (name, file_name, content_type, data) in parts {
+                body.extend_from_slice(format!("--{BOUNDARY}\r\n").as_bytes());
+                let mut disposition = format!("Content-Disposition: form-data; name=\"{name}\"");
+                if let Some(file_name) = file_name {
+                    disposition.push_str(&format!("; filename=\"{file_name}\""));
+                }
+                body.extend_from_slice(format!("{disposition}\r\n").as_bytes());
+                if let Some(content_type) = content_type {
+                    body.extend_from_slice(format!("Content-Type: {content_type}\r\n").as_bytes());
+                }
+                body.extend_from_slice(b"\r\n");
+                body.extend_from_slice(data);
+                body.extend_from_slice(b"\r\n");
+            }
+            body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+
+            let request = axum::http::Request::post(format!(
+                "/upload?statement_id={}",
+                self.statement_id
+            ))
+            .header(PAIRING_TOKEN_HEADER, &self.token)
+            .header(
+                "content-type",
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )
+            .body(Body::from(body))
+            .unwrap();
+
+            let response = self.router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json = serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(&bytes).into()));
+            (status, json)
+        }
+
+        async fn attachments(&self) -> Vec<Attachment> {
+            attachments::list_for_statement(&self.pool, &self.statement_id)
+                .await
+                .unwrap()
+        }
+
+        fn stored_files(&self) -> usize {
+            std::fs::read_dir(attachments::attachments_dir(&self.data_dir))
+                .map(|entries| entries.count())
+                .unwrap_or_default()
+        }
+    }
+
+    impl Drop for Desk {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.data_dir);
+        }
+    }
+
+    const PHOTO: &[u8] = b"\xff\xd8\xff\xe0 not much of a photo";
+    const PDF: &[u8] = b"%PDF-1.7 a one page scan";
+
+    #[tokio::test]
+    async fn reports_each_file_of_an_upload() {
+        let desk = Desk::new(None).await;
+
+        let (status, body) = desk
+            .upload(&[
+                ("file", Some("photo.jpg"), Some("image/jpeg"), PHOTO),
+                ("note", None, None, b"not a file"),
+                ("file", Some("notes.txt"), Some("text/plain"), b"plain text"),
+                ("file", Some("referral.pdf"), None, PDF),
+            ])
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["fileName"], "photo.jpg");
+        assert!(results[0]["attachmentId"].is_string());
+        assert!(results[0]["error"].is_null());
+        assert_eq!(results[1]["fileName"], "notes.txt");
+        assert!(results[1]["attachmentId"].is_null());
+        assert!(results[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("got text/plain"));
+        assert_eq!(results[2]["fileName"], "referral.pdf");
+        assert!(results[2]["attachmentId"].is_string());
+
+        let saved = desk.attachments().await;
+        assert_eq!(saved.len(), 2);
+        let pdf = saved
+            .iter()
+            .find(|attachment| attachment.file_name == "referral.pdf")
+            .unwrap();
+        // Typed from the file name when the phone sends none
+        assert_eq!(pdf.file_type, "application/pdf");
+        assert_eq!(attachments::read(pdf, None).await.unwrap(), PDF);
+
+        // The refused file leaves nothing behind
+        assert_eq!(desk.stored_files(), 2);
+        assert_eq!(desk.events.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn refuses_uploads_of_unsupported_types() {
+        let desk = Desk::new(None).await;
+
+        let (status, body) = desk
+            .upload(&[
+                ("file", Some("archive.zip"), Some("application/zip"), b"PK"),
+                ("file", Some("page.html"), None, b"<html>"),
+            ])
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        for result in body["results"].as_array().unwrap() {
+            assert!(result["attachmentId"].is_null());
+            assert!(result["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("Only images and PDFs"));
+        }
+
+        assert!(desk.attachments().await.is_empty());
+        assert_eq!(desk.stored_files(), 0);
+        assert!(desk.events.lock().unwrap().is_empty());
+
+        let (status, _) = desk.upload(&[("note", None, None, b"no files")]).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn records_the_plaintext_size_of_sealed_uploads() {
+        let (_, key) = crate::encryption::KeyFile::create("correct horse battery")
+            .await
+            .unwrap();
+        let desk = Desk::new(Some(key)).await;
+
+        let (status, _) = desk
+            .upload(&[("file", Some("photo.jpg"), Some("image/jpeg"), PHOTO)])
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let saved = desk.attachments().await;
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].file_size, PHOTO.len() as i64);
+
+        let sealed = std::fs::read(&saved[0].file_path).unwrap();
+        assert!(sealed.len() > PHOTO.len());
+        assert_eq!(attachments::read(&saved[0], desk.key.as_ref()).await.unwrap(), PHOTO);
+    }
+}
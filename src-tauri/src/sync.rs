@@ -0,0 +1,231 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::{
+    net::TcpStream,
+    sync::{Mutex as AsyncMutex, MutexGuard},
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    app_state::AppState,
+    auth::Permission,
+    backup::{self, google_drive, BackupEntry, GoogleDriveStorage},
+    error::{Error, Result},
+    repository::attachments::now_millis,
+};
+
+const STATUS_FILE: &str = "sync_status.json";
+const CONNECTIVITY_PROBE: (&str, u16) = ("www.googleapis.com", 443);
+const CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    Success,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRun {
+    /// Unix time in milliseconds
+    pub started_at: i64,
+    /// Unix time in milliseconds
+    pub finished_at: i64,
+    pub outcome: SyncOutcome,
+    pub backup_id: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub auto_sync_enabled: bool,
+    pub last_run: Option<SyncRun>,
+}
+
+/// Runs the Google Drive backup on `sync_interval_minutes` while the app is open.
+///
+/// The status is kept in `DATA_DIR/sync_status.json` so the last run survives
+/// restarts. Manual backups take the same lock, so two runs never overlap,
+/// and so do restores and re-encryption, which replace the files a run reads.
+pub struct SyncScheduler {
+    path: PathBuf,
+    status: Mutex<SyncStatus>,
+    running: AsyncMutex<()>,
+    saving: AsyncMutex<()>,
+}
+
+impl SyncScheduler {
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(STATUS_FILE);
+        let status = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            status: Mutex::new(status),
+            running: AsyncMutex::new(()),
+            saving: AsyncMutex::new(()),
+        }
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Returns `None` while another backup is running.
+    pub fn try_begin(&self) -> Option<MutexGuard<'_, ()>> {
+        self.running.try_lock().ok()
+    }
+
+    async fn update(&self, f: impl FnOnce(&mut SyncStatus)) {
+        f(&mut self.status.lock().unwrap());
+
+        // Saves queue up and each writes the status as it is by then, so a
+        // slow save never overwrites a newer one
+        let _saving = self.saving.lock().await;
+        let result = match serde_json::to_vec_pretty(&self.status()) {
+            Ok(data) => tokio::fs::write(&self.path, data).await.map_err(Error::from),
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to save sync status: {}", err);
+        }
+    }
+}
+
+/// Spawns the scheduler loop; the first run happens one interval after startup.
+pub fn spawn(app: AppHandle) {
+    let minutes = app.state::<AppState>().config.sync_interval_minutes.max(1);
+    let period = Duration::from_secs(u64::from(minutes) * 60);
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            // Restores and re-encryption close the pool and relaunch the app
+            if app.state::<SqlitePool>().is_closed() {
+                log::info!("Stopping scheduled sync, the database was closed");
+                return;
+            }
+            run_scheduled(&app).await;
+        }
+    });
+
+    log::info!("Background sync scheduled every {} minutes", minutes);
+}
+
+async fn is_online() -> bool {
+    matches!(
+        time::timeout(CONNECTIVITY_TIMEOUT, TcpStream::connect(CONNECTIVITY_PROBE)).await,
+        Ok(Ok(_))
+    )
+}
+
+async fn run_scheduled(app: &AppHandle) {
+    let scheduler = app.state::<SyncScheduler>();
+    if !scheduler.status().auto_sync_enabled {
+        return;
+    }
+
+    let Some(_guard) = scheduler.try_begin() else {
+        log::info!("Skipping scheduled sync, a backup is already running");
+        return;
+    };
+
+    if !is_online().await {
+        log::info!("Skipping scheduled sync, offline");
+        return;
+    }
+
+    if let Err(Error::Unauthenticated) =
+        google_drive::access_token(app, &reqwest::Client::new()).await
+    {
+        log::info!("Skipping scheduled sync, not signed in to Google Drive");
+        return;
+    }
+
+    let _ = app.emit("sync-started", ());
+    let started_at = now_millis();
+
+    let result = sync(app).await;
+    let run = SyncRun {
+        started_at,
+        finished_at: now_millis(),
+        outcome: if result.is_ok() {
+            SyncOutcome::Success
+        } else {
+            SyncOutcome::Failed
+        },
+        backup_id: result.as_ref().ok().map(|entry| entry.id.clone()),
+        error: result.as_ref().err().map(|err| err.to_string()),
+    };
+
+    match &result {
+        Ok(_) => {
+            log::info!("Scheduled sync finished");
+            let _ = app.emit("sync-finished", run.clone());
+        }
+        Err(err) => {
+            log::error!("Scheduled sync failed: {}", err);
+            let _ = app.emit("sync-failed", run.clone());
+        }
+    }
+
+    scheduler.update(|status| status.last_run = Some(run)).await;
+}
+
+async fn sync(app: &AppHandle) -> Result<BackupEntry> {
+    let storage = GoogleDriveStorage::connect(app).await?;
+    let pool = app.state::<SqlitePool>();
+    let state = app.state::<AppState>();
+    let data_dir = PathBuf::from(&state.config.data_dir);
+    let version = app.package_info().version.to_string();
+    let local = state.encryption_key();
+    let backup_key = state.backup_key().await?;
+
+    backup::create(
+        &storage,
+        &pool,
+        &data_dir,
+        &version,
+        local.as_ref(),
+        backup_key.as_ref(),
+        |_| {},
+    )
+    .await
+}
+
+#[tauri::command]
+pub fn get_sync_status(scheduler: State<'_, SyncScheduler>) -> SyncStatus {
+    scheduler.status()
+}
+
+#[tauri::command]
+pub async fn set_auto_sync(
+    state: State<'_, AppState>,
+    scheduler: State<'_, SyncScheduler>,
+    enabled: bool,
+) -> Result<SyncStatus> {
+    state.require(Permission::ManageBackups)?;
+
+    scheduler
+        .update(|status| status.auto_sync_enabled = enabled)
+        .await;
+    Ok(scheduler.status())
+}
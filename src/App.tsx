@@ -3,6 +3,7 @@ import PatientsPage from "@/pages/patients/patients-page";
 import NewStatementPage from "@/pages/statements/new-statement-page";
 import StatementDetailsPage from "@/pages/statements/statement-details-page";
 import StatementsPage from "@/pages/statements/statements-page";
+import { useQuery } from "@tanstack/react-query";
 import { ReactQueryDevtools } from "@tanstack/react-query-devtools";
 import { info } from "@tauri-apps/plugin-log";
 import { useEffect } from "react";
@@ -15,19 +16,47 @@ import NewStatementForPatientPage from "./pages/patients/new-statement-for-patie
 import PatientDetailsPage from "./pages/patients/patient-details-page";
 import SettingsPage from "./pages/settings-page";
 import { useGlobalOnlineStatus } from "@/lib/hooks/use-global-online-status";
+import { useServerStatus } from "@/lib/hooks/use-server-status";
 import DoctorsPage from "./pages/doctors/doctors-page";
 import NewDoctorPage from "./pages/doctors/new-doctor-page";
 import ClinicsPage from "./pages/clinics/clinics-page";
 import NewClinicPage from "./pages/clinics/new-clinic-page";
+import LoginPage from "./pages/login-page";
+import UnlockPage from "./pages/unlock-page";
+import { getEncryptionStatusQueryOptions } from "@/lib/tanstack-query/encryption";
+import { getSessionQueryOptions } from "@/lib/tanstack-query/users";
+import { Spinner } from "./components/ui/spinner";
 
 function App() {
   useAutoSync();
   useGlobalOnlineStatus();
+  useServerStatus();
 
   useEffect(() => {
     info("App started");
   }, []);
 
+  const encryptionQuery = useQuery(getEncryptionStatusQueryOptions());
+  const databaseOpen = !!encryptionQuery.data?.databaseOpen;
+  // Everything else reads the database, which is closed until unlocked
+  const sessionQuery = useQuery({ ...getSessionQueryOptions(), enabled: databaseOpen });
+
+  if (encryptionQuery.data?.enabled && !databaseOpen) {
+    return <UnlockPage />;
+  }
+
+  if (!sessionQuery.data) {
+    return (
+      <div className="min-h-screen flex items-center justify-center">
+        <Spinner />
+      </div>
+    );
+  }
+
+  if (!sessionQuery.data.user) {
+    return <LoginPage needsSetup={sessionQuery.data.needsSetup} />;
+  }
+
   return (
     <>
       <SyncOverlay />
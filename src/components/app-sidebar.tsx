@@ -20,6 +20,7 @@ import { useTranslation } from "react-i18next";
 import { Link } from "react-router-dom";
 import { BackButton } from "./back-button";
 import { LanguageToggle } from "./language-toggle";
+import { UserMenu } from "./user-menu";
 
 export function AppSidebar()
 {
@@ -109,6 +110,7 @@ export function AppSidebar()
         </SidebarGroup>
       </SidebarContent>
       <SidebarFooter className="p-4 flex flex-col gap-4">
+        <UserMenu />
         <div className="flex items-center justify-between w-full">
           <div className="flex items-center gap-2">
             <div className={cn("p-1.5 rounded-md transition-colors", isOnline ? "text-green-500 bg-green-500/10" : "text-destructive bg-destructive/10")}>
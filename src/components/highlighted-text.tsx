@@ -0,0 +1,25 @@
+const MATCH = /\u0002([^\u0003]*)\u0003/;
+
+type HighlightedTextProps = {
+  text: string;
+  className?: string;
+};
+
+// Renders the match markers from the Rust search command without parsing markup
+export const HighlightedText = ({ text, className }: HighlightedTextProps) => {
+  const parts = text.split(MATCH);
+
+  return (
+    <span className={className}>
+      {parts.map((part, index) =>
+        index % 2 === 1 ? (
+          <mark key={index} className="rounded-sm bg-yellow-200 px-0.5 dark:bg-yellow-700">
+            {part}
+          </mark>
+        ) : (
+          part
+        ),
+      )}
+    </span>
+  );
+};
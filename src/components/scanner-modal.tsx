@@ -7,11 +7,10 @@ import
   DialogTitle,
 } from "@/components/ui/dialog";
 import { Spinner } from "@/components/ui/spinner";
-import { getAppConfig } from "@/lib/config/app";
+import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { error } from "@tauri-apps/plugin-log";
 import { Upload } from "lucide-react";
-import { QRCodeSVG } from "qrcode.react";
 import { useEffect, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
 
@@ -19,31 +18,57 @@ interface ScannerModalProps
 {
   isOpen: boolean;
   onOpenChange: (open: boolean) => void;
+  statementId: string;
+  // Phone uploads are saved by the scanner server, so only the new attachment id arrives here
+  onAttachmentReceived: (attachmentId: string) => void;
   onScanReceived: (data: { mime: string; data: string }) => void;
 }
 
-export function ScannerModal({ isOpen, onOpenChange, onScanReceived }: ScannerModalProps)
+export function ScannerModal({ isOpen, onOpenChange, statementId, onAttachmentReceived, onScanReceived }: ScannerModalProps)
 {
   const { t } = useTranslation();
   const [url, setUrl] = useState<string | null>(null);
+  const [qrImage, setQrImage] = useState<string | null>(null);
+  // Android phones cannot open `.local` names, the same link by IP address works there
+  const [fallback, setFallback] = useState<{ url: string; image: string } | null>(null);
+  const [useFallback, setUseFallback] = useState(false);
   const [errorMessage, setErrorMessage] = useState<string | null>(null);
+  // A phone can send several pages, so the dialog stays open and counts them
+  const [receivedCount, setReceivedCount] = useState(0);
   const fileInputRef = useRef<HTMLInputElement>(null);
 
+  // Kept in a ref so a re-render of the parent does not reissue the pairing token
+  const onAttachmentReceivedRef = useRef(onAttachmentReceived);
+  onAttachmentReceivedRef.current = onAttachmentReceived;
+
   useEffect(() =>
   {
     let unlisten: (() => void) | undefined;
+    setReceivedCount(0);
+    setUseFallback(false);
 
     const setup = async () =>
     {
       try
       {
-        const config = await getAppConfig();
-        setUrl(`http://${config.ip_address}:${config.port}/scan`);
+        const pairing = await invoke<{
+          scanUrl: string;
+          image: string;
+          fallbackUrl: string | null;
+          fallbackImage: string | null;
+        }>("get_scan_qr", { statementId });
+        setUrl(pairing.scanUrl);
+        setQrImage(pairing.image);
+        setFallback(
+          pairing.fallbackUrl && pairing.fallbackImage
+            ? { url: pairing.fallbackUrl, image: pairing.fallbackImage }
+            : null,
+        );
 
-        unlisten = await listen<{ mime: string; data: string }>("scan-received", (event) =>
+        unlisten = await listen<string>("scan-received", (event) =>
         {
-          onScanReceived(event.payload);
-          onOpenChange(false);
+          onAttachmentReceivedRef.current(event.payload);
+          setReceivedCount((count) => count + 1);
         });
       } catch (e: any)
       {
@@ -60,8 +85,9 @@ export function ScannerModal({ isOpen, onOpenChange, onScanReceived }: ScannerMo
     return () =>
     {
       if (unlisten) unlisten();
+      if (isOpen) invoke("revoke_pairing_token").catch(error);
     };
-  }, [isOpen, onOpenChange, onScanReceived]);
+  }, [isOpen, onOpenChange, statementId]);
 
   const handleManualUpload = (e: React.ChangeEvent<HTMLInputElement>) =>
   {
@@ -88,6 +114,8 @@ export function ScannerModal({ isOpen, onOpenChange, onScanReceived }: ScannerMo
     }
   };
 
+  const shown = useFallback && fallback ? fallback : url && qrImage ? { url, image: qrImage } : null;
+
   return (
     <Dialog open={isOpen} onOpenChange={onOpenChange}>
       <DialogContent className="sm:max-w-md">
@@ -97,14 +125,26 @@ export function ScannerModal({ isOpen, onOpenChange, onScanReceived }: ScannerMo
         <div className="flex flex-col items-center justify-center py-6 space-y-6">
           {errorMessage ? (
             <p className="text-destructive">{errorMessage}</p>
-          ) : url ? (
+          ) : shown ? (
             <>
               <div className="bg-white p-4 rounded-lg shadow-sm">
-                <QRCodeSVG value={url} size={200} />
+                <img src={shown.image} alt={shown.url} width={200} height={200} />
               </div>
               <p className="text-sm text-muted-foreground text-center px-4">
                 {t("scanner.instruction", "Scan this QR code with your phone to upload a photo directly from your camera.")}
               </p>
+              {fallback && (
+                <Button variant="link" size="sm" onClick={() => setUseFallback(!useFallback)}>
+                  {useFallback
+                    ? t("scanner.use_network_name", "Use the network name")
+                    : t("scanner.use_ip_address", "Phone cannot open the link? Use the IP address")}
+                </Button>
+              )}
+              {receivedCount > 0 && (
+                <p className="text-sm font-medium text-center">
+                  {t("scanner.received", "{{count}} file(s) received", { count: receivedCount })}
+                </p>
+              )}
 
               <div className="relative w-full flex items-center justify-center">
                 <div className="absolute inset-0 flex items-center">
@@ -130,7 +170,7 @@ export function ScannerModal({ isOpen, onOpenChange, onScanReceived }: ScannerMo
               </Button>
 
               <p className="text-xs text-muted-foreground font-mono bg-muted p-1 rounded">
-                {url}
+                {shown.url}
               </p>
             </>
           ) : (
@@ -141,8 +181,8 @@ export function ScannerModal({ isOpen, onOpenChange, onScanReceived }: ScannerMo
           )}
         </div>
         <div className="flex justify-end">
-          <Button variant="outline" onClick={() => onOpenChange(false)}>
-            {t("common.cancel")}
+          <Button variant={receivedCount > 0 ? "default" : "outline"} onClick={() => onOpenChange(false)}>
+            {receivedCount > 0 ? t("scanner.done", "Done") : t("common.cancel")}
           </Button>
         </div>
       </DialogContent>
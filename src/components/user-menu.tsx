@@ -0,0 +1,195 @@
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import {
+  DropdownMenu,
+  DropdownMenuContent,
+  DropdownMenuItem,
+  DropdownMenuTrigger,
+} from "@/components/ui/dropdown-menu";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Spinner } from "@/components/ui/spinner";
+import {
+  changePasswordMutationOptions,
+  getReminderLeadTimesQueryOptions,
+  getSessionQueryOptions,
+  logoutMutationOptions,
+  setReminderLeadTimesMutationOptions,
+} from "@/lib/tanstack-query/users";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import { BellRing, KeyRound, LogOut, UserRound } from "lucide-react";
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+export function UserMenu() {
+  const { t } = useTranslation();
+  const queryClient = useQueryClient();
+  const { data: session } = useQuery(getSessionQueryOptions());
+  const [isChangingPassword, setIsChangingPassword] = useState(false);
+  const [currentPassword, setCurrentPassword] = useState("");
+  const [newPassword, setNewPassword] = useState("");
+  const [isEditingReminders, setIsEditingReminders] = useState(false);
+  const [leadMinutes, setLeadMinutes] = useState("");
+
+  const { data: leadTimes } = useQuery({
+    ...getReminderLeadTimesQueryOptions(),
+    enabled: isEditingReminders,
+  });
+
+  useEffect(() => {
+    // An empty list turns reminders off, which an empty field cannot express
+    const minutes = leadTimes?.leadMinutes;
+    setLeadMinutes(minutes?.length === 0 ? "0" : (minutes?.join(", ") ?? ""));
+  }, [leadTimes, isEditingReminders]);
+
+  const logoutMutation = useMutation({
+    ...logoutMutationOptions(),
+    // Nothing the previous user loaded should show for the next one
+    onSuccess: () => queryClient.removeQueries({ predicate: (query) => query.queryKey[0] !== "session" }),
+  });
+
+  const changePasswordMutation = useMutation({
+    ...changePasswordMutationOptions(),
+    onSuccess: () => closePasswordDialog(),
+  });
+
+  const setLeadTimesMutation = useMutation({
+    ...setReminderLeadTimesMutationOptions(),
+    onSuccess: () => setIsEditingReminders(false),
+  });
+
+  // Empty follows the clinic setting
+  const parseLeadMinutes = () =>
+    leadMinutes.trim()
+      ? leadMinutes.split(",").filter((part) => part.trim()).map(Number).filter((minutes) => minutes !== 0)
+      : null;
+
+  const closePasswordDialog = () => {
+    setIsChangingPassword(false);
+    setCurrentPassword("");
+    setNewPassword("");
+  };
+
+  const user = session?.user;
+  if (!user) return null;
+
+  return (
+    <>
+      <DropdownMenu>
+        <DropdownMenuTrigger asChild>
+          <Button variant="ghost" className="w-full justify-start gap-2 h-auto py-2">
+            <UserRound className="h-4 w-4 shrink-0" />
+            <div className="flex flex-col items-start min-w-0">
+              <span className="text-sm font-medium truncate">{user.displayName}</span>
+              <span className="text-xs text-muted-foreground">{t(`users.roles.${user.role}`)}</span>
+            </div>
+          </Button>
+        </DropdownMenuTrigger>
+        <DropdownMenuContent align="start">
+          <DropdownMenuItem onClick={() => setIsChangingPassword(true)}>
+            <KeyRound className="h-4 w-4" />
+            {t("auth.change_password")}
+          </DropdownMenuItem>
+          <DropdownMenuItem onClick={() => setIsEditingReminders(true)}>
+            <BellRing className="h-4 w-4" />
+            {t("auth.reminder_times")}
+          </DropdownMenuItem>
+          <DropdownMenuItem onClick={() => logoutMutation.mutate()}>
+            <LogOut className="h-4 w-4" />
+            {t("auth.sign_out")}
+          </DropdownMenuItem>
+        </DropdownMenuContent>
+      </DropdownMenu>
+
+      <Dialog open={isChangingPassword} onOpenChange={(open) => !open && closePasswordDialog()}>
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>{t("auth.change_password")}</DialogTitle>
+            <DialogDescription>{user.username}</DialogDescription>
+          </DialogHeader>
+          <div className="space-y-4">
+            <div className="space-y-2">
+              <Label htmlFor="current-password">{t("auth.current_password")}</Label>
+              <Input
+                id="current-password"
+                type="password"
+                autoComplete="current-password"
+                value={currentPassword}
+                onChange={(e) => setCurrentPassword(e.target.value)}
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="new-password">{t("auth.new_password")}</Label>
+              <Input
+                id="new-password"
+                type="password"
+                autoComplete="new-password"
+                value={newPassword}
+                onChange={(e) => setNewPassword(e.target.value)}
+              />
+            </div>
+            {changePasswordMutation.isError && (
+              <p className="text-sm text-destructive">{changePasswordMutation.error.message}</p>
+            )}
+          </div>
+          <DialogFooter>
+            <Button variant="outline" onClick={closePasswordDialog}>
+              {t("common.cancel")}
+            </Button>
+            <Button
+              disabled={!currentPassword || !newPassword || changePasswordMutation.isPending}
+              onClick={() => changePasswordMutation.mutate({ currentPassword, newPassword })}
+            >
+              {changePasswordMutation.isPending && <Spinner />}
+              {t("common.save")}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+
+      <Dialog open={isEditingReminders} onOpenChange={setIsEditingReminders}>
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>{t("auth.reminder_times")}</DialogTitle>
+            <DialogDescription>
+              {t("auth.reminder_times_description", {
+                minutes: leadTimes?.defaultLeadMinutes.join(", ") || "0",
+              })}
+            </DialogDescription>
+          </DialogHeader>
+          <div className="space-y-2">
+            <Label htmlFor="reminder-lead-minutes">{t("auth.reminder_times")}</Label>
+            <Input
+              id="reminder-lead-minutes"
+              value={leadMinutes}
+              placeholder={leadTimes?.defaultLeadMinutes.join(", ")}
+              onChange={(e) => setLeadMinutes(e.target.value)}
+            />
+            {setLeadTimesMutation.isError && (
+              <p className="text-sm text-destructive">{setLeadTimesMutation.error.message}</p>
+            )}
+          </div>
+          <DialogFooter>
+            <Button variant="outline" onClick={() => setIsEditingReminders(false)}>
+              {t("common.cancel")}
+            </Button>
+            <Button
+              disabled={!leadTimes || setLeadTimesMutation.isPending}
+              onClick={() => setLeadTimesMutation.mutate(parseLeadMinutes())}
+            >
+              {setLeadTimesMutation.isPending && <Spinner />}
+              {t("common.save")}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+    </>
+  );
+}
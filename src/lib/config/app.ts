@@ -1,14 +1,24 @@
 import { invoke } from "@tauri-apps/api/core";
 
 
-interface AppConfig
+export interface AppConfig
 {
     ip_address: string;
+    bind_address: string;
     port: number;
+    port_range: number;
     data_dir: string;
     db_url: string;
     sync_interval_minutes: number;
-
+    pairing_ttl_minutes: number;
+    reminder_lead_minutes: number[];
+    overdue_reminder_days: number;
+    recycle_bin_retention_days: number;
+    archive_keep_daily: number;
+    archive_keep_weekly: number;
+    wal_archive_interval_minutes: number;
+    wal_retention_days: number;
+    mdns_hostname: string;
 }
 
 
@@ -21,4 +31,41 @@ export async function getAppConfig(): Promise<AppConfig>
         config = await invoke<AppConfig>("get_app_config");
     }
     return config;
-}
\ No newline at end of file
+}
+
+export interface Settings
+{
+    data_dir: string | null;
+    port: number | null;
+    port_range: number | null;
+    bind_address: string | null;
+    sync_interval_minutes: number | null;
+    pairing_ttl_minutes: number | null;
+    reminder_lead_minutes: number[] | null;
+    overdue_reminder_days: number | null;
+    recycle_bin_retention_days: number | null;
+    archive_keep_daily: number | null;
+    archive_keep_weekly: number | null;
+    wal_archive_interval_minutes: number | null;
+    wal_retention_days: number | null;
+    peer_sync_secret: string | null;
+    mdns_hostname: string | null;
+}
+
+export interface SettingsInfo
+{
+    settings: Settings;
+    path: string;
+    errors: string[];
+    restart_required: boolean;
+}
+
+export async function getSettings(): Promise<SettingsInfo>
+{
+    return await invoke<SettingsInfo>("get_settings");
+}
+
+export async function saveSettings(settings: Settings): Promise<SettingsInfo>
+{
+    return await invoke<SettingsInfo>("set_app_config", { settings });
+}
@@ -1,13 +1,9 @@
 import { getAppConfig } from "@/lib/config/app";
-import { getVersion } from "@tauri-apps/api/app";
 import { listen } from "@tauri-apps/api/event";
-import { join, tempDir } from "@tauri-apps/api/path";
-import { exists, mkdir, readDir, readFile, remove, writeFile } from "@tauri-apps/plugin-fs";
 import { fetch } from "@tauri-apps/plugin-http";
 import { openUrl } from "@tauri-apps/plugin-opener";
 import { Store } from "@tauri-apps/plugin-store";
 import { env } from "./config/env";
-import { getDb } from "./database";
 
 const CLIENT_ID = env.VITE_GOOGLE_CLIENT_ID;
 const CLIENT_SECRET = env.VITE_GOOGLE_CLIENT_SECRET;
@@ -16,7 +12,6 @@ const SCOPES = "https://www.googleapis.com/auth/drive.file";
 const TOKEN_ENDPOINT = "https://oauth2.googleapis.com/token";
 const AUTH_ENDPOINT = "https://accounts.google.com/o/oauth2/v2/auth";
 const DRIVE_API_URL = "https://www.googleapis.com/drive/v3/files";
-const UPLOAD_API_URL = "https://www.googleapis.com/upload/drive/v3/files";
 const STORE_PATH = "auth_store.bin";
 
 export interface BackupFile
@@ -27,6 +22,8 @@ export interface BackupFile
   properties?: {
     appVersion?: string;
     patientCount?: string;
+    /** "true" when sealed with the backup passphrase */
+    encrypted?: string;
   };
 }
 
@@ -59,21 +56,6 @@ function base64UrlEncode(array: Uint8Array)
     .replace(/=+$/, "");
 }
 
-// Compression Helpers
-async function compressData(data: Uint8Array): Promise<Uint8Array>
-{
-  // @ts-ignore - CompressionStream might not be in all TS libs yet
-  const stream = new Blob([data]).stream().pipeThrough(new CompressionStream("gzip"));
-  return new Uint8Array(await new Response(stream).arrayBuffer());
-}
-
-async function decompressData(data: Uint8Array): Promise<Uint8Array>
-{
-  // @ts-ignore - DecompressionStream might not be in all TS libs yet
-  const stream = new Blob([data]).stream().pipeThrough(new DecompressionStream("gzip"));
-  return new Uint8Array(await new Response(stream).arrayBuffer());
-}
-
 // Get Redirect URL
 async function getRedirectUrl()
 {
@@ -296,233 +278,6 @@ class GoogleDriveClient
     return folder.id;
   }
 
-  // List all files in a specific Drive folder to perform diff
-  private async listRemoteFiles(folderId: string): Promise<Map<string, string>>
-  {
-    const token = await this.getAccessToken();
-    const files = new Map<string, string>(); // Filename -> ID
-    let pageToken: string | undefined = undefined;
-
-    do
-    {
-      const params: Record<string, string> = {
-        q: `'${folderId}' in parents and trashed = false`,
-        fields: "nextPageToken, files(id, name)",
-        pageSize: "1000", // Fetch max per page for speed
-      };
-      if (pageToken) params.pageToken = pageToken;
-
-      const response = await fetch(`${DRIVE_API_URL}?${new URLSearchParams(params)}`, {
-        headers: { Authorization: `Bearer ${token}` },
-      });
-      const data = await response.json();
-
-      if (data.files)
-      {
-        for (const file of data.files)
-        {
-          files.set(file.name, file.id);
-        }
-      }
-      pageToken = data.nextPageToken;
-    } while (pageToken);
-
-    return files;
-  }
-
-  // Upload a single file to Drive
-  private async uploadFile(name: string, data: Uint8Array, parentId: string, mimeType: string = "application/octet-stream"): Promise<void>
-  {
-    const token = await this.getAccessToken();
-    const metadata = {
-      name,
-      parents: [parentId],
-    };
-
-    const formData = new FormData();
-    formData.append("metadata", new Blob([JSON.stringify(metadata)], { type: "application/json" }));
-    // @ts-ignore
-    formData.append("file", new Blob([data], { type: mimeType }));
-
-    const response = await fetch(`${UPLOAD_API_URL}?uploadType=multipart`, {
-      method: "POST",
-      headers: { Authorization: `Bearer ${token}` },
-      body: formData,
-    });
-
-    if (!response.ok)
-    {
-      console.warn(`Failed to upload ${name}: ${await response.text()}`);
-    }
-  }
-
-  async syncAttachments(rootFolderId: string): Promise<void>
-  {
-    try
-    {
-      // 1. Get/Create "attachments" folder inside "SGMC Backups"
-      const attachmentsFolderId = await this.getOrCreateFolder("attachments", rootFolderId);
-
-      // 2. List Remote Files
-      const remoteFiles = await this.listRemoteFiles(attachmentsFolderId);
-
-      // 3. List Local Files
-      const config = await getAppConfig();
-      const attachmentsDir = `${config.data_dir}/attachments`;
-      
-      if (!(await exists(attachmentsDir))) return;
-
-      const localEntries = await readDir(attachmentsDir);
-      const localFiles = localEntries.filter(e => e.isFile); // Only files
-
-      // 4. Diff & Upload
-      const uploads: Promise<void>[] = [];
-      
-      for (const file of localFiles)
-      {
-        if (!remoteFiles.has(file.name))
-        {
-          // New file! Upload it.
-          const uploadTask = async () => {
-             const filePath = await join(attachmentsDir, file.name);
-             const data = await readFile(filePath);
-             await this.uploadFile(file.name, data, attachmentsFolderId);
-          };
-          uploads.push(uploadTask());
-        }
-      }
-
-      // 5. Execute concurrent uploads (simple batching)
-      // For now, allow 3 parallel uploads to avoid choking network/drive API
-      const BATCH_SIZE = 3;
-      for (let i = 0; i < uploads.length; i += BATCH_SIZE) {
-          await Promise.all(uploads.slice(i, i + BATCH_SIZE));
-      }
-
-    } catch (e)
-    {
-      console.error("Attachment sync failed:", e);
-      // We don't fail the whole backup if attachments fail, just log it
-    }
-  }
-
-  async restoreAttachments(rootFolderId: string): Promise<void>
-  {
-    try 
-    {
-       const config = await getAppConfig();
-       const attachmentsDir = `${config.data_dir}/attachments`;
-       if (!(await exists(attachmentsDir))) {
-          await mkdir(attachmentsDir, { recursive: true });
-       }
-
-       // 1. Find remote attachments folder
-       const attachmentsFolderId = await this.getOrCreateFolder("attachments", rootFolderId);
-       
-       // 2. List all remote attachments
-       const remoteFiles = await this.listRemoteFiles(attachmentsFolderId);
-
-       // 3. Download missing files
-       const downloads: Promise<void>[] = [];
-       for (const [name, id] of remoteFiles.entries()) {
-          const localPath = await join(attachmentsDir, name);
-          if (!(await exists(localPath))) {
-             const task = async () => {
-                const data = await this.downloadFile(id); // Use separate download method
-                await writeFile(localPath, data);
-             };
-             downloads.push(task());
-          }
-       }
-
-       // 4. Execute concurrent downloads
-       const BATCH_SIZE = 5;
-       for (let i = 0; i < downloads.length; i += BATCH_SIZE) {
-          await Promise.all(downloads.slice(i, i + BATCH_SIZE));
-       }
-
-    } catch (e) {
-       console.error("Attachment restore failed:", e);
-    }
-  }
-
-  // Helper for generic file download (reused by backup download)
-  async downloadFile(fileId: string): Promise<Uint8Array> {
-      const token = await this.getAccessToken();
-      const response = await fetch(`${DRIVE_API_URL}/${fileId}?alt=media`, {
-        headers: { Authorization: `Bearer ${token}` },
-      });
-      if (!response.ok) throw new Error("Download failed");
-      return new Uint8Array(await response.arrayBuffer());
-  }
-
-  async uploadBackup(): Promise<string>
-  {
-    const token = await this.getAccessToken();
-    const folderId = await this.getOrCreateFolder("SGMC Backups");
-
-    // 1. Trigger Attachment Sync (Incremental)
-    // We await this so the backup is consistent, but UI might want to show separate progress?
-    // For now, part of the same Promise.
-    await this.syncAttachments(folderId);
-
-    const db = await getDb();
-    const tempName = `sgmc_backup_${new Date().toISOString().replace(/[:.]/g, "-")}.db.gz`;
-
-    // Fetch Metadata
-    const version = await getVersion();
-    const patientCountResult = await db.select<any[]>("SELECT COUNT(*) as count FROM patients");
-    const patientCount = patientCountResult?.[0]?.count?.toString() || "0";
-
-    // Get SYSTEM temp dir
-    const tempDirectory = await tempDir();
-    const uniqueId = crypto.randomUUID().replace(/-/g, "");
-    const tempDbPath = await join(tempDirectory, `temp_snapshot_${uniqueId}.db`);
-
-    // Create snapshot using VACUUM INTO
-    // Ensure path is absolute and correctly formatted for SQLite
-    await db.execute(`VACUUM INTO '${tempDbPath}'`);
-
-    try
-    {
-      const fileData = await readFile(tempDbPath);
-      // Compress the data
-      const compressedData = await compressData(fileData);
-
-      const metadata = {
-        name: tempName,
-        parents: [folderId],
-        properties: {
-          appVersion: version,
-          patientCount: patientCount,
-        }
-      };
-
-      const formData = new FormData();
-      formData.append("metadata", new Blob([JSON.stringify(metadata)], { type: "application/json" }));
-      // @ts-ignore - FormData.append might not be in all TS libs yet
-      formData.append("file", new Blob([compressedData], { type: "application/octet-stream" }));
-
-      const uploadResponse = await fetch(`${UPLOAD_API_URL}?uploadType=multipart`, {
-        method: "POST",
-        headers: { Authorization: `Bearer ${token}` },
-        body: formData,
-      });
-
-      if (!uploadResponse.ok)
-      {
-        throw new Error("Drive upload failed");
-      }
-
-      const data = await uploadResponse.json();
-      return data.id;
-    } finally
-    {
-      // Clean up temp file
-      await remove(tempDbPath);
-    }
-  }
-
   async listBackups(pageToken?: string, pageSize: number = 10): Promise<{ files: BackupFile[], nextPageToken?: string }>
   {
     const token = await this.getAccessToken();
@@ -555,36 +310,6 @@ class GoogleDriveClient
         nextPageToken: data.nextPageToken 
     };
   }
-
-  async downloadBackup(fileId: string): Promise<Uint8Array>
-  {
-    const token = await this.getAccessToken();
-
-    // Trigger restore of attachments alongside DB
-    // We need the root folder ID first.
-    // Optimization: We could store root ID, but fetching it is safe.
-    try {
-       const rootFolderId = await this.getOrCreateFolder("SGMC Backups");
-       await this.restoreAttachments(rootFolderId);
-    } catch (e) {
-       console.warn("Auto-restore of attachments failed, proceeding with DB only", e);
-    }
-
-    // First get metadata to check filename for .gz extension
-    const metaResponse = await fetch(`${DRIVE_API_URL}/${fileId}?fields=name`, {
-      headers: { Authorization: `Bearer ${token}` },
-    });
-    const meta = await metaResponse.json();
-    const isCompressed = meta.name?.endsWith(".gz");
-
-    const data = await this.downloadFile(fileId);
-
-    if (isCompressed)
-    {
-      return await decompressData(data);
-    }
-    return data;
-  }
 }
 
 export const googleDrive = new GoogleDriveClient();
\ No newline at end of file
@@ -1,27 +1,27 @@
 import { getAppConfig } from "@/lib/config/app";
-import { googleDrive } from "@/lib/google-drive";
 import { useSyncStore } from "@/lib/sync-store";
-import { uploadBackupMutationOptions } from "@/lib/tanstack-query/drive";
-import { useMutation } from "@tanstack/react-query";
+import { getBackupsQueryKey } from "@/lib/tanstack-query/drive";
+import { useQueryClient } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { error } from "@tauri-apps/plugin-log";
-import { useEffect, useRef, useState } from "react";
+import { useEffect, useState } from "react";
+
+interface SyncRun
+{
+  startedAt: number;
+  finishedAt: number;
+  outcome: "success" | "failed";
+  backupId: string | null;
+  error: string | null;
+}
 
 export function useAutoSync()
 {
-  const { isAutoSyncEnabled, setIsAutoSyncing, setLastSyncTime } = useSyncStore();
-  const intervalRef = useRef<NodeJS.Timeout | null>(null);
+  const { isAutoSyncEnabled, setIsAutoSyncing, setLastSyncTime, setLastSyncedFileId } = useSyncStore();
+  const queryClient = useQueryClient();
   const [syncIntervalMinutes, setSyncIntervalMinutes] = useState(60);
 
-  const uploadMutation = useMutation({
-    ...uploadBackupMutationOptions(),
-    onMutate: () => setIsAutoSyncing(true),
-    onSuccess: () =>
-    {
-      setLastSyncTime(Date.now());
-    },
-    onSettled: () => setIsAutoSyncing(false),
-  });
-
   useEffect(() =>
   {
     // Fetch runtime interval from Rust
@@ -29,50 +29,36 @@ export function useAutoSync()
       .catch((err) => error("Failed to get sync interval:", err));
   }, []);
 
-  const performAutoSync = async () =>
+  useEffect(() =>
   {
-    // 1. Check Connectivity
-    if (!navigator.onLine) return;
-
-    // 2. Check Auth (silently)
-    const isAuth = await googleDrive.isAuthenticated();
-    if (!isAuth) return;
-
-    // 3. Start Sync (Non-blocking)
-    uploadMutation.mutate();
-  };
+    // The scheduler runs in Rust so it keeps going while the window is hidden
+    invoke("set_auto_sync", { enabled: isAutoSyncEnabled })
+      .catch((err) => error(`Failed to update auto sync: ${err}`));
+  }, [isAutoSyncEnabled]);
 
   useEffect(() =>
   {
-    if (isAutoSyncEnabled)
-    {
-      if (intervalRef.current)
+    const listeners: Promise<UnlistenFn>[] = [
+      listen("sync-started", () => setIsAutoSyncing(true)),
+      listen<SyncRun>("sync-finished", (event) =>
       {
-        clearInterval(intervalRef.current);
-      }
-
-      intervalRef.current = setInterval(() =>
+        setIsAutoSyncing(false);
+        setLastSyncTime(event.payload.finishedAt);
+        setLastSyncedFileId(event.payload.backupId);
+        queryClient.invalidateQueries({ queryKey: getBackupsQueryKey() });
+      }),
+      listen<SyncRun>("sync-failed", (event) =>
       {
-        performAutoSync();
-      }, syncIntervalMinutes * 60 * 1000);
-    } else
-    {
-      if (intervalRef.current)
-      {
-        clearInterval(intervalRef.current);
-        intervalRef.current = null;
-      }
-    }
+        setIsAutoSyncing(false);
+        error(`Background sync failed: ${event.payload.error}`);
+      }),
+    ];
 
     return () =>
     {
-      if (intervalRef.current)
-      {
-        clearInterval(intervalRef.current);
-      }
+      listeners.forEach((unlisten) => unlisten.then((fn) => fn()));
     };
-  }, [isAutoSyncEnabled, syncIntervalMinutes]);
+  }, [queryClient, setIsAutoSyncing, setLastSyncTime, setLastSyncedFileId]);
 
   return { syncIntervalMinutes };
 }
-
@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { error } from "@tauri-apps/plugin-log";
+import { useEffect } from "react";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+
+export type ServerStatus =
+  | { status: "starting" }
+  | { status: "listening"; port: number }
+  | { status: "already_running"; port: number }
+  | { status: "failed"; reason: string };
+
+export function useServerStatus()
+{
+  const { t } = useTranslation();
+
+  useEffect(() =>
+  {
+    const report = (status: ServerStatus) =>
+    {
+      if (status.status === "failed")
+      {
+        toast.error(t("server.failed"), { id: "server-status", description: status.reason, duration: Infinity });
+      }
+    };
+
+    // The server starts before the window, so the first status has to be fetched
+    invoke<ServerStatus>("get_server_status")
+      .then(report)
+      .catch((err) => error(`Failed to get server status: ${err}`));
+
+    const unlisten = listen<ServerStatus>("server-status", (event) => report(event.payload));
+
+    return () =>
+    {
+      unlisten.then((fn) => fn());
+    };
+  }, [t]);
+}
@@ -1,3 +1,4 @@
+import { invoke } from "@tauri-apps/api/core";
 import i18n from "i18next";
 import { initReactI18next } from "react-i18next";
 import LanguageDetector from "i18next-browser-languagedetector";
@@ -32,14 +33,21 @@ i18n
     },
   });
 
+// Desktop notifications are written by the backend in the UI language
+function syncReminderLanguage(lng: string) {
+  invoke("set_reminder_language", { language: lng.startsWith("ar") ? "ar" : "en" }).catch(() => {});
+}
+
 // Update html lang and dir attributes
 i18n.on("languageChanged", (lng) => {
   document.documentElement.lang = lng;
   document.documentElement.dir = lng === "ar" ? "rtl" : "ltr";
+  syncReminderLanguage(lng);
 });
 
 // Initialize dir on start
 document.documentElement.lang = i18n.language;
 document.documentElement.dir = i18n.language === "ar" ? "rtl" : "ltr";
+syncReminderLanguage(i18n.language);
 
 export default i18n;
@@ -1,69 +1,17 @@
-import { getAppConfig } from "@/lib/config/app";
 import { mutationOptions } from "@tanstack/react-query";
-import { exists, mkdir, remove, writeFile } from "@tauri-apps/plugin-fs";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 import i18n from "../i18n";
-import { Attachment } from "../types/attachments";
+import { AddAttachmentSchema, Attachment } from "../types/attachments";
+import { commandError } from "../utils";
 import { getStatementDetailsQueryKey } from "./statements";
 
 export function addAttachmentMutationOptions()
 {
   return mutationOptions({
-    mutationFn: async (data: {
-      statementId: string;
-      fileData: string; // Base64
-      fileName: string;
-      fileType: string;
-    }) =>
+    mutationFn: async (attachment: AddAttachmentSchema) =>
     {
-      const db = await getDb();
-      const id = uuid();
-      const createdAt = Date.now();
-
-      // 1. Get absolute data directory from Rust
-      const config = await getAppConfig();
-      const dataDir = config.data_dir;
-      const attachmentsDir = `${dataDir}/attachments`;
-
-      // 2. Ensure directory exists
-      const dirExists = await exists(attachmentsDir);
-      if (!dirExists)
-      {
-        await mkdir(attachmentsDir, { recursive: true });
-      }
-
-      // 3. Decode Base64
-      const binaryString = atob(data.fileData);
-      const bytes = new Uint8Array(binaryString.length);
-      for (let i = 0; i < binaryString.length; i++)
-      {
-        bytes[i] = binaryString.charCodeAt(i);
-      }
-
-      // 4. Save File using absolute path
-      const extension = data.fileName.split(".").pop() || "jpg";
-      const safeFileName = `${id}.${extension}`;
-      const filePath = `${attachmentsDir}/${safeFileName}`;
-
-      await writeFile(filePath, bytes);
-
-      // 5. Save to DB
-      const result = await db.execute(
-        "INSERT INTO attachments (id, statement_id, file_name, file_path, file_type, file_size, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
-        [id, data.statementId, data.fileName, filePath, data.fileType, bytes.length, createdAt]
-      );
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("common.operation_failed"));
-
-      return {
-        id,
-        statementId: data.statementId,
-        fileName: data.fileName,
-        filePath,
-        fileType: data.fileType,
-        createdAt,
-      } as Attachment;
+      // Rust writes the file so it can be encrypted at rest
+      return invoke<Attachment>("add_attachment", { attachment }).catch(commandError());
     },
     meta: {
       invalidatesQueries: [["statements"]],
@@ -72,30 +20,26 @@ export function addAttachmentMutationOptions()
   });
 }
 
+/** Url for an attachment's contents, decrypted by Rust when encryption is on */
+export function attachmentSrc(attachment: Attachment)
+{
+  return convertFileSrc(attachment.id, "attachment");
+}
+
 export function deleteAttachmentMutationOptions(statementId: string)
 {
   return mutationOptions({
     mutationFn: async (attachment: Attachment) =>
     {
-      const db = await getDb();
-
-      // 1. Delete from DB
-      await db.execute("DELETE FROM attachments WHERE id = ?", [attachment.id]);
-
-      // 2. Delete from Disk (absolute path stored in DB)
-      try
-      {
-        await remove(attachment.filePath);
-      } catch (e)
-      {
-        console.warn("Failed to delete file:", e);
-      }
+      // Rust removes the row and the file together
+      await invoke("delete_attachment", { id: attachment.id }).catch(commandError());
 
       return attachment.id;
     },
     meta: {
       invalidatesQueries: [getStatementDetailsQueryKey(statementId)],
       successMessage: i18n.t("messages.attachment_deleted", "Attachment deleted successfully"),
+      errorMessage: i18n.t("common.operation_failed"),
     },
   });
 }
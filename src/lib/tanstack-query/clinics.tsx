@@ -1,8 +1,8 @@
 import { mutationOptions, queryOptions } from "@tanstack/react-query";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
+import { invoke } from "@tauri-apps/api/core";
 import i18n from "../i18n";
 import { AddClinicSchema, Clinic, UpdateClinicSchema } from "../types/clinics";
+import { commandError } from "../utils";
 
 export function getClinicsQueryKey() {
   return ["clinics"] as const;
@@ -11,10 +11,7 @@ export function getClinicsQueryKey() {
 export function getClinicsQueryOptions() {
   return queryOptions({
     queryKey: getClinicsQueryKey(),
-    queryFn: async () => {
-      const db = await getDb();
-      return await db.select<Clinic[]>("SELECT * FROM clinics ORDER BY name ASC");
-    },
+    queryFn: () => invoke<Clinic[]>("get_clinics").catch(commandError()),
   });
 }
 
@@ -24,21 +21,15 @@ export function addClinicMutationOptions() {
       const parseResult = AddClinicSchema.safeParse(addClinic);
       if (!parseResult.success) throw new Error(i18n.t("common.invalid_data"));
 
-      const data = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
-
-      const result = await db.execute(
-        "INSERT INTO clinics (id, name, created_at, updated_at) VALUES (?, ?, datetime('now'), datetime('now'))",
-        [id, data.name]
+      const clinic = await invoke<Clinic>("add_clinic", { clinic: parseResult.data }).catch(
+        commandError(),
       );
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("common.operation_failed"));
-      return id;
+      return clinic.id;
     },
     meta: {
       invalidatesQueries: [getClinicsQueryKey()],
       successMessage: i18n.t("messages.clinic_added"),
+      errorMessage: i18n.t("common.operation_failed"),
     },
   });
 }
@@ -49,20 +40,15 @@ export function updateClinicMutationOptions() {
       const parseResult = UpdateClinicSchema.safeParse(data.updateClinic);
       if (!parseResult.success) throw new Error(i18n.t("common.invalid_data"));
 
-      const updateData = parseResult.data;
-      const db = await getDb();
-
-      const result = await db.execute(
-        "UPDATE clinics SET name = ?, updated_at = datetime('now') WHERE id = ?",
-        [updateData.name, data.id]
+      await invoke<Clinic>("update_clinic", { id: data.id, clinic: parseResult.data }).catch(
+        commandError(),
       );
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("messages.clinic_updated_failed"));
       return data.id;
     },
     meta: {
       invalidatesQueries: [getClinicsQueryKey()],
       successMessage: i18n.t("messages.clinic_updated"),
+      errorMessage: i18n.t("messages.clinic_updated_failed"),
     },
   });
 }
@@ -70,15 +56,13 @@ export function updateClinicMutationOptions() {
 export function deleteClinicMutationOptions() {
   return mutationOptions({
     mutationFn: async (id: string) => {
-      const db = await getDb();
-      const result = await db.execute("DELETE FROM clinics WHERE id = ?", [id]);
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("messages.clinic_deleted_failed"));
+      await invoke("delete_clinic", { id }).catch(commandError());
       return id;
     },
     meta: {
       invalidatesQueries: [getClinicsQueryKey()],
       successMessage: i18n.t("messages.clinic_deleted"),
+      errorMessage: i18n.t("messages.clinic_deleted_failed"),
     },
   });
 }
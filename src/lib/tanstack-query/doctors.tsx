@@ -1,8 +1,8 @@
 import { mutationOptions, queryOptions } from "@tanstack/react-query";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
+import { invoke } from "@tauri-apps/api/core";
 import i18n from "../i18n";
 import { AddDoctorSchema, Doctor, UpdateDoctorSchema } from "../types/doctors";
+import { commandError } from "../utils";
 
 export function getDoctorsQueryKey() {
   return ["doctors"] as const;
@@ -11,10 +11,7 @@ export function getDoctorsQueryKey() {
 export function getDoctorsQueryOptions() {
   return queryOptions({
     queryKey: getDoctorsQueryKey(),
-    queryFn: async () => {
-      const db = await getDb();
-      return await db.select<Doctor[]>("SELECT * FROM doctors ORDER BY name ASC");
-    },
+    queryFn: () => invoke<Doctor[]>("get_doctors").catch(commandError()),
   });
 }
 
@@ -24,21 +21,15 @@ export function addDoctorMutationOptions() {
       const parseResult = AddDoctorSchema.safeParse(addDoctor);
       if (!parseResult.success) throw new Error(i18n.t("common.invalid_data"));
 
-      const data = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
-
-      const result = await db.execute(
-        "INSERT INTO doctors (id, name, phone, created_at, updated_at) VALUES (?, ?, ?, datetime('now'), datetime('now'))",
-        [id, data.name, data.phone]
+      const doctor = await invoke<Doctor>("add_doctor", { doctor: parseResult.data }).catch(
+        commandError(),
       );
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("common.operation_failed"));
-      return id;
+      return doctor.id;
     },
     meta: {
       invalidatesQueries: [getDoctorsQueryKey()],
       successMessage: i18n.t("messages.doctor_added"),
+      errorMessage: i18n.t("common.operation_failed"),
     },
   });
 }
@@ -49,20 +40,15 @@ export function updateDoctorMutationOptions() {
       const parseResult = UpdateDoctorSchema.safeParse(data.updateDoctor);
       if (!parseResult.success) throw new Error(i18n.t("common.invalid_data"));
 
-      const updateData = parseResult.data;
-      const db = await getDb();
-
-      const result = await db.execute(
-        "UPDATE doctors SET name = ?, phone = ?, updated_at = datetime('now') WHERE id = ?",
-        [updateData.name, updateData.phone, data.id]
+      await invoke<Doctor>("update_doctor", { id: data.id, doctor: parseResult.data }).catch(
+        commandError(),
       );
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("messages.doctor_updated_failed"));
       return data.id;
     },
     meta: {
       invalidatesQueries: [getDoctorsQueryKey()],
       successMessage: i18n.t("messages.doctor_updated"),
+      errorMessage: i18n.t("messages.doctor_updated_failed"),
     },
   });
 }
@@ -70,16 +56,13 @@ export function updateDoctorMutationOptions() {
 export function deleteDoctorMutationOptions() {
   return mutationOptions({
     mutationFn: async (id: string) => {
-      const db = await getDb();
-      // On Delete Set Null is configured in migration 2 for statements.
-      const result = await db.execute("DELETE FROM doctors WHERE id = ?", [id]);
-
-      if (result.rowsAffected === 0) throw new Error(i18n.t("messages.doctor_deleted_failed"));
+      await invoke("delete_doctor", { id }).catch(commandError());
       return id;
     },
     meta: {
       invalidatesQueries: [getDoctorsQueryKey()],
       successMessage: i18n.t("messages.doctor_deleted"),
+      errorMessage: i18n.t("messages.doctor_deleted_failed"),
     },
   });
 }
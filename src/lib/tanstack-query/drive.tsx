@@ -1,11 +1,15 @@
-import { getAppConfig } from "@/lib/config/app";
 import { infiniteQueryOptions, mutationOptions } from "@tanstack/react-query";
-import { rename, writeFile } from "@tauri-apps/plugin-fs";
+import { invoke } from "@tauri-apps/api/core";
 import { relaunch } from "@tauri-apps/plugin-process";
 import { toast } from "sonner";
-import { googleDrive } from "../google-drive";
+import { BackupFile, googleDrive } from "../google-drive";
 import i18n from "../i18n";
 import { useSyncStore } from "../sync-store";
+import { BackupVerification } from "../types/backups";
+import { commandError } from "../utils";
+
+// Backups go through Rust so they are sealed with the backup passphrase
+const GOOGLE_DRIVE = { kind: "googleDrive" } as const;
 
 export function getBackupsQueryKey()
 {
@@ -31,9 +35,10 @@ export function uploadBackupMutationOptions()
   return mutationOptions({
     mutationFn: async () =>
     {
-      const fileId = await googleDrive.uploadBackup();
-      useSyncStore.getState().setLastSyncedFileId(fileId);
-      return fileId;
+      const backup = await invoke<BackupFile>("create_backup", { target: GOOGLE_DRIVE })
+        .catch(commandError());
+      useSyncStore.getState().setLastSyncedFileId(backup.id);
+      return backup.id;
     },
     onMutate: () =>
     {
@@ -51,21 +56,28 @@ export function uploadBackupMutationOptions()
   });
 }
 
-export function restoreBackupMutationOptions()
+// Checks the snapshot without touching live data, restore runs the same checks again
+export function verifyBackupMutationOptions()
 {
   return mutationOptions({
-    mutationFn: async (fileId: string) =>
+    mutationFn: async ({ fileId, passphrase }: { fileId: string; passphrase?: string }) =>
     {
-      const data = await googleDrive.downloadBackup(fileId);
-      const config = await getAppConfig();
-      const dbPath = config.db_url;
-      const tempPath = `${dbPath}.tmp`;
-
-      // 1. Write to temp file
-      await writeFile(tempPath, data);
+      return await invoke<BackupVerification>("verify_backup", { target: GOOGLE_DRIVE, backupId: fileId, passphrase })
+        .catch(commandError());
+    },
+    meta: {
+      errorMessage: i18n.t("sync.verify_failed"),
+    },
+  });
+}
 
-      // 2. Atomic Rename (Safe Replace)
-      await rename(tempPath, dbPath);
+export function restoreBackupMutationOptions()
+{
+  return mutationOptions({
+    mutationFn: async ({ fileId, passphrase }: { fileId: string; passphrase?: string }) =>
+    {
+      await invoke("restore_backup", { target: GOOGLE_DRIVE, backupId: fileId, passphrase })
+        .catch(commandError());
 
       useSyncStore.getState().setLastSyncedFileId(fileId);
 
@@ -0,0 +1,66 @@
+import { mutationOptions, queryOptions } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
+import { relaunch } from "@tauri-apps/plugin-process";
+import i18n from "../i18n";
+import { EncryptionChange, EncryptionStatus } from "../types/encryption";
+import { commandError } from "../utils";
+
+export function getEncryptionStatusQueryKey() {
+  return ["encryption"] as const;
+}
+
+export function getEncryptionStatusQueryOptions() {
+  return queryOptions({
+    queryKey: getEncryptionStatusQueryKey(),
+    queryFn: () => invoke<EncryptionStatus>("get_encryption_status"),
+    // A plain database opens in the background during startup
+    refetchInterval: (query) =>
+      query.state.data && !query.state.data.enabled && !query.state.data.databaseOpen ? 500 : false,
+  });
+}
+
+export function unlockDatabaseMutationOptions() {
+  return mutationOptions({
+    mutationFn: (passphrase: string) =>
+      invoke("unlock_database", { passphrase }).catch(commandError()),
+    meta: {
+      invalidatesQueries: [getEncryptionStatusQueryKey()],
+    },
+  });
+}
+
+export function changeEncryptionMutationOptions() {
+  return mutationOptions({
+    mutationFn: async (change: EncryptionChange) => {
+      await invoke("change_encryption", { change }).catch(commandError());
+
+      // The database was closed to re-encrypt it
+      setTimeout(() => relaunch(), 1500);
+    },
+    meta: {
+      successMessage: i18n.t("encryption.changed"),
+    },
+  });
+}
+
+export function setBackupPassphraseMutationOptions() {
+  return mutationOptions({
+    mutationFn: (passphrase: string | null) =>
+      invoke("set_backup_passphrase", { passphrase }).catch(commandError()),
+    meta: {
+      invalidatesQueries: [getEncryptionStatusQueryKey()],
+      successMessage: i18n.t("encryption.backup_passphrase_saved"),
+    },
+  });
+}
+
+export function unlockBackupsMutationOptions() {
+  return mutationOptions({
+    mutationFn: (passphrase: string) =>
+      invoke("unlock_backups", { passphrase }).catch(commandError()),
+    meta: {
+      invalidatesQueries: [getEncryptionStatusQueryKey()],
+      successMessage: i18n.t("encryption.backups_unlocked"),
+    },
+  });
+}
@@ -1,13 +1,14 @@
 import { mutationOptions, queryOptions } from "@tanstack/react-query";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
+import { invoke } from "@tauri-apps/api/core";
 import i18n from "../i18n";
+import { commandError } from "../utils";
 import { PagedList, PagingParams } from "../types";
 import
 {
   AddPatientSchema,
   Patient,
   PatientDetails,
+  PatientSearchResult,
   UpdatePatientSchema,
 } from "../types/patients";
 
@@ -33,37 +34,19 @@ export function getPatientsQueryOptions(
 ) {
   return queryOptions({
     queryKey: getPatientsQueryKey(params),
-    queryFn: async () => {
-      const db = await getDb();
-
-      const { search, offset, limit } = params;
-
-      const patients = await db.select<Patient[]>(
-        `
-          SELECT
-            id,
-            name,
-            phone,
-            created_at AS createdAt,
-            updated_at AS updatedAt
-          FROM patients
-          WHERE (? IS NULL OR name LIKE '%' || ? || '%' OR phone LIKE '%' || ? || '%')
-          ORDER BY created_at DESC
-          LIMIT ? OFFSET ?
-        `,
-        [search, search, search, limit, offset],
-      );
-
-      const [total] = await db.select<number[]>(
-        `
-          SELECT COUNT(*) as count
-          FROM patients
-          WHERE (? IS NULL OR name LIKE '%' || ? || '%' OR phone LIKE '%' || ? || '%')
-        `,
-        [search, search, search],
-      );
-
-      return new PagedList(patients, params, total);
+    queryFn: async (): Promise<PagedList<Patient | PatientSearchResult>> => {
+      const { search, page, pageSize } = params;
+
+      // Typed searches go through the full-text index, ranked by relevance
+      if (search?.trim()) {
+        return await invoke<PagedList<PatientSearchResult>>("search", {
+          params: { query: search, page, pageSize },
+        });
+      }
+
+      return await invoke<PagedList<Patient>>("get_patients", {
+        params: { page, pageSize },
+      }).catch(commandError());
     },
   });
 }
@@ -72,52 +55,11 @@ export function getPatientDetailsQueryKey(id: string) {
   return [...getPatientsQueryKey(), { patientId: id }] as const;
 }
 
+// Totals come from the ledger, the same numbers the balance reports show
 async function getPatientDetails(id: string) {
-  const db = await getDb();
-
-  const patients = await db.select<PatientDetails[]>(
-    `
-      WITH statement_totals AS (
-        SELECT
-          s.id,
-          s.patient_id,
-          s.total as statement_total,
-          (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id) as deposited_total
-        FROM statements s
-      ),
-      patient_aggregates AS (
-        SELECT
-          p.id,
-          p.name,
-          p.phone,
-          p.created_at,
-          p.updated_at,
-          COUNT(DISTINCT st.id) as statementCount,
-          SUM(st.statement_total) as totalRequired,
-          SUM(st.deposited_total) as totalPaid,
-          COUNT(DISTINCT CASE WHEN st.statement_total > st.deposited_total THEN st.id END) as overdueCount
-        FROM patients p
-        LEFT JOIN statement_totals st ON p.id = st.patient_id
-        WHERE p.id = ?
-        GROUP BY p.id
-      )
-      SELECT
-        id,
-        name,
-        phone,
-        statementCount,
-        overdueCount,
-        COALESCE(totalRequired, 0) as totalRequired,
-        COALESCE(totalPaid, 0) as totalPaid,
-        COALESCE(totalRequired, 0) - COALESCE(totalPaid, 0) as totalRemaining,
-        created_at AS createdAt,
-        updated_at AS updatedAt
-      FROM patient_aggregates
-    `,
-    [id],
+  return await invoke<PatientDetails>("get_patient_details", { id }).catch(
+    commandError(i18n.t("patients.not_found")),
   );
-
-  return patients?.[0] as PatientDetails | undefined;
 }
 
 export function getPatientDetailsQueryOptions(id: string) {
@@ -138,29 +80,9 @@ export function addPatientMutationOptions() {
         throw new Error(i18n.t("common.invalid_data"));
       }
 
-      addPatient = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
-
-      const queryResult = await db.execute(
-        `
-        INSERT INTO patients (id, name, phone, created_at, updated_at)
-        VALUES (?, ?, ?, datetime('now'), datetime('now'))
-        RETURNING
-          id,
-          name,
-          phone,
-          created_at AS createdAt,
-          updated_at AS updatedAt
-      `,
-        [id, addPatient.name, addPatient.phone],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("common.operation_failed"));
-      }
-
-      return (await getPatientDetails(id))!;
+      return await invoke<PatientDetails>("add_patient", {
+        patient: parseResult.data,
+      }).catch(commandError());
     },
     meta: {
       invalidatesQueries: [getPatientsQueryKey()],
@@ -181,24 +103,10 @@ export function updatePatientMutationOptions() {
         throw new Error(i18n.t("patients.not_found"));
       }
 
-      const updatePatient = parseResult.data;
-
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        `
-              UPDATE patients
-              SET name = ?, phone = ?, updated_at = datetime('now')
-              WHERE id = ?
-            `,
-        [updatePatient.name, updatePatient.phone, data.id],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.patient_updated_failed"));
-      }
-
-      return (await getPatientDetails(data.id))!;
+      return await invoke<PatientDetails>("update_patient", {
+        id: data.id,
+        patient: parseResult.data,
+      }).catch(commandError(i18n.t("messages.patient_updated_failed")));
     },
     meta: {
       invalidatesQueries: [getPatientsQueryKey()],
@@ -210,17 +118,14 @@ export function updatePatientMutationOptions() {
 export function deletePatientMutationOptions() {
   return mutationOptions({
     mutationFn: async (id: string) => {
-      const db = await getDb();
-      const queryResult = await db.execute("DELETE FROM patients WHERE id = ?", [id]);
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.patient_deleted_failed"));
-      }
+      // Soft delete, the patient stays in the recycle bin until purged
+      await invoke("delete_patient", { id }).catch(commandError());
       return id;
     },
     meta: {
       invalidatesQueries: [getPatientsQueryKey()],
       successMessage: i18n.t("messages.patient_deleted"),
+      errorMessage: i18n.t("messages.patient_deleted_failed"),
     },
   });
 }
@@ -1,8 +1,8 @@
 import { mutationOptions } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
 import { debug } from "@tauri-apps/plugin-log";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
 import i18n from "../i18n";
+import { commandError } from "../utils";
 import { AddPaymentSchema, UpdatePaymentSchema } from "../types/payments";
 import
 {
@@ -22,20 +22,11 @@ export function addPaymentMutationOptions() {
       }
 
       const data = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
 
-      const queryResult = await db.execute(
-        `
-        INSERT INTO payments (id, statement_id, amount, created_at, updated_at)
-        VALUES (?, ?, ?, datetime('now'), datetime('now'))
-      `,
-        [id, data.statementId, data.amount],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("common.operation_failed"));
-      }
+      // Goes through Rust so the signed in user's role is checked
+      const { id } = await invoke<{ id: string }>("add_payment", {
+        payment: data,
+      }).catch(commandError());
 
       return { id, ...data };
     },
@@ -60,20 +51,10 @@ export function updatePaymentMutationOptions(statementId: string) {
 
       const updateData = parseResult.data;
 
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        `
-              UPDATE payments
-              SET amount = ?, updated_at = datetime('now')
-              WHERE id = ?
-            `,
-        [updateData.amount, data.id],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.payment_updated_failed"));
-      }
+      await invoke("update_payment", {
+        id: data.id,
+        payment: updateData,
+      }).catch(commandError(i18n.t("messages.payment_updated_failed")));
 
       return { id: data.id, ...updateData };
     },
@@ -87,17 +68,10 @@ export function updatePaymentMutationOptions(statementId: string) {
 export function deletePaymentMutationOptions(statementId: string) {
   return mutationOptions({
     mutationFn: async (id: string) => {
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        `DELETE FROM payments WHERE id = ?`,
-        [id],
+      await invoke("delete_payment", { id }).catch(
+        commandError(i18n.t("messages.payment_deleted_failed")),
       );
 
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.payment_deleted_failed"));
-      }
-
       return id;
     },
     meta: {
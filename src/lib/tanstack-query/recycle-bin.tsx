@@ -0,0 +1,63 @@
+import { mutationOptions, queryOptions } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
+import i18n from "../i18n";
+import { PagedList, PagingParams } from "../types";
+import { DeletedItem, DeletedKind } from "../types/recycle-bin";
+import { commandError } from "../utils";
+import { getPatientsQueryKey } from "./patients";
+import { getStatementsQueryKey } from "./statements";
+
+export class GetRecycleBinParams extends PagingParams {
+  /** Patient name or phone */
+  search?: string;
+
+  constructor(init?: Partial<GetRecycleBinParams>) {
+    super(init);
+    Object.assign(this, init);
+  }
+}
+
+export function getRecycleBinQueryKey(params: GetRecycleBinParams | undefined = undefined) {
+  return params ? (["recycle-bin", { ...params }] as const) : (["recycle-bin"] as const);
+}
+
+export function getRecycleBinQueryOptions(params: GetRecycleBinParams) {
+  return queryOptions({
+    queryKey: getRecycleBinQueryKey(params),
+    queryFn: () =>
+      invoke<PagedList<DeletedItem>>("get_recycle_bin", {
+        params: {
+          page: params.page,
+          pageSize: params.pageSize,
+          search: params.search?.trim() || null,
+        },
+      }).catch(commandError()),
+  });
+}
+
+// Restoring or purging changes what the patient and statement lists show
+const affectedQueries = () => [getRecycleBinQueryKey(), getPatientsQueryKey(), getStatementsQueryKey()];
+
+export function restoreDeletedMutationOptions() {
+  return mutationOptions({
+    mutationFn: (item: { kind: DeletedKind; id: string }) =>
+      invoke("restore_deleted", item).catch(commandError()),
+    meta: {
+      invalidatesQueries: affectedQueries(),
+      successMessage: i18n.t("recycle_bin.restored"),
+      errorMessage: i18n.t("recycle_bin.restore_failed"),
+    },
+  });
+}
+
+export function purgeDeletedMutationOptions() {
+  return mutationOptions({
+    mutationFn: (item: { kind: DeletedKind; id: string }) =>
+      invoke("purge_deleted", item).catch(commandError()),
+    meta: {
+      invalidatesQueries: affectedQueries(),
+      successMessage: i18n.t("recycle_bin.purged"),
+      errorMessage: i18n.t("recycle_bin.purge_failed"),
+    },
+  });
+}
@@ -1,8 +1,8 @@
 import { mutationOptions } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
 import { debug } from "@tauri-apps/plugin-log";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
 import i18n from "../i18n";
+import { commandError } from "../utils";
 import { AddSessionSchema, UpdateSessionSchema } from "../types/sessions";
 import { getStatementsQueryKey } from "./statements";
 
@@ -18,20 +18,10 @@ export function addSessionMutationOptions() {
       }
 
       const data = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
 
-      const queryResult = await db.execute(
-        `
-        INSERT INTO sessions (id, statement_id, "procedure", created_at, updated_at)
-        VALUES (?, ?, ?, datetime('now'), datetime('now'))
-      `,
-        [id, data.statementId, data.procedure],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("common.operation_failed"));
-      }
+      const { id } = await invoke<{ id: string }>("add_session", {
+        session: data,
+      }).catch(commandError());
 
       return { id, ...data };
     },
@@ -56,20 +46,10 @@ export function updateSessionMutationOptions() {
 
       const updateData = parseResult.data;
 
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        `
-              UPDATE sessions
-              SET "procedure" = ?, updated_at = datetime('now')
-              WHERE id = ?
-            `,
-        [updateData.procedure, data.id],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.session_updated_failed"));
-      }
+      await invoke("update_session", {
+        id: data.id,
+        session: updateData,
+      }).catch(commandError(i18n.t("messages.session_updated_failed")));
 
       return { id: data.id, ...updateData };
     },
@@ -83,17 +63,10 @@ export function updateSessionMutationOptions() {
 export function deleteSessionMutationOptions() {
   return mutationOptions({
     mutationFn: async (id: string) => {
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        `DELETE FROM sessions WHERE id = ?`,
-        [id],
+      await invoke("delete_session", { id }).catch(
+        commandError(i18n.t("messages.session_deleted_failed")),
       );
 
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.session_deleted_failed"));
-      }
-
       return id;
     },
     meta: {
@@ -1,10 +1,9 @@
 import { mutationOptions, queryOptions } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
 import { debug } from "@tauri-apps/plugin-log";
-import { v7 as uuid } from "uuid";
-import { getDb } from "../database";
 import i18n from "../i18n";
 import { PagedList, PagingParams } from "../types";
-import { AddPaymentSchema } from "../types/payments";
+import { commandError } from "../utils";
 import
 {
   AddStatementSchema,
@@ -40,93 +39,19 @@ export function getStatementsQueryOptions(
   return queryOptions({
     queryKey: getStatementsQueryKey(params),
     queryFn: async () => {
-      const db = await getDb();
-
-      const { search, offset, limit, patientId, remainingFilter, doctorId, clinicId } = params;
-
-      const items = await db.select<any[]>(
-        `
-          SELECT * FROM (
-            SELECT
-              s.id,
-              s.total,
-              (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id) as totalPaid,
-              (s.total - (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id)) as totalRemaining,
-              s.created_at as createdAt,
-              s.updated_at as updatedAt,
-              json_object(
-                  'id', p.id,
-                  'name', p.name,
-                  'phone', p.phone,
-                  'createdAt', p.created_at,
-                  'updatedAt', p.updated_at
-              ) as patient,
-              CASE WHEN s.doctor_id IS NOT NULL THEN
-                json_object(
-                    'id', d.id,
-                    'name', d.name,
-                    'phone', d.phone,
-                    'createdAt', d.created_at,
-                    'updatedAt', d.updated_at
-                )
-              ELSE NULL END as doctor,
-              CASE WHEN s.clinic_id IS NOT NULL THEN
-                json_object(
-                    'id', c.id,
-                    'name', c.name,
-                    'createdAt', c.created_at,
-                    'updatedAt', c.updated_at
-                )
-              ELSE NULL END as clinic
-            FROM statements s
-            JOIN patients p ON s.patient_id = p.id
-            LEFT JOIN doctors d ON s.doctor_id = d.id
-            LEFT JOIN clinics c ON s.clinic_id = c.id
-            WHERE
-                (?1 IS NULL OR s.patient_id = ?1) AND
-                (?2 IS NULL OR p.name LIKE '%' || ?2 || '%' OR p.phone LIKE '%' || ?2 || '%') AND
-                (?6 IS NULL OR s.doctor_id = ?6) AND
-                (?7 IS NULL OR s.clinic_id = ?7)
-          )
-          WHERE
-            (?5 = 'all' OR ?5 IS NULL) OR
-            (?5 = 'positive' AND totalRemaining > 0) OR
-            (?5 = 'negative' AND totalRemaining < 0)
-          ORDER BY createdAt DESC
-          LIMIT ?3 OFFSET ?4
-        `,
-        [patientId, search, limit, offset, remainingFilter, doctorId, clinicId],
-      );
-
-      const [total] = await db.select<[{ count: number }]>(
-        `
-          SELECT COUNT(*) as count FROM (
-            SELECT
-              (s.total - (SELECT COALESCE(SUM(amount), 0) FROM payments WHERE statement_id = s.id)) as totalRemaining
-            FROM statements s
-            JOIN patients p ON s.patient_id = p.id
-            WHERE
-              (?1 IS NULL OR s.patient_id = ?1) AND
-              (?2 IS NULL OR p.name LIKE '%' || ?2 || '%' OR p.phone LIKE '%' || ?2 || '%') AND
-              (?4 IS NULL OR s.doctor_id = ?4) AND
-              (?5 IS NULL OR s.clinic_id = ?5)
-          )
-          WHERE
-            (?3 = 'all' OR ?3 IS NULL) OR
-            (?3 = 'positive' AND totalRemaining > 0) OR
-            (?3 = 'negative' AND totalRemaining < 0)
-        `,
-        [patientId, search, remainingFilter, doctorId, clinicId],
-      );
-
-      const statements = items.map((i) => ({
-        ...i,
-        patient: JSON.parse(i.patient),
-        doctor: i.doctor ? JSON.parse(i.doctor) : undefined,
-        clinic: i.clinic ? JSON.parse(i.clinic) : undefined,
-      }));
-
-      return new PagedList<Statement>(statements, params, total.count);
+      const { page, pageSize, search, patientId, remainingFilter, doctorId, clinicId } = params;
+
+      return await invoke<PagedList<Statement>>("get_statements", {
+        params: {
+          page,
+          pageSize,
+          search: search || null,
+          patientId: patientId ?? null,
+          remainingFilter: remainingFilter ?? "all",
+          doctorId: doctorId ?? null,
+          clinicId: clinicId ?? null,
+        },
+      }).catch(commandError());
     },
   });
 }
@@ -136,117 +61,9 @@ export function getStatementDetailsQueryKey(id: string) {
 }
 
 async function getStatementDetails(id: string) {
-  const db = await getDb();
-
-  const [statement] = await db.select<any[]>(
-    `
-      WITH statement_sessions AS (
-        SELECT
-            statement_id,
-            json_group_array(
-                json_object(
-                    'id', id,
-                    'statementId', statement_id,
-                    'procedure', "procedure",
-                    'createdAt', created_at,
-                    'updatedAt', updated_at
-                )
-            ) as sessions
-        FROM (SELECT * FROM sessions ORDER BY created_at DESC)
-        GROUP BY statement_id
-      ),
-      statement_payments AS (
-        SELECT
-            statement_id,
-            COALESCE(SUM(amount), 0) as totalPaid,
-            json_group_array(
-                json_object(
-                    'id', id,
-                    'statementId', statement_id,
-                    'amount', amount,
-                    'createdAt', created_at,
-                    'updatedAt', updated_at
-                )
-            ) as payments
-        FROM (SELECT * FROM payments ORDER BY created_at DESC)
-        GROUP BY statement_id
-      ),
-      statement_attachments AS (
-        SELECT
-            statement_id,
-            json_group_array(
-                json_object(
-                    'id', id,
-                    'statementId', statement_id,
-                    'fileName', file_name,
-                    'filePath', file_path,
-                    'fileType', file_type,
-                    'fileSize', file_size,
-                    'createdAt', created_at
-                )
-            ) as attachments
-        FROM (SELECT * FROM attachments ORDER BY created_at DESC)
-        GROUP BY statement_id
-      )
-      SELECT
-          s.id,
-          s.total,
-          s.created_at as createdAt,
-          s.updated_at as updatedAt,
-          json_object(
-              'id', p.id,
-              'name', p.name,
-              'phone', p.phone,
-              'createdAt', p.created_at,
-              'updatedAt', p.updated_at
-          ) as patient,
-          CASE WHEN s.doctor_id IS NOT NULL THEN
-            json_object(
-                'id', d.id,
-                'name', d.name,
-                'phone', d.phone,
-                'createdAt', d.created_at,
-                'updatedAt', d.updated_at
-            )
-          ELSE NULL END as doctor,
-          CASE WHEN s.clinic_id IS NOT NULL THEN
-            json_object(
-                'id', c.id,
-                'name', c.name,
-                'createdAt', c.created_at,
-                'updatedAt', c.updated_at
-            )
-          ELSE NULL END as clinic,
-          COALESCE(sp.totalPaid, 0) as totalPaid,
-          (s.total - COALESCE(sp.totalPaid, 0)) as totalRemaining,
-          COALESCE(ss.sessions, '[]') as sessions,
-          COALESCE(sp.payments, '[]') as payments,
-          COALESCE(sa.attachments, '[]') as attachments
-      FROM statements s
-      JOIN patients p ON s.patient_id = p.id
-      LEFT JOIN doctors d ON s.doctor_id = d.id
-      LEFT JOIN clinics c ON s.clinic_id = c.id
-      LEFT JOIN statement_sessions ss ON s.id = ss.statement_id
-      LEFT JOIN statement_payments sp ON s.id = sp.statement_id
-      LEFT JOIN statement_attachments sa ON s.id = sa.statement_id
-      WHERE s.id = ?
-    `,
-    [id],
+  return await invoke<StatementDetails>("get_statement_details", { id }).catch(
+    commandError(i18n.t("statements.not_found")),
   );
-
-  if (!statement) {
-    return undefined;
-  }
-
-  return {
-    ...statement,
-    patient: statement.patient && JSON.parse(statement.patient),
-    doctor: statement.doctor ? JSON.parse(statement.doctor) : undefined,
-    clinic: statement.clinic ? JSON.parse(statement.clinic) : undefined,
-    payments: statement.payments && JSON.parse(statement.payments),
-    sessions: statement.sessions && JSON.parse(statement.sessions),
-    attachments: statement.attachments && JSON.parse(statement.attachments),
-  } as StatementDetails;
 }
 
 export function getStatementDetailsQueryOptions(id: string) {
@@ -269,23 +86,9 @@ export function addStatementMutationOptions() {
         throw new Error("Invalid statement data");
       }
 
-      addStatement = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
-
-      const queryResult = await db.execute(
-        `
-        INSERT INTO statements (id, patient_id, total, doctor_id, clinic_id, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))
-      `,
-        [id, addStatement.patientId, addStatement.total, addStatement.doctorId || null, addStatement.clinicId || null],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error("Failed to add statement");
-      }
-
-      return (await getStatementDetails(id))!;
+      return await invoke<StatementDetails>("add_statement", {
+        statement: parseResult.data,
+      }).catch(commandError());
     },
     meta: {
       invalidatesQueries: [getStatementsQueryKey()],
@@ -308,22 +111,14 @@ export function updateStatementMutationOptions() {
 
       const updateStatement = parseResult.data;
 
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        `
-              UPDATE statements
-              SET total = ?, doctor_id = ?, clinic_id = ?, updated_at = datetime('now')
-              WHERE id = ?
-            `,
-        [updateStatement.total, updateStatement.doctorId || null, updateStatement.clinicId || null, data.id],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.statement_updated_failed"));
-      }
-
-      return (await getStatementDetails(data.id))!;
+      return await invoke<StatementDetails>("update_statement", {
+        id: data.id,
+        statement: {
+          ...updateStatement,
+          doctorId: updateStatement.doctorId ?? null,
+          clinicId: updateStatement.clinicId ?? null,
+        },
+      }).catch(commandError(i18n.t("messages.statement_updated_failed")));
     },
     meta: {
       invalidatesQueries: [getStatementsQueryKey()],
@@ -335,59 +130,15 @@ export function updateStatementMutationOptions() {
 export function deleteStatementMutationOptions() {
   return mutationOptions({
     mutationFn: async (id: string) => {
-      const db = await getDb();
-
-      const queryResult = await db.execute(
-        "DELETE FROM statements WHERE id = ?",
-        [id],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error(i18n.t("messages.statement_deleted_failed"));
-      }
+      // Soft delete, the statement stays in the recycle bin until purged
+      await invoke("delete_statement", { id }).catch(commandError());
 
       return id;
     },
     meta: {
       invalidatesQueries: [getStatementsQueryKey()],
       successMessage: i18n.t("messages.statement_deleted"),
+      errorMessage: i18n.t("messages.statement_deleted_failed"),
     },
   });
 }
-
-export function addPaymentMutationOptions() {
-  return mutationOptions({
-    mutationFn: async (addPayment: AddPaymentSchema) => {
-      debug(`Adding payment for ${JSON.stringify(addPayment, null, 2)}`);
-
-      const parseResult = AddPaymentSchema.safeParse(addPayment);
-
-      if (!parseResult.success) {
-        throw new Error("Invalid payment data");
-      }
-
-      addPayment = parseResult.data;
-      const db = await getDb();
-      const id = uuid();
-
-      const queryResult = await db.execute(
-        `
-        INSERT INTO payments (id, statement_id, amount, created_at, updated_at)
-        VALUES (?, ?, ?, datetime('now'), datetime('now'))
-      `,
-        [id, addPayment.statementId, addPayment.amount],
-      );
-
-      if (queryResult.rowsAffected === 0) {
-        throw new Error("Failed to add payment");
-      }
-
-      return (await getStatementDetails(addPayment.statementId))!;
-    },
-        meta: {
-          invalidatesQueries: [getStatementsQueryKey()],
-          successMessage: i18n.t("messages.payment_added"),
-        },
-      });
-    }
-    
\ No newline at end of file
@@ -0,0 +1,136 @@
+import { mutationOptions, queryOptions } from "@tanstack/react-query";
+import { invoke } from "@tauri-apps/api/core";
+import i18n from "../i18n";
+import { commandError } from "../utils";
+import { AddUserSchema, ReminderLeadTimes, SaveUserSchema, SessionInfo, User } from "../types/users";
+
+export function getSessionQueryKey() {
+  return ["session"] as const;
+}
+
+export function getSessionQueryOptions() {
+  return queryOptions({
+    queryKey: getSessionQueryKey(),
+    queryFn: () => invoke<SessionInfo>("get_session"),
+  });
+}
+
+export function getUsersQueryKey() {
+  return ["users"] as const;
+}
+
+export function getUsersQueryOptions() {
+  return queryOptions({
+    queryKey: getUsersQueryKey(),
+    queryFn: () => invoke<User[]>("get_users"),
+  });
+}
+
+export function loginMutationOptions() {
+  return mutationOptions({
+    mutationFn: (data: { username: string; password: string }) =>
+      invoke<User>("login", data).catch(commandError()),
+    meta: {
+      invalidatesQueries: [getSessionQueryKey()],
+    },
+  });
+}
+
+export function logoutMutationOptions() {
+  return mutationOptions({
+    mutationFn: () => invoke("logout").catch(commandError()),
+    meta: {
+      invalidatesQueries: [getSessionQueryKey()],
+    },
+  });
+}
+
+export function createFirstAdminMutationOptions() {
+  return mutationOptions({
+    mutationFn: (data: { username: string; displayName: string; password: string }) =>
+      invoke<User>("create_first_admin", data).catch(commandError()),
+    meta: {
+      invalidatesQueries: [getSessionQueryKey()],
+    },
+  });
+}
+
+export function changePasswordMutationOptions() {
+  return mutationOptions({
+    mutationFn: (data: { currentPassword: string; newPassword: string }) =>
+      invoke("change_password", data).catch(commandError()),
+    meta: {
+      successMessage: i18n.t("auth.password_changed"),
+      errorMessage: i18n.t("common.operation_failed"),
+    },
+  });
+}
+
+export function getReminderLeadTimesQueryKey() {
+  return ["reminder-lead-times"] as const;
+}
+
+export function getReminderLeadTimesQueryOptions() {
+  return queryOptions({
+    queryKey: getReminderLeadTimesQueryKey(),
+    queryFn: () => invoke<ReminderLeadTimes>("get_reminder_lead_times"),
+  });
+}
+
+export function setReminderLeadTimesMutationOptions() {
+  return mutationOptions({
+    mutationFn: (leadMinutes: number[] | null) =>
+      invoke<ReminderLeadTimes>("set_reminder_lead_times", { leadMinutes }).catch(commandError()),
+    meta: {
+      invalidatesQueries: [getReminderLeadTimesQueryKey()],
+      successMessage: i18n.t("auth.reminder_times_saved"),
+      errorMessage: i18n.t("common.operation_failed"),
+    },
+  });
+}
+
+export function addUserMutationOptions() {
+  return mutationOptions({
+    mutationFn: async (addUser: AddUserSchema) => {
+      const parseResult = AddUserSchema.safeParse(addUser);
+      if (!parseResult.success) throw new Error(i18n.t("common.invalid_data"));
+
+      return await invoke<User>("add_user", { user: parseResult.data }).catch(commandError());
+    },
+    meta: {
+      invalidatesQueries: [getUsersQueryKey()],
+      successMessage: i18n.t("users.added"),
+      errorMessage: i18n.t("common.operation_failed"),
+    },
+  });
+}
+
+export function updateUserMutationOptions() {
+  return mutationOptions({
+    mutationFn: async (data: { id: string; updateUser: SaveUserSchema }) => {
+      const parseResult = SaveUserSchema.safeParse(data.updateUser);
+      if (!parseResult.success) throw new Error(i18n.t("common.invalid_data"));
+
+      return await invoke<User>("update_user", { id: data.id, user: parseResult.data }).catch(
+        commandError(),
+      );
+    },
+    meta: {
+      // Editing your own account changes the session too
+      invalidatesQueries: [getUsersQueryKey(), getSessionQueryKey()],
+      successMessage: i18n.t("users.updated"),
+      errorMessage: i18n.t("common.operation_failed"),
+    },
+  });
+}
+
+export function resetUserPasswordMutationOptions() {
+  return mutationOptions({
+    mutationFn: (data: { id: string; password: string }) =>
+      invoke("reset_user_password", data).catch(commandError()),
+    meta: {
+      successMessage: i18n.t("users.password_reset"),
+      errorMessage: i18n.t("common.operation_failed"),
+    },
+  });
+}
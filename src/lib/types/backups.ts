@@ -0,0 +1,14 @@
+export interface BackupVerification {
+  backupId: string;
+  /** `PRAGMA integrity_check` and the foreign key check both passed */
+  integrityOk: boolean;
+  schemaVersion: number;
+  /** Older snapshots are migrated on the next launch */
+  latestSchemaVersion: number;
+  patientCount: number;
+  attachmentCount: number;
+  /** Files the snapshot refers to that are neither in the backup nor on this machine */
+  missingAttachments: string[];
+  problems: string[];
+  restorable: boolean;
+}
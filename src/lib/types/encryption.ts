@@ -0,0 +1,14 @@
+export interface EncryptionStatus {
+  enabled: boolean;
+  /** False until the passphrase is entered, or while the plain database is being checked */
+  databaseOpen: boolean;
+  /** Backups are sealed with a backup passphrase before they are uploaded */
+  backupsEncrypted: boolean;
+  /** The backup passphrase has to be entered before the next backup */
+  backupsLocked: boolean;
+}
+
+export type EncryptionChange =
+  | { action: "enable"; passphrase: string }
+  | { action: "rotate"; currentPassphrase: string; newPassphrase: string }
+  | { action: "disable"; currentPassphrase: string };
@@ -8,6 +8,14 @@ export interface Patient {
   updatedAt: string;
 }
 
+/** Matched words are wrapped in \u0002 and \u0003, see `HighlightedText` */
+export interface PatientSearchResult extends Patient {
+  highlightedName: string;
+  highlightedPhone: string;
+  snippet: string | null;
+  rank: number;
+}
+
 export const AddPatientSchema = z.object({
   name: z.string().min(1, "Name is required"),
   phone: z.string().min(1, "Phone is required"),
@@ -22,7 +30,7 @@ export type UpdatePatientSchema = z.infer<typeof UpdatePatientSchema>;
 
 export interface PatientDetails extends Patient {
   statementCount: number;
-  overdueCount: number;
+  unpaidCount: number;
 
   totalRequired: number;
   totalPaid: number;
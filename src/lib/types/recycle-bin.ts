@@ -0,0 +1,14 @@
+export type DeletedKind = "patient" | "statement";
+
+export interface DeletedItem {
+  kind: DeletedKind;
+  id: string;
+  patientId: string;
+  patientName: string;
+  patientPhone: string;
+  /** Statement total, missing for patients */
+  total: number | null;
+  deletedAt: string;
+  /** When the item is purged, missing when the bin is kept forever */
+  purgeAt: string | null;
+}
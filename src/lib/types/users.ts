@@ -0,0 +1,41 @@
+import { z } from "zod";
+
+export const Roles = ["admin", "doctor", "receptionist", "accountant"] as const;
+
+export type Role = (typeof Roles)[number];
+
+export interface User {
+  id: string;
+  username: string;
+  displayName: string;
+  role: Role;
+  active: boolean;
+  createdAt: string;
+  updatedAt: string;
+}
+
+export interface SessionInfo {
+  user: User | null;
+  /** No accounts exist yet, the first one becomes the admin */
+  needsSetup: boolean;
+}
+
+export interface ReminderLeadTimes {
+  /** Minutes before an appointment this user is reminded, null follows the app setting */
+  leadMinutes: number[] | null;
+  defaultLeadMinutes: number[];
+}
+
+export const SaveUserSchema = z.object({
+  username: z.string().trim().min(1),
+  displayName: z.string().trim().min(1),
+  role: z.enum(Roles),
+  active: z.boolean(),
+});
+
+export const AddUserSchema = SaveUserSchema.extend({
+  password: z.string().min(8),
+});
+
+export type SaveUserSchema = z.infer<typeof SaveUserSchema>;
+export type AddUserSchema = z.infer<typeof AddUserSchema>;
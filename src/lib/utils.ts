@@ -33,3 +33,13 @@ export function formatDate(dateString: string | Date, locale = i18n.language) {
     minute: "2-digit",
   }).format(date);
 }
+
+/**
+ * Rethrows a failed `invoke` with the message Rust gave, such as a missing
+ * permission, falling back to `fallback` for anything else.
+ */
+export function commandError(fallback = i18n.t("common.operation_failed")) {
+  return (err: unknown): never => {
+    throw new Error(typeof err === "string" ? err : fallback);
+  };
+}
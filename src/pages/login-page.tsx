@@ -0,0 +1,119 @@
+import logo from "@/assets/logo.svg";
+import { Button } from "@/components/ui/button";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Field, FieldError, FieldLabel } from "@/components/ui/field";
+import { Input } from "@/components/ui/input";
+import { Spinner } from "@/components/ui/spinner";
+import { LanguageToggle } from "@/components/language-toggle";
+import {
+  createFirstAdminMutationOptions,
+  loginMutationOptions,
+} from "@/lib/tanstack-query/users";
+import { zodResolver } from "@hookform/resolvers/zod";
+import { useMutation } from "@tanstack/react-query";
+import { Controller, useForm } from "react-hook-form";
+import { useTranslation } from "react-i18next";
+import { z } from "zod";
+
+const LoginSchema = z.object({
+  username: z.string().trim().min(1),
+  displayName: z.string(),
+  password: z.string().min(1),
+  confirmPassword: z.string(),
+});
+
+type LoginSchema = z.infer<typeof LoginSchema>;
+
+interface LoginPageProps {
+  /** No accounts exist yet, so this creates the admin instead of signing in */
+  needsSetup: boolean;
+}
+
+export default function LoginPage({ needsSetup }: LoginPageProps) {
+  const { t, i18n } = useTranslation();
+  const form = useForm<LoginSchema>({
+    resolver: zodResolver(LoginSchema),
+    defaultValues: { username: "", displayName: "", password: "", confirmPassword: "" },
+  });
+
+  const loginMutation = useMutation(loginMutationOptions());
+  const setupMutation = useMutation(createFirstAdminMutationOptions());
+  const mutation = needsSetup ? setupMutation : loginMutation;
+
+  const onSubmit = (data: LoginSchema) => {
+    if (!needsSetup) {
+      loginMutation.mutate({ username: data.username, password: data.password });
+      return;
+    }
+
+    if (data.password !== data.confirmPassword) {
+      form.setError("confirmPassword", { message: t("auth.passwords_differ") });
+      return;
+    }
+
+    setupMutation.mutate({
+      username: data.username,
+      displayName: data.displayName.trim() || data.username,
+      password: data.password,
+    });
+  };
+
+  const field = (
+    name: keyof LoginSchema,
+    label: string,
+    type = "text",
+    autoComplete = "off",
+  ) => (
+    <Controller
+      name={name}
+      control={form.control}
+      render={({ field, fieldState }) => (
+        <Field data-invalid={fieldState.invalid}>
+          <FieldLabel htmlFor={field.name}>{label}</FieldLabel>
+          <Input
+            {...field}
+            id={field.name}
+            type={type}
+            aria-invalid={fieldState.invalid}
+            autoComplete={autoComplete}
+          />
+          {fieldState.invalid && <FieldError errors={[fieldState.error]} />}
+        </Field>
+      )}
+    />
+  );
+
+  return (
+    <div dir={i18n.dir()} className="min-h-screen flex items-center justify-center p-4 bg-muted/30">
+      <Card className="w-full max-w-sm">
+        <CardHeader className="space-y-3">
+          <div className="flex items-center justify-between">
+            <img src={logo} alt={`${t("common.app_name")} Logo`} className="w-10 h-10 rounded-md" />
+            <LanguageToggle />
+          </div>
+          <CardTitle>{needsSetup ? t("auth.setup_title") : t("auth.sign_in")}</CardTitle>
+          <CardDescription>
+            {needsSetup ? t("auth.setup_description") : t("auth.sign_in_description")}
+          </CardDescription>
+        </CardHeader>
+        <CardContent>
+          <form onSubmit={form.handleSubmit(onSubmit)} className="space-y-4">
+            {field("username", t("auth.username"), "text", "username")}
+            {needsSetup && field("displayName", t("auth.display_name"))}
+            {field("password", t("auth.password"), "password", needsSetup ? "new-password" : "current-password")}
+            {needsSetup && field("confirmPassword", t("auth.confirm_password"), "password", "new-password")}
+
+            {mutation.isError && (
+              <p className="text-sm text-destructive">{mutation.error.message}</p>
+            )}
+
+            <Button type="submit" className="w-full" disabled={mutation.isPending}>
+              {mutation.isPending && <Spinner />}
+              {needsSetup ? t("auth.create_admin") : t("auth.sign_in")}
+            </Button>
+          </form>
+        </CardContent>
+      </Card>
+    </div>
+  );
+}
@@ -1,4 +1,5 @@
 import { ErrorMessage } from "@/components/error-message";
+import { HighlightedText } from "@/components/highlighted-text";
 import { LoadingMessage } from "@/components/table-loading";
 import { Badge } from "@/components/ui/badge";
 import { DataTable } from "@/components/ui/data-table";
@@ -8,7 +9,7 @@ import {
   GetPatientsParams,
   getPatientsQueryOptions,
 } from "@/lib/tanstack-query/patients";
-import { Patient } from "@/lib/types/patients";
+import { Patient, PatientSearchResult } from "@/lib/types/patients";
 import { shortenUuid } from "@/lib/utils";
 import { useQuery } from "@tanstack/react-query";
 import { ColumnDef, PaginationState } from "@tanstack/react-table";
@@ -60,10 +61,29 @@ export function PatientsTable() {
         {
           accessorKey: "name",
           header: t("patients.name"),
+          cell: ({ row }) => {
+            const result = row.original as Partial<PatientSearchResult>;
+            if (!result.highlightedName) return row.original.name;
+
+            return (
+              <div className="flex flex-col">
+                <HighlightedText text={result.highlightedName} />
+                {result.snippet && (
+                  <HighlightedText text={result.snippet} className="text-xs text-muted-foreground" />
+                )}
+              </div>
+            );
+          },
         },
         {
           accessorKey: "phone",
           header: t("patients.phone"),
+          cell: ({ row }) => {
+            const result = row.original as Partial<PatientSearchResult>;
+            return result.highlightedPhone
+              ? <HighlightedText text={result.highlightedPhone} />
+              : row.original.phone;
+          },
         },
         {
           accessorKey: "createdAt",
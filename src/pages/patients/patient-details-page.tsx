@@ -245,20 +245,20 @@ export default function PatientDetailsPage() {
                   </div>
                 </div>
 
-                {/* Overdue Summary */}
-                {patient.overdueCount > 0 && (
+                {/* Unpaid Summary */}
+                {patient.unpaidCount > 0 && (
                   <div className="mt-6 bg-red-50 dark:bg-red-900/20 border border-red-200 dark:border-red-800 rounded-lg p-4">
                     <div className="flex items-center justify-between">
                       <div>
                         <h4 className="font-medium text-red-800 dark:text-red-300">
-                          {t("financial.overdue_statements")}
+                          {t("financial.unpaid_statements")}
                         </h4>
                         <p className="text-sm text-red-700 dark:text-red-400 mt-1">
-                          {patient.overdueCount} {t("financial.require_attention")}
+                          {patient.unpaidCount} {t("financial.require_attention")}
                         </p>
                       </div>
                       <Badge variant="destructive" className="px-3 py-1">
-                        {patient.overdueCount} {t("statements.status.overdue")}
+                        {patient.unpaidCount} {t("statements.status.unpaid")}
                       </Badge>
                     </div>
                   </div>
@@ -1,18 +1,26 @@
 import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { googleDrive } from "@/lib/google-drive";
+import { getSessionQueryOptions } from "@/lib/tanstack-query/users";
+import { useQuery } from "@tanstack/react-query";
 import { error } from "@tauri-apps/plugin-log";
-import { Cloud, CloudOff, Loader2 } from "lucide-react";
+import { Cloud, CloudOff, Loader2, Server, ShieldCheck, Trash2, Users } from "lucide-react";
 import { useEffect, useState } from "react";
 import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
+import { AppSettings } from "./settings/components/app-settings";
+import { EncryptionSettings } from "./settings/components/encryption-settings";
+import { RecycleBin } from "./settings/components/recycle-bin";
 import { SyncManager } from "./settings/components/sync-manager";
+import { UsersManager } from "./settings/components/users-manager";
 
 export default function SettingsPage()
 {
   const { t } = useTranslation();
   const [isConnected, setIsConnected] = useState(false);
   const [isLoading, setIsLoading] = useState(true);
+  const { data: session } = useQuery(getSessionQueryOptions());
+  const isAdmin = session?.user?.role === "admin";
 
   useEffect(() =>
   {
@@ -178,6 +186,72 @@ export default function SettingsPage()
 
       </Card>
 
+      <Card>
+        <CardHeader>
+          <div className="flex items-center justify-between">
+            <div className="space-y-1">
+              <CardTitle>{t("settings.title")}</CardTitle>
+              <CardDescription>{t("settings.description")}</CardDescription>
+            </div>
+            <Server className="h-8 w-8 text-muted-foreground" />
+          </div>
+        </CardHeader>
+        <CardContent>
+          <AppSettings />
+        </CardContent>
+      </Card>
+
+      {isAdmin && (
+        <Card>
+          <CardHeader>
+            <div className="flex items-center justify-between">
+              <div className="space-y-1">
+                <CardTitle>{t("users.title")}</CardTitle>
+                <CardDescription>{t("users.description")}</CardDescription>
+              </div>
+              <Users className="h-8 w-8 text-muted-foreground" />
+            </div>
+          </CardHeader>
+          <CardContent>
+            <UsersManager />
+          </CardContent>
+        </Card>
+      )}
+
+      {isAdmin && (
+        <Card>
+          <CardHeader>
+            <div className="flex items-center justify-between">
+              <div className="space-y-1">
+                <CardTitle>{t("recycle_bin.title")}</CardTitle>
+                <CardDescription>{t("recycle_bin.description")}</CardDescription>
+              </div>
+              <Trash2 className="h-8 w-8 text-muted-foreground" />
+            </div>
+          </CardHeader>
+          <CardContent>
+            <RecycleBin />
+          </CardContent>
+        </Card>
+      )}
+
+      {isAdmin && (
+        <Card>
+          <CardHeader>
+            <div className="flex items-center justify-between">
+              <div className="space-y-1">
+                <CardTitle>{t("encryption.title")}</CardTitle>
+                <CardDescription>{t("encryption.description")}</CardDescription>
+              </div>
+              <ShieldCheck className="h-8 w-8 text-muted-foreground" />
+            </div>
+          </CardHeader>
+          <CardContent>
+            <EncryptionSettings />
+          </CardContent>
+        </Card>
+      )}
+
     </div>
 
   );
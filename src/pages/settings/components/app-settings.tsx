@@ -0,0 +1,174 @@
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { getSettings, saveSettings, Settings, SettingsInfo } from "@/lib/config/app";
+import { error } from "@tauri-apps/plugin-log";
+import { relaunch } from "@tauri-apps/plugin-process";
+import { AlertTriangle, Loader2, RotateCw, Save } from "lucide-react";
+import { useEffect, useState } from "react";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+
+type SettingsForm = Record<keyof Settings, string>;
+
+function toForm(settings: Settings): SettingsForm
+{
+  return {
+    data_dir: settings.data_dir ?? "",
+    port: settings.port?.toString() ?? "",
+    port_range: settings.port_range?.toString() ?? "",
+    bind_address: settings.bind_address ?? "",
+    sync_interval_minutes: settings.sync_interval_minutes?.toString() ?? "",
+    pairing_ttl_minutes: settings.pairing_ttl_minutes?.toString() ?? "",
+    // An empty list turns reminders off, which an empty field cannot express
+    reminder_lead_minutes: settings.reminder_lead_minutes?.length === 0
+      ? "0"
+      : settings.reminder_lead_minutes?.join(", ") ?? "",
+    overdue_reminder_days: settings.overdue_reminder_days?.toString() ?? "",
+    recycle_bin_retention_days: settings.recycle_bin_retention_days?.toString() ?? "",
+    archive_keep_daily: settings.archive_keep_daily?.toString() ?? "",
+    archive_keep_weekly: settings.archive_keep_weekly?.toString() ?? "",
+    wal_archive_interval_minutes: settings.wal_archive_interval_minutes?.toString() ?? "",
+    wal_retention_days: settings.wal_retention_days?.toString() ?? "",
+    peer_sync_secret: settings.peer_sync_secret ?? "",
+    mdns_hostname: settings.mdns_hostname ?? "",
+  };
+}
+
+// Empty fields are saved as null so the built-in default applies
+function fromForm(form: SettingsForm): Settings
+{
+  const text = (value: string) => value.trim() || null;
+  const number = (value: string) => (value.trim() ? Number(value) : null);
+  const numbers = (value: string) =>
+    value.trim()
+      ? value.split(",").filter((part) => part.trim()).map(Number).filter((minutes) => minutes !== 0)
+      : null;
+
+  return {
+    data_dir: text(form.data_dir),
+    port: number(form.port),
+    port_range: number(form.port_range),
+    bind_address: text(form.bind_address),
+    sync_interval_minutes: number(form.sync_interval_minutes),
+    pairing_ttl_minutes: number(form.pairing_ttl_minutes),
+    reminder_lead_minutes: numbers(form.reminder_lead_minutes),
+    overdue_reminder_days: number(form.overdue_reminder_days),
+    recycle_bin_retention_days: number(form.recycle_bin_retention_days),
+    archive_keep_daily: number(form.archive_keep_daily),
+    archive_keep_weekly: number(form.archive_keep_weekly),
+    wal_archive_interval_minutes: number(form.wal_archive_interval_minutes),
+    wal_retention_days: number(form.wal_retention_days),
+    peer_sync_secret: text(form.peer_sync_secret),
+    mdns_hostname: text(form.mdns_hostname),
+  };
+}
+
+export function AppSettings()
+{
+  const { t } = useTranslation();
+  const [info, setInfo] = useState<SettingsInfo | null>(null);
+  const [form, setForm] = useState<SettingsForm | null>(null);
+  const [isSaving, setIsSaving] = useState(false);
+
+  useEffect(() =>
+  {
+    getSettings()
+      .then((info) =>
+      {
+        setInfo(info);
+        setForm(toForm(info.settings));
+      })
+      .catch((err) => error(`Failed to load settings: ${err}`));
+  }, []);
+
+  const handleSave = async () =>
+  {
+    if (!form) return;
+
+    setIsSaving(true);
+    try
+    {
+      const saved = await saveSettings(fromForm(form));
+      setInfo(saved);
+      setForm(toForm(saved.settings));
+      toast.success(t("settings.saved"));
+    } catch (err)
+    {
+      toast.error(t("settings.invalid"), { description: String(err) });
+    } finally
+    {
+      setIsSaving(false);
+    }
+  };
+
+  if (!form || !info)
+  {
+    return (
+      <div className="flex justify-center py-6">
+        <Loader2 className="animate-spin h-6 w-6 text-primary" />
+      </div>
+    );
+  }
+
+  const field = (key: keyof Settings, label: string, placeholder: string, type = "text") => (
+    <div className="space-y-2">
+      <Label htmlFor={key}>{label}</Label>
+      <Input
+        id={key}
+        type={type}
+        value={form[key]}
+        placeholder={placeholder}
+        onChange={(e) => setForm({ ...form, [key]: e.target.value })}
+      />
+    </div>
+  );
+
+  return (
+    <div className="space-y-6">
+      {info.errors.length > 0 && (
+        <div className="flex gap-3 rounded-lg border border-destructive/30 bg-destructive/5 p-4 text-sm text-destructive">
+          <AlertTriangle className="h-4 w-4 shrink-0 mt-0.5" />
+          <ul className="space-y-1">
+            {info.errors.map((message) => <li key={message}>{message}</li>)}
+          </ul>
+        </div>
+      )}
+
+      <div className="grid gap-4 sm:grid-cols-2">
+        {field("data_dir", t("settings.data_dir"), t("settings.default"))}
+        {field("port", t("settings.port"), "14200", "number")}
+        {field("port_range", t("settings.port_range"), "10", "number")}
+        {field("bind_address", t("settings.bind_address"), "0.0.0.0")}
+        {field("sync_interval_minutes", t("settings.sync_interval"), "60", "number")}
+        {field("pairing_ttl_minutes", t("settings.pairing_ttl"), "10", "number")}
+        {field("reminder_lead_minutes", t("settings.reminder_lead"), "1440, 60")}
+        {field("overdue_reminder_days", t("settings.overdue_reminder"), "30", "number")}
+        {field("recycle_bin_retention_days", t("settings.recycle_bin_retention"), "30", "number")}
+        {field("archive_keep_daily", t("settings.archive_keep_daily"), "7", "number")}
+        {field("archive_keep_weekly", t("settings.archive_keep_weekly"), "4", "number")}
+        {field("wal_archive_interval_minutes", t("settings.wal_archive_interval"), "5", "number")}
+        {field("wal_retention_days", t("settings.wal_retention"), "7", "number")}
+        {field("mdns_hostname", t("settings.mdns_hostname"), t("settings.default"))}
+        {field("peer_sync_secret", t("settings.peer_sync_secret"), t("settings.peer_sync_off"), "password")}
+      </div>
+
+      <p className="text-xs text-muted-foreground break-all">
+        {t("settings.file_location", { path: info.path })}
+      </p>
+
+      <div className="flex flex-wrap items-center justify-end gap-2">
+        {info.restart_required && (
+          <Button variant="outline" size="sm" className="gap-2" onClick={() => relaunch()}>
+            <RotateCw className="h-4 w-4" />
+            {t("settings.restart")}
+          </Button>
+        )}
+        <Button size="sm" className="gap-2" onClick={handleSave} disabled={isSaving}>
+          {isSaving ? <Loader2 className="animate-spin h-4 w-4" /> : <Save className="h-4 w-4" />}
+          {t("common.save")}
+        </Button>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,227 @@
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { Spinner } from "@/components/ui/spinner";
+import {
+  changeEncryptionMutationOptions,
+  getEncryptionStatusQueryOptions,
+  setBackupPassphraseMutationOptions,
+  unlockBackupsMutationOptions,
+} from "@/lib/tanstack-query/encryption";
+import { EncryptionChange } from "@/lib/types/encryption";
+import { useMutation, useQuery } from "@tanstack/react-query";
+import { CloudUpload, KeyRound, Lock, LockOpen } from "lucide-react";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+
+// Backup passphrases have no re-encryption step, only a new one to set or
+// the current one to enter when the database can't keep it
+type Action = EncryptionChange["action"] | "backup" | "unlockBackups";
+
+interface PassphraseForm {
+  current: string;
+  next: string;
+  confirm: string;
+}
+
+const emptyForm: PassphraseForm = { current: "", next: "", confirm: "" };
+
+export function EncryptionSettings() {
+  const { t } = useTranslation();
+  const { data: status } = useQuery(getEncryptionStatusQueryOptions());
+  const [action, setAction] = useState<Action | null>(null);
+  const [form, setForm] = useState<PassphraseForm>(emptyForm);
+  const [mismatch, setMismatch] = useState(false);
+
+  const changeMutation = useMutation(changeEncryptionMutationOptions());
+  const backupMutation = useMutation({
+    ...setBackupPassphraseMutationOptions(),
+    onSuccess: () => setAction(null),
+  });
+  const unlockBackupsMutation = useMutation({
+    ...unlockBackupsMutationOptions(),
+    onSuccess: () => setAction(null),
+  });
+
+  const open = (action: Action) => {
+    setForm(emptyForm);
+    setMismatch(false);
+    changeMutation.reset();
+    backupMutation.reset();
+    unlockBackupsMutation.reset();
+    setAction(action);
+  };
+
+  const handleSave = () => {
+    const needsNew = action !== "disable" && action !== "unlockBackups";
+    if (needsNew && form.next !== form.confirm) {
+      setMismatch(true);
+      return;
+    }
+
+    switch (action) {
+      case "enable":
+        changeMutation.mutate({ action, passphrase: form.next });
+        break;
+      case "rotate":
+        changeMutation.mutate({ action, currentPassphrase: form.current, newPassphrase: form.next });
+        break;
+      case "disable":
+        changeMutation.mutate({ action, currentPassphrase: form.current });
+        break;
+      case "backup":
+        backupMutation.mutate(form.next);
+        break;
+      case "unlockBackups":
+        unlockBackupsMutation.mutate(form.current);
+        break;
+    }
+  };
+
+  if (!status) return null;
+
+  const passphraseInput = (id: keyof PassphraseForm, label: string, autoComplete: string) => (
+    <div className="space-y-2">
+      <Label htmlFor={`encryption-${id}`}>{label}</Label>
+      <Input
+        id={`encryption-${id}`}
+        type="password"
+        autoComplete={autoComplete}
+        value={form[id]}
+        onChange={(e) => setForm({ ...form, [id]: e.target.value })}
+      />
+    </div>
+  );
+
+  // Closing mid-change would hide the relaunch that follows it
+  const isBusy =
+    changeMutation.isPending ||
+    changeMutation.isSuccess ||
+    backupMutation.isPending ||
+    unlockBackupsMutation.isPending;
+  const mutation =
+    action === "backup" ? backupMutation : action === "unlockBackups" ? unlockBackupsMutation : changeMutation;
+
+  return (
+    <div className="space-y-4">
+      <div className="flex items-center justify-between gap-4">
+        <div className="space-y-1">
+          <Badge variant={status.enabled ? "default" : "secondary"}>
+            {status.enabled ? t("encryption.enabled") : t("encryption.disabled")}
+          </Badge>
+          <p className="text-sm text-muted-foreground">{t("encryption.warning")}</p>
+        </div>
+        <div className="flex gap-2 shrink-0">
+          {status.enabled ? (
+            <>
+              <Button variant="outline" size="sm" className="gap-2" onClick={() => open("rotate")}>
+                <KeyRound className="h-4 w-4" />
+                {t("encryption.rotate")}
+              </Button>
+              <Button variant="outline" size="sm" className="gap-2" onClick={() => open("disable")}>
+                <LockOpen className="h-4 w-4" />
+                {t("encryption.disable")}
+              </Button>
+            </>
+          ) : (
+            <Button size="sm" className="gap-2" onClick={() => open("enable")}>
+              <Lock className="h-4 w-4" />
+              {t("encryption.enable")}
+            </Button>
+          )}
+        </div>
+      </div>
+
+      <div className="flex items-center justify-between gap-4 border-t pt-4">
+        <div className="space-y-1">
+          <Badge variant={status.backupsEncrypted ? "default" : "secondary"}>
+            {status.backupsEncrypted ? t("encryption.backups_encrypted") : t("encryption.backups_plain")}
+          </Badge>
+          <p className="text-sm text-muted-foreground">{t("encryption.backup_warning")}</p>
+          {status.backupsLocked && <p className="text-sm text-destructive">{t("encryption.backups_locked")}</p>}
+        </div>
+        <div className="flex gap-2 shrink-0">
+          {status.backupsLocked && (
+            <Button size="sm" className="gap-2" onClick={() => open("unlockBackups")}>
+              <KeyRound className="h-4 w-4" />
+              {t("encryption.unlock_backups")}
+            </Button>
+          )}
+          <Button variant="outline" size="sm" className="gap-2" onClick={() => open("backup")}>
+            <CloudUpload className="h-4 w-4" />
+            {status.backupsEncrypted ? t("encryption.change_backup_passphrase") : t("encryption.set_backup_passphrase")}
+          </Button>
+          {status.backupsEncrypted && (
+            <Button
+              variant="outline"
+              size="sm"
+              disabled={backupMutation.isPending}
+              onClick={() => backupMutation.mutate(null)}
+            >
+              {t("encryption.stop_backup_encryption")}
+            </Button>
+          )}
+        </div>
+      </div>
+
+      <Dialog open={action !== null} onOpenChange={(open) => !open && !isBusy && setAction(null)}>
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>
+              {action === "backup"
+                ? t("encryption.set_backup_passphrase")
+                : action === "unlockBackups"
+                  ? t("encryption.unlock_backups")
+                  : action && t(`encryption.${action}`)}
+            </DialogTitle>
+            <DialogDescription>
+              {action === "backup"
+                ? t("encryption.backup_notice")
+                : action === "unlockBackups"
+                  ? t("encryption.backups_locked")
+                  : t("encryption.relaunch_notice")}
+            </DialogDescription>
+          </DialogHeader>
+
+          <div className="space-y-4">
+            {action !== "enable" && action !== "backup" &&
+              passphraseInput(
+                "current",
+                action === "unlockBackups" ? t("encryption.passphrase") : t("encryption.current_passphrase"),
+                "current-password",
+              )}
+            {action !== "disable" && action !== "unlockBackups" && (
+              <>
+                {passphraseInput("next", t("encryption.new_passphrase"), "new-password")}
+                {passphraseInput("confirm", t("encryption.confirm_passphrase"), "new-password")}
+              </>
+            )}
+            {mismatch && <p className="text-sm text-destructive">{t("encryption.passphrases_differ")}</p>}
+            {mutation.isError && (
+              <p className="text-sm text-destructive">{mutation.error.message}</p>
+            )}
+          </div>
+
+          <DialogFooter>
+            <Button variant="outline" onClick={() => setAction(null)} disabled={isBusy}>
+              {t("common.cancel")}
+            </Button>
+            <Button onClick={handleSave} disabled={isBusy}>
+              {isBusy && <Spinner />}
+              {t("common.save")}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+    </div>
+  );
+}
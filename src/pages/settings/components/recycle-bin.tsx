@@ -0,0 +1,177 @@
+import {
+  AlertDialog,
+  AlertDialogAction,
+  AlertDialogCancel,
+  AlertDialogContent,
+  AlertDialogDescription,
+  AlertDialogFooter,
+  AlertDialogHeader,
+  AlertDialogTitle,
+} from "@/components/ui/alert-dialog";
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Spinner } from "@/components/ui/spinner";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import {
+  GetRecycleBinParams,
+  getRecycleBinQueryOptions,
+  purgeDeletedMutationOptions,
+  restoreDeletedMutationOptions,
+} from "@/lib/tanstack-query/recycle-bin";
+import { DeletedItem } from "@/lib/types/recycle-bin";
+import { formatCurrency, formatDate } from "@/lib/utils";
+import { useMutation, useQuery } from "@tanstack/react-query";
+import { ChevronLeft, ChevronRight, RotateCcw, Trash2 } from "lucide-react";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+
+// Stored in UTC without a zone, see `datetime('now')`
+const utc = (value: string) => new Date(value.replace(" ", "T") + "Z");
+
+export function RecycleBin() {
+  const { t } = useTranslation();
+  const [search, setSearch] = useState("");
+  const [page, setPage] = useState(1);
+  const [purging, setPurging] = useState<DeletedItem | null>(null);
+
+  const binQuery = useQuery(
+    getRecycleBinQueryOptions(new GetRecycleBinParams({ page, pageSize: 10, search })),
+  );
+  const restoreMutation = useMutation(restoreDeletedMutationOptions());
+  const purgeMutation = useMutation({
+    ...purgeDeletedMutationOptions(),
+    onSettled: () => setPurging(null),
+  });
+
+  const items = binQuery.data?.items ?? [];
+  const pagingInfo = binQuery.data?.pagingInfo;
+
+  return (
+    <div className="space-y-4">
+      <Input
+        value={search}
+        placeholder={t("common.search")}
+        onChange={(e) => {
+          setSearch(e.target.value);
+          setPage(1);
+        }}
+      />
+
+      {binQuery.isLoading ? (
+        <div className="flex justify-center py-6">
+          <Spinner className="size-6 text-primary" />
+        </div>
+      ) : binQuery.isError ? (
+        <p className="text-sm text-destructive">{binQuery.error.message}</p>
+      ) : items.length === 0 ? (
+        <p className="text-sm text-muted-foreground text-center py-6">{t("recycle_bin.empty")}</p>
+      ) : (
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableHead>{t("recycle_bin.item")}</TableHead>
+              <TableHead>{t("common.name")}</TableHead>
+              <TableHead>{t("common.phone")}</TableHead>
+              <TableHead>{t("recycle_bin.deleted_at")}</TableHead>
+              <TableHead>{t("recycle_bin.purge_at")}</TableHead>
+              <TableHead className="text-end">{t("common.actions")}</TableHead>
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {items.map((item) => (
+              <TableRow key={`${item.kind}:${item.id}`}>
+                <TableCell>
+                  <Badge variant="secondary">{t(`recycle_bin.kinds.${item.kind}`)}</Badge>
+                  {item.total !== null && (
+                    <span className="ms-2 text-muted-foreground">{formatCurrency(item.total)}</span>
+                  )}
+                </TableCell>
+                <TableCell className="font-medium">{item.patientName}</TableCell>
+                <TableCell>{item.patientPhone}</TableCell>
+                <TableCell>{formatDate(utc(item.deletedAt))}</TableCell>
+                <TableCell>{item.purgeAt ? formatDate(utc(item.purgeAt)) : t("recycle_bin.kept")}</TableCell>
+                <TableCell className="text-end space-x-1">
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    title={t("recycle_bin.restore")}
+                    disabled={restoreMutation.isPending}
+                    onClick={() => restoreMutation.mutate({ kind: item.kind, id: item.id })}
+                  >
+                    <RotateCcw className="h-4 w-4" />
+                  </Button>
+                  <Button
+                    variant="ghost"
+                    size="icon"
+                    title={t("recycle_bin.purge")}
+                    className="text-destructive hover:text-destructive"
+                    onClick={() => setPurging(item)}
+                  >
+                    <Trash2 className="h-4 w-4" />
+                  </Button>
+                </TableCell>
+              </TableRow>
+            ))}
+          </TableBody>
+        </Table>
+      )}
+
+      {pagingInfo && (pagingInfo.hasPreviousPage || pagingInfo.hasNextPage) && (
+        <div className="flex items-center justify-end gap-2">
+          <Button
+            variant="outline"
+            size="icon"
+            disabled={!pagingInfo.hasPreviousPage}
+            onClick={() => setPage(page - 1)}
+          >
+            <ChevronLeft className="h-4 w-4 rtl:rotate-180" />
+          </Button>
+          <span className="text-sm text-muted-foreground">{pagingInfo.page}</span>
+          <Button
+            variant="outline"
+            size="icon"
+            disabled={!pagingInfo.hasNextPage}
+            onClick={() => setPage(page + 1)}
+          >
+            <ChevronRight className="h-4 w-4 rtl:rotate-180" />
+          </Button>
+        </div>
+      )}
+
+      <AlertDialog open={!!purging} onOpenChange={(open) => !open && setPurging(null)}>
+        <AlertDialogContent>
+          <AlertDialogHeader>
+            <AlertDialogTitle>{t("common.are_you_sure")}</AlertDialogTitle>
+            <AlertDialogDescription>
+              {t("recycle_bin.purge_description", { name: purging?.patientName })}{" "}
+              {t("common.cannot_be_undone")}
+            </AlertDialogDescription>
+          </AlertDialogHeader>
+          <AlertDialogFooter>
+            <AlertDialogCancel>{t("common.cancel")}</AlertDialogCancel>
+            <AlertDialogAction
+              className="bg-red-600 hover:bg-red-700"
+              disabled={purgeMutation.isPending}
+              onClick={(e) => {
+                // Stay open until the purge settles
+                e.preventDefault();
+                if (purging) purgeMutation.mutate({ kind: purging.kind, id: purging.id });
+              }}
+            >
+              {purgeMutation.isPending && <Spinner className="mr-2 text-white" />}
+              {t("recycle_bin.purge")}
+            </AlertDialogAction>
+          </AlertDialogFooter>
+        </AlertDialogContent>
+      </AlertDialog>
+    </div>
+  );
+}
@@ -12,6 +12,7 @@ import
 } from "@/components/ui/alert-dialog";
 import { Badge } from "@/components/ui/badge";
 import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Switch } from "@/components/ui/switch";
 import { useAutoSync } from "@/lib/hooks/use-auto-sync";
@@ -20,10 +21,12 @@ import {
   getBackupsInfiniteQueryOptions,
   restoreBackupMutationOptions,
   uploadBackupMutationOptions,
+  verifyBackupMutationOptions,
 } from "@/lib/tanstack-query/drive";
 import { cn, formatDate } from "@/lib/utils";
 import { useInfiniteQuery, useMutation } from "@tanstack/react-query";
-import { Check, Clock, Cloud, Download, Loader2, RefreshCw, Upload, Wifi, WifiOff, ChevronDown } from "lucide-react";
+import { Check, Clock, Cloud, Download, Loader2, Lock, RefreshCw, ShieldCheck, Upload, Wifi, WifiOff, ChevronDown } from "lucide-react";
+import { useState } from "react";
 import { useTranslation } from "react-i18next";
 
 export function SyncManager()
@@ -31,6 +34,7 @@ export function SyncManager()
   const { t } = useTranslation();
   const { isAutoSyncEnabled, toggleAutoSync, lastSyncTime, isSyncing, isOnline, setIsSyncing, setLastSyncTime, lastSyncedFileId } = useSyncStore();
   const { syncIntervalMinutes } = useAutoSync();
+  const [passphrase, setPassphrase] = useState("");
 
   const backupsQuery = useInfiniteQuery(getBackupsInfiniteQueryOptions(isOnline));
 
@@ -38,6 +42,10 @@ export function SyncManager()
     ...restoreBackupMutationOptions(),
   });
 
+  const verifyMutation = useMutation({
+    ...verifyBackupMutationOptions(),
+  });
+
   const uploadMutation = useMutation({
     ...uploadBackupMutationOptions(),
     onMutate: () => setIsSyncing(true),
@@ -50,11 +58,18 @@ export function SyncManager()
     uploadMutation.mutate();
   };
 
-  const handleRestore = async (fileId: string) =>
+  const handleRestore = async (fileId: string, encrypted: boolean) =>
+  {
+    restoreMutation.mutate({ fileId, passphrase: encrypted ? passphrase : undefined });
+  };
+
+  const handleVerify = (fileId: string, encrypted: boolean) =>
   {
-    restoreMutation.mutate(fileId);
+    verifyMutation.mutate({ fileId, passphrase: encrypted ? passphrase : undefined });
   };
 
+  const verification = verifyMutation.data;
+
   const backups = backupsQuery.data?.pages.flatMap((page) => page.files) || [];
   const isFetchingBackups = backupsQuery.isFetching && !backupsQuery.isFetchingNextPage;
 
@@ -129,6 +144,7 @@ export function SyncManager()
             ) : (
               backups.map((backup) => {
                 const isActive = backup.id === lastSyncedFileId;
+                const isEncrypted = backup.properties?.encrypted === "true";
                 return (
                   <div 
                     key={backup.id} 
@@ -141,6 +157,7 @@ export function SyncManager()
                   >
                     <div className="flex flex-col gap-1 pr-4 rtl:pr-0 rtl:pl-4">
                       <div className="flex items-center gap-2">
+                        {isEncrypted && <Lock className="h-3 w-3 shrink-0 text-muted-foreground" aria-label={t("sync.encrypted")} />}
                         <span className="text-sm font-medium break-all line-clamp-1">{backup.name}</span>
                         {isActive && (
                           <Badge variant="outline" className="h-5 px-1.5 gap-1 text-[10px] border-primary/30 text-primary bg-primary/5">
@@ -165,7 +182,7 @@ export function SyncManager()
                       </div>
                     </div>
                     <div className="flex justify-end shrink-0">
-                      <AlertDialog>
+                      <AlertDialog onOpenChange={() => { setPassphrase(""); verifyMutation.reset(); }}>
                         <AlertDialogTrigger asChild>
                           <Button variant="outline" size="sm" className="gap-2 h-8" disabled={!isOnline}>
                             <Download className="h-3 w-3" /> {t("sync.restore")}
@@ -178,9 +195,66 @@ export function SyncManager()
                               {t("sync.restore_confirm_desc", { date: formatDate(backup.createdTime) })}
                             </AlertDialogDescription>
                           </AlertDialogHeader>
+                          {isEncrypted && (
+                            <div className="space-y-2">
+                              <Label htmlFor={`restore-passphrase-${backup.id}`}>{t("sync.backup_passphrase")}</Label>
+                              <Input
+                                id={`restore-passphrase-${backup.id}`}
+                                type="password"
+                                autoComplete="off"
+                                value={passphrase}
+                                onChange={(e) => setPassphrase(e.target.value)}
+                              />
+                            </div>
+                          )}
+                          {verification?.backupId === backup.id && (
+                            <div className={cn(
+                              "rounded-md border p-3 text-xs space-y-1",
+                              verification.restorable ? "bg-muted/30" : "border-destructive/50 text-destructive",
+                            )}>
+                              <p className="font-medium">
+                                {verification.restorable ? t("sync.verify_ok") : t("sync.verify_not_restorable")}
+                              </p>
+                              <p>
+                                {t("sync.verify_schema", {
+                                  version: verification.schemaVersion,
+                                  latest: verification.latestSchemaVersion,
+                                })}
+                              </p>
+                              <p>{t("sync.patients_count", { count: verification.patientCount })}</p>
+                              {verification.missingAttachments.length > 0 && (
+                                <p>
+                                  {t("sync.verify_missing_attachments", {
+                                    count: verification.missingAttachments.length,
+                                    total: verification.attachmentCount,
+                                  })}
+                                </p>
+                              )}
+                              {verification.problems.length > 0 && (
+                                <ul className="list-disc ps-4">
+                                  {verification.problems.map((problem) => <li key={problem}>{problem}</li>)}
+                                </ul>
+                              )}
+                            </div>
+                          )}
                           <AlertDialogFooter>
                             <AlertDialogCancel>{t("common.cancel")}</AlertDialogCancel>
-                            <AlertDialogAction onClick={() => handleRestore(backup.id)} className="bg-destructive hover:bg-destructive/90 text-white">
+                            <Button
+                              variant="outline"
+                              className="gap-2"
+                              onClick={() => handleVerify(backup.id, isEncrypted)}
+                              disabled={(isEncrypted && !passphrase) || verifyMutation.isPending}
+                            >
+                              {verifyMutation.isPending
+                                ? <Loader2 className="h-4 w-4 animate-spin" />
+                                : <ShieldCheck className="h-4 w-4" />}
+                              {t("sync.verify")}
+                            </Button>
+                            <AlertDialogAction
+                              onClick={() => handleRestore(backup.id, isEncrypted)}
+                              disabled={isEncrypted && !passphrase}
+                              className="bg-destructive hover:bg-destructive/90 text-white"
+                            >
                               {t("sync.restore_confirm_action")}
                             </AlertDialogAction>
                           </AlertDialogFooter>
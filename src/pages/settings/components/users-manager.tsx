@@ -0,0 +1,236 @@
+import { Badge } from "@/components/ui/badge";
+import { Button } from "@/components/ui/button";
+import {
+  Dialog,
+  DialogContent,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from "@/components/ui/dialog";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import {
+  Select,
+  SelectContent,
+  SelectItem,
+  SelectTrigger,
+  SelectValue,
+} from "@/components/ui/select";
+import { Spinner } from "@/components/ui/spinner";
+import { Switch } from "@/components/ui/switch";
+import {
+  Table,
+  TableBody,
+  TableCell,
+  TableHead,
+  TableHeader,
+  TableRow,
+} from "@/components/ui/table";
+import {
+  addUserMutationOptions,
+  getUsersQueryOptions,
+  resetUserPasswordMutationOptions,
+  updateUserMutationOptions,
+} from "@/lib/tanstack-query/users";
+import { Role, Roles, User } from "@/lib/types/users";
+import { useMutation, useQuery } from "@tanstack/react-query";
+import { KeyRound, Pencil, UserPlus } from "lucide-react";
+import { useState } from "react";
+import { useTranslation } from "react-i18next";
+
+interface UserForm {
+  username: string;
+  displayName: string;
+  role: Role;
+  active: boolean;
+  password: string;
+}
+
+const emptyForm: UserForm = {
+  username: "",
+  displayName: "",
+  role: "receptionist",
+  active: true,
+  password: "",
+};
+
+// `null` while closed, a new user when `user` is missing
+type Editing = { user?: User; resetPassword?: boolean } | null;
+
+export function UsersManager() {
+  const { t } = useTranslation();
+  const usersQuery = useQuery(getUsersQueryOptions());
+  const [editing, setEditing] = useState<Editing>(null);
+  const [form, setForm] = useState<UserForm>(emptyForm);
+
+  const close = () => setEditing(null);
+  const addMutation = useMutation({ ...addUserMutationOptions(), onSuccess: close });
+  const updateMutation = useMutation({ ...updateUserMutationOptions(), onSuccess: close });
+  const resetMutation = useMutation({ ...resetUserPasswordMutationOptions(), onSuccess: close });
+
+  const open = (editing: NonNullable<Editing>) => {
+    const user = editing.user;
+    setForm(user ? { ...user, password: "" } : emptyForm);
+    setEditing(editing);
+  };
+
+  const handleSave = () => {
+    const { password, ...user } = form;
+
+    if (!editing?.user) {
+      addMutation.mutate({ ...user, password });
+    } else if (editing.resetPassword) {
+      resetMutation.mutate({ id: editing.user.id, password });
+    } else {
+      updateMutation.mutate({ id: editing.user.id, updateUser: user });
+    }
+  };
+
+  const isPending = addMutation.isPending || updateMutation.isPending || resetMutation.isPending;
+  const showsPassword = !editing?.user || editing.resetPassword;
+  const showsDetails = !editing?.resetPassword;
+
+  if (usersQuery.isLoading) {
+    return (
+      <div className="flex justify-center py-6">
+        <Spinner className="size-6 text-primary" />
+      </div>
+    );
+  }
+
+  return (
+    <div className="space-y-4">
+      <div className="flex justify-end">
+        <Button size="sm" className="gap-2" onClick={() => open({})}>
+          <UserPlus className="h-4 w-4" />
+          {t("users.add")}
+        </Button>
+      </div>
+
+      <Table>
+        <TableHeader>
+          <TableRow>
+            <TableHead>{t("common.name")}</TableHead>
+            <TableHead>{t("auth.username")}</TableHead>
+            <TableHead>{t("users.role")}</TableHead>
+            <TableHead>{t("common.status")}</TableHead>
+            <TableHead className="text-end">{t("common.actions")}</TableHead>
+          </TableRow>
+        </TableHeader>
+        <TableBody>
+          {usersQuery.data?.map((user) => (
+            <TableRow key={user.id}>
+              <TableCell className="font-medium">{user.displayName}</TableCell>
+              <TableCell>{user.username}</TableCell>
+              <TableCell>{t(`users.roles.${user.role}`)}</TableCell>
+              <TableCell>
+                <Badge variant={user.active ? "default" : "secondary"}>
+                  {user.active ? t("users.active") : t("users.inactive")}
+                </Badge>
+              </TableCell>
+              <TableCell className="text-end space-x-1">
+                <Button variant="ghost" size="icon" title={t("users.edit")} onClick={() => open({ user })}>
+                  <Pencil className="h-4 w-4" />
+                </Button>
+                <Button
+                  variant="ghost"
+                  size="icon"
+                  title={t("users.reset_password")}
+                  onClick={() => open({ user, resetPassword: true })}
+                >
+                  <KeyRound className="h-4 w-4" />
+                </Button>
+              </TableCell>
+            </TableRow>
+          ))}
+        </TableBody>
+      </Table>
+
+      <Dialog open={editing !== null} onOpenChange={(open) => !open && close()}>
+        <DialogContent>
+          <DialogHeader>
+            <DialogTitle>
+              {!editing?.user
+                ? t("users.add")
+                : editing.resetPassword
+                  ? t("users.reset_password")
+                  : t("users.edit")}
+            </DialogTitle>
+          </DialogHeader>
+
+          <div className="space-y-4">
+            {showsDetails && (
+              <>
+                <div className="space-y-2">
+                  <Label htmlFor="user-username">{t("auth.username")}</Label>
+                  <Input
+                    id="user-username"
+                    autoComplete="off"
+                    value={form.username}
+                    onChange={(e) => setForm({ ...form, username: e.target.value })}
+                  />
+                </div>
+                <div className="space-y-2">
+                  <Label htmlFor="user-display-name">{t("auth.display_name")}</Label>
+                  <Input
+                    id="user-display-name"
+                    autoComplete="off"
+                    value={form.displayName}
+                    onChange={(e) => setForm({ ...form, displayName: e.target.value })}
+                  />
+                </div>
+                <div className="space-y-2">
+                  <Label>{t("users.role")}</Label>
+                  <Select value={form.role} onValueChange={(role) => setForm({ ...form, role: role as Role })}>
+                    <SelectTrigger>
+                      <SelectValue />
+                    </SelectTrigger>
+                    <SelectContent>
+                      {Roles.map((role) => (
+                        <SelectItem key={role} value={role}>
+                          {t(`users.roles.${role}`)}
+                        </SelectItem>
+                      ))}
+                    </SelectContent>
+                  </Select>
+                </div>
+                <div className="flex items-center justify-between">
+                  <Label htmlFor="user-active">{t("users.active")}</Label>
+                  <Switch
+                    id="user-active"
+                    checked={form.active}
+                    onCheckedChange={(active) => setForm({ ...form, active })}
+                  />
+                </div>
+              </>
+            )}
+            {showsPassword && (
+              <div className="space-y-2">
+                <Label htmlFor="user-password">
+                  {editing?.user ? t("auth.new_password") : t("auth.password")}
+                </Label>
+                <Input
+                  id="user-password"
+                  type="password"
+                  autoComplete="new-password"
+                  value={form.password}
+                  onChange={(e) => setForm({ ...form, password: e.target.value })}
+                />
+              </div>
+            )}
+          </div>
+
+          <DialogFooter>
+            <Button variant="outline" onClick={close} disabled={isPending}>
+              {t("common.cancel")}
+            </Button>
+            <Button onClick={handleSave} disabled={isPending}>
+              {isPending && <Spinner />}
+              {t("common.save")}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+    </div>
+  );
+}
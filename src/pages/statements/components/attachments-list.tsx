@@ -13,12 +13,12 @@ import { Button } from "@/components/ui/button";
 import { Dialog, DialogContent, DialogFooter } from "@/components/ui/dialog";
 import
 {
+  attachmentSrc,
   deleteAttachmentMutationOptions,
 } from "@/lib/tanstack-query/attachments";
 import { Attachment } from "@/lib/types/attachments";
 import { DialogClose } from "@radix-ui/react-dialog";
 import { useMutation } from "@tanstack/react-query";
-import { convertFileSrc } from "@tauri-apps/api/core";
 import { FileText, Maximize2, Trash, X } from "lucide-react";
 import { useState } from "react";
 import { useTranslation } from "react-i18next";
@@ -49,7 +49,7 @@ export function AttachmentsList({ statementId, attachments }: AttachmentsListPro
     <div className="grid grid-cols-2 md:grid-cols-4 lg:grid-cols-6 gap-4">
       {attachments.map((att) =>
       {
-        const src = convertFileSrc(att.filePath);
+        const src = attachmentSrc(att);
         return (
           <div key={att.id} className="group relative aspect-square bg-muted rounded-lg overflow-hidden border border-border shadow-sm">
             <img
@@ -100,7 +100,7 @@ export function AttachmentsList({ statementId, attachments }: AttachmentsListPro
           <div className="relative w-full h-full flex items-center justify-center p-4">
             {selectedImage && (
               <img
-                src={convertFileSrc(selectedImage.filePath)}
+                src={attachmentSrc(selectedImage)}
                 alt={selectedImage.fileName}
                 className="max-w-full max-h-full object-contain shadow-2xl animate-in zoom-in-95 duration-200"
               />
@@ -1,15 +1,18 @@
 import logo from "@/assets/logo.svg";
 import { Button } from "@/components/ui/button";
 import { Dialog, DialogContent, DialogHeader, DialogTitle } from "@/components/ui/dialog";
+import { attachmentSrc } from "@/lib/tanstack-query/attachments";
 import { Attachment } from "@/lib/types/attachments";
 import { StatementDetails } from "@/lib/types/statements";
 import { cn, formatCurrency, formatDate } from "@/lib/utils";
 import { DragDropContext, Draggable, Droppable, DropResult } from "@hello-pangea/dnd";
-import { convertFileSrc } from "@tauri-apps/api/core";
-import { GripVertical, Printer } from "lucide-react";
+import { invoke } from "@tauri-apps/api/core";
+import { save } from "@tauri-apps/plugin-dialog";
+import { FileDown, GripVertical, Loader2, Printer } from "lucide-react";
 import { forwardRef, useEffect, useRef, useState } from "react";
 import { useTranslation } from "react-i18next";
 import { useReactToPrint } from "react-to-print";
+import { toast } from "sonner";
 
 interface PrintPreviewProps
 {
@@ -20,11 +23,12 @@ interface PrintPreviewProps
 
 export function PrintPreview({ isOpen, onOpenChange, statement }: PrintPreviewProps)
 {
-  const { t } = useTranslation();
+  const { t, i18n } = useTranslation();
   const printRef = useRef<HTMLDivElement>(null);
 
   const [printAttachments, setPrintAttachments] = useState<Attachment[]>([]);
   const [selectedIds, setSelectedIds] = useState<string[]>([]);
+  const [isSavingPdf, setIsSavingPdf] = useState(false);
 
   useEffect(() =>
   {
@@ -41,6 +45,36 @@ export function PrintPreview({ isOpen, onOpenChange, statement }: PrintPreviewPr
     bodyClass: "print-body",
   });
 
+  // Rendered in Rust so the file looks the same on every platform
+  const handleSavePdf = async () =>
+  {
+    const path = await save({
+      defaultPath: `Statement-${statement.id}.pdf`,
+      filters: [{ name: "PDF", extensions: ["pdf"] }],
+    });
+    if (!path) return;
+
+    setIsSavingPdf(true);
+    try
+    {
+      await invoke("export_statement_pdf", {
+        statementId: statement.id,
+        options: {
+          path,
+          language: i18n.language.startsWith("ar") ? "ar" : "en",
+          attachmentIds: printAttachments.filter(a => selectedIds.includes(a.id)).map(a => a.id),
+        },
+      });
+      toast.success(t("messages.pdf_saved"));
+    } catch (err)
+    {
+      toast.error(t("common.operation_failed"), { description: String(err) });
+    } finally
+    {
+      setIsSavingPdf(false);
+    }
+  };
+
   const onDragEnd = (result: DropResult) =>
   {
     if (!result.destination) return;
@@ -120,7 +154,7 @@ export function PrintPreview({ isOpen, onOpenChange, statement }: PrintPreviewPr
                               <p className="text-[11px] font-bold truncate leading-tight mb-0.5">{att.fileName}</p>
                               <p className="text-[9px] text-muted-foreground uppercase">{formatDate(new Date(att.createdAt))}</p>
                             </div>
-                            <img src={convertFileSrc(att.filePath)} className="h-10 w-10 object-cover rounded-md border" />
+                            <img src={attachmentSrc(att)} className="h-10 w-10 object-cover rounded-md border" />
                           </div>
                         )}
                       </Draggable>
@@ -151,6 +185,10 @@ export function PrintPreview({ isOpen, onOpenChange, statement }: PrintPreviewPr
           <Button variant="outline" onClick={() => onOpenChange(false)} className="px-8 rounded-xl font-bold h-11 border-zinc-200">
             {t("common.cancel")}
           </Button>
+          <Button variant="outline" onClick={handleSavePdf} disabled={isSavingPdf} className="px-8 rounded-xl font-bold h-11 border-zinc-200">
+            {isSavingPdf ? <Loader2 className="me-2 h-4 w-4 animate-spin" /> : <FileDown className="me-2 h-4 w-4" />}
+            {t("common.save_pdf")}
+          </Button>
           <Button onClick={() => handlePrint()} className="px-12 rounded-xl font-bold h-11 shadow-xl shadow-primary/20">
             <Printer className="me-2 h-4 w-4" />
             {t("common.print")}
@@ -407,7 +445,7 @@ const PrintableStatementContent = forwardRef<
             className="flex items-center justify-center overflow-hidden w-full h-full p-0 border-0"
           >
             <img
-              src={convertFileSrc(att.filePath)}
+              src={attachmentSrc(att)}
               alt={att.fileName}
               style={{
                 width: "auto",
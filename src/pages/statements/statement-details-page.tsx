@@ -1,7 +1,7 @@
 import { useState } from "react";
 import { Link, useParams, useNavigate } from "react-router-dom";
-import { useQuery, useMutation } from "@tanstack/react-query";
-import { getStatementDetailsQueryOptions, deleteStatementMutationOptions } from "@/lib/tanstack-query/statements";
+import { useQuery, useMutation, useQueryClient } from "@tanstack/react-query";
+import { getStatementDetailsQueryKey, getStatementDetailsQueryOptions, deleteStatementMutationOptions } from "@/lib/tanstack-query/statements";
 import { addAttachmentMutationOptions } from "@/lib/tanstack-query/attachments";
 import { LoadingMessage } from "@/components/table-loading";
 import { ErrorMessage } from "@/components/error-message";
@@ -32,6 +32,7 @@ function StatementDetailsPage() {
   const { t } = useTranslation();
   const { id } = useParams<{ id: string }>();
   const navigate = useNavigate();
+  const queryClient = useQueryClient();
   
   // Modals state
   const [isAddPaymentOpen, setIsAddPaymentOpen] = useState(false);
@@ -79,6 +80,10 @@ function StatementDetailsPage() {
     setEditingImageData(data.data);
   };
 
+  const handleAttachmentReceived = () => {
+    queryClient.invalidateQueries({ queryKey: getStatementDetailsQueryKey(statement.id) });
+  };
+
   const handleSaveAttachment = (processedBase64: string) => {
     addAttachmentMutation.mutate({
       statementId: statement.id,
@@ -301,6 +306,8 @@ function StatementDetailsPage() {
       <ScannerModal 
         isOpen={isScannerOpen} 
         onOpenChange={setIsScannerOpen}
+        statementId={statement.id}
+        onAttachmentReceived={handleAttachmentReceived}
         onScanReceived={handleScanReceived}
       />
 
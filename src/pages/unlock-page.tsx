@@ -0,0 +1,61 @@
+import logo from "@/assets/logo.svg";
+import { Button } from "@/components/ui/button";
+import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
+import { Field, FieldLabel } from "@/components/ui/field";
+import { Input } from "@/components/ui/input";
+import { Spinner } from "@/components/ui/spinner";
+import { LanguageToggle } from "@/components/language-toggle";
+import { unlockDatabaseMutationOptions } from "@/lib/tanstack-query/encryption";
+import { useMutation } from "@tanstack/react-query";
+import { FormEvent, useState } from "react";
+import { useTranslation } from "react-i18next";
+
+export default function UnlockPage() {
+  const { t, i18n } = useTranslation();
+  const [passphrase, setPassphrase] = useState("");
+  const unlockMutation = useMutation(unlockDatabaseMutationOptions());
+
+  const onSubmit = (event: FormEvent) => {
+    event.preventDefault();
+    unlockMutation.mutate(passphrase);
+  };
+
+  return (
+    <div dir={i18n.dir()} className="min-h-screen flex items-center justify-center p-4 bg-muted/30">
+      <Card className="w-full max-w-sm">
+        <CardHeader className="space-y-3">
+          <div className="flex items-center justify-between">
+            <img src={logo} alt={`${t("common.app_name")} Logo`} className="w-10 h-10 rounded-md" />
+            <LanguageToggle />
+          </div>
+          <CardTitle>{t("encryption.unlock_title")}</CardTitle>
+          <CardDescription>{t("encryption.unlock_description")}</CardDescription>
+        </CardHeader>
+        <CardContent>
+          <form onSubmit={onSubmit} className="space-y-4">
+            <Field>
+              <FieldLabel htmlFor="passphrase">{t("encryption.passphrase")}</FieldLabel>
+              <Input
+                id="passphrase"
+                type="password"
+                autoComplete="current-password"
+                autoFocus
+                value={passphrase}
+                onChange={(e) => setPassphrase(e.target.value)}
+              />
+            </Field>
+
+            {unlockMutation.isError && (
+              <p className="text-sm text-destructive">{unlockMutation.error.message}</p>
+            )}
+
+            <Button type="submit" className="w-full" disabled={!passphrase || unlockMutation.isPending}>
+              {unlockMutation.isPending && <Spinner />}
+              {t("encryption.unlock")}
+            </Button>
+          </form>
+        </CardContent>
+      </Card>
+    </div>
+  );
+}